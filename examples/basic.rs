@@ -1,7 +1,10 @@
 #![allow(clippy::disallowed_names)]
 #![allow(clippy::let_underscore_future)]
 
-use fred::{prelude::*, types::RespVersion};
+use fred::{
+  prelude::*,
+  types::{RespNegotiation, RespVersion},
+};
 #[cfg(feature = "partial-tracing")]
 use fred::{tracing::Level, types::TracingConfig};
 
@@ -17,15 +20,18 @@ async fn main() -> Result<(), RedisError> {
     username: Some("foo".into()),
     password: Some("bar".into()),
     version: RespVersion::RESP2,
+    negotiation: RespNegotiation::Fixed,
     database: None,
+    key_prefix: None,
     #[cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))]
     tls: None,
     #[cfg(feature = "partial-tracing")]
     tracing: TracingConfig {
-      enabled:                                             false,
-      default_tracing_level:                               Level::INFO,
+      enabled: false,
+      default_tracing_level: Level::INFO,
       #[cfg(feature = "full-tracing")]
-      full_tracing_level:                                  Level::DEBUG,
+      full_tracing_level: Level::DEBUG,
+      ..Default::default()
     },
   };
   // see the Builder interface for more information
@@ -5,7 +5,7 @@ mod redis;
 mod transaction;
 
 pub use options::WithOptions;
-pub use pipeline::Pipeline;
+pub use pipeline::{Pipeline, PipelineResults};
 pub use pool::RedisPool;
 pub use redis::RedisClient;
 pub use transaction::Transaction;
@@ -22,6 +22,12 @@ mod pubsub;
 #[cfg_attr(docsrs, doc(cfg(feature = "subscriber-client")))]
 pub use pubsub::SubscriberClient;
 
+#[cfg(feature = "stream-consumer")]
+mod stream_consumer;
+#[cfg(feature = "stream-consumer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stream-consumer")))]
+pub use stream_consumer::StreamConsumer;
+
 #[cfg(feature = "replicas")]
 mod replica;
 #[cfg(feature = "replicas")]
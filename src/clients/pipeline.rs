@@ -1,5 +1,5 @@
 use crate::{
-  error::RedisError,
+  error::{RedisError, RedisErrorKind},
   interfaces,
   interfaces::{
     AclInterface,
@@ -27,13 +27,15 @@ use crate::{
   prelude::{RedisResult, RedisValue},
   protocol::{
     command::{RedisCommand, RouterCommand},
+    cross_slot,
     responders::ResponseKind,
     utils as protocol_utils,
   },
+  trace,
   utils,
 };
 use parking_lot::Mutex;
-use std::{collections::VecDeque, fmt, fmt::Formatter, sync::Arc};
+use std::{collections::VecDeque, fmt, fmt::Formatter, mem, sync::Arc};
 use tokio::sync::oneshot::{channel as oneshot_channel, Receiver as OneshotReceiver};
 
 #[cfg(feature = "redis-json")]
@@ -50,6 +52,16 @@ fn clone_buffered_commands(buffer: &Mutex<VecDeque<RedisCommand>>) -> VecDeque<R
   out
 }
 
+/// Create the parent span for a pipeline, attaching a child span to each command.
+fn trace_commands(inner: &Arc<RedisClientInner>, commands: &mut VecDeque<RedisCommand>) -> trace::Span {
+  let span = trace::create_pipeline_span(inner, commands.len());
+  for command in commands.iter_mut() {
+    trace::set_child_command_span(inner, &span, command);
+  }
+
+  span
+}
+
 fn prepare_all_commands(
   commands: VecDeque<RedisCommand>,
   error_early: bool,
@@ -76,9 +88,72 @@ fn prepare_all_commands(
   (command, rx)
 }
 
+/// The results from each command in a pipeline, converted to the caller's type on demand.
+///
+/// See [results](Pipeline::results) for more information.
+#[derive(Clone, Debug)]
+pub struct PipelineResults {
+  results: Vec<RedisResult<RedisValue>>,
+}
+
+impl PipelineResults {
+  /// Read the number of results.
+  pub fn len(&self) -> usize {
+    self.results.len()
+  }
+
+  /// Whether the pipeline did not contain any commands.
+  pub fn is_empty(&self) -> bool {
+    self.results.is_empty()
+  }
+
+  /// Read and convert the result of the command at `index`.
+  ///
+  /// Returns an error if the command failed, if the index is out of bounds, or if the value cannot be converted.
+  pub fn get<R>(&self, index: usize) -> RedisResult<R>
+  where
+    R: FromRedis,
+  {
+    match self.results.get(index) {
+      Some(Ok(value)) => value.clone().convert(),
+      Some(Err(error)) => Err(error.clone()),
+      None => Err(invalid_index(index)),
+    }
+  }
+
+  /// Take and convert the result of the command at `index`, leaving `nil` in its place.
+  ///
+  /// This avoids copying the value, which may be useful with large responses.
+  pub fn take<R>(&mut self, index: usize) -> RedisResult<R>
+  where
+    R: FromRedis,
+  {
+    match self.results.get_mut(index) {
+      Some(result) => mem::replace(result, Ok(RedisValue::Null)).and_then(|value| value.convert()),
+      None => Err(invalid_index(index)),
+    }
+  }
+
+  /// Read the inner results.
+  pub fn into_inner(self) -> Vec<RedisResult<RedisValue>> {
+    self.results
+  }
+}
+
+fn invalid_index(index: usize) -> RedisError {
+  RedisError::new(
+    RedisErrorKind::InvalidArgument,
+    format!("Invalid pipeline result index: {}", index),
+  )
+}
+
 /// Send a series of commands in a [pipeline](https://redis.io/docs/manual/pipelining/).
 ///
 /// See the [all](Self::all), [last](Self::last), and [try_all](Self::try_all) functions for more information.
+///
+/// When connected to a cluster any multi-key commands (`MGET`, `MSET`, `DEL`, `UNLINK`, `EXISTS`, `TOUCH`) with keys
+/// in different hash slots are split into one command per hash slot. The pieces are sent to each cluster node in
+/// parallel and the responses are merged back into the original order.
 pub struct Pipeline<C: ClientLike> {
   commands: Arc<Mutex<VecDeque<RedisCommand>>>,
   client:   C,
@@ -124,6 +199,11 @@ impl<C: ClientLike> ClientLike for Pipeline<C> {
     self.client.change_command(command);
   }
 
+  #[doc(hidden)]
+  fn is_buffered(&self) -> bool {
+    true
+  }
+
   #[doc(hidden)]
   fn send_command<T>(&self, command: T) -> Result<(), RedisError>
   where
@@ -226,6 +306,38 @@ impl<C: ClientLike> Pipeline<C> {
       .collect()
   }
 
+  /// Send the pipeline and respond with each individual result, converting each result to a different type on demand.
+  ///
+  /// Unlike [try_all](Self::try_all), errors that affect the entire pipeline (timeouts, connection errors, etc) are
+  /// returned for each command.
+  ///
+  /// ```rust no_run
+  /// # use fred::prelude::*;
+  /// # use std::collections::HashMap;
+  /// async fn example(client: &RedisClient) -> Result<(), RedisError> {
+  ///   let pipeline = client.pipeline();
+  ///   let _: () = pipeline.get("foo").await?;
+  ///   let _: () = pipeline.hgetall("bar").await?;
+  ///
+  ///   let results = pipeline.results().await;
+  ///   let foo: Option<String> = results.get(0)?;
+  ///   let bar: HashMap<String, i64> = results.get(1)?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn results(&self) -> PipelineResults {
+    let commands = clone_buffered_commands(&self.commands);
+    let len = commands.len();
+    let mut results = try_send_all(self.client.inner(), commands).await;
+    if results.len() < len {
+      if let Some(Err(error)) = results.first() {
+        results = vec![Err(error.clone()); len];
+      }
+    }
+
+    PipelineResults { results }
+  }
+
   /// Send the pipeline and respond with only the result of the last command.
   ///
   /// ```rust no_run
@@ -257,6 +369,8 @@ async fn try_send_all(
   if commands.is_empty() {
     return Vec::new();
   }
+  let (mut commands, splits) = cross_slot::split_pipeline(inner, commands);
+  let span = trace_commands(inner, &mut commands);
 
   let (mut command, rx) = prepare_all_commands(commands, false);
   command.inherit_options(inner);
@@ -265,7 +379,7 @@ async fn try_send_all(
   if let Err(e) = interfaces::send_to_router(inner, command) {
     return vec![Err(e)];
   };
-  let frame = match utils::apply_timeout(rx, timeout_dur).await {
+  let frame = match trace::instrument(span, utils::apply_timeout(rx, timeout_dur)).await {
     Ok(result) => match result {
       Ok(f) => f,
       Err(e) => return vec![Err(e)],
@@ -273,6 +387,7 @@ async fn try_send_all(
     Err(e) => return vec![Err(e)],
   };
 
+  let frame = cross_slot::merge_pipeline(&splits, frame);
   if let Resp3Frame::Array { data, .. } = frame {
     data.into_iter().map(protocol_utils::frame_to_results).collect()
   } else {
@@ -284,33 +399,115 @@ async fn send_all(inner: &Arc<RedisClientInner>, commands: VecDeque<RedisCommand
   if commands.is_empty() {
     return Ok(RedisValue::Array(Vec::new()));
   }
+  let (mut commands, splits) = cross_slot::split_pipeline(inner, commands);
+  let span = trace_commands(inner, &mut commands);
 
   let (mut command, rx) = prepare_all_commands(commands, true);
   command.inherit_options(inner);
   let timeout_dur = command.timeout_dur().unwrap_or_else(|| inner.default_command_timeout());
 
   interfaces::send_to_router(inner, command)?;
-  let frame = utils::apply_timeout(rx, timeout_dur).await??;
-  protocol_utils::frame_to_results(frame)
+  let frame = trace::instrument(span, utils::apply_timeout(rx, timeout_dur)).await??;
+  protocol_utils::frame_to_results(cross_slot::merge_pipeline(&splits, frame))
 }
 
 async fn send_last(
   inner: &Arc<RedisClientInner>,
-  commands: VecDeque<RedisCommand>,
+  mut commands: VecDeque<RedisCommand>,
 ) -> Result<RedisValue, RedisError> {
   if commands.is_empty() {
     return Ok(RedisValue::Null);
   }
-
-  let len = commands.len();
   let (tx, rx) = oneshot_channel();
-  let mut commands: Vec<RedisCommand> = commands.into_iter().collect();
-  commands[len - 1].response = ResponseKind::Respond(Some(tx));
+  let last = commands.pop_back();
+  let (mut commands, _) = cross_slot::split_pipeline(inner, commands);
+  if let Some(mut last) = last {
+    match cross_slot::split(&last) {
+      Some((pieces, split)) if inner.config.server.is_clustered() => {
+        commands.extend(cross_slot::buffer_pieces(pieces, split, tx));
+      },
+      _ => {
+        last.response = ResponseKind::Respond(Some(tx));
+        commands.push_back(last);
+      },
+    };
+  }
+  let span = trace_commands(inner, &mut commands);
+
+  let commands: Vec<RedisCommand> = commands.into_iter().collect();
   let mut command = RouterCommand::Pipeline { commands };
   command.inherit_options(inner);
   let timeout_dur = command.timeout_dur().unwrap_or_else(|| inner.default_command_timeout());
 
   interfaces::send_to_router(inner, command)?;
-  let frame = utils::apply_timeout(rx, timeout_dur).await??;
+  let frame = trace::instrument(span, utils::apply_timeout(rx, timeout_dur)).await??;
   protocol_utils::frame_to_results(frame)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    clients::RedisClient,
+    types::{PerformanceConfig, RedisConfig},
+  };
+  use std::time::Duration;
+
+  fn results() -> PipelineResults {
+    PipelineResults {
+      results: vec![
+        Ok(RedisValue::from("foo")),
+        Ok(RedisValue::Integer(1)),
+        Err(RedisError::new(RedisErrorKind::Unknown, "bar")),
+      ],
+    }
+  }
+
+  #[test]
+  fn should_get_typed_pipeline_results() {
+    let results = results();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results.get::<String>(0).unwrap(), "foo");
+    assert_eq!(results.get::<i64>(1).unwrap(), 1);
+    assert_eq!(results.get::<String>(1).unwrap(), "1");
+    assert_eq!(
+      *results.get::<RedisValue>(2).unwrap_err().kind(),
+      RedisErrorKind::Unknown
+    );
+    assert_eq!(
+      *results.get::<RedisValue>(3).unwrap_err().kind(),
+      RedisErrorKind::InvalidArgument
+    );
+  }
+
+  #[test]
+  fn should_take_pipeline_results() {
+    let mut results = results();
+    assert_eq!(results.take::<String>(0).unwrap(), "foo");
+    assert_eq!(results.get::<Option<String>>(0).unwrap(), None);
+  }
+
+  #[tokio::test]
+  async fn should_return_pipeline_errors_for_each_command() {
+    let perf = PerformanceConfig {
+      default_command_timeout: Duration::from_millis(10),
+      ..Default::default()
+    };
+    // the client is not connected, so the pipeline times out
+    let client = RedisClient::new(RedisConfig::default(), Some(perf), None, None);
+    let pipeline = client.pipeline();
+    let _: () = pipeline.get("foo").await.unwrap();
+    let _: () = pipeline.incr("bar").await.unwrap();
+
+    let results = pipeline.results().await;
+    assert_eq!(results.len(), 2);
+    for idx in 0 .. 2 {
+      assert_eq!(
+        *results.get::<RedisValue>(idx).unwrap_err().kind(),
+        RedisErrorKind::Timeout
+      );
+    }
+    // `try_all` returns one error for the entire pipeline
+    assert_eq!(pipeline.try_all::<RedisValue>().await.len(), 1);
+  }
+}
@@ -5,7 +5,9 @@ use crate::{
   interfaces::*,
   modules::inner::RedisClientInner,
   prelude::{ClientLike, StreamsInterface},
+  protocol::command::RedisCommandKind,
   types::*,
+  utils,
 };
 use bytes_utils::Str;
 use futures::Stream;
@@ -229,6 +231,92 @@ impl RedisClient {
     commands::scan::zscan(&self.inner, key.into(), pattern.into(), count)
   }
 
+  // --------------- STREAMING RESPONSES ---------------
+
+  /// Run a custom command, streaming the elements of the response as they're received.
+  ///
+  /// Array and set responses yield each element in order, and map responses yield a flat sequence of keys and
+  /// values. Any other response is returned as a single element, and `nil` responses yield no elements.
+  ///
+  /// Elements of array, set, and map responses, including streamed RESP3 aggregate types (`*?`, etc), are decoded
+  /// and sent to the caller as they arrive on the connection rather than buffering the entire response in memory
+  /// first. This is useful with commands that return very large collections (`LRANGE`, `HGETALL`, `ZRANGE`, etc).
+  ///
+  /// The command is sent when the stream is first polled, and it uses the same options, key prefix, middleware, and
+  /// tracing as other commands. The command timeout applies to each element rather than the entire response.
+  ///
+  /// The response stream returns an error if the connection closes before the last element is received. Commands
+  /// used with this interface are written at most once, regardless of the `max_command_attempts` setting, since
+  /// elements may have already been sent to the caller.
+  ///
+  /// **Note: only a small number of elements are buffered for the caller.** Once that buffer is full the connection
+  /// stops reading from the socket until the caller reads the next element, so a slow consumer will also delay any
+  /// other commands sent on the same connection.
+  ///
+  /// ```rust no_run
+  /// # use fred::prelude::*;
+  /// # use fred::types::CustomCommand;
+  /// # use futures::TryStreamExt;
+  /// async fn example(client: &RedisClient) -> Result<(), RedisError> {
+  ///   let cmd = CustomCommand::new_static("LRANGE", None, false);
+  ///   let mut elements = client.custom_stream(cmd, vec!["foo", "0", "-1"]);
+  ///
+  ///   while let Some(element) = elements.try_next().await? {
+  ///     println!("Element: {:?}", element);
+  ///   }
+  ///   Ok(())
+  /// }
+  /// ```
+  pub fn custom_stream<T>(
+    &self,
+    cmd: CustomCommand,
+    args: Vec<T>,
+  ) -> impl Stream<Item = Result<RedisValue, RedisError>> + Unpin
+  where
+    T: TryInto<RedisValue>,
+    T::Error: Into<RedisError>,
+  {
+    commands::stream_response(self, move || {
+      Ok((RedisCommandKind::_Custom(cmd), utils::try_into_vec(args)?))
+    })
+  }
+
+  /// Stream the specified elements of the list stored at `key` as they're received.
+  ///
+  /// See [custom_stream](Self::custom_stream) for more information.
+  ///
+  /// <https://redis.io/commands/lrange>
+  pub fn lrange_stream<K>(
+    &self,
+    key: K,
+    start: i64,
+    stop: i64,
+  ) -> impl Stream<Item = Result<RedisValue, RedisError>> + Unpin
+  where
+    K: Into<RedisKey>,
+  {
+    commands::stream_response(self, move || {
+      Ok((RedisCommandKind::LRange, vec![
+        key.into().into(),
+        start.into(),
+        stop.into(),
+      ]))
+    })
+  }
+
+  /// Stream the fields and values of the hash stored at `key` as they're received, alternating between fields and
+  /// values.
+  ///
+  /// See [custom_stream](Self::custom_stream) for more information.
+  ///
+  /// <https://redis.io/commands/hgetall>
+  pub fn hgetall_stream<K>(&self, key: K) -> impl Stream<Item = Result<RedisValue, RedisError>> + Unpin
+  where
+    K: Into<RedisKey>,
+  {
+    commands::stream_response(self, move || Ok((RedisCommandKind::HGetAll, vec![key.into().into()])))
+  }
+
   /// Send a series of commands in a [pipeline](https://redis.io/docs/manual/pipelining/).
   pub fn pipeline(&self) -> Pipeline<RedisClient> {
     Pipeline::from(self.clone())
@@ -303,6 +391,36 @@ impl RedisClient {
 mod tests {
   #[cfg(feature = "sha-1")]
   use crate::util;
+  #[cfg(feature = "fake")]
+  use crate::{fake::Server, types::RespVersion};
+  #[cfg(feature = "mocks")]
+  use crate::{mocks::Echo, types::RedisValue};
+  #[cfg(any(feature = "fake", feature = "mocks"))]
+  use crate::{prelude::*, types::ReconnectPolicy};
+  #[cfg(feature = "fake")]
+  use futures::StreamExt;
+  #[cfg(any(feature = "fake", feature = "mocks"))]
+  use futures::TryStreamExt;
+  #[cfg(feature = "mocks")]
+  use std::sync::Arc;
+  #[cfg(feature = "fake")]
+  use std::time::Duration;
+
+  #[cfg(any(feature = "fake", feature = "mocks"))]
+  async fn create_client(config: RedisConfig) -> RedisClient {
+    let policy = ReconnectPolicy::new_constant(0, 10);
+    let client = RedisClient::new(config, None, None, Some(policy));
+    let _ = client.connect();
+    client.wait_for_connect().await.expect("Failed to connect");
+    client
+  }
+
+  #[cfg(feature = "fake")]
+  async fn create_list(client: &RedisClient, key: &str, len: usize) -> Vec<String> {
+    let values: Vec<String> = (0 .. len).map(|i| i.to_string()).collect();
+    let _: () = client.rpush(key, values.clone()).await.unwrap();
+    values
+  }
 
   #[test]
   #[cfg(feature = "sha-1")]
@@ -317,4 +435,72 @@ mod tests {
       "45c118f5de7c3fd3a4022135dc6acfb526f3c225"
     );
   }
+
+  #[tokio::test]
+  #[cfg(feature = "mocks")]
+  async fn should_stream_echo_mock_response() {
+    let client = create_client(RedisConfig {
+      mocks: Some(Arc::new(Echo)),
+      ..Default::default()
+    })
+    .await;
+
+    let actual: Vec<RedisValue> = client
+      .lrange_stream("foo", 0, -1)
+      .try_collect()
+      .await
+      .expect("Failed to stream LRANGE");
+
+    let expected: Vec<RedisValue> = vec!["foo".into(), 0.into(), (-1).into()];
+    assert_eq!(actual, expected);
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "fake")]
+  async fn should_stream_prefixed_list_elements() {
+    let server = Server::start().await.unwrap();
+    for version in [RespVersion::RESP2, RespVersion::RESP3] {
+      let client = create_client(RedisConfig {
+        version,
+        key_prefix: Some("app:".into()),
+        ..server.redis_config().unwrap()
+      })
+      .await;
+      let expected = create_list(&client, "foo", 200).await;
+
+      let actual: Vec<String> = client
+        .lrange_stream("foo", 0, -1)
+        .map(|value| value.and_then(|value| value.convert()))
+        .try_collect()
+        .await
+        .unwrap();
+      assert_eq!(actual, expected);
+      assert_eq!(client.llen::<usize, _>("foo").await.unwrap(), 200);
+
+      let _: () = client.del("foo").await.unwrap();
+      let _ = client.quit().await;
+    }
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "fake")]
+  async fn should_stop_reading_until_the_caller_reads_elements() {
+    let server = Server::start().await.unwrap();
+    let client = create_client(server.redis_config().unwrap()).await;
+    let expected = create_list(&client, "foo", 1000).await;
+
+    let mut elements = client.lrange_stream("foo", 0, -1);
+    let first: String = elements.next().await.unwrap().unwrap().convert().unwrap();
+    assert_eq!(first, expected[0]);
+
+    let pending = tokio::time::timeout(Duration::from_millis(100), client.llen::<usize, _>("foo")).await;
+    assert!(pending.is_err());
+
+    let mut actual = vec![first];
+    while let Some(element) = elements.try_next().await.unwrap() {
+      actual.push(element.convert().unwrap());
+    }
+    assert_eq!(actual, expected);
+    assert_eq!(client.llen::<usize, _>("foo").await.unwrap(), 1000);
+  }
 }
@@ -0,0 +1,459 @@
+use crate::{
+  commands,
+  error::RedisError,
+  interfaces::StreamsInterface,
+  types::{ClientUnblockFlag, RedisValue, StreamConsumerConfig, StreamEntry, XReadResponse, XReadValue, XID},
+};
+use futures::future::join_all;
+use std::{collections::HashMap, fmt, fmt::Formatter, future::Future, sync::Arc, time::Duration};
+use tokio::{sync::watch, task::JoinHandle, time::sleep};
+
+/// The amount of time, in milliseconds, to wait before reading again after an error.
+const ERROR_DELAY_MS: u64 = 1000;
+
+/// A worker that reads entries from a stream as part of a [consumer group](https://redis.io/docs/data-types/streams/#consumer-groups)
+/// and passes them to a handler function.
+///
+/// The consumer will:
+/// * Create the consumer group with `MKSTREAM` if it does not exist.
+/// * Claim entries that have been pending longer than `claim_min_idle` with `XAUTOCLAIM`. This includes entries
+///   delivered to other consumers that failed or exited before acknowledging them.
+/// * Read new entries with `XREADGROUP`, blocking for up to `block` milliseconds.
+/// * Call the handler with up to `concurrency` entries at a time, acknowledging each entry with `XACK` if the handler
+///   returns `Ok`.
+/// * Move claimed entries to the `dead_letter_stream`, if provided, once they have been delivered more than
+///   `max_deliveries` times.
+///
+/// Entries are not acknowledged if the handler returns an error, so they will be claimed and retried once they have
+/// been pending for `claim_min_idle` milliseconds.
+///
+/// Calling [stop](Self::stop) will exit once the handlers for the current entries finish. If the consumer is waiting
+/// on `XREADGROUP` the blocked connection is interrupted with `CLIENT UNBLOCK`. A read that was not yet written to
+/// the server when the consumer stopped may still block the connection for up to `block` milliseconds. A consumer
+/// cannot be restarted once it is stopped.
+///
+/// **Note: `XREADGROUP` blocks the connection while waiting for new entries, and stopping the consumer unblocks any
+/// blocked command on the client, so callers should use a dedicated client for each consumer.**
+///
+/// ```rust no_run
+/// # use fred::{clients::StreamConsumer, prelude::*, types::StreamConsumerConfig};
+/// async fn example(client: RedisClient) -> Result<(), RedisError> {
+///   let mut config = StreamConsumerConfig::new("events", "workers", "worker-1");
+///   config.concurrency = 10;
+///   config.dead_letter_stream = Some("events:dead".into());
+///
+///   let consumer = StreamConsumer::new(client, config);
+///   let task = consumer.spawn(|entry| async move {
+///     println!("Processing {} with fields {:?}", entry.id, entry.fields);
+///     Ok(())
+///   });
+///
+///   // ...
+///
+///   consumer.stop();
+///   task.await??;
+///   Ok(())
+/// }
+/// ```
+#[derive(Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "stream-consumer")))]
+pub struct StreamConsumer<C: StreamsInterface> {
+  client:   C,
+  config:   Arc<StreamConsumerConfig>,
+  shutdown: Arc<watch::Sender<bool>>,
+}
+
+impl<C: StreamsInterface> fmt::Debug for StreamConsumer<C> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    f.debug_struct("StreamConsumer")
+      .field("id", &self.client.inner().id)
+      .field("stream", &self.config.stream)
+      .field("group", &self.config.group)
+      .field("consumer", &self.config.consumer)
+      .finish()
+  }
+}
+
+impl<C: StreamsInterface + Sync> StreamConsumer<C> {
+  /// Create a new consumer from a client and the consumer group options.
+  pub fn new(client: C, config: StreamConsumerConfig) -> Self {
+    let (tx, _) = watch::channel(false);
+
+    StreamConsumer {
+      client,
+      config: Arc::new(config),
+      shutdown: Arc::new(tx),
+    }
+  }
+
+  /// Read the consumer group options.
+  pub fn config(&self) -> &StreamConsumerConfig {
+    &self.config
+  }
+
+  /// Signal the consumer to exit once the handlers for the current entries finish.
+  pub fn stop(&self) {
+    self.shutdown.send_replace(true);
+  }
+
+  /// Whether the consumer has been signaled to stop.
+  pub fn is_stopped(&self) -> bool {
+    *self.shutdown.borrow()
+  }
+
+  /// Read and process entries until [stop](Self::stop) is called.
+  ///
+  /// An error is returned if the consumer group cannot be created. Errors that occur while reading or acknowledging
+  /// entries are logged and the consumer will try again after a short delay.
+  pub async fn run<F, Fut>(&self, func: F) -> Result<(), RedisError>
+  where
+    F: Fn(StreamEntry) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<(), RedisError>> + Send,
+  {
+    let mut shutdown = self.shutdown.subscribe();
+    self.create_group().await?;
+    let mut cursor = String::from("0-0");
+    let inner = self.client.inner();
+
+    while !*shutdown.borrow() {
+      let result = tokio::select! {
+        result = self.next_entries(&mut cursor) => result,
+        _ = shutdown.changed() => {
+          self.unblock().await;
+          break;
+        },
+      };
+
+      match result {
+        Ok(entries) => self.process(&func, entries).await,
+        Err(error) => {
+          _warn!(inner, "Error reading from stream {:?}: {:?}", self.config.stream, error);
+
+          tokio::select! {
+            _ = sleep(Duration::from_millis(ERROR_DELAY_MS)) => {},
+            _ = shutdown.changed() => break,
+          };
+        },
+      }
+    }
+
+    _debug!(inner, "Stopping stream consumer {}", self.config.consumer);
+    Ok(())
+  }
+
+  /// Spawn a task that reads and processes entries until [stop](Self::stop) is called.
+  ///
+  /// See [run](Self::run) for more information.
+  pub fn spawn<F, Fut>(&self, func: F) -> JoinHandle<Result<(), RedisError>>
+  where
+    C: Clone + Send + 'static,
+    F: Fn(StreamEntry) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<(), RedisError>> + Send,
+  {
+    let consumer = self.clone();
+    tokio::spawn(async move { consumer.run(func).await })
+  }
+
+  /// Create the consumer group, ignoring the error if it already exists.
+  async fn create_group(&self) -> Result<(), RedisError> {
+    let result: Result<RedisValue, RedisError> = self
+      .client
+      .xgroup_create(
+        self.config.stream.clone(),
+        self.config.group.clone(),
+        self.config.group_start.clone(),
+        true,
+      )
+      .await;
+
+    match result {
+      Ok(_) => Ok(()),
+      Err(error) if error.details().starts_with("BUSYGROUP") => Ok(()),
+      Err(error) => Err(error),
+    }
+  }
+
+  /// Claim idle pending entries, or read new entries if there are no idle entries.
+  async fn next_entries(&self, cursor: &mut String) -> Result<Vec<StreamEntry>, RedisError> {
+    let claimed = self.claim_entries(cursor).await?;
+    if !claimed.is_empty() {
+      return Ok(claimed);
+    }
+
+    let response: XReadResponse<String, String, String, RedisValue> = self
+      .client
+      .xreadgroup_map(
+        self.config.group.clone(),
+        self.config.consumer.clone(),
+        Some(self.config.concurrency),
+        Some(self.config.block),
+        false,
+        self.config.stream.clone(),
+        XID::NewInGroup,
+      )
+      .await?;
+
+    Ok(
+      response
+        .into_iter()
+        .flat_map(|(_, entries)| entries)
+        .map(|(id, fields)| StreamEntry {
+          id,
+          fields,
+          deliveries: 1,
+        })
+        .collect(),
+    )
+  }
+
+  /// Claim idle pending entries, moving entries that were delivered too many times to the dead letter stream.
+  async fn claim_entries(&self, cursor: &mut String) -> Result<Vec<StreamEntry>, RedisError> {
+    let (next_cursor, claimed): (String, Vec<XReadValue<String, String, RedisValue>>) = self
+      .client
+      .xautoclaim_values(
+        self.config.stream.clone(),
+        self.config.group.clone(),
+        self.config.consumer.clone(),
+        self.config.claim_min_idle,
+        cursor.as_str(),
+        Some(self.config.concurrency),
+        false,
+      )
+      .await?;
+    *cursor = next_cursor;
+
+    // entries that were deleted from the stream are returned without any fields by Redis versions before 7.0
+    let (claimed, deleted): (Vec<_>, Vec<_>) = claimed.into_iter().partition(|(_, fields)| !fields.is_empty());
+    let mut finished: Vec<String> = deleted.into_iter().map(|(id, _)| id).collect();
+
+    let mut deliveries = self.deliveries(&claimed).await?;
+    let mut entries = Vec::with_capacity(claimed.len());
+    for (id, fields) in claimed.into_iter() {
+      let deliveries = match deliveries.remove(&id) {
+        Some(count) => count,
+        None => self.entry_deliveries(&id).await?,
+      };
+      let entry = StreamEntry { id, fields, deliveries };
+
+      if should_dead_letter(&self.config, &entry) {
+        self.dead_letter(&entry).await?;
+        finished.push(entry.id);
+      } else {
+        entries.push(entry);
+      }
+    }
+
+    if !finished.is_empty() {
+      self.ack(finished).await?;
+    }
+    Ok(entries)
+  }
+
+  /// Read the number of times each claimed entry has been delivered with one `XPENDING` range call.
+  ///
+  /// The range may include other entries pending for this consumer, so some claimed entries may be missing from the
+  /// result.
+  async fn deliveries(
+    &self,
+    claimed: &[XReadValue<String, String, RedisValue>],
+  ) -> Result<HashMap<String, u64>, RedisError> {
+    let (first, last) = match (claimed.first(), claimed.last()) {
+      (Some((first, _)), Some((last, _))) => (first.as_str(), last.as_str()),
+      _ => return Ok(HashMap::new()),
+    };
+    let count = claimed.len() as u64 + self.config.concurrency;
+
+    let pending: Vec<(String, String, u64, u64)> = self
+      .client
+      .xpending(
+        self.config.stream.clone(),
+        self.config.group.clone(),
+        (first, last, count, self.config.consumer.clone()),
+      )
+      .await?;
+
+    Ok(pending.into_iter().map(|(id, _, _, count)| (id, count)).collect())
+  }
+
+  /// Read the number of times a pending entry has been delivered.
+  async fn entry_deliveries(&self, id: &str) -> Result<u64, RedisError> {
+    let pending: Vec<(String, String, u64, u64)> = self
+      .client
+      .xpending(
+        self.config.stream.clone(),
+        self.config.group.clone(),
+        (id, id, 1, self.config.consumer.clone()),
+      )
+      .await?;
+
+    Ok(pending.first().map(|(_, _, _, count)| *count).unwrap_or(1))
+  }
+
+  /// Interrupt a blocking `XREADGROUP` after the consumer is stopped.
+  async fn unblock(&self) {
+    let inner = self.client.inner();
+    if !inner.backchannel.read().await.is_blocked() {
+      return;
+    }
+
+    _debug!(inner, "Unblocking stream consumer {}", self.config.consumer);
+    if let Err(error) = commands::client::unblock_self(&self.client, Some(ClientUnblockFlag::Error)).await {
+      _warn!(inner, "Error unblocking stream consumer: {:?}", error);
+    }
+  }
+
+  /// Copy an entry to the dead letter stream.
+  async fn dead_letter(&self, entry: &StreamEntry) -> Result<(), RedisError> {
+    let stream = match self.config.dead_letter_stream {
+      Some(ref stream) => stream.clone(),
+      None => return Ok(()),
+    };
+    let inner = self.client.inner();
+    _debug!(
+      inner,
+      "Moving stream entry {} to {:?} after {} deliveries.",
+      entry.id,
+      stream,
+      entry.deliveries
+    );
+
+    let fields: Vec<(String, RedisValue)> = entry
+      .fields
+      .iter()
+      .map(|(key, value)| (key.clone(), value.clone()))
+      .collect();
+    self.client.xadd(stream, false, None, XID::Auto, fields).await
+  }
+
+  async fn ack(&self, ids: Vec<String>) -> Result<(), RedisError> {
+    self
+      .client
+      .xack(self.config.stream.clone(), self.config.group.clone(), ids)
+      .await
+  }
+
+  /// Call the handler for each entry concurrently, acknowledging the entries that were processed successfully.
+  async fn process<F, Fut>(&self, func: &F, entries: Vec<StreamEntry>)
+  where
+    F: Fn(StreamEntry) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<(), RedisError>> + Send,
+  {
+    if entries.is_empty() {
+      return;
+    }
+    let inner = self.client.inner();
+
+    let results = join_all(entries.into_iter().map(|entry| {
+      let id = entry.id.clone();
+      let fut = func(entry);
+      async move { (id, fut.await) }
+    }))
+    .await;
+
+    let mut ids = Vec::with_capacity(results.len());
+    for (id, result) in results.into_iter() {
+      match result {
+        Ok(_) => ids.push(id),
+        Err(error) => _warn!(inner, "Error processing stream entry {}: {:?}", id, error),
+      }
+    }
+
+    if !ids.is_empty() {
+      if let Err(error) = self.ack(ids).await {
+        _warn!(inner, "Error acknowledging stream entries: {:?}", error);
+      }
+    }
+  }
+}
+
+/// Whether a claimed entry should be moved to the dead letter stream instead of being processed.
+fn should_dead_letter(config: &StreamConsumerConfig, entry: &StreamEntry) -> bool {
+  config.dead_letter_stream.is_some() && entry.deliveries > config.max_deliveries
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+
+  fn entry(deliveries: u64) -> StreamEntry {
+    StreamEntry {
+      id: "1-0".into(),
+      fields: HashMap::new(),
+      deliveries,
+    }
+  }
+
+  #[test]
+  fn should_dead_letter_after_max_deliveries() {
+    let mut config = StreamConsumerConfig::new("foo", "bar", "baz");
+    config.dead_letter_stream = Some("foo:dead".into());
+
+    assert!(!should_dead_letter(&config, &entry(config.max_deliveries)));
+    assert!(should_dead_letter(&config, &entry(config.max_deliveries + 1)));
+  }
+
+  #[test]
+  fn should_not_dead_letter_without_stream() {
+    let config = StreamConsumerConfig::new("foo", "bar", "baz");
+    assert!(!should_dead_letter(&config, &entry(config.max_deliveries + 1)));
+  }
+
+  #[cfg(feature = "mocks")]
+  #[tokio::test]
+  async fn should_read_claimed_deliveries_with_one_xpending_call() {
+    use crate::{
+      clients::RedisClient,
+      interfaces::ClientLike,
+      mocks::{MockCommand, Mocks},
+      types::RedisConfig,
+    };
+    use parking_lot::Mutex;
+
+    #[derive(Debug, Default)]
+    struct Streams {
+      commands: Mutex<Vec<String>>,
+    }
+
+    impl Mocks for Streams {
+      fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+        self.commands.lock().push(command.cmd.to_string());
+        let entry = |id: &str| RedisValue::Array(vec![id.into(), RedisValue::Array(vec!["a".into(), "b".into()])]);
+        let pending =
+          |id: &str, count: i64| RedisValue::Array(vec![id.into(), "baz".into(), 10.into(), count.into()]);
+
+        match &*command.cmd {
+          "XAUTOCLAIM" => Ok(RedisValue::Array(vec![
+            "0-0".into(),
+            RedisValue::Array(vec![entry("1-0"), entry("2-0"), entry("3-0")]),
+          ])),
+          "XPENDING" => Ok(RedisValue::Array(vec![
+            pending("1-0", 2),
+            pending("2-0", 7),
+            pending("3-0", 1),
+          ])),
+          _ => Ok(RedisValue::Integer(1)),
+        }
+      }
+    }
+
+    let mocks = Arc::new(Streams::default());
+    let config = RedisConfig {
+      mocks: Some(mocks.clone()),
+      ..Default::default()
+    };
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    client.wait_for_connect().await.unwrap();
+
+    let mut config = StreamConsumerConfig::new("foo", "bar", "baz");
+    config.dead_letter_stream = Some("foo:dead".into());
+    let consumer = StreamConsumer::new(client, config);
+    let mut cursor = "0-0".to_string();
+    let entries = consumer.claim_entries(&mut cursor).await.unwrap();
+
+    let deliveries: Vec<(String, u64)> = entries.into_iter().map(|entry| (entry.id, entry.deliveries)).collect();
+    assert_eq!(deliveries, vec![("1-0".to_string(), 2), ("3-0".to_string(), 1)]);
+    assert_eq!(*mocks.commands.lock(), vec!["XAUTOCLAIM", "XPENDING", "XADD", "XACK"]);
+  }
+}
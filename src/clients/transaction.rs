@@ -10,21 +10,28 @@ use crate::{
     responders::ResponseKind,
     utils as protocol_utils,
   },
-  types::{FromRedis, MultipleKeys, Options, RedisKey, Server},
+  trace,
+  types::{ClusterTransactionGroup, ClusterTransactionResult, FromRedis, MultipleKeys, Options, RedisKey, Server},
   utils,
 };
+use futures::future::join_all;
 use parking_lot::Mutex;
-use std::{collections::VecDeque, fmt, sync::Arc};
+use std::{
+  collections::VecDeque,
+  fmt,
+  sync::{atomic::AtomicBool, Arc},
+};
 use tokio::sync::oneshot::channel as oneshot_channel;
 
 /// A cheaply cloneable transaction block.
 #[derive(Clone)]
 pub struct Transaction {
-  id:        u64,
-  inner:     Arc<RedisClientInner>,
-  commands:  Arc<Mutex<VecDeque<RedisCommand>>>,
-  watched:   Arc<Mutex<VecDeque<RedisKey>>>,
-  hash_slot: Arc<Mutex<Option<u16>>>,
+  id:         u64,
+  inner:      Arc<RedisClientInner>,
+  commands:   Arc<Mutex<VecDeque<RedisCommand>>>,
+  watched:    Arc<Mutex<VecDeque<RedisKey>>>,
+  hash_slot:  Arc<Mutex<Option<u16>>>,
+  cross_slot: Arc<AtomicBool>,
 }
 
 impl fmt::Debug for Transaction {
@@ -34,6 +41,7 @@ impl fmt::Debug for Transaction {
       .field("id", &self.id)
       .field("length", &self.commands.lock().len())
       .field("hash_slot", &self.hash_slot.lock())
+      .field("cross_slot", &utils::read_bool_atomic(&self.cross_slot))
       .finish()
   }
 }
@@ -52,6 +60,11 @@ impl ClientLike for Transaction {
     &self.inner
   }
 
+  #[doc(hidden)]
+  fn is_buffered(&self) -> bool {
+    true
+  }
+
   #[doc(hidden)]
   fn send_command<C>(&self, command: C) -> Result<(), RedisError>
   where
@@ -101,17 +114,18 @@ impl Transaction {
   /// Create a new transaction.
   pub(crate) fn from_inner(inner: &Arc<RedisClientInner>) -> Self {
     Transaction {
-      inner:     inner.clone(),
-      commands:  Arc::new(Mutex::new(VecDeque::new())),
-      watched:   Arc::new(Mutex::new(VecDeque::new())),
-      hash_slot: Arc::new(Mutex::new(None)),
-      id:        utils::random_u64(u64::MAX),
+      inner:      inner.clone(),
+      commands:   Arc::new(Mutex::new(VecDeque::new())),
+      watched:    Arc::new(Mutex::new(VecDeque::new())),
+      hash_slot:  Arc::new(Mutex::new(None)),
+      cross_slot: Arc::new(AtomicBool::new(false)),
+      id:         utils::random_u64(u64::MAX),
     }
   }
 
   /// Check and update the hash slot for the transaction.
   pub(crate) fn update_hash_slot(&self, command: &RedisCommand) -> Result<(), RedisError> {
-    if !self.inner.config.server.is_clustered() {
+    if !self.inner.config.server.is_clustered() || utils::read_bool_atomic(&self.cross_slot) {
       return Ok(());
     }
 
@@ -179,6 +193,9 @@ impl Transaction {
   /// any of the commands prior to `EXEC`. This does **not** apply to `MOVED` or `ASK` errors, which wll be followed
   /// automatically.
   ///
+  /// If [allow_cross_slot](Self::allow_cross_slot) was called on a clustered client the queued commands must still
+  /// belong to one hash slot. Use [exec_cross_slot](Self::exec_cross_slot) to run commands from several hash slots.
+  ///
   /// <https://redis.io/commands/exec>
   ///
   /// ```rust no_run
@@ -200,7 +217,7 @@ impl Transaction {
   where
     R: FromRedis,
   {
-    let commands = {
+    let commands: VecDeque<RedisCommand> = {
       self
         .commands
         .lock()
@@ -209,12 +226,101 @@ impl Transaction {
         .collect()
     };
     let watched = { self.watched.lock().iter().cloned().collect() };
-    let hash_slot = utils::read_mutex(&self.hash_slot);
+    let hash_slot = if utils::read_bool_atomic(&self.cross_slot) {
+      single_hash_slot(&self.inner, &commands)?
+    } else {
+      utils::read_mutex(&self.hash_slot)
+    };
     exec(&self.inner, commands, watched, hash_slot, abort_on_error, self.id)
       .await?
       .convert()
   }
 
+  /// Allow commands with keys in different cluster hash slots.
+  ///
+  /// The queued commands are grouped by hash slot and [exec_cross_slot](Self::exec_cross_slot) runs a separate
+  /// `MULTI`/`EXEC` block for each group. Note that this does **not** provide atomicity across groups.
+  ///
+  /// [exec](Self::exec) returns an error if the queued commands belong to more than one hash slot.
+  pub fn allow_cross_slot(&self) {
+    utils::set_bool_atomic(&self.cross_slot, true);
+  }
+
+  /// Executes all previously queued commands with one transaction per cluster hash slot.
+  ///
+  /// Each group of commands runs in a separate `MULTI`/`EXEC` block on the cluster node that owns the hash slot.
+  /// Commands without keys run with the group of the first command that has a key. Keys provided to
+  /// [watch_before](Self::watch_before) are watched in the group for their hash slot, and must belong to the same
+  /// hash slot as at least one of the queued commands.
+  ///
+  /// The result from each group is returned separately, so callers can decide how to compensate when some groups
+  /// succeed and others fail. Centralized and sentinel clients will run one transaction.
+  ///
+  /// ```rust no_run
+  /// # use fred::prelude::*;
+  /// async fn example(client: &RedisClient) -> Result<(), RedisError> {
+  ///   let trx = client.multi();
+  ///   trx.allow_cross_slot();
+  ///   let _: () = trx.incr("foo").await?;
+  ///   let _: () = trx.incr("bar").await?;
+  ///
+  ///   let results = trx.exec_cross_slot(true).await?;
+  ///   for group in results.failed() {
+  ///     println!("Failed to run commands {:?}: {:?}", group.commands, group.result);
+  ///   }
+  ///   let foo: i64 = results.get(0)?;
+  ///   Ok(())
+  /// }
+  /// ```
+  pub async fn exec_cross_slot(&self, abort_on_error: bool) -> Result<ClusterTransactionResult, RedisError> {
+    let commands: Vec<RedisCommand> = {
+      self
+        .commands
+        .lock()
+        .iter()
+        .map(|cmd| cmd.duplicate(ResponseKind::Skip))
+        .collect()
+    };
+    let watched: Vec<RedisKey> = { self.watched.lock().iter().cloned().collect() };
+    let groups = group_by_hash_slot(&self.inner, commands, watched)?;
+    let inner = &self.inner;
+    _debug!(
+      inner,
+      "Sending cross slot transaction {} with {} groups.",
+      self.id,
+      groups.len()
+    );
+
+    let groups = join_all(groups.into_iter().map(|group| async move {
+      let id = utils::random_u64(u64::MAX);
+      let server = group.hash_slot.and_then(|slot| {
+        inner
+          .with_cluster_state(|state| Ok(state.get_server(slot).cloned()))
+          .ok()
+          .and_then(|server| server)
+      });
+      let result = exec(
+        inner,
+        group.commands,
+        group.watched,
+        group.hash_slot,
+        abort_on_error,
+        id,
+      )
+      .await;
+
+      ClusterTransactionGroup {
+        hash_slot: group.hash_slot,
+        commands: group.indexes,
+        server,
+        result,
+      }
+    }))
+    .await;
+
+    Ok(ClusterTransactionResult { groups })
+  }
+
   /// Send the `WATCH` command with the provided keys before starting the transaction.
   pub fn watch_before<K>(&self, keys: K)
   where
@@ -223,6 +329,30 @@ impl Transaction {
     self.watched.lock().extend(keys.into().inner());
   }
 
+  /// Pin the transaction to the hash slot of the provided keys.
+  pub(crate) fn pin_hash_slot(&self, keys: &MultipleKeys) {
+    if !self.inner.config.server.is_clustered() {
+      return;
+    }
+
+    let mut command = RedisCommand::new(RedisCommandKind::Watch, keys.clone().into_values());
+    command.key_prefix = self.inner.config.key_prefix.clone();
+    command.apply_key_prefix();
+    if let Some(slot) = command.cluster_hash() {
+      utils::set_mutex(&self.hash_slot, Some(slot));
+    }
+  }
+
+  /// Send the queued commands to the provided cluster node, if any, without retrying them on a new connection.
+  pub(crate) fn pin_connection(&self, server: Option<Server>) {
+    for command in self.commands.lock().iter_mut() {
+      command.attempts_remaining = 1;
+      if server.is_some() {
+        command.cluster_node = server.clone();
+      }
+    }
+  }
+
   /// Read the hash slot against which this transaction will run, if known.  
   pub fn hash_slot(&self) -> Option<u16> {
     utils::read_mutex(&self.hash_slot)
@@ -240,6 +370,96 @@ impl Transaction {
   }
 }
 
+/// Read the hash slot of the commands in a transaction that allows cross slot commands.
+///
+/// An error is returned if the commands belong to more than one hash slot.
+fn single_hash_slot(
+  inner: &Arc<RedisClientInner>,
+  commands: &VecDeque<RedisCommand>,
+) -> Result<Option<u16>, RedisError> {
+  if !inner.config.server.is_clustered() {
+    return Ok(None);
+  }
+
+  let mut slots = commands.iter().filter_map(|command| command.cluster_hash());
+  let hash_slot = slots.next();
+  if slots.any(|slot| Some(slot) != hash_slot) {
+    Err(RedisError::new(
+      RedisErrorKind::InvalidArgument,
+      "Transaction commands belong to more than one hash slot. Use `exec_cross_slot` instead.",
+    ))
+  } else {
+    Ok(hash_slot)
+  }
+}
+
+/// A group of commands in a cross slot transaction.
+struct SlotGroup {
+  hash_slot: Option<u16>,
+  indexes:   Vec<usize>,
+  commands:  VecDeque<RedisCommand>,
+  watched:   VecDeque<RedisKey>,
+}
+
+/// Group the commands and watched keys in a cross slot transaction by hash slot.
+fn group_by_hash_slot(
+  inner: &Arc<RedisClientInner>,
+  commands: Vec<RedisCommand>,
+  watched: Vec<RedisKey>,
+) -> Result<Vec<SlotGroup>, RedisError> {
+  let clustered = inner.config.server.is_clustered();
+  let default_slot = if clustered {
+    commands.iter().find_map(|command| command.cluster_hash())
+  } else {
+    None
+  };
+
+  let mut groups: Vec<SlotGroup> = Vec::new();
+  for (idx, command) in commands.into_iter().enumerate() {
+    let hash_slot = if clustered {
+      command.cluster_hash().or(default_slot)
+    } else {
+      None
+    };
+
+    match groups.iter_mut().find(|group| group.hash_slot == hash_slot) {
+      Some(group) => {
+        group.indexes.push(idx);
+        group.commands.push_back(command);
+      },
+      None => groups.push(SlotGroup {
+        hash_slot,
+        indexes: vec![idx],
+        commands: VecDeque::from([command]),
+        watched: VecDeque::new(),
+      }),
+    };
+  }
+
+  for key in watched.into_iter() {
+    let hash_slot = if clustered {
+      let mut command = RedisCommand::new(RedisCommandKind::Watch, vec![key.clone().into()]);
+      command.key_prefix = inner.config.key_prefix.clone();
+      command.apply_key_prefix();
+      command.cluster_hash()
+    } else {
+      None
+    };
+
+    match groups.iter_mut().find(|group| group.hash_slot == hash_slot) {
+      Some(group) => group.watched.push_back(key),
+      None => {
+        return Err(RedisError::new(
+          RedisErrorKind::InvalidArgument,
+          "Watched keys must belong to the same hash slot as a queued command.",
+        ))
+      },
+    };
+  }
+
+  Ok(groups)
+}
+
 async fn exec(
   inner: &Arc<RedisClientInner>,
   commands: VecDeque<RedisCommand>,
@@ -257,10 +477,12 @@ async fn exec(
   let mut multi = RedisCommand::new(RedisCommandKind::Multi, vec![]);
   trx_options.apply(&mut multi);
 
+  let span = trace::create_transaction_span(inner, id, commands.len());
   let commands: Vec<RedisCommand> = [multi]
     .into_iter()
     .chain(commands.into_iter())
     .map(|mut command| {
+      trace::set_child_command_span(inner, &span, &mut command);
       command.inherit_options(inner);
       command.response = ResponseKind::Skip;
       command.can_pipeline = false;
@@ -282,6 +504,8 @@ async fn exec(
     watch_cmd.can_pipeline = false;
     watch_cmd.skip_backpressure = true;
     watch_cmd.transaction_id = Some(id);
+    watch_cmd.key_prefix = inner.config.key_prefix.clone();
+    watch_cmd.apply_key_prefix();
     if let Some(hash_slot) = hash_slot.as_ref() {
       watch_cmd.hasher = ClusterHash::Custom(*hash_slot);
     }
@@ -305,6 +529,81 @@ async fn exec(
   let timeout_dur = trx_options.timeout.unwrap_or_else(|| inner.default_command_timeout());
 
   interfaces::send_to_router(inner, command)?;
-  let frame = utils::apply_timeout(rx, timeout_dur).await??;
+  let frame = trace::instrument(span, utils::apply_timeout(rx, timeout_dur)).await??;
   protocol_utils::frame_to_results(frame)
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::{ClusterRouting, ConnectionConfig, PerformanceConfig, RedisConfig, ServerConfig};
+
+  fn create_inner() -> Arc<RedisClientInner> {
+    let config = RedisConfig {
+      server: ServerConfig::new_clustered(vec![("localhost", 30001)]),
+      ..Default::default()
+    };
+
+    RedisClientInner::new(config, PerformanceConfig::default(), ConnectionConfig::default(), None)
+  }
+
+  fn incr(key: &str) -> RedisCommand {
+    RedisCommand::new(RedisCommandKind::Incr, vec![key.into()])
+  }
+
+  #[tokio::test]
+  async fn should_group_commands_by_hash_slot() {
+    let inner = create_inner();
+    let commands = vec![
+      incr("foo"),
+      incr("bar"),
+      RedisCommand::new(RedisCommandKind::Ping, vec![]),
+      incr("foo"),
+    ];
+    let groups = group_by_hash_slot(&inner, commands, vec!["bar".into()]).unwrap();
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].hash_slot, Some(ClusterRouting::hash_key(b"foo")));
+    assert_eq!(groups[0].indexes, vec![0, 2, 3]);
+    assert!(groups[0].watched.is_empty());
+    assert_eq!(groups[1].hash_slot, Some(ClusterRouting::hash_key(b"bar")));
+    assert_eq!(groups[1].indexes, vec![1]);
+    assert_eq!(groups[1].watched, VecDeque::from([RedisKey::from("bar")]));
+  }
+
+  #[tokio::test]
+  async fn should_error_with_watched_key_in_unused_hash_slot() {
+    let inner = create_inner();
+    let result = group_by_hash_slot(&inner, vec![incr("foo")], vec!["bar".into()]);
+
+    assert_eq!(*result.err().unwrap().kind(), RedisErrorKind::InvalidArgument);
+  }
+
+  #[tokio::test]
+  async fn should_error_on_exec_with_cross_slot_commands() {
+    let inner = create_inner();
+    let trx = Transaction::from_inner(&inner);
+    trx.allow_cross_slot();
+    let _: () = trx.incr("foo").await.unwrap();
+    let _: () = trx.incr("bar").await.unwrap();
+
+    let error = trx.exec::<RedisValue>(true).await.unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::InvalidArgument);
+    assert!(error.details().contains("exec_cross_slot"));
+  }
+
+  #[tokio::test]
+  async fn should_read_single_hash_slot_with_cross_slot_enabled() {
+    let inner = create_inner();
+    let commands = VecDeque::from([
+      incr("foo"),
+      RedisCommand::new(RedisCommandKind::Ping, vec![]),
+      incr("foo"),
+    ]);
+
+    assert_eq!(
+      single_hash_slot(&inner, &commands).unwrap(),
+      Some(ClusterRouting::hash_key(b"foo"))
+    );
+  }
+}
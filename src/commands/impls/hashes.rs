@@ -32,13 +32,13 @@ pub async fn hget<C: ClientLike>(client: &C, key: RedisKey, field: RedisKey) ->
   args_value_cmd(client, RedisCommandKind::HGet, args).await
 }
 
-pub async fn hgetall<C: ClientLike>(client: &C, key: RedisKey) -> Result<RedisValue, RedisError> {
+pub async fn hgetall<C: ClientLike>(client: &C, key: RedisKey) -> Result<Resp3Frame, RedisError> {
   let frame = utils::request_response(client, move || Ok((RedisCommandKind::HGetAll, vec![key.into()]))).await?;
 
   if protocol_utils::frame_is_queued(&frame) {
-    protocol_utils::frame_to_results(frame)
+    Ok(frame)
   } else {
-    Ok(RedisValue::Map(protocol_utils::frame_to_map(frame)?))
+    protocol_utils::array_frame_to_map_frame(frame)
   }
 }
 
@@ -62,17 +62,16 @@ pub async fn hincrbyfloat<C: ClientLike>(
   args_value_cmd(client, RedisCommandKind::HIncrByFloat, args).await
 }
 
-pub async fn hkeys<C: ClientLike>(client: &C, key: RedisKey) -> Result<RedisValue, RedisError> {
-  let frame = utils::request_response(client, move || Ok((RedisCommandKind::HKeys, vec![key.into()]))).await?;
-  protocol_utils::frame_to_results(frame)
+pub async fn hkeys<C: ClientLike>(client: &C, key: RedisKey) -> Result<Resp3Frame, RedisError> {
+  one_arg_frame_cmd(client, RedisCommandKind::HKeys, key.into()).await
 }
 
 pub async fn hlen<C: ClientLike>(client: &C, key: RedisKey) -> Result<RedisValue, RedisError> {
   one_arg_value_cmd(client, RedisCommandKind::HLen, key.into()).await
 }
 
-pub async fn hmget<C: ClientLike>(client: &C, key: RedisKey, fields: MultipleKeys) -> Result<RedisValue, RedisError> {
-  let frame = utils::request_response(client, move || {
+pub async fn hmget<C: ClientLike>(client: &C, key: RedisKey, fields: MultipleKeys) -> Result<Resp3Frame, RedisError> {
+  utils::request_response(client, move || {
     let mut args = Vec::with_capacity(1 + fields.len());
     args.push(key.into());
 
@@ -81,9 +80,7 @@ pub async fn hmget<C: ClientLike>(client: &C, key: RedisKey, fields: MultipleKey
     }
     Ok((RedisCommandKind::HMGet, args))
   })
-  .await?;
-
-  protocol_utils::frame_to_results(frame)
+  .await
 }
 
 pub async fn hmset<C: ClientLike>(client: &C, key: RedisKey, values: RedisMap) -> Result<RedisValue, RedisError> {
@@ -182,6 +179,6 @@ pub async fn hstrlen<C: ClientLike>(client: &C, key: RedisKey, field: RedisKey)
   protocol_utils::frame_to_results(frame)
 }
 
-pub async fn hvals<C: ClientLike>(client: &C, key: RedisKey) -> Result<RedisValue, RedisError> {
-  one_arg_values_cmd(client, RedisCommandKind::HVals, key.into()).await
+pub async fn hvals<C: ClientLike>(client: &C, key: RedisKey) -> Result<Resp3Frame, RedisError> {
+  one_arg_frame_cmd(client, RedisCommandKind::HVals, key.into()).await
 }
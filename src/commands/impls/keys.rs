@@ -5,10 +5,28 @@ use crate::{
   types::*,
   utils,
 };
+use bytes_utils::Str;
 use std::convert::TryInto;
 
+#[cfg(feature = "serde-json")]
+use bytes::Bytes;
+#[cfg(feature = "serde-json")]
+use futures::Future;
+#[cfg(feature = "serde-json")]
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(feature = "serde-json")]
+use serde_json::Value;
+#[cfg(feature = "serde-json")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "serde-json")]
+use tokio::time::sleep;
+
 value_cmd!(randomkey, Randomkey);
 
+pub async fn keys<C: ClientLike>(client: &C, pattern: Str) -> Result<RedisValue, RedisError> {
+  one_arg_values_cmd(client, RedisCommandKind::Keys, pattern.into()).await
+}
+
 pub async fn get<C: ClientLike>(client: &C, key: RedisKey) -> Result<RedisValue, RedisError> {
   one_arg_values_cmd(client, RedisCommandKind::Get, key.into()).await
 }
@@ -260,10 +278,10 @@ pub async fn strlen<C: ClientLike>(client: &C, key: RedisKey) -> Result<RedisVal
   one_arg_value_cmd(client, RedisCommandKind::Strlen, key.into()).await
 }
 
-pub async fn mget<C: ClientLike>(client: &C, keys: MultipleKeys) -> Result<RedisValue, RedisError> {
+pub async fn mget<C: ClientLike>(client: &C, keys: MultipleKeys) -> Result<Resp3Frame, RedisError> {
   utils::check_empty_keys(&keys)?;
 
-  let frame = utils::request_response(client, move || {
+  utils::request_response(client, move || {
     let mut args = Vec::with_capacity(keys.len());
 
     for key in keys.inner().into_iter() {
@@ -272,9 +290,7 @@ pub async fn mget<C: ClientLike>(client: &C, keys: MultipleKeys) -> Result<Redis
 
     Ok((RedisCommandKind::Mget, args))
   })
-  .await?;
-
-  protocol_utils::frame_to_results(frame)
+  .await
 }
 
 pub async fn mset<C: ClientLike>(client: &C, values: RedisMap) -> Result<RedisValue, RedisError> {
@@ -391,3 +407,281 @@ pub async fn lcs<C: ClientLike>(
 
   protocol_utils::frame_to_results(frame)
 }
+
+/// The amount of time, in milliseconds, to wait before reading a value again while another caller holds the lease.
+#[cfg(feature = "serde-json")]
+const CACHE_LEASE_POLL_MS: u64 = 50;
+/// The number of random characters in the token that identifies the holder of a cache lease.
+#[cfg(feature = "serde-json")]
+const CACHE_LEASE_TOKEN_LEN: usize = 32;
+/// Delete the lease key if it contains the provided token.
+#[cfg(feature = "serde-json")]
+const CACHE_LEASE_RELEASE_LUA: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+/// A cached value and the metadata used to refresh it early.
+#[cfg(feature = "serde-json")]
+struct CacheEntry<T> {
+  value:   T,
+  /// The time it took to compute the value, in milliseconds.
+  delta:   u64,
+  /// The unix timestamp, in milliseconds, when the value is no longer fresh.
+  expires: u64,
+}
+
+#[cfg(feature = "serde-json")]
+fn cache_lease_key(key: &RedisKey) -> RedisKey {
+  let mut lease = Vec::with_capacity(key.as_bytes().len() + 6);
+  lease.extend_from_slice(key.as_bytes());
+  lease.extend_from_slice(b":lease");
+  RedisKey::from(Bytes::from(lease))
+}
+
+#[cfg(feature = "serde-json")]
+async fn read_cache_entry<C, T>(client: &C, key: &RedisKey) -> Result<Option<CacheEntry<T>>, RedisError>
+where
+  C: ClientLike,
+  T: DeserializeOwned,
+{
+  let value = get(client, key.clone()).await?;
+  if value.is_null() {
+    return Ok(None);
+  }
+  let value = match value.as_str() {
+    Some(value) => value,
+    None => return Err(RedisError::new(RedisErrorKind::Parse, "Invalid cache entry.")),
+  };
+
+  let mut entry: Value = serde_json::from_str(&value)?;
+  match (entry["delta"].as_u64(), entry["expires"].as_u64()) {
+    (Some(delta), Some(expires)) => Ok(Some(CacheEntry {
+      value: serde_json::from_value(entry["value"].take())?,
+      delta,
+      expires,
+    })),
+    _ => Err(RedisError::new(RedisErrorKind::Parse, "Invalid cache entry.")),
+  }
+}
+
+#[cfg(feature = "serde-json")]
+async fn write_cache_entry<C: ClientLike>(
+  client: &C,
+  key: RedisKey,
+  value: Value,
+  delta: u64,
+  options: &CacheOptions,
+) -> Result<(), RedisError> {
+  let ttl = utils::jittered_ttl(options.ttl.as_millis() as u64, options.jitter);
+  let entry = serde_json::json!({
+    "value": value,
+    "delta": delta,
+    "expires": utils::unix_ms() + ttl,
+  });
+  let expiration = Expiration::PX((ttl + options.stale_ttl.as_millis() as u64) as i64);
+
+  set(client, key, entry.to_string().into(), Some(expiration), None, false)
+    .await
+    .map(|_| ())
+}
+
+/// Try to take the lease that allows the caller to compute a value with `SET NX PX`, returning the lease key and the
+/// random token stored in it if the lease was taken.
+#[cfg(feature = "serde-json")]
+async fn take_cache_lease<C: ClientLike>(
+  client: &C,
+  lease: &RedisKey,
+  options: &CacheOptions,
+) -> Result<Option<(RedisKey, String)>, RedisError> {
+  let token = utils::random_string(CACHE_LEASE_TOKEN_LEN);
+  let expiration = Expiration::PX((options.lease_ttl.as_millis() as i64).max(1));
+  let value = set(
+    client,
+    lease.clone(),
+    token.as_str().into(),
+    Some(expiration),
+    Some(SetOptions::NX),
+    false,
+  )
+  .await?;
+
+  Ok(if value.is_null() {
+    None
+  } else {
+    Some((lease.clone(), token))
+  })
+}
+
+/// Delete the lease key if it still contains `token`, so a caller whose lease expired does not release a lease taken
+/// by another caller.
+#[cfg(feature = "serde-json")]
+async fn release_cache_lease<C: ClientLike>(client: &C, lease: RedisKey, token: String) -> Result<(), RedisError> {
+  let keys: MultipleKeys = lease.into();
+  let args: MultipleValues = token.into();
+
+  super::lua::eval(client, Str::from_static(CACHE_LEASE_RELEASE_LUA), keys, args)
+    .await
+    .map(|_| ())
+}
+
+/// Compute and cache a value, releasing the lease if one is provided.
+#[cfg(feature = "serde-json")]
+async fn compute_cache_entry<C, T, F, Fut>(
+  client: &C,
+  key: RedisKey,
+  lease: Option<(RedisKey, String)>,
+  options: &CacheOptions,
+  func: F,
+) -> Result<T, RedisError>
+where
+  C: ClientLike,
+  T: Serialize,
+  F: FnOnce() -> Fut,
+  Fut: Future<Output = Result<T, RedisError>>,
+{
+  let started = Instant::now();
+  let result = func().await;
+  let delta = started.elapsed().as_millis() as u64;
+
+  let inner = client.inner();
+  // serialize the value before writing it so `T` does not need to be `Sync`
+  let serialized = result.as_ref().ok().map(serde_json::to_value);
+  if let Some(value) = serialized {
+    let written = match value {
+      Ok(value) => write_cache_entry(client, key, value, delta, options).await,
+      Err(error) => Err(error.into()),
+    };
+
+    if let Err(error) = written {
+      _warn!(inner, "Error writing cache entry: {:?}", error);
+    }
+  }
+  if let Some((lease, token)) = lease {
+    if let Err(error) = release_cache_lease(client, lease, token).await {
+      _warn!(inner, "Error releasing cache lease: {:?}", error);
+    }
+  }
+
+  result
+}
+
+#[cfg(feature = "serde-json")]
+pub async fn get_or_compute<C, T, F, Fut>(
+  client: &C,
+  key: RedisKey,
+  options: CacheOptions,
+  func: F,
+) -> Result<T, RedisError>
+where
+  C: ClientLike,
+  T: Serialize + DeserializeOwned + Send,
+  F: FnOnce() -> Fut + Send,
+  Fut: Future<Output = Result<T, RedisError>> + Send,
+{
+  if options.ttl.as_millis() == 0 {
+    return Err(RedisError::new(
+      RedisErrorKind::InvalidArgument,
+      "Cache TTL must be greater than zero.",
+    ));
+  }
+  let lease = cache_lease_key(&key);
+  let started = Instant::now();
+
+  loop {
+    match read_cache_entry::<C, T>(client, &key).await? {
+      Some(entry) => {
+        // this is always true once the value expires, so stale values are also refreshed here
+        let random = 1.0 - rand::random::<f64>();
+        let now = utils::unix_ms();
+        if !utils::should_refresh_early(now, entry.expires, entry.delta, options.beta, random) {
+          return Ok(entry.value);
+        }
+        // serve the current value while another caller refreshes it
+        let lease = match take_cache_lease(client, &lease, &options).await? {
+          Some(lease) => lease,
+          None => return Ok(entry.value),
+        };
+
+        return match compute_cache_entry(client, key, Some(lease), &options, func).await {
+          Ok(value) => Ok(value),
+          Err(error) => {
+            let inner = client.inner();
+            _warn!(inner, "Error refreshing cache entry: {:?}", error);
+            Ok(entry.value)
+          },
+        };
+      },
+      None => {
+        if let Some(lease) = take_cache_lease(client, &lease, &options).await? {
+          return compute_cache_entry(client, key, Some(lease), &options, func).await;
+        }
+        if started.elapsed() >= options.lease_ttl {
+          return compute_cache_entry(client, key, None, &options, func).await;
+        }
+
+        sleep(Duration::from_millis(CACHE_LEASE_POLL_MS)).await;
+      },
+    }
+  }
+}
+
+#[cfg(all(test, feature = "mocks", feature = "serde-json"))]
+mod tests {
+  use crate::{
+    clients::RedisClient,
+    error::RedisError,
+    interfaces::{ClientLike, KeysInterface},
+    mocks::{Buffer, MockCommand, Mocks},
+    types::{RedisConfig, RedisValue},
+  };
+  use std::{sync::Arc, time::Duration};
+
+  #[derive(Debug)]
+  struct Lease(Buffer);
+
+  impl Mocks for Lease {
+    fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+      let response = match &*command.cmd {
+        "GET" => RedisValue::Null,
+        "SET" => "OK".into(),
+        _ => 1.into(),
+      };
+      self.0.push_back(command);
+      Ok(response)
+    }
+  }
+
+  #[tokio::test]
+  async fn should_release_cache_lease_with_token() {
+    let lease = Arc::new(Lease(Buffer::new()));
+    let client = RedisClient::new(
+      RedisConfig {
+        mocks: Some(lease.clone()),
+        ..Default::default()
+      },
+      None,
+      None,
+      None,
+    );
+    let _ = client.connect();
+    client.wait_for_connect().await.expect("Failed to connect");
+
+    let value: u64 = client
+      .get_or_compute("foo", Duration::from_secs(10), || async { Ok(1) })
+      .await
+      .expect("Failed to compute value");
+    assert_eq!(value, 1);
+
+    let commands = lease.0.take();
+    let cmds: Vec<&str> = commands.iter().map(|command| &*command.cmd).collect();
+    assert_eq!(cmds, vec!["GET", "SET", "SET", "EVAL"]);
+
+    let token = commands[1].args[1].clone();
+    assert_eq!(commands[1].args[0], "foo:lease".as_bytes().into());
+    assert_eq!(commands[3].args[2], "foo:lease".as_bytes().into());
+    assert_eq!(commands[3].args[3], token);
+  }
+}
@@ -270,9 +270,9 @@ pub async fn lrange<C: ClientLike>(
   key: RedisKey,
   start: i64,
   stop: i64,
-) -> Result<RedisValue, RedisError> {
+) -> Result<Resp3Frame, RedisError> {
   let (key, start, stop) = (key.into(), start.into(), stop.into());
-  args_values_cmd(client, RedisCommandKind::LRange, vec![key, start, stop]).await
+  args_frame_cmd(client, RedisCommandKind::LRange, vec![key, start, stop]).await
 }
 
 pub async fn lrem<C: ClientLike>(
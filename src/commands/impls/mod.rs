@@ -5,6 +5,8 @@ use crate::{
   types::RedisValue,
   utils,
 };
+use futures::Stream;
+use redis_protocol::resp3::types::Frame as Resp3Frame;
 
 pub static MATCH: &str = "MATCH";
 pub static COUNT: &str = "COUNT";
@@ -115,6 +117,16 @@ pub async fn one_arg_values_cmd<C: ClientLike>(
   protocol_utils::frame_to_results(frame)
 }
 
+/// A function that issues a command that only takes one argument and returns the response frame without converting
+/// it to a `RedisValue`.
+pub async fn one_arg_frame_cmd<C: ClientLike>(
+  client: &C,
+  kind: RedisCommandKind,
+  arg: RedisValue,
+) -> Result<Resp3Frame, RedisError> {
+  utils::request_response(client, move || Ok((kind, vec![arg]))).await
+}
+
 /// A function that issues a command that only takes one argument and expects an OK response - returning `()` to the
 /// caller.
 pub async fn one_arg_ok_cmd<C: ClientLike>(
@@ -150,6 +162,28 @@ pub async fn args_values_cmd<C: ClientLike>(
   protocol_utils::frame_to_results(frame)
 }
 
+/// A function that issues a command that takes any number of arguments and returns the response frame without
+/// converting it to a `RedisValue`.
+pub async fn args_frame_cmd<C: ClientLike>(
+  client: &C,
+  kind: RedisCommandKind,
+  args: Vec<RedisValue>,
+) -> Result<Resp3Frame, RedisError> {
+  utils::request_response(client, move || Ok((kind, args))).await
+}
+
+/// A function that issues a command and streams the elements of the response to the caller.
+///
+/// The command is written at most once since elements may have already been sent to the caller when the connection
+/// closes.
+pub fn stream_response<C, F>(client: &C, func: F) -> impl Stream<Item = Result<RedisValue, RedisError>> + Unpin
+where
+  C: ClientLike,
+  F: FnOnce() -> Result<(RedisCommandKind, Vec<RedisValue>), RedisError>,
+{
+  utils::stream_request(client, func)
+}
+
 /// A function that issues a command that takes any number of arguments and expects an OK response - returning `()` to
 /// the caller.
 pub async fn args_ok_cmd<C: ClientLike>(
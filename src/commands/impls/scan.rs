@@ -48,6 +48,7 @@ pub fn scan_cluster(
   r#type: Option<ScanType>,
 ) -> impl Stream<Item = Result<ScanResult, RedisError>> {
   let (tx, rx) = unbounded_channel();
+  let pattern = utils::add_pattern_prefix(inner.config.key_prefix.as_ref(), pattern);
 
   let hash_slots = inner.with_cluster_state(|state| Ok(state.unique_hash_slots()));
   let hash_slots = match hash_slots {
@@ -100,6 +101,7 @@ pub fn scan(
   server: Option<Server>,
 ) -> impl Stream<Item = Result<ScanResult, RedisError>> {
   let (tx, rx) = unbounded_channel();
+  let pattern = utils::add_pattern_prefix(inner.config.key_prefix.as_ref(), pattern);
 
   let hash_slot = if inner.config.server.is_clustered() {
     if utils::clustered_scan_pattern_has_hash_tag(inner, &pattern) {
@@ -155,7 +157,9 @@ pub fn hscan(
     cursor_idx: 1,
     args,
   });
-  let command: RedisCommand = (RedisCommandKind::Hscan, Vec::new(), response).into();
+  let mut command: RedisCommand = (RedisCommandKind::Hscan, Vec::new(), response).into();
+  command.key_prefix = inner.config.key_prefix.clone();
+  command.apply_key_prefix();
 
   if let Err(e) = interfaces::default_send_command(inner, command) {
     early_error(&tx, e);
@@ -183,7 +187,9 @@ pub fn sscan(
     cursor_idx: 1,
     args,
   });
-  let command: RedisCommand = (RedisCommandKind::Sscan, Vec::new(), response).into();
+  let mut command: RedisCommand = (RedisCommandKind::Sscan, Vec::new(), response).into();
+  command.key_prefix = inner.config.key_prefix.clone();
+  command.apply_key_prefix();
 
   if let Err(e) = interfaces::default_send_command(inner, command) {
     early_error(&tx, e);
@@ -211,7 +217,9 @@ pub fn zscan(
     cursor_idx: 1,
     args,
   });
-  let command: RedisCommand = (RedisCommandKind::Zscan, Vec::new(), response).into();
+  let mut command: RedisCommand = (RedisCommandKind::Zscan, Vec::new(), response).into();
+  command.key_prefix = inner.config.key_prefix.clone();
+  command.apply_key_prefix();
 
   if let Err(e) = interfaces::default_send_command(inner, command) {
     early_error(&tx, e);
@@ -208,14 +208,6 @@ pub async fn auth<C: ClientLike>(client: &C, username: Option<String>, password:
   }
 }
 
-pub async fn custom<C: ClientLike>(
-  client: &C,
-  cmd: CustomCommand,
-  args: Vec<RedisValue>,
-) -> Result<RedisValue, RedisError> {
-  args_values_cmd(client, RedisCommandKind::_Custom(cmd), args).await
-}
-
 pub async fn custom_raw<C: ClientLike>(
   client: &C,
   cmd: CustomCommand,
@@ -124,8 +124,8 @@ pub async fn smismember<C: ClientLike>(
   protocol_utils::frame_to_results(frame)
 }
 
-pub async fn smembers<C: ClientLike>(client: &C, key: RedisKey) -> Result<RedisValue, RedisError> {
-  one_arg_values_cmd(client, RedisCommandKind::Smembers, key.into()).await
+pub async fn smembers<C: ClientLike>(client: &C, key: RedisKey) -> Result<Resp3Frame, RedisError> {
+  one_arg_frame_cmd(client, RedisCommandKind::Smembers, key.into()).await
 }
 
 pub async fn smove<C: ClientLike>(
@@ -18,7 +18,7 @@ pub trait HashesInterface: ClientLike + Sized {
     K: Into<RedisKey> + Send,
   {
     into!(key);
-    commands::hashes::hgetall(self, key).await?.convert()
+    R::from_frame(commands::hashes::hgetall(self, key).await?)
   }
 
   /// Removes the specified fields from the hash stored at `key`.
@@ -98,7 +98,7 @@ pub trait HashesInterface: ClientLike + Sized {
     K: Into<RedisKey> + Send,
   {
     into!(key);
-    commands::hashes::hkeys(self, key).await?.convert()
+    R::from_frame(commands::hashes::hkeys(self, key).await?)
   }
 
   /// Returns the number of fields contained in the hash stored at `key`.
@@ -123,7 +123,7 @@ pub trait HashesInterface: ClientLike + Sized {
     F: Into<MultipleKeys> + Send,
   {
     into!(key, fields);
-    commands::hashes::hmget(self, key, fields).await?.convert()
+    R::from_frame(commands::hashes::hmget(self, key, fields).await?)
   }
 
   /// Sets the specified fields to their respective values in the hash stored at `key`.
@@ -208,6 +208,6 @@ pub trait HashesInterface: ClientLike + Sized {
     K: Into<RedisKey> + Send,
   {
     into!(key);
-    commands::hashes::hvals(self, key).await?.convert()
+    R::from_frame(commands::hashes::hvals(self, key).await?)
   }
 }
@@ -4,8 +4,16 @@ use crate::{
   interfaces::{ClientLike, RedisResult},
   types::{Expiration, FromRedis, MultipleKeys, RedisKey, RedisMap, RedisValue, SetOptions},
 };
+use bytes_utils::Str;
 use std::convert::TryInto;
 
+#[cfg(feature = "serde-json")]
+use crate::types::CacheOptions;
+#[cfg(feature = "serde-json")]
+use futures::Future;
+#[cfg(feature = "serde-json")]
+use serde::{de::DeserializeOwned, Serialize};
+
 /// Functions that implement the generic [keys](https://redis.io/commands#generic) interface.
 #[async_trait]
 pub trait KeysInterface: ClientLike + Sized {
@@ -37,6 +45,22 @@ pub trait KeysInterface: ClientLike + Sized {
     commands::keys::randomkey(self).await?.convert()
   }
 
+  /// Return all the keys matching `pattern`.
+  ///
+  /// If a key prefix is configured it is escaped and added to the pattern, and removed from the returned keys. The
+  /// prefix is not removed from `KEYS` results inside a transaction. `KEYS` blocks the server while it runs, so
+  /// [scan](crate::clients::RedisClient::scan) should be used on production servers.
+  ///
+  /// <https://redis.io/commands/keys>
+  async fn keys<R, P>(&self, pattern: P) -> RedisResult<R>
+  where
+    R: FromRedis,
+    P: Into<Str> + Send,
+  {
+    into!(pattern);
+    commands::keys::keys(self, pattern).await?.convert()
+  }
+
   /// This command copies the value stored at the source key to the destination key.
   ///
   /// <https://redis.io/commands/copy>
@@ -282,7 +306,7 @@ pub trait KeysInterface: ClientLike + Sized {
     K: Into<MultipleKeys> + Send,
   {
     into!(keys);
-    commands::keys::mget(self, keys).await?.convert()
+    R::from_frame(commands::keys::mget(self, keys).await?)
   }
 
   /// Sets the given keys to their respective values.
@@ -488,4 +512,48 @@ pub trait KeysInterface: ClientLike + Sized {
       .await?
       .convert()
   }
+
+  /// Read a cached value with `GET`, or compute and cache the value with `func` if it does not exist.
+  ///
+  /// Values are stored as JSON along with the time it took to compute them. To avoid cache stampedes:
+  ///
+  /// * Only the caller that takes a short lease with `SET NX PX` computes a missing value. Other callers wait for the
+  ///   value to be written, up to `lease_ttl`, before computing it themselves.
+  /// * Expired values are kept for `stale_ttl` and served to other callers while the lease holder refreshes them.
+  /// * Values are refreshed early with probability increasing as they approach expiration, according to the [XFetch](https://cseweb.ucsd.edu/~avattani/papers/cache_stampede.pdf)
+  ///   algorithm.
+  /// * A random `jitter` is added to the expiration of each value.
+  ///
+  /// If `func` fails while refreshing an existing value the existing value is returned. Errors writing the computed
+  /// value are logged and ignored.
+  ///
+  /// ```rust no_run
+  /// # use fred::{prelude::*, types::CacheOptions};
+  /// # use std::time::Duration;
+  /// async fn example(client: &RedisClient) -> Result<(), RedisError> {
+  ///   let mut options = CacheOptions::new(Duration::from_secs(60));
+  ///   options.stale_ttl = Duration::from_secs(10);
+  ///
+  ///   let value: Vec<String> = client
+  ///     .get_or_compute("foo", options, || async {
+  ///       // read from the database, etc
+  ///       Ok(vec!["a".to_string(), "b".to_string()])
+  ///     })
+  ///     .await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  #[cfg(feature = "serde-json")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+  async fn get_or_compute<T, K, O, F, Fut>(&self, key: K, options: O, func: F) -> RedisResult<T>
+  where
+    T: Serialize + DeserializeOwned + Send,
+    K: Into<RedisKey> + Send,
+    O: Into<CacheOptions> + Send,
+    F: FnOnce() -> Fut + Send,
+    Fut: Future<Output = RedisResult<T>> + Send,
+  {
+    into!(key, options);
+    commands::keys::get_or_compute(self, key, options, func).await
+  }
 }
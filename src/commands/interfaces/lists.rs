@@ -225,7 +225,7 @@ pub trait ListInterface: ClientLike + Sized {
     K: Into<RedisKey> + Send,
   {
     into!(key);
-    commands::lists::lrange(self, key, start, stop).await?.convert()
+    R::from_frame(commands::lists::lrange(self, key, start, stop).await?)
   }
 
   /// Removes the first `count` occurrences of elements equal to `element` from the list stored at `key`.
@@ -18,6 +18,17 @@ pub trait MetricsInterface: ClientLike + Sized {
     self.inner().counters.take_redelivery_count()
   }
 
+  /// Read the number of commands that were not retried after a connection error due to their
+  /// [RetryPolicy](crate::types::RetryPolicy).
+  fn read_retry_skipped_count(&self) -> usize {
+    self.inner().counters.read_retry_skipped_count()
+  }
+
+  /// Read and reset the number of commands that were not retried due to their retry policy.
+  fn take_retry_skipped_count(&self) -> usize {
+    self.inner().counters.take_retry_skipped_count()
+  }
+
   /// Read the number of buffered commands that have not yet been sent to the server.
   fn command_queue_len(&self) -> usize {
     self.inner().counters.read_cmd_buffer_len()
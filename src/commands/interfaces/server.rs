@@ -31,6 +31,9 @@ pub trait AuthInterface: ClientLike {
   ///
   /// If running against clustered servers this function will issue the HELLO command to each server concurrently.
   ///
+  /// When the client uses [RespNegotiation::Auto](crate::types::RespNegotiation::Auto) the new version is also used
+  /// for new connections until the client reconnects and negotiates again.
+  ///
   /// <https://redis.io/commands/hello>
   async fn hello(&self, version: RespVersion, auth: Option<(String, String)>) -> RedisResult<()> {
     commands::server::hello(self, version, auth).await
@@ -125,7 +125,7 @@ pub trait SetsInterface: ClientLike + Sized {
     K: Into<RedisKey> + Send,
   {
     into!(key);
-    commands::sets::smembers(self, key).await?.convert()
+    R::from_frame(commands::sets::smembers(self, key).await?)
   }
 
   /// Move `member` from the set at `source` to the set at `destination`.
@@ -34,6 +34,8 @@ pub trait StreamsInterface: ClientLike + Sized {
   /// `key`.
   ///
   /// <https://redis.io/commands/xinfo-consumers>
+  ///
+  /// See [StreamConsumerInfo](crate::types::StreamConsumerInfo) for a typed representation of each consumer.
   async fn xinfo_consumers<R, K, S>(&self, key: K, groupname: S) -> RedisResult<R>
   where
     R: FromRedis,
@@ -49,6 +51,8 @@ pub trait StreamsInterface: ClientLike + Sized {
   /// This command returns the list of all consumers groups of the stream stored at `key`.
   ///
   /// <https://redis.io/commands/xinfo-groups>
+  ///
+  /// See [StreamGroupInfo](crate::types::StreamGroupInfo) for a typed representation of each group.
   async fn xinfo_groups<R, K>(&self, key: K) -> RedisResult<R>
   where
     R: FromRedis,
@@ -61,6 +65,9 @@ pub trait StreamsInterface: ClientLike + Sized {
   /// This command returns information about the stream stored at `key`.
   ///
   /// <https://redis.io/commands/xinfo-stream>
+  ///
+  /// The response can be parsed as a [StreamInfo](crate::types::StreamInfo), or a
+  /// [StreamInfoFull](crate::types::StreamInfoFull) when `full` is `true`.
   async fn xinfo_stream<R, K>(&self, key: K, full: bool, count: Option<u64>) -> RedisResult<R>
   where
     R: FromRedis,
@@ -155,6 +162,8 @@ pub trait StreamsInterface: ClientLike + Sized {
   /// <https://redis.io/commands/xrange>
   ///
   /// **See [xrange_values](Self::xrange_values) for a variation of this function that may be more useful.**
+  ///
+  /// The entries can also be decoded into user-defined types with [XEntry](crate::types::XEntry).
   async fn xrange<R, K, S, E>(&self, key: K, start: S, end: E, count: Option<u64>) -> RedisResult<R>
   where
     R: FromRedis,
@@ -677,6 +686,10 @@ pub trait StreamsInterface: ClientLike + Sized {
   /// Inspect the list of pending messages in a consumer group.
   ///
   /// <https://redis.io/commands/xpending>
+  ///
+  /// The summary form of the response can be parsed as a [PendingSummary](crate::types::PendingSummary), and the
+  /// extended form can be parsed as a `Vec<PendingEntry>`. See [PendingEntry](crate::types::PendingEntry) for more
+  /// information.
   async fn xpending<R, K, G, A>(&self, key: K, group: G, args: A) -> RedisResult<R>
   where
     R: FromRedis,
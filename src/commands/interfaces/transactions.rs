@@ -1,4 +1,66 @@
-use crate::{clients::Transaction, interfaces::ClientLike};
+use crate::{
+  clients::Transaction,
+  commands,
+  error::{RedisError, RedisErrorKind},
+  interfaces::{ClientLike, RedisResult},
+  protocol::command::{RedisCommand, RedisCommandKind},
+  types::{FromRedis, MultipleKeys, Options, RedisKey, RedisValue, RetryPolicy, Server},
+  utils,
+};
+use futures::Future;
+use redis_protocol::redis_keyslot;
+use std::convert::TryFrom;
+use tokio::{
+  sync::broadcast::{error::TryRecvError, Receiver as BroadcastReceiver},
+  time::sleep,
+};
+
+/// Find the cluster node that owns the hash slot of the watched keys, or `None` with a centralized deployment.
+///
+/// An error is returned if the keys belong to more than one hash slot.
+fn watched_cluster_node<C: ClientLike>(client: &C, keys: &MultipleKeys) -> Result<Option<Server>, RedisError> {
+  let inner = client.inner();
+  if !inner.config.server.is_clustered() {
+    return Ok(None);
+  }
+
+  let mut command = RedisCommand::new(RedisCommandKind::Watch, keys.clone().into_values());
+  let _ = utils::prepare_command(client, &mut command);
+  let mut slots = command
+    .args()
+    .iter()
+    .filter_map(|key| RedisKey::try_from(key.clone()).ok())
+    .map(|key| redis_keyslot(key.as_bytes()));
+  let slot = match slots.next() {
+    Some(slot) => slot,
+    None => return Ok(None),
+  };
+  if slots.any(|other| other != slot) {
+    return Err(RedisError::new(
+      RedisErrorKind::InvalidArgument,
+      "Watched keys must belong to the same hash slot.",
+    ));
+  }
+
+  inner.with_cluster_state(|state| match state.get_server(slot) {
+    Some(server) => Ok(Some(server.clone())),
+    None => Err(RedisError::new(
+      RedisErrorKind::Cluster,
+      "Missing hash slot owner for watched keys.",
+    )),
+  })
+}
+
+/// Whether any connection was re-established since the last call, dropping the `WATCH` state on that connection.
+fn check_reconnected(rx: &mut BroadcastReceiver<Server>) -> bool {
+  let mut reconnected = false;
+  loop {
+    match rx.try_recv() {
+      Ok(_) | Err(TryRecvError::Lagged(_)) => reconnected = true,
+      Err(TryRecvError::Empty) | Err(TryRecvError::Closed) => return reconnected,
+    }
+  }
+}
 
 /// Functions that implement the [transactions](https://redis.io/commands#transactions) interface.
 ///
@@ -11,4 +73,291 @@ pub trait TransactionInterface: ClientLike + Sized {
   fn multi(&self) -> Transaction {
     Transaction::from_inner(self.inner())
   }
+
+  /// Run an optimistic locking (check-and-set) transaction, retrying if any of the watched keys change before the
+  /// transaction runs.
+  ///
+  /// The client will `WATCH` the provided keys, read their values with `MGET`, and call `func` with a new transaction
+  /// and the values. `func` should queue commands on the provided transaction, which is then executed. If `EXEC` is
+  /// aborted because a watched key changed the client will wait for the delay from `policy` and try again, up to
+  /// `max_retries` times. `RetryPolicy::Never` disables retries, and the idempotency settings on the policy are
+  /// ignored. If `func` does not queue any commands the keys are unwatched and `nil` is returned.
+  ///
+  /// `MGET` returns `nil` for keys that do not exist and for keys that hold a value other than a string. Callers that
+  /// watch lists, hashes, or other data types can ignore the provided values and read the keys inside `func` instead,
+  /// since any reads that happen after `WATCH` are still protected by it.
+  ///
+  /// `WATCH` state is associated with a connection, so `WATCH`, `MGET`, `UNWATCH`, and the transaction are sent to
+  /// the same connection, and they are not retried or redirected to a different connection. In a clustered
+  /// deployment all the keys must belong to the same hash slot, and an error is returned if they do not or if the
+  /// hash slot moves to a different node. If any connection is re-established before `EXEC` is sent the transaction
+  /// is discarded and retried as if a watched key changed.
+  ///
+  /// Note: other tasks using the same client instance share the same connections. Callers should avoid running other
+  /// `WATCH`, `UNWATCH`, `MULTI`, or `EXEC` commands on the same client concurrently.
+  ///
+  /// ```rust no_run
+  /// # use fred::{prelude::*, types::RetryPolicy};
+  /// async fn example(client: &RedisClient) -> Result<(), RedisError> {
+  ///   // increment a counter stored as a string, without INCR
+  ///   let _: Vec<RedisValue> = client
+  ///     .transaction_with_retry("foo", 5, RetryPolicy::new_always(10, 1000, 2), |trx, values| async move {
+  ///       let current = values[0].as_i64().unwrap_or(0);
+  ///       trx.set("foo", current + 1, None, None, false).await
+  ///     })
+  ///     .await?;
+  ///   Ok(())
+  /// }
+  /// ```
+  async fn transaction_with_retry<R, K, F, Fut>(
+    &self,
+    keys: K,
+    max_retries: u32,
+    policy: RetryPolicy,
+    mut func: F,
+  ) -> RedisResult<R>
+  where
+    R: FromRedis,
+    K: Into<MultipleKeys> + Send,
+    F: FnMut(Transaction, Vec<RedisValue>) -> Fut + Send,
+    Fut: Future<Output = RedisResult<()>> + Send,
+  {
+    into!(keys);
+    let server = watched_cluster_node(self, &keys)?;
+    // the WATCH state does not survive a reconnection, so these commands cannot be retried by the router
+    let pinned = self.with_options(&Options {
+      max_attempts: Some(1),
+      cluster_node: server.clone(),
+      ..Default::default()
+    });
+    let mut reconnect_rx = self.inner().notifications.reconnect.load().subscribe();
+    let mut attempts = 0;
+
+    loop {
+      check_reconnected(&mut reconnect_rx);
+      commands::keys::watch(&pinned, keys.clone()).await?;
+      let values: Vec<RedisValue> = if keys.len() > 0 {
+        FromRedis::from_frame(commands::keys::mget(&pinned, keys.clone()).await?)?
+      } else {
+        Vec::new()
+      };
+
+      let trx = self.multi();
+      trx.pin_hash_slot(&keys);
+      if let Err(error) = func(trx.clone(), values).await {
+        let _ = commands::keys::unwatch(&pinned).await;
+        return Err(error);
+      }
+      if trx.len() == 0 {
+        commands::keys::unwatch(&pinned).await?;
+        return R::from_value(RedisValue::Null);
+      }
+
+      let result: RedisValue = if check_reconnected(&mut reconnect_rx) {
+        let _ = commands::keys::unwatch(&pinned).await;
+        RedisValue::Null
+      } else {
+        trx.pin_connection(server.clone());
+        trx.exec(true).await?
+      };
+      if !result.is_null() {
+        return R::from_value(result);
+      }
+
+      attempts += 1;
+      if attempts > max_retries || !policy.should_retry(true) {
+        return Err(RedisError::new(
+          RedisErrorKind::Canceled,
+          "Transaction aborted after too many retries.",
+        ));
+      }
+
+      if let Some(delay) = policy.delay(attempts) {
+        let inner = self.inner();
+        _debug!(
+          inner,
+          "Retrying aborted transaction after {} ms (attempt {})",
+          delay.as_millis(),
+          attempts
+        );
+        sleep(delay).await;
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "mocks", feature = "fake"))]
+mod tests {
+  use super::*;
+  #[cfg(feature = "mocks")]
+  use crate::mocks::{MockCommand, Mocks};
+  use crate::{clients::RedisClient, interfaces::KeysInterface, types::RedisConfig};
+  #[cfg(feature = "fake")]
+  use crate::{
+    fake::{Cluster, Server as FakeServer},
+    types::ReconnectPolicy,
+  };
+  use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  };
+  #[cfg(feature = "fake")]
+  use std::time::Duration;
+
+  /// Aborts the first `conflicts` transactions as if a watched key changed.
+  #[cfg(feature = "mocks")]
+  #[derive(Debug)]
+  struct Conflicts {
+    conflicts: usize,
+    execs:     AtomicUsize,
+  }
+
+  #[cfg(feature = "mocks")]
+  impl Mocks for Conflicts {
+    fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+      match &*command.cmd {
+        "MGET" => Ok(RedisValue::Array(vec![RedisValue::Integer(1)])),
+        _ => Ok(RedisValue::new_ok()),
+      }
+    }
+
+    fn process_transaction(&self, _: Vec<MockCommand>) -> Result<RedisValue, RedisError> {
+      if self.execs.fetch_add(1, Ordering::SeqCst) < self.conflicts {
+        Ok(RedisValue::Null)
+      } else {
+        Ok(RedisValue::Array(vec![RedisValue::new_ok()]))
+      }
+    }
+  }
+
+  #[cfg(feature = "mocks")]
+  async fn create_client(conflicts: usize) -> (RedisClient, Arc<Conflicts>) {
+    let mocks = Arc::new(Conflicts {
+      conflicts,
+      execs: AtomicUsize::new(0),
+    });
+    let config = RedisConfig {
+      mocks: Some(mocks.clone()),
+      ..Default::default()
+    };
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    client.wait_for_connect().await.unwrap();
+
+    (client, mocks)
+  }
+
+  async fn increment(client: &RedisClient, max_retries: u32, policy: RetryPolicy) -> RedisResult<Vec<String>> {
+    client
+      .transaction_with_retry("foo", max_retries, policy, |trx, values| async move {
+        let current = values[0].as_i64().unwrap_or(0);
+        trx.set("foo", current + 1, None, None, false).await
+      })
+      .await
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "mocks")]
+  async fn should_retry_after_watch_conflict() {
+    let (client, mocks) = create_client(2).await;
+
+    let actual = increment(&client, 2, RetryPolicy::new_always(1, 10, 2)).await.unwrap();
+    assert_eq!(actual, vec!["OK"]);
+    assert_eq!(mocks.execs.load(Ordering::SeqCst), 3);
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "mocks")]
+  async fn should_stop_after_max_retries() {
+    let (client, mocks) = create_client(usize::MAX).await;
+
+    let error = increment(&client, 2, RetryPolicy::new_always(1, 10, 2))
+      .await
+      .unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::Canceled);
+    assert_eq!(mocks.execs.load(Ordering::SeqCst), 3);
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "mocks")]
+  async fn should_not_retry_with_never_policy() {
+    let (client, mocks) = create_client(1).await;
+
+    let error = increment(&client, 5, RetryPolicy::Never).await.unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::Canceled);
+    assert_eq!(mocks.execs.load(Ordering::SeqCst), 1);
+  }
+
+  #[cfg(feature = "fake")]
+  async fn create_fake_client(config: RedisConfig) -> RedisClient {
+    let policy = ReconnectPolicy::new_constant(0, 10);
+    let client = RedisClient::new(config, None, None, Some(policy));
+    let _ = client.connect();
+    client.wait_for_connect().await.unwrap();
+    client
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "fake")]
+  async fn should_reject_watched_keys_in_different_hash_slots() {
+    let cluster = Cluster::start(3).await.unwrap();
+    let client = create_fake_client(cluster.redis_config()).await;
+
+    let error = client
+      .transaction_with_retry::<RedisValue, _, _, _>(vec!["foo", "bar"], 1, RetryPolicy::Never, |_, _| async {
+        Ok(())
+      })
+      .await
+      .unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::InvalidArgument);
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "fake")]
+  async fn should_pin_clustered_transaction_to_hash_slot_owner() {
+    let cluster = Cluster::start(3).await.unwrap();
+    let client = create_fake_client(cluster.redis_config()).await;
+    let _: () = client.set("{foo}1", 1, None, None, false).await.unwrap();
+    let _: () = client.set("{foo}2", 2, None, None, false).await.unwrap();
+
+    let actual: Vec<String> = client
+      .transaction_with_retry(
+        vec!["{foo}1", "{foo}2"],
+        1,
+        RetryPolicy::Never,
+        |trx, values| async move {
+          let sum = values[0].as_i64().unwrap_or(0) + values[1].as_i64().unwrap_or(0);
+          trx.set("{foo}1", sum, None, None, false).await
+        },
+      )
+      .await
+      .unwrap();
+    assert_eq!(actual, vec!["OK"]);
+    assert_eq!(client.get::<i64, _>("{foo}1").await.unwrap(), 3);
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "fake")]
+  async fn should_retry_after_reconnecting_before_exec() {
+    let server = Arc::new(FakeServer::start().await.unwrap());
+    let client = create_fake_client(server.redis_config().unwrap()).await;
+    let calls = Arc::new(AtomicUsize::new(0));
+
+    let actual: Vec<String> = client
+      .transaction_with_retry("foo", 1, RetryPolicy::new_always(1, 10, 2), |trx, _| {
+        let (server, calls) = (server.clone(), calls.clone());
+        async move {
+          if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            server.kill_connections();
+            tokio::time::sleep(Duration::from_millis(100)).await;
+          }
+          trx.set("foo", "bar", None, None, false).await
+        }
+      })
+      .await
+      .unwrap();
+    assert_eq!(actual, vec!["OK"]);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
 }
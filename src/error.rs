@@ -59,6 +59,8 @@ pub enum RedisErrorKind {
   NotFound,
   /// An error indicating that the caller should apply backpressure and retry the command.
   Backpressure,
+  /// An error indicating that the circuit breaker for the server is open and the command was not sent.
+  CircuitOpen,
   /// An error associated with a replica node.
   #[cfg(feature = "replicas")]
   #[cfg_attr(docsrs, doc(cfg(feature = "replicas")))]
@@ -85,6 +87,7 @@ impl RedisErrorKind {
       RedisErrorKind::Sentinel => "Sentinel Error",
       RedisErrorKind::NotFound => "Not Found",
       RedisErrorKind::Backpressure => "Backpressure",
+      RedisErrorKind::CircuitOpen => "Circuit Open",
       #[cfg(feature = "replicas")]
       RedisErrorKind::Replica => "Replica",
     }
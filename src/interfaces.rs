@@ -3,9 +3,13 @@ use crate::{
   commands,
   error::{RedisError, RedisErrorKind},
   modules::inner::RedisClientInner,
-  protocol::command::{RedisCommand, RouterCommand},
+  protocol::{
+    command::{RedisCommand, RouterCommand},
+    cross_slot,
+  },
   router::commands as router_commands,
   types::{
+    CircuitBreakerEvent,
     ClientState,
     ClusterStateChange,
     ConnectHandle,
@@ -52,6 +56,9 @@ where
   );
   command.inherit_options(inner);
 
+  if let Some(pieces) = cross_slot::split_command(inner, &mut command) {
+    return send_to_router(inner, RouterCommand::Pipeline { commands: pieces });
+  }
   send_to_router(inner, command.into())
 }
 
@@ -99,6 +106,12 @@ pub trait ClientLike: Clone + Send + Sized {
   #[doc(hidden)]
   fn change_command(&self, _: &mut RedisCommand) {}
 
+  /// Whether commands are buffered in memory and sent later, as with a pipeline or transaction.
+  #[doc(hidden)]
+  fn is_buffered(&self) -> bool {
+    false
+  }
+
   /// Helper function to intercept and customize how a command is sent to the connection layer.
   #[doc(hidden)]
   fn send_command<C>(&self, command: C) -> Result<(), RedisError>
@@ -311,7 +324,7 @@ pub trait ClientLike: Clone + Send + Sized {
     T::Error: Into<RedisError> + Send,
   {
     let args = utils::try_into_vec(args)?;
-    commands::server::custom(self, cmd, args).await?.convert()
+    R::from_frame(commands::server::custom_raw(self, cmd, args).await?)
   }
 
   /// Run a custom command similar to [custom](Self::custom), but return the response frame directly without any
@@ -392,6 +405,17 @@ pub trait EventInterface: ClientLike {
     spawn_event_listener(rx, func)
   }
 
+  /// Spawn a task that runs the provided function whenever the state of a server's circuit breaker changes.
+  ///
+  /// See [CircuitBreakerConfig](crate::types::CircuitBreakerConfig) for more information.
+  fn on_circuit_breaker<F>(&self, func: F) -> JoinHandle<RedisResult<()>>
+  where
+    F: Fn(CircuitBreakerEvent) -> RedisResult<()> + Send + 'static,
+  {
+    let rx = self.circuit_breaker_rx();
+    spawn_event_listener(rx, func)
+  }
+
   /// Spawn a task that runs the provided function whenever the client detects an unresponsive connection.
   #[cfg(feature = "check-unresponsive")]
   #[cfg_attr(docsrs, doc(cfg(feature = "check-unresponsive")))]
@@ -471,6 +495,11 @@ pub trait EventInterface: ClientLike {
     self.inner().notifications.errors.load().subscribe()
   }
 
+  /// Listen for circuit breaker state changes.
+  fn circuit_breaker_rx(&self) -> BroadcastReceiver<CircuitBreakerEvent> {
+    self.inner().notifications.circuit.load().subscribe()
+  }
+
   /// Receive a message when the client initiates a reconnection after detecting an unresponsive connection.
   #[cfg(feature = "check-unresponsive")]
   #[cfg_attr(docsrs, doc(cfg(feature = "check-unresponsive")))]
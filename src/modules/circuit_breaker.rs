@@ -0,0 +1,260 @@
+use crate::{
+  error::{RedisError, RedisErrorKind},
+  modules::inner::RedisClientInner,
+  protocol::{
+    command::{RedisCommand, RedisCommandKind},
+    types::Server,
+    utils as protocol_utils,
+  },
+  types::{CircuitBreakerEvent, CircuitBreakerState},
+};
+use parking_lot::Mutex;
+use std::{
+  collections::{HashMap, VecDeque},
+  sync::Arc,
+  time::Instant,
+};
+
+/// The circuit breaker state for one server.
+#[derive(Debug)]
+enum Breaker {
+  /// The timestamps of recent errors or timeouts.
+  Closed(VecDeque<Instant>),
+  /// The time at which the circuit opened.
+  Open(Instant),
+  /// A probe `PING` is in flight.
+  HalfOpen,
+}
+
+/// Circuit breaker state for each server, shared between the router and reader tasks.
+#[derive(Debug, Default)]
+pub struct CircuitBreakers {
+  state: Mutex<HashMap<Server, Breaker>>,
+}
+
+impl CircuitBreakers {
+  /// Check whether commands can be sent to `server`.
+  ///
+  /// If the circuit has been open for longer than the configured `open_duration` this will move the circuit to the
+  /// half-open state and start a probe `PING` to the server.
+  pub fn check(&self, inner: &Arc<RedisClientInner>, server: &Server) -> Result<(), RedisError> {
+    let config = match inner.connection.circuit_breaker {
+      Some(ref config) => config,
+      None => return Ok(()),
+    };
+
+    let start_probe = {
+      let mut guard = self.state.lock();
+      let start_probe = match guard.get(server) {
+        None | Some(Breaker::Closed(_)) => return Ok(()),
+        Some(Breaker::HalfOpen) => false,
+        Some(Breaker::Open(opened)) => opened.elapsed() >= config.open_duration,
+      };
+
+      if start_probe {
+        guard.insert(server.clone(), Breaker::HalfOpen);
+      }
+      start_probe
+    };
+    if start_probe {
+      _debug!(inner, "Sending circuit breaker probe to {}", server);
+      broadcast(inner, server, CircuitBreakerState::HalfOpen);
+      spawn_probe(inner, server);
+    }
+
+    Err(RedisError::new(
+      RedisErrorKind::CircuitOpen,
+      format!("Circuit breaker is open for {}.", server),
+    ))
+  }
+
+  /// Record a connection error or timeout associated with `server`, opening the circuit if needed.
+  pub fn record_failure(&self, inner: &Arc<RedisClientInner>, server: &Server) {
+    let config = match inner.connection.circuit_breaker {
+      Some(ref config) => config,
+      None => return,
+    };
+
+    let opened = {
+      let mut guard = self.state.lock();
+      let breaker = guard
+        .entry(server.clone())
+        .or_insert_with(|| Breaker::Closed(VecDeque::new()));
+
+      if let Breaker::Closed(ref mut failures) = breaker {
+        let now = Instant::now();
+        while failures
+          .front()
+          .map(|failure| now.duration_since(*failure) > config.window)
+          .unwrap_or(false)
+        {
+          failures.pop_front();
+        }
+        failures.push_back(now);
+
+        if failures.len() >= config.max_errors as usize {
+          *breaker = Breaker::Open(now);
+          true
+        } else {
+          false
+        }
+      } else {
+        false
+      }
+    };
+
+    if opened {
+      _warn!(inner, "Opening circuit breaker for {}", server);
+      broadcast(inner, server, CircuitBreakerState::Open);
+    }
+  }
+
+  /// Start tracking the server that receives `command` so that caller-side timeouts can be recorded.
+  ///
+  /// Returns `None` if no circuit breaker is configured.
+  pub fn track(inner: &Arc<RedisClientInner>, command: &mut RedisCommand) -> Option<Arc<Mutex<Option<Server>>>> {
+    inner.connection.circuit_breaker.as_ref()?;
+    let written_to = Arc::new(Mutex::new(None));
+    command.written_to = Some(written_to.clone());
+    Some(written_to)
+  }
+
+  /// Record a timeout against the server that most recently received the command, if any.
+  pub fn record_timeout(&self, inner: &Arc<RedisClientInner>, written_to: &Option<Arc<Mutex<Option<Server>>>>) {
+    let server = match written_to.as_ref().and_then(|server| server.lock().clone()) {
+      Some(server) => server,
+      // the command timed out before it was written
+      None => return,
+    };
+
+    _debug!(inner, "Recording timeout for {}", server);
+    self.record_failure(inner, &server);
+  }
+
+  /// Close or reopen the circuit for `server` based on the result of a probe.
+  fn finish_probe(&self, inner: &Arc<RedisClientInner>, server: &Server, success: bool) {
+    {
+      let mut guard = self.state.lock();
+      if !matches!(guard.get(server), Some(Breaker::HalfOpen)) {
+        return;
+      }
+
+      if success {
+        guard.remove(server);
+      } else {
+        guard.insert(server.clone(), Breaker::Open(Instant::now()));
+      }
+    }
+
+    if success {
+      _debug!(inner, "Closing circuit breaker for {}", server);
+      broadcast(inner, server, CircuitBreakerState::Closed);
+    } else {
+      _debug!(inner, "Reopening circuit breaker for {} after failed probe.", server);
+      broadcast(inner, server, CircuitBreakerState::Open);
+    }
+  }
+}
+
+fn broadcast(inner: &Arc<RedisClientInner>, server: &Server, state: CircuitBreakerState) {
+  inner.notifications.broadcast_circuit_breaker(CircuitBreakerEvent {
+    server: server.clone(),
+    state,
+  });
+}
+
+/// Send a `PING` to the server on the backchannel connection.
+fn spawn_probe(inner: &Arc<RedisClientInner>, server: &Server) {
+  let (inner, server) = (inner.clone(), server.clone());
+
+  tokio::spawn(async move {
+    let command = RedisCommand::new(RedisCommandKind::Ping, vec![]);
+    let result = inner
+      .backchannel
+      .write()
+      .await
+      .request_response(&inner, &server, command)
+      .await;
+
+    let success = match result {
+      Ok(frame) => protocol_utils::frame_to_error(&frame).is_none(),
+      Err(e) => {
+        _debug!(inner, "Circuit breaker probe to {} failed: {:?}", server, e);
+        false
+      },
+    };
+    inner.circuit_breakers.finish_probe(&inner, &server, success);
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::{CircuitBreakerConfig, ConnectionConfig, PerformanceConfig, RedisConfig};
+  use std::time::Duration;
+
+  fn create_inner(max_errors: u32) -> Arc<RedisClientInner> {
+    let connection = ConnectionConfig {
+      circuit_breaker: Some(CircuitBreakerConfig {
+        max_errors,
+        window: Duration::from_secs(60),
+        open_duration: Duration::from_secs(60),
+      }),
+      ..Default::default()
+    };
+
+    RedisClientInner::new(RedisConfig::default(), PerformanceConfig::default(), connection, None)
+  }
+
+  #[tokio::test]
+  async fn should_open_after_max_errors() {
+    let inner = create_inner(2);
+    let server = Server::new("localhost", 6379);
+
+    inner.circuit_breakers.record_failure(&inner, &server);
+    assert!(inner.circuit_breakers.check(&inner, &server).is_ok());
+    inner.circuit_breakers.record_failure(&inner, &server);
+
+    let error = inner.circuit_breakers.check(&inner, &server).unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::CircuitOpen);
+  }
+
+  #[tokio::test]
+  async fn should_track_servers_separately() {
+    let inner = create_inner(1);
+    let (first, second) = (Server::new("localhost", 6379), Server::new("localhost", 6380));
+
+    inner.circuit_breakers.record_failure(&inner, &first);
+    assert!(inner.circuit_breakers.check(&inner, &first).is_err());
+    assert!(inner.circuit_breakers.check(&inner, &second).is_ok());
+  }
+
+  #[tokio::test]
+  async fn should_record_timeouts_against_written_server() {
+    let inner = create_inner(1);
+    let server = Server::new("localhost", 6379);
+    let mut command = RedisCommand::new(RedisCommandKind::Get, vec!["foo".into()]);
+
+    let written_to = CircuitBreakers::track(&inner, &mut command);
+    inner.circuit_breakers.record_timeout(&inner, &written_to);
+    assert!(inner.circuit_breakers.check(&inner, &server).is_ok());
+
+    *command.written_to.as_ref().unwrap().lock() = Some(server.clone());
+    inner.circuit_breakers.record_timeout(&inner, &written_to);
+    assert!(inner.circuit_breakers.check(&inner, &server).is_err());
+  }
+
+  #[test]
+  fn should_not_track_without_config() {
+    let inner = RedisClientInner::new(
+      RedisConfig::default(),
+      PerformanceConfig::default(),
+      ConnectionConfig::default(),
+      None,
+    );
+    let mut command = RedisCommand::new(RedisCommandKind::Get, vec!["foo".into()]);
+
+    assert!(CircuitBreakers::track(&inner, &mut command).is_none());
+    assert!(command.written_to.is_none());
+  }
+}
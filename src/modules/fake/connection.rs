@@ -0,0 +1,486 @@
+use crate::{
+  modules::{
+    fake::{Message, Shared},
+    keyspace::{utils::*, DATABASES},
+  },
+  protocol::public::{RespServer, RespVersion},
+};
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use redis_protocol::{redis_keyslot, resp3::types::Frame as Resp3Frame};
+use std::{
+  sync::Arc,
+  time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::{
+  io::{AsyncRead, AsyncWrite},
+  sync::mpsc::UnboundedReceiver,
+};
+use tokio_util::codec::Framed;
+
+/// The state of a client connection that is not visible to other connections.
+struct Connection {
+  id:            u64,
+  db:            usize,
+  authenticated: bool,
+  is_resp3:      bool,
+  closing:       bool,
+  transaction:   Option<Vec<(String, Vec<Bytes>)>>,
+  /// Whether a command failed to queue in the current transaction.
+  aborted:       bool,
+}
+
+/// Read and process commands from a client connection until it closes.
+pub async fn run<T>(shared: Arc<Shared>, socket: T, id: u64, mut rx: UnboundedReceiver<Message>)
+where
+  T: AsyncRead + AsyncWrite + Unpin,
+{
+  let mut framed = Framed::new(socket, RespServer::default());
+  let mut connection = Connection {
+    id,
+    db: 0,
+    authenticated: shared.config.password.is_none(),
+    is_resp3: false,
+    closing: false,
+    transaction: None,
+    aborted: false,
+  };
+
+  loop {
+    tokio::select! {
+      message = rx.recv() => match message {
+        Some(Message::Frame(frame)) => {
+          if framed.send(frame).await.is_err() {
+            break;
+          }
+        },
+        Some(Message::Close) | None => break,
+      },
+      args = framed.next() => {
+        let args = match args {
+          Some(Ok(args)) => args,
+          Some(Err(e)) => {
+            debug!("Fake server closing connection {} after error: {:?}", id, e);
+            break;
+          },
+          None => break,
+        };
+
+        let replies = connection.handle(&shared, args);
+        let version = if connection.is_resp3 { RespVersion::RESP3 } else { RespVersion::RESP2 };
+        framed.codec_mut().set_version(version);
+
+        let mut failed = false;
+        for reply in replies.into_iter() {
+          if framed.feed(reply).await.is_err() {
+            failed = true;
+            break;
+          }
+        }
+        if failed || framed.flush().await.is_err() || connection.closing {
+          break;
+        }
+      },
+    }
+  }
+
+  shared.clients.lock().remove(&id);
+}
+
+impl Connection {
+  fn is_subscribed(&self, shared: &Shared) -> bool {
+    shared
+      .clients
+      .lock()
+      .get(&self.id)
+      .map(|client| client.subscriptions(false) + client.subscriptions(true) > 0)
+      .unwrap_or(false)
+  }
+
+  /// Process a command, returning the frames that should be sent to the client.
+  fn handle(&mut self, shared: &Shared, args: Vec<Bytes>) -> Vec<Resp3Frame> {
+    let (cmd, args) = match args.split_first() {
+      Some((cmd, args)) => (upper(cmd), args),
+      None => return Vec::new(),
+    };
+
+    if !self.authenticated && !matches!(cmd.as_str(), "AUTH" | "HELLO" | "QUIT") {
+      return vec![error("NOAUTH Authentication required.")];
+    }
+    if self.transaction.is_some() && !matches!(cmd.as_str(), "EXEC" | "DISCARD" | "MULTI" | "WATCH" | "QUIT") {
+      return vec![self.queue(shared, cmd, args)];
+    }
+
+    match cmd.as_str() {
+      "SUBSCRIBE" | "PSUBSCRIBE" | "SSUBSCRIBE" => self.subscribe(shared, &cmd, args),
+      "UNSUBSCRIBE" | "PUNSUBSCRIBE" | "SUNSUBSCRIBE" => self.unsubscribe(shared, &cmd, args),
+      _ => vec![self.execute(shared, &cmd, args).unwrap_or_else(|e| e)],
+    }
+  }
+
+  /// Queue a command in a transaction.
+  fn queue(&mut self, shared: &Shared, cmd: String, args: &[Bytes]) -> Resp3Frame {
+    if let Err(e) = shared.check_slots(&cmd, args) {
+      self.aborted = true;
+      return e;
+    }
+
+    if let Some(ref mut commands) = self.transaction {
+      commands.push((cmd, args.to_vec()));
+    }
+    simple("QUEUED")
+  }
+
+  fn execute(&mut self, shared: &Shared, cmd: &str, args: &[Bytes]) -> Reply {
+    match cmd {
+      "PING" => self.ping(shared, args),
+      "ECHO" => {
+        check_args(cmd, args, 1, Some(1), 1)?;
+        Ok(blob(args[0].clone()))
+      },
+      "QUIT" => {
+        self.closing = true;
+        Ok(ok())
+      },
+      "HELLO" => self.hello(shared, args),
+      "AUTH" => self.auth(shared, args),
+      "SELECT" => self.select(shared, args),
+      "CLIENT" => self.client(shared, args),
+      "INFO" => Ok(blob(info(shared))),
+      "CLUSTER" => cluster(shared, args),
+      "READONLY" | "READWRITE" => Ok(ok()),
+      "ROLE" => Ok(array(vec![blob("master"), int(0), array(vec![])])),
+      "TIME" => {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        Ok(array(vec![
+          blob(now.as_secs().to_string()),
+          blob(now.subsec_micros().to_string()),
+        ]))
+      },
+      "FLUSHALL" => {
+        shared.keyspace.lock().flush_all();
+        Ok(ok())
+      },
+      "PUBLISH" => {
+        check_args(cmd, args, 2, Some(2), 1)?;
+        Ok(int(shared.publish(&args[0], &args[1])))
+      },
+      "SPUBLISH" => {
+        check_args(cmd, args, 2, Some(2), 1)?;
+        shared.check_slots(cmd, args)?;
+        Ok(int(shared.spublish(&args[0], &args[1])))
+      },
+      "MULTI" => {
+        if self.transaction.is_some() {
+          return Err(error("ERR MULTI calls can not be nested"));
+        }
+        self.transaction = Some(Vec::new());
+        self.aborted = false;
+        Ok(ok())
+      },
+      "EXEC" => self.exec(shared),
+      "DISCARD" => match self.transaction.take() {
+        Some(_) => Ok(ok()),
+        None => Err(error("ERR DISCARD without MULTI")),
+      },
+      "WATCH" if self.transaction.is_some() => Err(error("ERR WATCH inside MULTI is not allowed")),
+      "WATCH" | "UNWATCH" => Ok(ok()),
+      _ => {
+        shared.check_slots(cmd, args)?;
+        shared
+          .keyspace
+          .lock()
+          .execute(self.db, cmd, args, self.is_resp3)
+          .unwrap_or_else(|| Err(unknown_command(cmd)))
+      },
+    }
+  }
+
+  fn ping(&mut self, shared: &Shared, args: &[Bytes]) -> Reply {
+    check_args("PING", args, 0, Some(1), 1)?;
+
+    if !self.is_resp3 && self.is_subscribed(shared) {
+      let message = args.first().cloned().unwrap_or_default();
+      Ok(array(vec![blob("pong"), blob(message)]))
+    } else {
+      match args.first() {
+        Some(message) => Ok(blob(message.clone())),
+        None => Ok(simple("PONG")),
+      }
+    }
+  }
+
+  /// Check the provided credentials, marking the connection as authenticated if they are valid.
+  fn check_auth(&mut self, shared: &Shared, username: Option<&Bytes>, password: &Bytes) -> Result<(), Resp3Frame> {
+    let config = &shared.config;
+    let valid = match config.password {
+      Some(ref expected) => {
+        let username_matches = username
+          .map(|username| username == config.username.as_bytes())
+          .unwrap_or(config.username == "default");
+
+        username_matches && password == expected.as_bytes()
+      },
+      None => true,
+    };
+
+    if valid {
+      self.authenticated = true;
+      Ok(())
+    } else {
+      Err(error("WRONGPASS invalid username-password pair or user is disabled."))
+    }
+  }
+
+  fn auth(&mut self, shared: &Shared, args: &[Bytes]) -> Reply {
+    check_args("AUTH", args, 1, Some(2), 1)?;
+
+    if args.len() == 2 {
+      self.check_auth(shared, Some(&args[0]), &args[1])?;
+    } else {
+      self.check_auth(shared, None, &args[0])?;
+    }
+    Ok(ok())
+  }
+
+  fn hello(&mut self, shared: &Shared, args: &[Bytes]) -> Reply {
+    if !supports_hello(shared) {
+      return Err(unknown_command("HELLO"));
+    }
+    let mut args = args.iter();
+    let is_resp3 = match args.next().map(parse_i64) {
+      Some(Ok(2)) => false,
+      Some(Ok(3)) => true,
+      Some(_) => return Err(error("NOPROTO unsupported protocol version")),
+      None => self.is_resp3,
+    };
+
+    while let Some(arg) = args.next() {
+      match upper(arg).as_str() {
+        "AUTH" => match (args.next(), args.next()) {
+          (Some(username), Some(password)) => self.check_auth(shared, Some(username), password)?,
+          _ => return Err(syntax_error()),
+        },
+        "SETNAME" => match args.next() {
+          Some(name) => self.set_name(shared, name.clone()),
+          None => return Err(syntax_error()),
+        },
+        _ => return Err(syntax_error()),
+      }
+    }
+
+    if !self.authenticated {
+      return Err(error(
+        "NOAUTH HELLO must be called with the client already authenticated, otherwise the HELLO <proto> AUTH <user> \
+         <pass> option can be used to authenticate the client and select the RESP protocol version at the same time",
+      ));
+    }
+    self.is_resp3 = is_resp3;
+
+    let mode = if shared.is_clustered() { "cluster" } else { "standalone" };
+    Ok(map(vec![
+      (blob("server"), blob("redis")),
+      (blob("version"), blob(shared.config.version.clone())),
+      (blob("proto"), int(if is_resp3 { 3 } else { 2 })),
+      (blob("id"), int(self.id as i64)),
+      (blob("mode"), blob(mode)),
+      (blob("role"), blob("master")),
+      (blob("modules"), array(vec![])),
+    ]))
+  }
+
+  fn select(&mut self, shared: &Shared, args: &[Bytes]) -> Reply {
+    check_args("SELECT", args, 1, Some(1), 1)?;
+    if shared.is_clustered() {
+      return Err(error("ERR SELECT is not allowed in cluster mode"));
+    }
+
+    let db = parse_i64(&args[0])?;
+    if db < 0 || db as usize >= DATABASES {
+      return Err(error("ERR DB index is out of range"));
+    }
+    self.db = db as usize;
+    Ok(ok())
+  }
+
+  fn set_name(&self, shared: &Shared, name: Bytes) {
+    if let Some(client) = shared.clients.lock().get_mut(&self.id) {
+      client.name = if name.is_empty() { None } else { Some(name) };
+    }
+  }
+
+  fn client(&mut self, shared: &Shared, args: &[Bytes]) -> Reply {
+    check_args("CLIENT", args, 1, None, 1)?;
+
+    match upper(&args[0]).as_str() {
+      "ID" => Ok(int(self.id as i64)),
+      "SETNAME" => {
+        check_args("CLIENT|SETNAME", args, 2, Some(2), 1)?;
+        self.set_name(shared, args[1].clone());
+        Ok(ok())
+      },
+      "GETNAME" => {
+        let name = shared
+          .clients
+          .lock()
+          .get(&self.id)
+          .and_then(|client| client.name.clone());
+        Ok(name.map(blob).unwrap_or(Resp3Frame::Null))
+      },
+      "SETINFO" => {
+        check_args("CLIENT|SETINFO", args, 3, Some(3), 1)?;
+        Ok(ok())
+      },
+      subcommand => Err(error(format!("ERR unknown subcommand '{}'.", subcommand))),
+    }
+  }
+
+  fn exec(&mut self, shared: &Shared) -> Reply {
+    let commands = match self.transaction.take() {
+      Some(commands) => commands,
+      None => return Err(error("ERR EXEC without MULTI")),
+    };
+    if self.aborted {
+      return Err(error("EXECABORT Transaction discarded because of previous errors."));
+    }
+
+    let results = commands
+      .into_iter()
+      .map(|(cmd, args)| self.execute(shared, &cmd, &args).unwrap_or_else(|e| e))
+      .collect();
+    Ok(array(results))
+  }
+
+  fn subscribe(&mut self, shared: &Shared, cmd: &str, args: &[Bytes]) -> Vec<Resp3Frame> {
+    if let Err(e) = check_args(cmd, args, 1, None, 1).and_then(|_| shared.check_slots(cmd, args)) {
+      return vec![e];
+    }
+
+    let mut clients = shared.clients.lock();
+    let client = match clients.get_mut(&self.id) {
+      Some(client) => client,
+      None => return Vec::new(),
+    };
+
+    args
+      .iter()
+      .map(|channel| {
+        let (kind, shard) = match cmd {
+          "SUBSCRIBE" => {
+            client.channels.insert(channel.clone());
+            ("subscribe", false)
+          },
+          "PSUBSCRIBE" => {
+            client.patterns.insert(channel.clone());
+            ("psubscribe", false)
+          },
+          _ => {
+            client.shard_channels.insert(channel.clone());
+            ("ssubscribe", true)
+          },
+        };
+
+        push(vec![
+          blob(kind),
+          blob(channel.clone()),
+          int(client.subscriptions(shard)),
+        ])
+      })
+      .collect()
+  }
+
+  fn unsubscribe(&mut self, shared: &Shared, cmd: &str, args: &[Bytes]) -> Vec<Resp3Frame> {
+    let mut clients = shared.clients.lock();
+    let client = match clients.get_mut(&self.id) {
+      Some(client) => client,
+      None => return Vec::new(),
+    };
+
+    let (kind, shard) = match cmd {
+      "UNSUBSCRIBE" => ("unsubscribe", false),
+      "PUNSUBSCRIBE" => ("punsubscribe", false),
+      _ => ("sunsubscribe", true),
+    };
+    let channels: Vec<Bytes> = if args.is_empty() {
+      match cmd {
+        "UNSUBSCRIBE" => client.channels.iter().cloned().collect(),
+        "PUNSUBSCRIBE" => client.patterns.iter().cloned().collect(),
+        _ => client.shard_channels.iter().cloned().collect(),
+      }
+    } else {
+      args.to_vec()
+    };
+
+    if channels.is_empty() {
+      return vec![push(vec![
+        blob(kind),
+        Resp3Frame::Null,
+        int(client.subscriptions(shard)),
+      ])];
+    }
+    channels
+      .into_iter()
+      .map(|channel| {
+        match cmd {
+          "UNSUBSCRIBE" => client.channels.remove(&channel),
+          "PUNSUBSCRIBE" => client.patterns.remove(&channel),
+          _ => client.shard_channels.remove(&channel),
+        };
+
+        push(vec![blob(kind), blob(channel), int(client.subscriptions(shard))])
+      })
+      .collect()
+  }
+}
+
+/// Read the `INFO` response.
+/// Whether the configured server version supports `HELLO`, which was added in 6.0.0.
+fn supports_hello(shared: &Shared) -> bool {
+  let major = shared
+    .config
+    .version
+    .split('.')
+    .next()
+    .and_then(|s| s.parse::<u64>().ok());
+  major.map(|major| major >= 6).unwrap_or(true)
+}
+
+fn info(shared: &Shared) -> String {
+  let mode = if shared.is_clustered() { "cluster" } else { "standalone" };
+  let clients = shared.clients.lock().len();
+
+  format!(
+    "# Server\r\nredis_version:{}\r\nredis_mode:{}\r\ntcp_port:{}\r\n\r\n# Clients\r\nconnected_clients:{}\r\n\r\n# \
+     Cluster\r\ncluster_enabled:{}\r\n",
+    shared.config.version,
+    mode,
+    shared.port,
+    clients,
+    shared.is_clustered() as u8
+  )
+}
+
+fn cluster(shared: &Shared, args: &[Bytes]) -> Reply {
+  check_args("CLUSTER", args, 1, None, 1)?;
+  let (cluster, myself) = match shared.cluster {
+    Some((ref cluster, myself)) => (cluster, myself),
+    None => return Err(error("ERR This instance has cluster support disabled")),
+  };
+
+  match upper(&args[0]).as_str() {
+    "INFO" => Ok(blob(format!(
+      "cluster_enabled:1\r\ncluster_state:ok\r\ncluster_slots_assigned:16384\r\ncluster_slots_ok:16384\r\\
+       ncluster_known_nodes:{}\r\ncluster_size:{}\r\n",
+      cluster.nodes.len(),
+      cluster.nodes.len()
+    ))),
+    "SLOTS" => Ok(cluster.slots_reply()),
+    "NODES" => Ok(cluster.nodes_reply(myself)),
+    "MYID" => Ok(blob(cluster.nodes[myself].id.clone())),
+    "KEYSLOT" => {
+      check_args("CLUSTER|KEYSLOT", args, 2, Some(2), 1)?;
+      Ok(int(redis_keyslot(&args[1]) as i64))
+    },
+    subcommand => Err(error(format!("ERR unknown subcommand '{}'.", subcommand))),
+  }
+}
@@ -0,0 +1,937 @@
+//! An in-process fake Redis server for use in tests.
+//!
+//! The fake server speaks RESP2 and RESP3 over a local TCP port or Unix socket and stores data in an in-memory
+//! keyspace. It supports the commands used to set up client connections (`HELLO`, `AUTH`, `SELECT`, `CLIENT`,
+//! `INFO`, `CLUSTER`, etc), transactions, pubsub, and the common string, hash, list, set, sorted set, and expiration
+//! commands.
+//!
+//! * `Server` - A centralized server.
+//! * `Cluster` - A set of servers that share a slot map and redirect clients with `MOVED` errors.
+//!
+//! Both interfaces can close client connections on demand in order to test reconnection logic. Blocking commands,
+//! scripting, streams, and keyspace notifications are not supported, and `WATCH` is accepted without tracking keys.
+//!
+//! ```rust no_run
+//! use fred::{fake::Server, prelude::*};
+//!
+//! #[tokio::test]
+//! async fn should_use_fake_server() -> Result<(), RedisError> {
+//!   let server = Server::start().await?;
+//!   let client = Builder::from_config(server.redis_config().unwrap()).build()?;
+//!   client.init().await?;
+//!
+//!   client.set("foo", "bar", None, None, false).await?;
+//!   assert_eq!(client.get::<String, _>("foo").await?, "bar");
+//!
+//!   // force the client to reconnect
+//!   server.kill_connections();
+//!   Ok(())
+//! }
+//! ```
+
+mod connection;
+
+use crate::{
+  error::{RedisError, RedisErrorKind},
+  modules::keyspace::{command_keys, utils::*, Keyspace},
+  types::{RedisConfig, ServerConfig},
+};
+use bytes::Bytes;
+use parking_lot::{Mutex, RwLock};
+use redis_protocol::{redis_keyslot, resp3::types::Frame as Resp3Frame};
+use std::{
+  collections::{BTreeSet, HashMap},
+  net::SocketAddr,
+  sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+    Weak,
+  },
+};
+use tokio::{
+  io::{AsyncRead, AsyncWrite},
+  net::{TcpListener, ToSocketAddrs},
+  sync::mpsc::{unbounded_channel, UnboundedSender},
+  task::JoinHandle,
+};
+
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+
+/// The number of hash slots in a cluster.
+const SLOTS: usize = 16384;
+
+/// Configuration options for a fake server.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FakeConfig {
+  /// The username that clients must use to authenticate.
+  ///
+  /// Default: `default`
+  pub username: String,
+  /// The password that clients must use to authenticate. If `None` any credentials are accepted.
+  ///
+  /// Default: `None`
+  pub password: Option<String>,
+  /// The server version reported via `INFO` and `HELLO`. Versions before 6.0.0 reject `HELLO`.
+  ///
+  /// Default: `7.2.0`
+  pub version:  String,
+}
+
+impl Default for FakeConfig {
+  fn default() -> Self {
+    FakeConfig {
+      username: "default".into(),
+      password: None,
+      version:  "7.2.0".into(),
+    }
+  }
+}
+
+/// A message sent to a connection task.
+enum Message {
+  Frame(Resp3Frame),
+  Close,
+}
+
+/// The state shared between connection tasks that is visible to other connections.
+struct ClientHandle {
+  tx:             UnboundedSender<Message>,
+  name:           Option<Bytes>,
+  channels:       BTreeSet<Bytes>,
+  patterns:       BTreeSet<Bytes>,
+  shard_channels: BTreeSet<Bytes>,
+}
+
+impl ClientHandle {
+  /// Read the number of subscriptions reported in `(P|S)SUBSCRIBE` and `(P|S)UNSUBSCRIBE` replies.
+  fn subscriptions(&self, shard: bool) -> i64 {
+    if shard {
+      self.shard_channels.len() as i64
+    } else {
+      (self.channels.len() + self.patterns.len()) as i64
+    }
+  }
+}
+
+/// A cluster node.
+struct ClusterNode {
+  id:   String,
+  host: String,
+  port: u16,
+}
+
+/// The slot map and node list shared by each server in a cluster.
+struct ClusterState {
+  nodes:   Vec<ClusterNode>,
+  /// The index of the node that owns each slot.
+  slots:   RwLock<Vec<usize>>,
+  members: RwLock<Vec<Weak<Shared>>>,
+}
+
+impl ClusterState {
+  fn new(addrs: &[SocketAddr]) -> Self {
+    let nodes = addrs
+      .iter()
+      .enumerate()
+      .map(|(idx, addr)| ClusterNode {
+        id:   format!("{:040x}", idx + 1),
+        host: addr.ip().to_string(),
+        port: addr.port(),
+      })
+      .collect();
+    let slots = (0 .. SLOTS).map(|slot| slot * addrs.len() / SLOTS).collect();
+
+    ClusterState {
+      nodes,
+      slots: RwLock::new(slots),
+      members: RwLock::new(Vec::new()),
+    }
+  }
+
+  /// Read the `CLUSTER SLOTS` response.
+  fn slots_reply(&self) -> Resp3Frame {
+    let slots = self.slots.read();
+    let mut ranges = Vec::new();
+    let mut start = 0;
+
+    for slot in 1 ..= SLOTS {
+      if slot == SLOTS || slots[slot] != slots[start] {
+        let node = &self.nodes[slots[start]];
+        ranges.push(array(vec![
+          int(start as i64),
+          int(slot as i64 - 1),
+          array(vec![
+            blob(node.host.clone()),
+            int(node.port as i64),
+            blob(node.id.clone()),
+            array(vec![]),
+          ]),
+        ]));
+        start = slot;
+      }
+    }
+
+    array(ranges)
+  }
+
+  /// Read the `CLUSTER NODES` response.
+  fn nodes_reply(&self, myself: usize) -> Resp3Frame {
+    let slots = self.slots.read();
+    let mut out = String::new();
+
+    for (idx, node) in self.nodes.iter().enumerate() {
+      let flags = if idx == myself { "myself,master" } else { "master" };
+      out.push_str(&format!(
+        "{} {}:{}@{} {} - 0 0 {} connected",
+        node.id,
+        node.host,
+        node.port,
+        node.port as u32 + 10000,
+        flags,
+        idx + 1
+      ));
+
+      let mut start = None;
+      for slot in 0 ..= SLOTS {
+        let owned = slot < SLOTS && slots[slot] == idx;
+        match (start, owned) {
+          (None, true) => start = Some(slot),
+          (Some(first), false) => {
+            if first == slot - 1 {
+              out.push_str(&format!(" {}", first));
+            } else {
+              out.push_str(&format!(" {}-{}", first, slot - 1));
+            }
+            start = None;
+          },
+          _ => {},
+        }
+      }
+      out.push('\n');
+    }
+
+    blob(out)
+  }
+}
+
+/// State shared by the connections on a server.
+struct Shared {
+  config:   FakeConfig,
+  keyspace: Mutex<Keyspace>,
+  clients:  Mutex<HashMap<u64, ClientHandle>>,
+  next_id:  AtomicU64,
+  port:     u16,
+  cluster:  Option<(Arc<ClusterState>, usize)>,
+}
+
+impl Shared {
+  fn new(config: FakeConfig, port: u16, cluster: Option<(Arc<ClusterState>, usize)>) -> Self {
+    Shared {
+      config,
+      port,
+      cluster,
+      keyspace: Mutex::new(Keyspace::default()),
+      clients: Mutex::new(HashMap::new()),
+      next_id: AtomicU64::new(1),
+    }
+  }
+
+  fn is_clustered(&self) -> bool {
+    self.cluster.is_some()
+  }
+
+  /// Return a `MOVED` or `CROSSSLOT` error if the command's keys are not served by this node.
+  fn check_slots(&self, cmd: &str, args: &[Bytes]) -> Result<(), Resp3Frame> {
+    let (cluster, myself) = match self.cluster {
+      Some((ref cluster, myself)) => (cluster, myself),
+      None => return Ok(()),
+    };
+
+    let mut slot = None;
+    for key in command_keys(cmd, args) {
+      let key_slot = redis_keyslot(key);
+      if slot.map(|slot| slot != key_slot).unwrap_or(false) {
+        return Err(error("CROSSSLOT Keys in request don't hash to the same slot"));
+      }
+      slot = Some(key_slot);
+    }
+
+    if let Some(slot) = slot {
+      let owner = cluster.slots.read()[slot as usize];
+      if owner != myself {
+        let node = &cluster.nodes[owner];
+        return Err(error(format!("MOVED {} {}:{}", slot, node.host, node.port)));
+      }
+    }
+    Ok(())
+  }
+
+  /// Send a message to subscribers on this node, returning the number of receivers.
+  fn publish_local(&self, channel: &Bytes, message: &Bytes) -> i64 {
+    let clients = self.clients.lock();
+    let mut receivers = 0;
+
+    for client in clients.values() {
+      if client.channels.contains(channel) {
+        let frame = push(vec![blob("message"), blob(channel.clone()), blob(message.clone())]);
+        let _ = client.tx.send(Message::Frame(frame));
+        receivers += 1;
+      }
+      for pattern in client.patterns.iter() {
+        if glob_match(pattern, channel) {
+          let frame = push(vec![
+            blob("pmessage"),
+            blob(pattern.clone()),
+            blob(channel.clone()),
+            blob(message.clone()),
+          ]);
+          let _ = client.tx.send(Message::Frame(frame));
+          receivers += 1;
+        }
+      }
+    }
+
+    receivers
+  }
+
+  /// Send a message to subscribers on every node in the cluster, or on this node if the server is centralized.
+  fn publish(&self, channel: &Bytes, message: &Bytes) -> i64 {
+    match self.cluster {
+      Some((ref cluster, _)) => {
+        let members: Vec<_> = cluster.members.read().iter().filter_map(Weak::upgrade).collect();
+        members
+          .iter()
+          .map(|shared| shared.publish_local(channel, message))
+          .sum()
+      },
+      None => self.publish_local(channel, message),
+    }
+  }
+
+  /// Send a message to shard channel subscribers on this node, returning the number of receivers.
+  fn spublish(&self, channel: &Bytes, message: &Bytes) -> i64 {
+    let clients = self.clients.lock();
+    let mut receivers = 0;
+
+    for client in clients.values() {
+      if client.shard_channels.contains(channel) {
+        let frame = push(vec![blob("smessage"), blob(channel.clone()), blob(message.clone())]);
+        let _ = client.tx.send(Message::Frame(frame));
+        receivers += 1;
+      }
+    }
+
+    receivers
+  }
+
+  /// Close all client connections, returning the number of connections that were closed.
+  fn kill_connections(&self) -> usize {
+    let clients = self.clients.lock();
+    for client in clients.values() {
+      let _ = client.tx.send(Message::Close);
+    }
+
+    clients.len()
+  }
+
+  /// Start processing commands from a new client connection.
+  fn spawn_connection<T>(self: &Arc<Self>, socket: T)
+  where
+    T: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+  {
+    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+    let (tx, rx) = unbounded_channel();
+    self.clients.lock().insert(id, ClientHandle {
+      tx,
+      name: None,
+      channels: BTreeSet::new(),
+      patterns: BTreeSet::new(),
+      shard_channels: BTreeSet::new(),
+    });
+
+    tokio::spawn(connection::run(self.clone(), socket, id, rx));
+  }
+}
+
+/// Accept TCP connections until the task is aborted.
+fn spawn_tcp_listener(shared: Arc<Shared>, listener: TcpListener) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    loop {
+      match listener.accept().await {
+        Ok((socket, addr)) => {
+          debug!("Fake server on port {} accepted connection from {}", shared.port, addr);
+          let _ = socket.set_nodelay(true);
+          shared.spawn_connection(socket);
+        },
+        Err(e) => {
+          debug!(
+            "Fake server on port {} failed to accept connection: {:?}",
+            shared.port, e
+          );
+        },
+      }
+    }
+  })
+}
+
+/// Accept Unix socket connections until the task is aborted.
+#[cfg(unix)]
+fn spawn_unix_listener(shared: Arc<Shared>, listener: UnixListener) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    loop {
+      match listener.accept().await {
+        Ok((socket, _)) => shared.spawn_connection(socket),
+        Err(e) => debug!("Fake server failed to accept Unix socket connection: {:?}", e),
+      }
+    }
+  })
+}
+
+/// An in-process fake Redis server.
+///
+/// The server stops accepting connections and closes existing connections when dropped.
+pub struct Server {
+  shared:     Arc<Shared>,
+  local_addr: Option<SocketAddr>,
+  #[cfg(unix)]
+  path:       Option<PathBuf>,
+  task:       JoinHandle<()>,
+}
+
+impl Server {
+  /// Start a server with the default config on a random local port.
+  pub async fn start() -> Result<Server, RedisError> {
+    Server::bind("127.0.0.1:0", FakeConfig::default()).await
+  }
+
+  /// Start a server on the provided TCP address.
+  pub async fn bind<A>(addr: A, config: FakeConfig) -> Result<Server, RedisError>
+  where
+    A: ToSocketAddrs,
+  {
+    let listener = TcpListener::bind(addr).await?;
+    Server::from_listener(listener, config, None)
+  }
+
+  /// Start a server on the provided Unix socket path.
+  ///
+  /// Note: the client does not support Unix sockets, so [redis_config](Self::redis_config) returns `None` for these
+  /// servers.
+  #[cfg(unix)]
+  #[cfg_attr(docsrs, doc(cfg(unix)))]
+  pub async fn bind_unix<P>(path: P, config: FakeConfig) -> Result<Server, RedisError>
+  where
+    P: AsRef<Path>,
+  {
+    let listener = UnixListener::bind(path.as_ref())?;
+    let shared = Arc::new(Shared::new(config, 0, None));
+    let task = spawn_unix_listener(shared.clone(), listener);
+
+    Ok(Server {
+      shared,
+      task,
+      local_addr: None,
+      path: Some(path.as_ref().to_path_buf()),
+    })
+  }
+
+  fn from_listener(
+    listener: TcpListener,
+    config: FakeConfig,
+    cluster: Option<(Arc<ClusterState>, usize)>,
+  ) -> Result<Server, RedisError> {
+    let local_addr = listener.local_addr()?;
+    let shared = Arc::new(Shared::new(config, local_addr.port(), cluster));
+    let task = spawn_tcp_listener(shared.clone(), listener);
+
+    Ok(Server {
+      shared,
+      task,
+      local_addr: Some(local_addr),
+      #[cfg(unix)]
+      path: None,
+    })
+  }
+
+  /// Read the address of the TCP listener, or `None` if the server listens on a Unix socket.
+  pub fn local_addr(&self) -> Option<SocketAddr> {
+    self.local_addr
+  }
+
+  /// Read the path of the Unix socket, or `None` if the server listens on a TCP port.
+  #[cfg(unix)]
+  #[cfg_attr(docsrs, doc(cfg(unix)))]
+  pub fn path(&self) -> Option<&Path> {
+    self.path.as_deref()
+  }
+
+  /// Create a client config that connects to this server.
+  ///
+  /// Returns `None` if the server listens on a Unix socket.
+  pub fn redis_config(&self) -> Option<RedisConfig> {
+    self.local_addr.map(|addr| RedisConfig {
+      server: ServerConfig::new_centralized(addr.ip().to_string(), addr.port()),
+      username: self
+        .shared
+        .config
+        .password
+        .as_ref()
+        .map(|_| self.shared.config.username.clone()),
+      password: self.shared.config.password.clone(),
+      ..Default::default()
+    })
+  }
+
+  /// Read the number of open client connections.
+  pub fn connections(&self) -> usize {
+    self.shared.clients.lock().len()
+  }
+
+  /// Read the names set by clients via `CLIENT SETNAME` or `HELLO`.
+  pub fn client_names(&self) -> Vec<String> {
+    self
+      .shared
+      .clients
+      .lock()
+      .values()
+      .filter_map(|client| client.name.as_ref())
+      .map(|name| String::from_utf8_lossy(name).into_owned())
+      .collect()
+  }
+
+  /// Close all client connections, returning the number of connections that were closed.
+  ///
+  /// The server continues to accept new connections, so clients will reconnect according to their reconnect policy.
+  pub fn kill_connections(&self) -> usize {
+    self.shared.kill_connections()
+  }
+
+  /// Remove all keys from all databases.
+  pub fn flush_all(&self) {
+    self.shared.keyspace.lock().flush_all();
+  }
+
+  /// Stop accepting new connections and close all existing connections.
+  pub fn shutdown(&self) {
+    self.task.abort();
+    self.shared.kill_connections();
+  }
+}
+
+impl Drop for Server {
+  fn drop(&mut self) {
+    self.shutdown();
+
+    #[cfg(unix)]
+    if let Some(ref path) = self.path {
+      let _ = std::fs::remove_file(path);
+    }
+  }
+}
+
+/// A set of fake servers that act as a cluster.
+///
+/// Hash slots are split evenly between the nodes, and each node returns `MOVED` errors for keys in slots that it does
+/// not own. Slots can be moved between nodes via [move_slot](Self::move_slot) in order to test how clients handle
+/// cluster changes.
+///
+/// ```rust no_run
+/// use fred::{fake::Cluster, prelude::*, util::redis_keyslot};
+///
+/// #[tokio::test]
+/// async fn should_follow_moved_errors() -> Result<(), RedisError> {
+///   let cluster = Cluster::start(3).await?;
+///   let client = Builder::from_config(cluster.redis_config()).build()?;
+///   client.init().await?;
+///
+///   client.set("foo", "bar", None, None, false).await?;
+///   let slot = redis_keyslot(b"foo");
+///   cluster.move_slot(slot, (cluster.slot_owner(slot) + 1) % 3)?;
+///
+///   // the client follows the `MOVED` error and updates its cached cluster state
+///   assert_eq!(client.get::<String, _>("foo").await?, "bar");
+///   Ok(())
+/// }
+/// ```
+pub struct Cluster {
+  state: Arc<ClusterState>,
+  nodes: Vec<Server>,
+}
+
+impl Cluster {
+  /// Start a cluster with the default config and `nodes` primary nodes on random local ports.
+  pub async fn start(nodes: usize) -> Result<Cluster, RedisError> {
+    Cluster::start_with_config(nodes, FakeConfig::default()).await
+  }
+
+  /// Start a cluster with `nodes` primary nodes on random local ports.
+  pub async fn start_with_config(nodes: usize, config: FakeConfig) -> Result<Cluster, RedisError> {
+    if nodes == 0 {
+      return Err(RedisError::new(
+        RedisErrorKind::InvalidArgument,
+        "A cluster requires at least one node.",
+      ));
+    }
+
+    let mut listeners = Vec::with_capacity(nodes);
+    for _ in 0 .. nodes {
+      listeners.push(TcpListener::bind("127.0.0.1:0").await?);
+    }
+    let addrs = listeners
+      .iter()
+      .map(|listener| listener.local_addr())
+      .collect::<Result<Vec<_>, _>>()?;
+    let state = Arc::new(ClusterState::new(&addrs));
+
+    let nodes = listeners
+      .into_iter()
+      .enumerate()
+      .map(|(idx, listener)| Server::from_listener(listener, config.clone(), Some((state.clone(), idx))))
+      .collect::<Result<Vec<_>, _>>()?;
+    *state.members.write() = nodes.iter().map(|node| Arc::downgrade(&node.shared)).collect();
+
+    Ok(Cluster { state, nodes })
+  }
+
+  /// Read the servers in the cluster.
+  pub fn nodes(&self) -> &[Server] {
+    &self.nodes
+  }
+
+  /// Create a client config that connects to this cluster.
+  pub fn redis_config(&self) -> RedisConfig {
+    let hosts = self
+      .state
+      .nodes
+      .iter()
+      .map(|node| (node.host.clone(), node.port))
+      .collect();
+    let config = &self.nodes[0].shared.config;
+
+    RedisConfig {
+      server: ServerConfig::new_clustered(hosts),
+      username: config.password.as_ref().map(|_| config.username.clone()),
+      password: config.password.clone(),
+      ..Default::default()
+    }
+  }
+
+  /// Read the index of the node that owns `slot`.
+  pub fn slot_owner(&self, slot: u16) -> usize {
+    self.state.slots.read()[slot as usize % SLOTS]
+  }
+
+  /// Move `slot` and the keys in it to the node at index `node`.
+  ///
+  /// Subsequent commands on the slot sent to the previous owner will receive a `MOVED` error.
+  pub fn move_slot(&self, slot: u16, node: usize) -> Result<(), RedisError> {
+    if slot as usize >= SLOTS || node >= self.nodes.len() {
+      return Err(RedisError::new(
+        RedisErrorKind::InvalidArgument,
+        "Invalid slot or node index.",
+      ));
+    }
+
+    let mut slots = self.state.slots.write();
+    let owner = slots[slot as usize];
+    if owner != node {
+      let keys = self.nodes[owner]
+        .shared
+        .keyspace
+        .lock()
+        .take_keys(|key| redis_keyslot(key) == slot);
+      self.nodes[node].shared.keyspace.lock().insert_keys(keys);
+      slots[slot as usize] = node;
+    }
+
+    Ok(())
+  }
+
+  /// Close all client connections on all nodes, returning the number of connections that were closed.
+  pub fn kill_connections(&self) -> usize {
+    self.nodes.iter().map(|node| node.kill_connections()).sum()
+  }
+
+  /// Remove all keys from all nodes.
+  pub fn flush_all(&self) {
+    for node in self.nodes.iter() {
+      node.flush_all();
+    }
+  }
+
+  /// Stop accepting new connections and close all existing connections on all nodes.
+  pub fn shutdown(&self) {
+    for node in self.nodes.iter() {
+      node.shutdown();
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    clients::RedisClient,
+    interfaces::*,
+    types::{Expiration, ReconnectPolicy, RedisValue, RespNegotiation, RespVersion},
+  };
+  use std::time::Duration;
+
+  async fn create_client(config: RedisConfig) -> RedisClient {
+    let policy = ReconnectPolicy::new_constant(0, 10);
+    let client = RedisClient::new(config, None, None, Some(policy));
+    let _ = client.connect();
+    client.wait_for_connect().await.expect("Failed to connect");
+    client
+  }
+
+  #[tokio::test]
+  async fn should_read_and_write_values() {
+    let server = Server::start().await.unwrap();
+    let client = create_client(server.redis_config().unwrap()).await;
+
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    assert_eq!(client.incr::<i64, _>("count").await.unwrap(), 1);
+
+    let _: () = client.hset("hash", ("a", 1)).await.unwrap();
+    assert_eq!(client.hget::<i64, _, _>("hash", "a").await.unwrap(), 1);
+    let _: () = client.rpush("list", vec![1, 2, 3]).await.unwrap();
+    assert_eq!(client.lrange::<Vec<i64>, _>("list", 0, -1).await.unwrap(), vec![
+      1, 2, 3
+    ]);
+    let _: () = client
+      .zadd("zset", None, None, false, false, vec![(2.0, "b"), (1.0, "a")])
+      .await
+      .unwrap();
+    assert_eq!(
+      client
+        .zrange::<Vec<String>, _, _, _>("zset", 0, -1, None, false, None, false)
+        .await
+        .unwrap(),
+      vec!["a", "b"]
+    );
+
+    let _: () = client
+      .set("foo", "bar", Some(Expiration::PX(10)), None, false)
+      .await
+      .unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(client.get::<Option<String>, _>("foo").await.unwrap(), None);
+    assert_eq!(
+      client.get::<RedisValue, _>("hash").await.unwrap_err().details(),
+      "WRONGTYPE Operation against a key holding the wrong kind of value"
+    );
+  }
+
+  #[tokio::test]
+  async fn should_authenticate_with_resp3() {
+    let server = Server::bind("127.0.0.1:0", FakeConfig {
+      password: Some("bar".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+    let mut config = server.redis_config().unwrap();
+    config.version = RespVersion::RESP3;
+    let client = create_client(config.clone()).await;
+
+    let _: () = client.hset("foo", ("a", "b")).await.unwrap();
+    let value: HashMap<String, String> = client.hgetall("foo").await.unwrap();
+    assert_eq!(value.get("a").map(|s| s.as_str()), Some("b"));
+
+    config.password = Some("baz".into());
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    assert!(client.wait_for_connect().await.is_err());
+  }
+
+  #[tokio::test]
+  async fn should_fall_back_to_resp2_without_hello() {
+    let server = Server::bind("127.0.0.1:0", FakeConfig {
+      password: Some("bar".into()),
+      version: "5.0.0".into(),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+    let mut config = server.redis_config().unwrap();
+    config.negotiation = RespNegotiation::Auto;
+    let client = create_client(config.clone()).await;
+    assert_eq!(client.protocol_version(), RespVersion::RESP2);
+
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+
+    // the version is negotiated again when reconnecting
+    let mut reconnect_rx = client.reconnect_rx();
+    assert_eq!(server.kill_connections(), 1);
+    let _ = tokio::time::timeout(Duration::from_secs(5), reconnect_rx.recv())
+      .await
+      .unwrap();
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    assert_eq!(client.protocol_version(), RespVersion::RESP2);
+
+    config.negotiation = RespNegotiation::Fixed;
+    config.version = RespVersion::RESP3;
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    assert!(client.wait_for_connect().await.is_err());
+  }
+
+  #[tokio::test]
+  async fn should_negotiate_resp3_on_reconnect() {
+    let server = Server::bind("127.0.0.1:0", FakeConfig {
+      password: Some("bar".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+    let mut config = server.redis_config().unwrap();
+    config.negotiation = RespNegotiation::Auto;
+    let client = create_client(config.clone()).await;
+    assert_eq!(client.protocol_version(), RespVersion::RESP3);
+
+    let mut reconnect_rx = client.reconnect_rx();
+    assert_eq!(server.kill_connections(), 1);
+    let _ = tokio::time::timeout(Duration::from_secs(5), reconnect_rx.recv())
+      .await
+      .unwrap();
+    let _: () = client.hset("foo", ("a", "b")).await.unwrap();
+    let value: HashMap<String, String> = client.hgetall("foo").await.unwrap();
+    assert_eq!(value.get("a").map(|s| s.as_str()), Some("b"));
+    assert_eq!(client.protocol_version(), RespVersion::RESP3);
+
+    let _: () = client.hello(RespVersion::RESP2, None).await.unwrap();
+    assert_eq!(client.protocol_version(), RespVersion::RESP2);
+    assert_eq!(client.hget::<String, _, _>("foo", "a").await.unwrap(), "b");
+
+    // authentication errors are not retried with AUTH
+    config.password = Some("baz".into());
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    assert!(client.wait_for_connect().await.is_err());
+  }
+
+  #[tokio::test]
+  async fn should_negotiate_again_after_failing_over_to_an_older_server() {
+    let server = Server::start().await.unwrap();
+    let addr = server.local_addr().unwrap();
+    let mut config = server.redis_config().unwrap();
+    config.negotiation = RespNegotiation::Auto;
+    let client = create_client(config).await;
+    assert_eq!(client.protocol_version(), RespVersion::RESP3);
+
+    let mut reconnect_rx = client.reconnect_rx();
+    drop(server);
+    let mut server = None;
+    for _ in 0 .. 50 {
+      let fake_config = FakeConfig {
+        version: "5.0.0".into(),
+        ..Default::default()
+      };
+      if let Ok(s) = Server::bind(addr, fake_config).await {
+        server = Some(s);
+        break;
+      }
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(server.is_some());
+    let _ = tokio::time::timeout(Duration::from_secs(5), reconnect_rx.recv())
+      .await
+      .unwrap();
+
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    assert_eq!(client.protocol_version(), RespVersion::RESP2);
+  }
+
+  #[tokio::test]
+  async fn should_publish_messages() {
+    let server = Server::start().await.unwrap();
+    let subscriber = create_client(server.redis_config().unwrap()).await;
+    let publisher = create_client(server.redis_config().unwrap()).await;
+
+    let mut rx = subscriber.on_message();
+    let _: () = subscriber.subscribe("foo").await.unwrap();
+    let receivers: i64 = publisher.publish("foo", "bar").await.unwrap();
+    assert_eq!(receivers, 1);
+
+    let message = rx.recv().await.unwrap();
+    assert_eq!(message.channel, "foo");
+    assert_eq!(message.value.as_str().unwrap(), "bar");
+  }
+
+  #[tokio::test]
+  async fn should_run_transactions() {
+    let server = Server::start().await.unwrap();
+    let client = create_client(server.redis_config().unwrap()).await;
+
+    let trx = client.multi();
+    let _: () = trx.incr("foo").await.unwrap();
+    let _: () = trx.incr("foo").await.unwrap();
+    let results: Vec<i64> = trx.exec(true).await.unwrap();
+    assert_eq!(results, vec![1, 2]);
+  }
+
+  #[tokio::test]
+  async fn should_reconnect_after_killing_connections() {
+    let server = Server::start().await.unwrap();
+    let client = create_client(server.redis_config().unwrap()).await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+
+    let mut reconnect_rx = client.reconnect_rx();
+    assert_eq!(server.kill_connections(), 1);
+    let _ = tokio::time::timeout(Duration::from_secs(5), reconnect_rx.recv())
+      .await
+      .unwrap();
+
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+  }
+
+  #[tokio::test]
+  async fn should_follow_moved_errors() {
+    let cluster = Cluster::start(3).await.unwrap();
+    let client = create_client(cluster.redis_config()).await;
+
+    for idx in 0 .. 10 {
+      let _: () = client.set(format!("foo{}", idx), idx, None, None, false).await.unwrap();
+    }
+    let slot = redis_keyslot(b"foo1");
+    cluster.move_slot(slot, (cluster.slot_owner(slot) + 1) % 3).unwrap();
+
+    for idx in 0 .. 10 {
+      assert_eq!(client.get::<i64, _>(format!("foo{}", idx)).await.unwrap(), idx);
+    }
+    let error = client.mget::<RedisValue, _>(vec!["foo1", "foo2"]).await.unwrap_err();
+    assert!(error.details().starts_with("CROSSSLOT"));
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn should_accept_unix_socket_connections() {
+    use crate::codec::{Resp2, Resp2Frame};
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::UnixStream;
+    use tokio_util::codec::Framed;
+
+    let path = std::env::temp_dir().join(format!("fred-fake-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let server = Server::bind_unix(&path, FakeConfig::default()).await.unwrap();
+    assert!(server.redis_config().is_none());
+
+    let socket = UnixStream::connect(&path).await.unwrap();
+    let mut framed = Framed::new(socket, Resp2::default());
+    framed
+      .send(Resp2Frame::Array(vec![Resp2Frame::BulkString("PING".into())]))
+      .await
+      .unwrap();
+    assert_eq!(
+      framed.next().await.unwrap().unwrap(),
+      Resp2Frame::SimpleString("PONG".into())
+    );
+  }
+}
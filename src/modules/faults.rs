@@ -0,0 +1,550 @@
+//! A fault injection interface for resilience testing.
+//!
+//! Faults are configured as a list of [FaultRule] values on a shared [Faults] struct that is provided via the
+//! [RedisConfig](crate::types::RedisConfig). The usual reconnection, cluster redirection, and retry logic reacts to
+//! each fault as if the server had misbehaved.
+//!
+//! Error faults (`MOVED`, `ASK`, `TRYAGAIN`, `CLUSTERDOWN`, and `LOADING`) are injected instead of writing the
+//! command, so the server never runs a command that receives one of these errors. Network faults (latency, dropped
+//! connections, and stalls) are applied as response frames are read from the socket.
+//!
+//! ```rust no_run
+//! # use fred::{prelude::*, faults::{Fault, FaultRule, Faults, Trigger}};
+//! # use std::{sync::Arc, time::Duration};
+//! async fn example() -> Result<(), RedisError> {
+//!   let faults = Arc::new(Faults::new());
+//!   // close the connection while waiting on every 10th `GET` response
+//!   faults.add(FaultRule {
+//!     trigger: Trigger::Every(10),
+//!     commands: vec!["GET".into()],
+//!     ..FaultRule::new(Fault::Drop)
+//!   });
+//!   // add 50 ms of latency to 5% of responses
+//!   faults.add(FaultRule {
+//!     trigger: Trigger::Probability(0.05),
+//!     ..FaultRule::new(Fault::Latency(Duration::from_millis(50)))
+//!   });
+//!
+//!   let config = RedisConfig {
+//!     faults: Some(faults.clone()),
+//!     ..Default::default()
+//!   };
+//!   let client = Builder::from_config(config).build()?;
+//!   let _ = client.connect();
+//!   client.wait_for_connect().await?;
+//!
+//!   for _ in 0 .. 100 {
+//!     let _: Option<String> = client.get("foo").await?;
+//!   }
+//!   println!("Injected {} faults", faults.injected());
+//!   Ok(())
+//! }
+//! ```
+//!
+//! Note: faults are not applied when a [mocking layer](crate::types::RedisConfig::mocks) is used, and error faults
+//! are not injected into blocking commands.
+
+use crate::{
+  error::{RedisError, RedisErrorKind},
+  modules::inner::RedisClientInner,
+  protocol::{
+    command::RedisCommand,
+    connection::{RedisWriter, SharedBuffer},
+    types::Server,
+  },
+  router::{centralized, clustered, responses, Written},
+};
+use bytes_utils::Str;
+use parking_lot::Mutex;
+use rand::Rng;
+use redis_protocol::resp3::types::Frame as Resp3Frame;
+use std::{
+  collections::VecDeque,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+  },
+  time::Duration,
+};
+
+/// A fault that can be injected in place of a response.
+///
+/// Error faults replace the response without writing the command to the server.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Fault {
+  /// Wait before processing the response. Later responses on the same connection are delayed as well.
+  Latency(Duration),
+  /// Close the connection instead of processing the response.
+  Drop,
+  /// Replace the response with a `MOVED` error that redirects to a different primary node, if possible.
+  Moved,
+  /// Replace the response with an `ASK` error that redirects to a different primary node, if possible.
+  Ask,
+  /// Replace the response with a `TRYAGAIN` error.
+  TryAgain,
+  /// Replace the response with a `CLUSTERDOWN` error.
+  ClusterDown,
+  /// Replace the response with a `LOADING` error.
+  Loading,
+  /// Stop processing responses on the connection until it is closed.
+  ///
+  /// Callers should typically use a command timeout or the `check-unresponsive` feature with this fault.
+  Stall,
+}
+
+/// The condition used to decide whether a matching response should be replaced with a fault.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Trigger {
+  /// Inject the fault on every matching response.
+  Always,
+  /// Inject the fault randomly with the provided probability, from `0.0` to `1.0`.
+  Probability(f64),
+  /// Inject the fault on every Nth matching response.
+  Every(usize),
+  /// Inject the fault on the provided matching responses, where `1` refers to the first matching response.
+  Schedule(Vec<usize>),
+}
+
+/// A rule describing which responses should be replaced with a fault.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FaultRule {
+  /// The fault to inject.
+  pub fault:    Fault,
+  /// The condition used to decide when the fault is injected.
+  ///
+  /// Default: `Trigger::Always`
+  pub trigger:  Trigger,
+  /// The commands to which the rule applies, such as `GET` or `XGROUP`. The comparison is case-insensitive and only
+  /// uses the first word of the command.
+  ///
+  /// Default: `Vec::new()` (all commands)
+  pub commands: Vec<Str>,
+  /// The servers to which the rule applies.
+  ///
+  /// Default: `Vec::new()` (all servers)
+  pub servers:  Vec<Server>,
+}
+
+impl Fault {
+  fn is_error(&self) -> bool {
+    matches!(
+      self,
+      Fault::Moved | Fault::Ask | Fault::TryAgain | Fault::ClusterDown | Fault::Loading
+    )
+  }
+}
+
+impl FaultRule {
+  /// Create a new rule that injects `fault` on every response.
+  pub fn new(fault: Fault) -> Self {
+    FaultRule {
+      fault,
+      trigger: Trigger::Always,
+      commands: Vec::new(),
+      servers: Vec::new(),
+    }
+  }
+
+  fn matches(&self, cmd: &str, server: &Server) -> bool {
+    (self.commands.is_empty() || self.commands.iter().any(|c| c.eq_ignore_ascii_case(cmd)))
+      && (self.servers.is_empty() || self.servers.contains(server))
+  }
+}
+
+#[derive(Debug)]
+struct RuleState {
+  rule:    FaultRule,
+  matched: usize,
+}
+
+impl RuleState {
+  fn should_inject(&mut self) -> bool {
+    self.matched += 1;
+
+    match self.rule.trigger {
+      Trigger::Always => true,
+      Trigger::Probability(p) => rand::thread_rng().gen::<f64>() < p,
+      Trigger::Every(n) => self.matched.checked_rem(n) == Some(0),
+      Trigger::Schedule(ref schedule) => schedule.contains(&self.matched),
+    }
+  }
+}
+
+/// A shared list of fault injection rules.
+///
+/// Rules can be added or removed while clients are running. When several rules match a response the first rule
+/// that triggers is used.
+#[derive(Debug, Default)]
+pub struct Faults {
+  rules:    Mutex<Vec<RuleState>>,
+  injected: AtomicUsize,
+}
+
+impl Faults {
+  /// Create an empty list of rules.
+  pub fn new() -> Self {
+    Faults::default()
+  }
+
+  /// Add a rule to the end of the list.
+  pub fn add(&self, rule: FaultRule) {
+    self.rules.lock().push(RuleState { rule, matched: 0 });
+  }
+
+  /// Remove all rules.
+  pub fn clear(&self) {
+    self.rules.lock().clear();
+  }
+
+  /// Read the number of faults that have been injected.
+  pub fn injected(&self) -> usize {
+    self.injected.load(Ordering::Acquire)
+  }
+
+  /// Find the fault, if any, that should replace the response to `cmd` from `server`.
+  ///
+  /// Error faults are checked before the command is written, and all other faults are checked as the response is
+  /// read.
+  fn check(&self, cmd: &str, server: &Server, before_write: bool) -> Option<Fault> {
+    let fault = self
+      .rules
+      .lock()
+      .iter_mut()
+      .filter(|state| state.rule.fault.is_error() == before_write && state.rule.matches(cmd, server))
+      .find_map(|state| {
+        if state.should_inject() {
+          Some(state.rule.fault.clone())
+        } else {
+          None
+        }
+      });
+
+    if fault.is_some() {
+      self.injected.fetch_add(1, Ordering::AcqRel);
+    }
+    fault
+  }
+}
+
+fn error_frame(data: String) -> Resp3Frame {
+  Resp3Frame::SimpleError {
+    data:       data.into(),
+    attributes: None,
+  }
+}
+
+/// Find a primary node other than `server` to use as the target of a redirection.
+fn redirection_target(inner: &Arc<RedisClientInner>, server: &Server) -> Server {
+  let primaries = inner
+    .with_cluster_state(|state| Ok(state.unique_primary_nodes()))
+    .unwrap_or_default();
+
+  match primaries.iter().position(|primary| primary == server) {
+    Some(idx) => primaries[(idx + 1) % primaries.len()].clone(),
+    None => primaries.first().cloned().unwrap_or_else(|| server.clone()),
+  }
+}
+
+/// Apply any configured error faults to `command` before it is written to `writer`.
+///
+/// If a fault is injected the command is not written. Instead, the injected error is processed as if it was the
+/// response from the server and the returned `Written` value should be used as the result of the write.
+pub(crate) async fn intercept(
+  inner: &Arc<RedisClientInner>,
+  writer: &RedisWriter,
+  mut command: RedisCommand,
+) -> Result<RedisCommand, Written> {
+  let faults = match inner.config.faults {
+    Some(ref faults) => faults,
+    None => return Ok(command),
+  };
+  if command.has_no_responses() || command.blocks_connection() {
+    return Ok(command);
+  }
+  let cmd = command.kind.cmd_str();
+  let fault = match faults.check(&cmd, &writer.server, true) {
+    Some(fault) => fault,
+    None => return Ok(command),
+  };
+
+  _debug!(
+    inner,
+    "Injecting {:?} instead of writing {} to {}",
+    fault,
+    cmd,
+    writer.server
+  );
+  let frame = match fault {
+    Fault::Moved | Fault::Ask => {
+      let slot = command.cluster_hash().unwrap_or(0);
+      let target = redirection_target(inner, &writer.server);
+      let kind = if fault == Fault::Moved { "MOVED" } else { "ASK" };
+      error_frame(format!("{} {} {}:{}", kind, slot, target.host, target.port))
+    },
+    Fault::TryAgain => error_frame("TRYAGAIN Multiple keys request during rehashing of slot".into()),
+    Fault::ClusterDown => error_frame("CLUSTERDOWN The cluster is down".into()),
+    Fault::Loading => error_frame("LOADING Redis is loading the dataset in memory".into()),
+    _ => return Ok(command),
+  };
+  command.write_attempts += 1;
+  if let Some(ref written_to) = command.written_to {
+    *written_to.lock() = Some(writer.server.clone());
+  }
+
+  if let Some(error) = responses::check_special_errors(inner, &frame) {
+    // the reader task closes the connection after these errors, and the command is retried after reconnecting
+    return Err(Written::Disconnected((
+      Some(writer.server.clone()),
+      Some(command),
+      error,
+    )));
+  }
+
+  let buffer: SharedBuffer = Arc::new(Mutex::new(VecDeque::from([command])));
+  writer.counters.incr_in_flight();
+  let result = if inner.config.server.is_clustered() {
+    clustered::process_response_frame(inner, &writer.server, &buffer, &writer.counters, frame).await
+  } else {
+    centralized::process_response_frame(inner, &writer.server, &buffer, &writer.counters, frame).await
+  };
+  if let Err(e) = result {
+    _debug!(inner, "Error processing injected fault for {}: {:?}", writer.server, e);
+  }
+
+  Err(Written::Sent((writer.server.clone(), true)))
+}
+
+/// Apply any configured network faults to a response frame read from `server`.
+///
+/// Returns `None` if the frame should not be processed, or an error if the connection should be closed.
+pub(crate) async fn apply(
+  inner: &Arc<RedisClientInner>,
+  server: &Server,
+  buffer: &SharedBuffer,
+  stalled: &mut bool,
+  frame: Resp3Frame,
+) -> Result<Option<Resp3Frame>, RedisError> {
+  if *stalled {
+    return Ok(None);
+  }
+  let faults = match inner.config.faults {
+    Some(ref faults) => faults,
+    None => return Ok(Some(frame)),
+  };
+  if frame.is_pubsub_message() {
+    return Ok(Some(frame));
+  }
+  let cmd = match buffer.lock().front() {
+    Some(command) => command.kind.cmd_str(),
+    None => return Ok(Some(frame)),
+  };
+  let fault = match faults.check(&cmd, server, false) {
+    Some(fault) => fault,
+    None => return Ok(Some(frame)),
+  };
+
+  _debug!(inner, "Injecting {:?} in response to {} from {}", fault, cmd, server);
+  match fault {
+    Fault::Latency(duration) => {
+      tokio::time::sleep(duration).await;
+      Ok(Some(frame))
+    },
+    Fault::Drop => Err(RedisError::new(
+      RedisErrorKind::IO,
+      "Connection closed by fault injection.",
+    )),
+    Fault::Stall => {
+      *stalled = true;
+      Ok(None)
+    },
+    _ => Ok(Some(frame)),
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "fake")]
+mod tests {
+  use super::*;
+  use crate::{
+    clients::RedisClient,
+    fake::{Cluster, Server as FakeServer},
+    interfaces::*,
+    types::{Options, ReconnectPolicy, RedisConfig},
+  };
+  use std::time::Instant;
+
+  async fn create_client(config: RedisConfig, faults: &Arc<Faults>) -> RedisClient {
+    let config = RedisConfig {
+      faults: Some(faults.clone()),
+      ..config
+    };
+    let policy = ReconnectPolicy::new_constant(0, 10);
+    let client = RedisClient::new(config, None, None, Some(policy));
+    let _ = client.connect();
+    client.wait_for_connect().await.expect("Failed to connect");
+    client
+  }
+
+  #[test]
+  fn should_trigger_on_schedule() {
+    let faults = Faults::new();
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![2, 3]),
+      commands: vec!["get".into()],
+      ..FaultRule::new(Fault::TryAgain)
+    });
+    let server = Server::new("127.0.0.1", 6379);
+
+    assert_eq!(faults.check("SET", &server, true), None);
+    assert_eq!(faults.check("GET", &server, true), None);
+    assert_eq!(faults.check("GET", &server, true), Some(Fault::TryAgain));
+    assert_eq!(faults.check("GET", &server, true), Some(Fault::TryAgain));
+    assert_eq!(faults.check("GET", &server, true), None);
+    assert_eq!(faults.injected(), 2);
+  }
+
+  #[tokio::test]
+  async fn should_retry_after_dropped_connection() {
+    let server = FakeServer::start().await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(server.redis_config().unwrap(), &faults).await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![1]),
+      commands: vec!["GET".into()],
+      ..FaultRule::new(Fault::Drop)
+    });
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    assert_eq!(faults.injected(), 1);
+  }
+
+  #[tokio::test]
+  async fn should_return_injected_errors() {
+    let server = FakeServer::start().await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(server.redis_config().unwrap(), &faults).await;
+
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![1]),
+      ..FaultRule::new(Fault::TryAgain)
+    });
+    let error = client.get::<Option<String>, _>("foo").await.unwrap_err();
+    assert!(error.details().starts_with("TRYAGAIN"));
+    assert_eq!(client.get::<Option<String>, _>("foo").await.unwrap(), None);
+  }
+
+  #[tokio::test]
+  async fn should_add_latency() {
+    let server = FakeServer::start().await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(server.redis_config().unwrap(), &faults).await;
+
+    faults.add(FaultRule::new(Fault::Latency(Duration::from_millis(50))));
+    let started = Instant::now();
+    let _: Option<String> = client.get("foo").await.unwrap();
+    assert!(started.elapsed() >= Duration::from_millis(50));
+  }
+
+  #[tokio::test]
+  async fn should_time_out_stalled_responses() {
+    let server = FakeServer::start().await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(server.redis_config().unwrap(), &faults).await;
+
+    faults.add(FaultRule {
+      commands: vec!["GET".into()],
+      ..FaultRule::new(Fault::Stall)
+    });
+    let error = client
+      .with_options(&Options {
+        timeout: Some(Duration::from_millis(50)),
+        ..Default::default()
+      })
+      .get::<Option<String>, _>("foo")
+      .await
+      .unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::Timeout);
+  }
+
+  #[tokio::test]
+  async fn should_follow_injected_redirections() {
+    let cluster = Cluster::start(3).await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(cluster.redis_config(), &faults).await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![1]),
+      commands: vec!["GET".into()],
+      ..FaultRule::new(Fault::Moved)
+    });
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![2]),
+      commands: vec!["GET".into()],
+      ..FaultRule::new(Fault::Ask)
+    });
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    assert_eq!(faults.injected(), 2);
+  }
+
+  #[tokio::test]
+  async fn should_redirect_to_different_node() {
+    let cluster = Cluster::start(3).await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(cluster.redis_config(), &faults).await;
+
+    let primaries = client
+      .inner()
+      .with_cluster_state(|state| Ok(state.unique_primary_nodes()))
+      .unwrap();
+    assert_eq!(primaries.len(), 3);
+    for primary in primaries.iter() {
+      assert_ne!(redirection_target(client.inner(), primary), *primary);
+    }
+  }
+
+  #[tokio::test]
+  async fn should_not_run_commands_after_injected_redirections() {
+    let cluster = Cluster::start(3).await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(cluster.redis_config(), &faults).await;
+
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![1]),
+      commands: vec!["INCR".into()],
+      ..FaultRule::new(Fault::Moved)
+    });
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![1]),
+      commands: vec!["LPUSH".into()],
+      ..FaultRule::new(Fault::Ask)
+    });
+    assert_eq!(client.incr::<i64, _>("foo").await.unwrap(), 1);
+    assert_eq!(client.lpush::<i64, _, _>("bar", "a").await.unwrap(), 1);
+    assert_eq!(faults.injected(), 2);
+  }
+
+  #[tokio::test]
+  async fn should_not_run_commands_after_injected_errors() {
+    let server = FakeServer::start().await.unwrap();
+    let faults = Arc::new(Faults::new());
+    let client = create_client(server.redis_config().unwrap(), &faults).await;
+
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![1]),
+      commands: vec!["INCR".into()],
+      ..FaultRule::new(Fault::TryAgain)
+    });
+    faults.add(FaultRule {
+      trigger: Trigger::Schedule(vec![1]),
+      commands: vec!["INCR".into()],
+      ..FaultRule::new(Fault::ClusterDown)
+    });
+    assert!(client.incr::<i64, _>("foo").await.is_err());
+    // the client reconnects and retries the command after `CLUSTERDOWN`
+    assert_eq!(client.incr::<i64, _>("foo").await.unwrap(), 1);
+    assert_eq!(faults.injected(), 2);
+  }
+}
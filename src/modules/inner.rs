@@ -2,7 +2,7 @@ use crate::{
   error::*,
   globals::globals,
   interfaces,
-  modules::backchannel::Backchannel,
+  modules::{backchannel::Backchannel, circuit_breaker::CircuitBreakers},
   protocol::{
     command::{ResponseSender, RouterCommand},
     connection::RedisTransport,
@@ -61,6 +61,8 @@ pub struct Notifications {
   pub cluster_change: ArcSwap<BroadcastSender<Vec<ClusterStateChange>>>,
   /// A broadcast channel for the `on_connect` interface.
   pub connect:        ArcSwap<BroadcastSender<Result<(), RedisError>>>,
+  /// A broadcast channel for the `on_circuit_breaker` interface.
+  pub circuit:        ArcSwap<BroadcastSender<CircuitBreakerEvent>>,
   /// A channel for events that should close all client tasks with `Canceled` errors.
   ///
   /// Emitted when QUIT, SHUTDOWN, etc are called.
@@ -86,6 +88,7 @@ impl Notifications {
       reconnect:                                           ArcSwap::new(Arc::new(broadcast::channel(capacity).0)),
       cluster_change:                                      ArcSwap::new(Arc::new(broadcast::channel(capacity).0)),
       connect:                                             ArcSwap::new(Arc::new(broadcast::channel(capacity).0)),
+      circuit:                                             ArcSwap::new(Arc::new(broadcast::channel(capacity).0)),
       #[cfg(feature = "client-tracking")]
       invalidations:                                       ArcSwap::new(Arc::new(broadcast::channel(capacity).0)),
       #[cfg(feature = "check-unresponsive")]
@@ -101,6 +104,7 @@ impl Notifications {
     utils::swap_new_broadcast_channel(&self.reconnect);
     utils::swap_new_broadcast_channel(&self.cluster_change);
     utils::swap_new_broadcast_channel(&self.connect);
+    utils::swap_new_broadcast_channel(&self.circuit);
     #[cfg(feature = "client-tracking")]
     utils::swap_new_broadcast_channel(&self.invalidations);
     #[cfg(feature = "check-unresponsive")]
@@ -143,6 +147,12 @@ impl Notifications {
     }
   }
 
+  pub fn broadcast_circuit_breaker(&self, event: CircuitBreakerEvent) {
+    if let Err(_) = self.circuit.load().send(event) {
+      debug!("{}: No `on_circuit_breaker` listeners.", self.id);
+    }
+  }
+
   /// Interrupt any tokio `sleep` calls.
   //`RedisClientInner::wait_with_interrupt` hides the subscription part from callers.
   pub fn broadcast_close(&self) {
@@ -168,15 +178,17 @@ impl Notifications {
 
 #[derive(Clone)]
 pub struct ClientCounters {
-  pub cmd_buffer_len:   Arc<AtomicUsize>,
-  pub redelivery_count: Arc<AtomicUsize>,
+  pub cmd_buffer_len:      Arc<AtomicUsize>,
+  pub redelivery_count:    Arc<AtomicUsize>,
+  pub retry_skipped_count: Arc<AtomicUsize>,
 }
 
 impl Default for ClientCounters {
   fn default() -> Self {
     ClientCounters {
-      cmd_buffer_len:   Arc::new(AtomicUsize::new(0)),
-      redelivery_count: Arc::new(AtomicUsize::new(0)),
+      cmd_buffer_len:      Arc::new(AtomicUsize::new(0)),
+      redelivery_count:    Arc::new(AtomicUsize::new(0)),
+      retry_skipped_count: Arc::new(AtomicUsize::new(0)),
     }
   }
 }
@@ -194,6 +206,10 @@ impl ClientCounters {
     utils::incr_atomic(&self.redelivery_count)
   }
 
+  pub fn incr_retry_skipped_count(&self) -> usize {
+    utils::incr_atomic(&self.retry_skipped_count)
+  }
+
   pub fn read_cmd_buffer_len(&self) -> usize {
     utils::read_atomic(&self.cmd_buffer_len)
   }
@@ -202,6 +218,10 @@ impl ClientCounters {
     utils::read_atomic(&self.redelivery_count)
   }
 
+  pub fn read_retry_skipped_count(&self) -> usize {
+    utils::read_atomic(&self.retry_skipped_count)
+  }
+
   pub fn take_cmd_buffer_len(&self) -> usize {
     utils::set_atomic(&self.cmd_buffer_len, 0)
   }
@@ -210,9 +230,14 @@ impl ClientCounters {
     utils::set_atomic(&self.redelivery_count, 0)
   }
 
+  pub fn take_retry_skipped_count(&self) -> usize {
+    utils::set_atomic(&self.retry_skipped_count, 0)
+  }
+
   pub fn reset(&self) {
     utils::set_atomic(&self.cmd_buffer_len, 0);
     utils::set_atomic(&self.redelivery_count, 0);
+    utils::set_atomic(&self.retry_skipped_count, 0);
   }
 }
 
@@ -383,35 +408,40 @@ fn create_resolver(id: &Str) -> Arc<dyn Resolve> {
 
 pub struct RedisClientInner {
   /// An internal lock used to sync certain operations that should not run concurrently across tasks.
-  pub _lock:         Mutex<()>,
+  pub _lock:            Mutex<()>,
   /// The client ID used for logging and the default `CLIENT SETNAME` value.
-  pub id:            Str,
+  pub id:               Str,
   /// Whether the client uses RESP3.
-  pub resp3:         Arc<AtomicBool>,
+  pub resp3:            Arc<AtomicBool>,
+  /// The protocol version negotiated with `HELLO` when the config specifies `RespNegotiation::Auto`, cleared when
+  /// the connections are closed.
+  pub negotiated:       RwLock<Option<RespVersion>>,
   /// The state of the underlying connection.
-  pub state:         RwLock<ClientState>,
+  pub state:            RwLock<ClientState>,
   /// Client configuration options.
-  pub config:        Arc<RedisConfig>,
+  pub config:           Arc<RedisConfig>,
   /// Connection configuration options.
-  pub connection:    Arc<ConnectionConfig>,
+  pub connection:       Arc<ConnectionConfig>,
   /// Performance config options for the client.
-  pub performance:   ArcSwap<PerformanceConfig>,
+  pub performance:      ArcSwap<PerformanceConfig>,
   /// An optional reconnect policy.
-  pub policy:        RwLock<Option<ReconnectPolicy>>,
+  pub policy:           RwLock<Option<ReconnectPolicy>>,
   /// Notification channels for the event interfaces.
-  pub notifications: Arc<Notifications>,
+  pub notifications:    Arc<Notifications>,
   /// An mpsc sender for commands to the router.
-  pub command_tx:    ArcSwap<CommandSender>,
+  pub command_tx:       ArcSwap<CommandSender>,
   /// Temporary storage for the receiver half of the router command channel.
-  pub command_rx:    RwLock<Option<CommandReceiver>>,
+  pub command_rx:       RwLock<Option<CommandReceiver>>,
   /// Shared counters.
-  pub counters:      ClientCounters,
+  pub counters:         ClientCounters,
   /// The DNS resolver to use when establishing new connections.
-  pub resolver:      AsyncRwLock<Arc<dyn Resolve>>,
+  pub resolver:         AsyncRwLock<Arc<dyn Resolve>>,
   /// A backchannel that can be used to control the router connections even while the connections are blocked.
-  pub backchannel:   Arc<AsyncRwLock<Backchannel>>,
+  pub backchannel:      Arc<AsyncRwLock<Backchannel>>,
   /// Server state cache for various deployment types.
-  pub server_state:  RwLock<ServerState>,
+  pub server_state:     RwLock<ServerState>,
+  /// Circuit breaker state for each server.
+  pub circuit_breakers: CircuitBreakers,
 
   /// Command latency metrics.
   #[cfg(feature = "metrics")]
@@ -457,7 +487,7 @@ impl RedisClientInner {
     let command_rx = RwLock::new(Some(command_rx));
     let backchannel = Arc::new(AsyncRwLock::new(Backchannel::default()));
     let server_state = RwLock::new(ServerState::new(&config));
-    let resp3 = if config.version == RespVersion::RESP3 {
+    let resp3 = if config.version == RespVersion::RESP3 || config.negotiation == RespNegotiation::Auto {
       Arc::new(AtomicBool::new(true))
     } else {
       Arc::new(AtomicBool::new(false))
@@ -478,6 +508,7 @@ impl RedisClientInner {
       #[cfg(feature = "check-unresponsive")]
       network_timeouts: NetworkTimeout::new(),
 
+      circuit_breakers: CircuitBreakers::default(),
       backchannel,
       command_rx,
       server_state,
@@ -488,6 +519,7 @@ impl RedisClientInner {
       performance,
       policy,
       resp3,
+      negotiated: RwLock::new(None),
       notifications,
       resolver,
       connection,
@@ -622,6 +654,40 @@ impl RedisClientInner {
     };
   }
 
+  /// Switch protocol versions after a `HELLO` command, using the new version for later connections until the client
+  /// negotiates again.
+  pub fn record_protocol_version(&self, version: RespVersion) {
+    if self.config.negotiation == RespNegotiation::Auto {
+      *self.negotiated.write() = Some(version.clone());
+    }
+    self.switch_protocol_versions(version);
+  }
+
+  /// Read the protocol version negotiated with `HELLO`, if any.
+  pub fn negotiated_version(&self) -> Option<RespVersion> {
+    self.negotiated.read().clone()
+  }
+
+  /// Switch to and record the protocol version negotiated with `HELLO` when the config specifies `Auto`.
+  ///
+  /// The connections share one codec version, so connections added while others are open use the version negotiated
+  /// by the first connection. Returns whether the provided version matches the negotiated version.
+  pub fn negotiate_protocol_version(&self, version: RespVersion) -> bool {
+    if self.config.negotiation == RespNegotiation::Auto {
+      let mut guard = self.negotiated.write();
+      let negotiated = guard.get_or_insert(version.clone()).clone();
+      self.switch_protocol_versions(negotiated.clone());
+      negotiated == version
+    } else {
+      true
+    }
+  }
+
+  /// Clear the negotiated protocol version so that the next connection negotiates again.
+  pub fn reset_negotiated_version(&self) {
+    self.negotiated.write().take();
+  }
+
   pub fn update_performance_config(&self, config: PerformanceConfig) {
     self.performance.store(Arc::new(config));
   }
@@ -639,9 +705,9 @@ impl RedisClientInner {
   }
 
   pub fn reset_protocol_version(&self) {
-    let resp3 = match self.config.version {
-      RespVersion::RESP3 => true,
-      RespVersion::RESP2 => false,
+    let resp3 = match self.config.negotiation {
+      RespNegotiation::Fixed => self.config.version == RespVersion::RESP3,
+      RespNegotiation::Auto => self.negotiated_version() != Some(RespVersion::RESP2),
     };
 
     utils::set_bool_atomic(&self.resp3, resp3);
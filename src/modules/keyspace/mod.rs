@@ -0,0 +1,1234 @@
+//! An in-memory keyspace used by the fake server and the mocking layer.
+
+pub mod utils;
+
+use crate::modules::keyspace::utils::*;
+use bytes::Bytes;
+use redis_protocol::resp3::types::Frame as Resp3Frame;
+use std::{
+  cmp::Ordering,
+  collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+  str,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "mocks")]
+use std::time::Duration;
+
+/// The number of databases available via `SELECT`.
+pub const DATABASES: usize = 16;
+
+/// A value stored in the keyspace.
+#[derive(Clone, Debug)]
+pub enum Value {
+  String(Bytes),
+  Hash(BTreeMap<Bytes, Bytes>),
+  List(VecDeque<Bytes>),
+  Set(BTreeSet<Bytes>),
+  SortedSet(BTreeMap<Bytes, f64>),
+}
+
+impl Value {
+  fn type_name(&self) -> &'static str {
+    match self {
+      Value::String(_) => "string",
+      Value::Hash(_) => "hash",
+      Value::List(_) => "list",
+      Value::Set(_) => "set",
+      Value::SortedSet(_) => "zset",
+    }
+  }
+
+  fn is_empty(&self) -> bool {
+    match self {
+      Value::String(_) => false,
+      Value::Hash(ref data) => data.is_empty(),
+      Value::List(ref data) => data.is_empty(),
+      Value::Set(ref data) => data.is_empty(),
+      Value::SortedSet(ref data) => data.is_empty(),
+    }
+  }
+}
+
+/// A value and its expiration time.
+#[derive(Clone, Debug)]
+pub struct Entry {
+  value:      Value,
+  /// The expiration time, in milliseconds since the unix epoch.
+  expires_at: Option<u64>,
+}
+
+impl Entry {
+  fn is_expired(&self, now: u64) -> bool {
+    self.expires_at.map(|at| at <= now).unwrap_or(false)
+  }
+}
+
+/// Generate the typed accessors used by the data commands.
+macro_rules! typed_accessors {
+  ($read:ident, $write:ident, $variant:ident, $ty:ty) => {
+    fn $read(&mut self, key: &Bytes) -> Result<Option<&$ty>, Resp3Frame> {
+      match self.get(key) {
+        Some(Value::$variant(ref data)) => Ok(Some(data)),
+        Some(_) => Err(wrong_type()),
+        None => Ok(None),
+      }
+    }
+
+    fn $write(&mut self, key: &Bytes) -> Result<&mut $ty, Resp3Frame> {
+      if self.get(key).is_none() {
+        self.entries.insert(key.clone(), Entry {
+          value:      Value::$variant(Default::default()),
+          expires_at: None,
+        });
+      }
+
+      match self.entries.get_mut(key).map(|entry| &mut entry.value) {
+        Some(Value::$variant(ref mut data)) => Ok(data),
+        _ => Err(wrong_type()),
+      }
+    }
+  };
+}
+
+/// A database within the keyspace.
+#[derive(Clone, Debug, Default)]
+struct Database {
+  entries:     HashMap<Bytes, Entry>,
+  /// The current time, in milliseconds since the unix epoch.
+  now:         u64,
+  /// The last key or member returned by each `SCAN` family cursor.
+  cursors:     HashMap<u64, Bytes>,
+  next_cursor: u64,
+}
+
+impl Database {
+  typed_accessors!(read_string, write_string, String, Bytes);
+
+  typed_accessors!(read_hash, write_hash, Hash, BTreeMap<Bytes, Bytes>);
+
+  typed_accessors!(read_list, write_list, List, VecDeque<Bytes>);
+
+  typed_accessors!(read_set, write_set, Set, BTreeSet<Bytes>);
+
+  typed_accessors!(read_zset, write_zset, SortedSet, BTreeMap<Bytes, f64>);
+
+  fn purge_expired(&mut self) {
+    let now = self.now;
+    self.entries.retain(|_, entry| !entry.is_expired(now));
+  }
+
+  /// Read the time `ms` milliseconds from now.
+  fn after(&self, ms: i64) -> u64 {
+    self.now.saturating_add(ms.max(0) as u64)
+  }
+
+  fn get(&mut self, key: &Bytes) -> Option<&Value> {
+    self.get_entry(key).map(|entry| &entry.value)
+  }
+
+  fn get_entry(&mut self, key: &Bytes) -> Option<&mut Entry> {
+    let now = self.now;
+    if self.entries.get(key).map(|e| e.is_expired(now)).unwrap_or(false) {
+      self.entries.remove(key);
+    }
+
+    self.entries.get_mut(key)
+  }
+
+  fn remove(&mut self, key: &Bytes) -> Option<Value> {
+    self.get_entry(key)?;
+    self.entries.remove(key).map(|entry| entry.value)
+  }
+
+  fn remove_if_empty(&mut self, key: &Bytes) {
+    if self
+      .entries
+      .get(key)
+      .map(|entry| entry.value.is_empty())
+      .unwrap_or(false)
+    {
+      self.entries.remove(key);
+    }
+  }
+
+  fn set_string(&mut self, key: &Bytes, value: Bytes, expires_at: Option<u64>) {
+    self.entries.insert(key.clone(), Entry {
+      value: Value::String(value),
+      expires_at,
+    });
+  }
+}
+
+/// Read the number of milliseconds since the unix epoch.
+fn system_time_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// Resolve a possibly negative index into a list of `len` elements.
+fn resolve_range(start: i64, stop: i64, len: usize) -> Option<(usize, usize)> {
+  let len = len as i64;
+  let start = if start < 0 { (len + start).max(0) } else { start };
+  let stop = if stop < 0 { len + stop } else { stop.min(len - 1) };
+
+  if start > stop || start >= len {
+    None
+  } else {
+    Some((start as usize, stop as usize))
+  }
+}
+
+/// Parse a sorted set score boundary (`1`, `(1`, `-inf`, `+inf`), returning the value and whether it is exclusive.
+fn parse_score_bound(arg: &Bytes) -> Result<(f64, bool), Resp3Frame> {
+  if arg.first() == Some(&b'(') {
+    parse_f64(&arg.slice(1 ..)).map(|f| (f, true))
+  } else {
+    parse_f64(arg).map(|f| (f, false))
+  }
+}
+
+fn sorted_members(data: &BTreeMap<Bytes, f64>) -> Vec<(Bytes, f64)> {
+  let mut members: Vec<_> = data.iter().map(|(m, s)| (m.clone(), *s)).collect();
+  members.sort_by(|(lm, ls), (rm, rs)| ls.partial_cmp(rs).unwrap_or(Ordering::Equal).then_with(|| lm.cmp(rm)));
+  members
+}
+
+fn zset_reply(members: Vec<(Bytes, f64)>, with_scores: bool, is_resp3: bool) -> Resp3Frame {
+  if !with_scores {
+    array(members.into_iter().map(|(member, _)| blob(member)).collect())
+  } else if is_resp3 {
+    array(
+      members
+        .into_iter()
+        .map(|(member, score)| array(vec![blob(member), double(score)]))
+        .collect(),
+    )
+  } else {
+    array(
+      members
+        .into_iter()
+        .flat_map(|(member, score)| [blob(member), blob(f64_to_bytes(score))])
+        .collect(),
+    )
+  }
+}
+
+/// Read the keys used to route a command in a cluster.
+///
+/// Commands that do not operate on keys return an empty list.
+#[cfg(feature = "fake")]
+pub fn command_keys<'a>(cmd: &str, args: &'a [Bytes]) -> Vec<&'a Bytes> {
+  match cmd {
+    "DEL" | "UNLINK" | "EXISTS" | "MGET" | "SSUBSCRIBE" | "SUNSUBSCRIBE" => args.iter().collect(),
+    "MSET" => args.iter().step_by(2).collect(),
+    "RENAME" => args.iter().take(2).collect(),
+    "GET" | "SET" | "SETNX" | "SETEX" | "PSETEX" | "GETDEL" | "INCR" | "DECR" | "INCRBY" | "DECRBY"
+    | "INCRBYFLOAT" | "APPEND" | "STRLEN" | "TYPE" | "EXPIRE" | "PEXPIRE" | "EXPIREAT" | "PEXPIREAT" | "TTL"
+    | "PTTL" | "PERSIST" | "HSET" | "HMSET" | "HSETNX" | "HGET" | "HMGET" | "HDEL" | "HGETALL" | "HKEYS"
+    | "HVALS" | "HLEN" | "HEXISTS" | "HINCRBY" | "HINCRBYFLOAT" | "HSCAN" | "SSCAN" | "ZSCAN" | "LPUSH" | "RPUSH"
+    | "LPOP" | "RPOP" | "LRANGE" | "LLEN" | "LINDEX" | "LTRIM" | "SADD" | "SREM" | "SMEMBERS" | "SISMEMBER"
+    | "SCARD" | "ZADD" | "ZREM" | "ZSCORE" | "ZCARD" | "ZINCRBY" | "ZRANGE" | "ZRANGEBYSCORE" | "ZRANK"
+    | "SPUBLISH" => args.iter().take(1).collect(),
+    _ => Vec::new(),
+  }
+}
+
+/// An in-memory keyspace with strings, hashes, lists, sets, sorted sets, and expiration.
+///
+/// Expiration uses the system clock unless the keyspace is created with [manual_clock](Self::manual_clock).
+#[derive(Debug)]
+pub struct Keyspace {
+  databases: Vec<Database>,
+  /// The current time, in milliseconds since the unix epoch, if the clock is controlled by the caller.
+  clock:     Option<u64>,
+}
+
+impl Default for Keyspace {
+  fn default() -> Self {
+    Keyspace {
+      databases: vec![Database::default(); DATABASES],
+      clock:     None,
+    }
+  }
+}
+
+impl Keyspace {
+  /// Create a keyspace where time only moves forward via [advance](Self::advance).
+  ///
+  /// The clock starts at the current system time.
+  #[cfg(feature = "mocks")]
+  pub fn manual_clock() -> Self {
+    Keyspace {
+      clock: Some(system_time_ms()),
+      ..Default::default()
+    }
+  }
+
+  /// Move the manual clock forward by `duration`.
+  ///
+  /// This has no effect if the keyspace uses the system clock.
+  #[cfg(feature = "mocks")]
+  pub fn advance(&mut self, duration: Duration) {
+    if let Some(ref mut now) = self.clock {
+      *now = now.saturating_add(duration.as_millis() as u64);
+    }
+  }
+
+  /// Read the current time, in milliseconds since the unix epoch.
+  pub fn now(&self) -> u64 {
+    self.clock.unwrap_or_else(system_time_ms)
+  }
+
+  /// Remove all keys from all databases.
+  pub fn flush_all(&mut self) {
+    for db in self.databases.iter_mut() {
+      db.entries.clear();
+    }
+  }
+
+  /// Remove and return the keys in the first database that match `func`.
+  #[cfg(feature = "fake")]
+  pub fn take_keys<F>(&mut self, func: F) -> Vec<(Bytes, Entry)>
+  where
+    F: Fn(&Bytes) -> bool,
+  {
+    let db = &mut self.databases[0];
+    let keys: Vec<Bytes> = db.entries.keys().filter(|key| func(key)).cloned().collect();
+
+    keys
+      .into_iter()
+      .filter_map(|key| db.entries.remove(&key).map(|entry| (key, entry)))
+      .collect()
+  }
+
+  /// Add the provided keys to the first database, overwriting any existing values.
+  #[cfg(feature = "fake")]
+  pub fn insert_keys(&mut self, entries: Vec<(Bytes, Entry)>) {
+    self.databases[0].entries.extend(entries);
+  }
+
+  /// Run a data command against the provided database.
+  ///
+  /// Returns `None` if the command is not supported.
+  pub fn execute(&mut self, db: usize, cmd: &str, args: &[Bytes], is_resp3: bool) -> Option<Reply> {
+    let now = self.now();
+    let db = self.databases.get_mut(db)?;
+    db.now = now;
+
+    let result = match cmd {
+      "DBSIZE" => dbsize(db),
+      "FLUSHDB" => {
+        db.entries.clear();
+        Ok(ok())
+      },
+      "DEL" | "UNLINK" => del(db, cmd, args),
+      "EXISTS" => exists(db, cmd, args),
+      "TYPE" => type_(db, cmd, args),
+      "KEYS" => keys(db, cmd, args),
+      "RENAME" => rename(db, cmd, args),
+      "EXPIRE" | "PEXPIRE" => expire(db, cmd, args),
+      "EXPIREAT" | "PEXPIREAT" => expireat(db, cmd, args),
+      "TTL" | "PTTL" => ttl(db, cmd, args),
+      "PERSIST" => persist(db, cmd, args),
+      "GET" => get(db, cmd, args),
+      "SET" => set(db, cmd, args),
+      "SETNX" => setnx(db, cmd, args),
+      "SETEX" | "PSETEX" => setex(db, cmd, args),
+      "GETDEL" => getdel(db, cmd, args),
+      "MGET" => mget(db, cmd, args),
+      "MSET" => mset(db, cmd, args),
+      "INCR" | "DECR" | "INCRBY" | "DECRBY" => incr(db, cmd, args),
+      "INCRBYFLOAT" => incrbyfloat(db, cmd, args),
+      "APPEND" => append(db, cmd, args),
+      "STRLEN" => strlen(db, cmd, args),
+      "HSET" | "HMSET" => hset(db, cmd, args),
+      "HSETNX" => hsetnx(db, cmd, args),
+      "HGET" => hget(db, cmd, args),
+      "HMGET" => hmget(db, cmd, args),
+      "HDEL" => hdel(db, cmd, args),
+      "HGETALL" => hgetall(db, cmd, args, is_resp3),
+      "HKEYS" | "HVALS" => hkeys(db, cmd, args),
+      "HLEN" => hlen(db, cmd, args),
+      "HEXISTS" => hexists(db, cmd, args),
+      "HINCRBY" => hincrby(db, cmd, args),
+      "HINCRBYFLOAT" => hincrbyfloat(db, cmd, args),
+      "LPUSH" | "RPUSH" => push_list(db, cmd, args),
+      "LPOP" | "RPOP" => pop_list(db, cmd, args),
+      "LRANGE" => lrange(db, cmd, args),
+      "LLEN" => llen(db, cmd, args),
+      "LINDEX" => lindex(db, cmd, args),
+      "LTRIM" => ltrim(db, cmd, args),
+      "SADD" => sadd(db, cmd, args),
+      "SREM" => srem(db, cmd, args),
+      "SMEMBERS" => smembers(db, cmd, args),
+      "SISMEMBER" => sismember(db, cmd, args),
+      "SCARD" => scard(db, cmd, args),
+      "ZADD" => zadd(db, cmd, args),
+      "ZREM" => zrem(db, cmd, args),
+      "ZSCORE" => zscore(db, cmd, args),
+      "ZCARD" => zcard(db, cmd, args),
+      "ZINCRBY" => zincrby(db, cmd, args),
+      "ZRANGE" => zrange(db, cmd, args, is_resp3),
+      "ZRANGEBYSCORE" => zrangebyscore(db, cmd, args, is_resp3),
+      "ZRANK" => zrank(db, cmd, args),
+      "SCAN" => scan(db, cmd, args),
+      "HSCAN" | "SSCAN" | "ZSCAN" => scan_value(db, cmd, args),
+      _ => return None,
+    };
+
+    Some(result)
+  }
+}
+
+fn dbsize(db: &mut Database) -> Reply {
+  db.purge_expired();
+  Ok(int(db.entries.len() as i64))
+}
+
+fn del(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, None, 1)?;
+  Ok(int(args.iter().filter(|key| db.remove(key).is_some()).count() as i64))
+}
+
+fn exists(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, None, 1)?;
+  Ok(int(args.iter().filter(|key| db.get(key).is_some()).count() as i64))
+}
+
+fn type_(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  let kind = db.get(&args[0]).map(|value| value.type_name()).unwrap_or("none");
+  Ok(simple(kind))
+}
+
+fn keys(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  db.purge_expired();
+
+  let mut keys: Vec<_> = db
+    .entries
+    .keys()
+    .filter(|key| glob_match(&args[0], key))
+    .cloned()
+    .collect();
+  keys.sort();
+  Ok(array(keys.into_iter().map(blob).collect()))
+}
+
+fn rename(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  if db.get_entry(&args[0]).is_none() {
+    return Err(error("ERR no such key"));
+  }
+
+  if let Some(entry) = db.entries.remove(&args[0]) {
+    db.entries.insert(args[1].clone(), entry);
+  }
+  Ok(ok())
+}
+
+fn expire(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let amount = parse_i64(&args[1])?;
+  let ms = if cmd == "EXPIRE" {
+    amount.saturating_mul(1000)
+  } else {
+    amount
+  };
+
+  if ms <= 0 {
+    return Ok(int(db.remove(&args[0]).is_some() as i64));
+  }
+  let expires_at = db.after(ms);
+  match db.get_entry(&args[0]) {
+    Some(entry) => {
+      entry.expires_at = Some(expires_at);
+      Ok(int(1))
+    },
+    None => Ok(int(0)),
+  }
+}
+
+fn expireat(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let at = parse_i64(&args[1])?;
+  let at = if cmd == "EXPIREAT" { at.saturating_mul(1000) } else { at };
+
+  if at <= db.now as i64 {
+    return Ok(int(db.remove(&args[0]).is_some() as i64));
+  }
+  match db.get_entry(&args[0]) {
+    Some(entry) => {
+      entry.expires_at = Some(at as u64);
+      Ok(int(1))
+    },
+    None => Ok(int(0)),
+  }
+}
+
+fn ttl(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  let expires_at = match db.get_entry(&args[0]) {
+    Some(entry) => entry.expires_at,
+    None => return Ok(int(-2)),
+  };
+
+  match expires_at {
+    Some(at) => {
+      let ms = at.saturating_sub(db.now) as i64;
+      Ok(int(if cmd == "TTL" { (ms + 500) / 1000 } else { ms }))
+    },
+    None => Ok(int(-1)),
+  }
+}
+
+fn persist(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  match db.get_entry(&args[0]) {
+    Some(entry) => Ok(int(entry.expires_at.take().is_some() as i64)),
+    None => Ok(int(0)),
+  }
+}
+
+fn get(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  Ok(db.read_string(&args[0])?.cloned().map(blob).unwrap_or(Resp3Frame::Null))
+}
+
+fn set(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  let (mut nx, mut xx, mut get, mut keep_ttl, mut expires_at) = (false, false, false, false, None);
+
+  let mut idx = 2;
+  while idx < args.len() {
+    let option = upper(&args[idx]);
+    match option.as_str() {
+      "NX" => nx = true,
+      "XX" => xx = true,
+      "GET" => get = true,
+      "KEEPTTL" => keep_ttl = true,
+      "EX" | "PX" => {
+        let amount = parse_i64(args.get(idx + 1).ok_or_else(syntax_error)?)?;
+        if amount <= 0 {
+          return Err(error("ERR invalid expire time in 'set' command"));
+        }
+        let ms = if option == "EX" {
+          amount.saturating_mul(1000)
+        } else {
+          amount
+        };
+        expires_at = Some(db.after(ms));
+        idx += 1;
+      },
+      _ => return Err(syntax_error()),
+    }
+    idx += 1;
+  }
+  if nx && xx {
+    return Err(syntax_error());
+  }
+
+  let previous = db.read_string(&args[0]);
+  let previous = if get {
+    previous?.cloned()
+  } else {
+    previous.ok().flatten().cloned()
+  };
+  let exists = db.get(&args[0]).is_some();
+
+  if (nx && exists) || (xx && !exists) {
+    return Ok(if get {
+      previous.map(blob).unwrap_or(Resp3Frame::Null)
+    } else {
+      Resp3Frame::Null
+    });
+  }
+  if keep_ttl {
+    expires_at = db.get_entry(&args[0]).and_then(|entry| entry.expires_at);
+  }
+  db.set_string(&args[0], args[1].clone(), expires_at);
+
+  Ok(if get {
+    previous.map(blob).unwrap_or(Resp3Frame::Null)
+  } else {
+    ok()
+  })
+}
+
+fn setnx(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  if db.get(&args[0]).is_some() {
+    Ok(int(0))
+  } else {
+    db.set_string(&args[0], args[1].clone(), None);
+    Ok(int(1))
+  }
+}
+
+fn setex(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, Some(3), 1)?;
+  let amount = parse_i64(&args[1])?;
+  if amount <= 0 {
+    return Err(error(format!(
+      "ERR invalid expire time in '{}' command",
+      cmd.to_lowercase()
+    )));
+  }
+  let ms = if cmd == "SETEX" {
+    amount.saturating_mul(1000)
+  } else {
+    amount
+  };
+
+  let expires_at = Some(db.after(ms));
+  db.set_string(&args[0], args[2].clone(), expires_at);
+  Ok(ok())
+}
+
+fn getdel(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  let value = db.read_string(&args[0])?.cloned();
+  if value.is_some() {
+    db.remove(&args[0]);
+  }
+  Ok(value.map(blob).unwrap_or(Resp3Frame::Null))
+}
+
+fn mget(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, None, 1)?;
+  let values = args
+    .iter()
+    .map(|key| match db.get(key) {
+      Some(Value::String(ref value)) => blob(value.clone()),
+      _ => Resp3Frame::Null,
+    })
+    .collect();
+
+  Ok(array(values))
+}
+
+fn mset(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 2)?;
+  for pair in args.chunks(2) {
+    db.set_string(&pair[0], pair[1].clone(), None);
+  }
+  Ok(ok())
+}
+
+fn incr(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  let amount = match cmd {
+    "INCR" | "DECR" => {
+      check_args(cmd, args, 1, Some(1), 1)?;
+      1
+    },
+    _ => {
+      check_args(cmd, args, 2, Some(2), 1)?;
+      parse_i64(&args[1])?
+    },
+  };
+  let amount = if cmd.starts_with('D') {
+    amount.checked_neg()
+  } else {
+    Some(amount)
+  };
+
+  let current = match db.read_string(&args[0])? {
+    Some(value) => parse_i64(value)?,
+    None => 0,
+  };
+  let updated = amount
+    .and_then(|amount| current.checked_add(amount))
+    .ok_or_else(|| error("ERR increment or decrement would overflow"))?;
+
+  let expires_at = db.get_entry(&args[0]).and_then(|entry| entry.expires_at);
+  db.set_string(&args[0], updated.to_string().into(), expires_at);
+  Ok(int(updated))
+}
+
+fn incrbyfloat(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let amount = parse_f64(&args[1])?;
+  let current = match db.read_string(&args[0])? {
+    Some(value) => parse_f64(value)?,
+    None => 0.0,
+  };
+  let updated = current + amount;
+  if !updated.is_finite() {
+    return Err(error("ERR increment would produce NaN or Infinity"));
+  }
+
+  let expires_at = db.get_entry(&args[0]).and_then(|entry| entry.expires_at);
+  db.set_string(&args[0], f64_to_bytes(updated), expires_at);
+  Ok(blob(f64_to_bytes(updated)))
+}
+
+fn append(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let value = db.write_string(&args[0])?;
+  let mut updated = value.to_vec();
+  updated.extend_from_slice(&args[1]);
+  *value = updated.into();
+
+  Ok(int(value.len() as i64))
+}
+
+fn strlen(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  Ok(int(db.read_string(&args[0])?.map(|v| v.len()).unwrap_or(0) as i64))
+}
+
+fn hset(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, None, 2)?;
+  let hash = db.write_hash(&args[0])?;
+  let added = args[1 ..]
+    .chunks(2)
+    .filter(|pair| hash.insert(pair[0].clone(), pair[1].clone()).is_none())
+    .count();
+
+  Ok(if cmd == "HMSET" { ok() } else { int(added as i64) })
+}
+
+fn hsetnx(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, Some(3), 1)?;
+  let hash = db.write_hash(&args[0])?;
+  if hash.contains_key(&args[1]) {
+    Ok(int(0))
+  } else {
+    hash.insert(args[1].clone(), args[2].clone());
+    Ok(int(1))
+  }
+}
+
+fn hget(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let value = db.read_hash(&args[0])?.and_then(|hash| hash.get(&args[1])).cloned();
+  Ok(value.map(blob).unwrap_or(Resp3Frame::Null))
+}
+
+fn hmget(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  let hash = db.read_hash(&args[0])?;
+  let values = args[1 ..]
+    .iter()
+    .map(|field| {
+      hash
+        .and_then(|hash| hash.get(field))
+        .cloned()
+        .map(blob)
+        .unwrap_or(Resp3Frame::Null)
+    })
+    .collect();
+
+  Ok(array(values))
+}
+
+fn hdel(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  if db.read_hash(&args[0])?.is_none() {
+    return Ok(int(0));
+  }
+
+  let hash = db.write_hash(&args[0])?;
+  let removed = args[1 ..].iter().filter(|field| hash.remove(*field).is_some()).count();
+  db.remove_if_empty(&args[0]);
+  Ok(int(removed as i64))
+}
+
+fn hgetall(db: &mut Database, cmd: &str, args: &[Bytes], is_resp3: bool) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  let pairs: Vec<_> = db
+    .read_hash(&args[0])?
+    .map(|hash| hash.iter().map(|(k, v)| (blob(k.clone()), blob(v.clone()))).collect())
+    .unwrap_or_default();
+
+  if is_resp3 {
+    Ok(map(pairs))
+  } else {
+    Ok(array(pairs.into_iter().flat_map(|(k, v)| [k, v]).collect()))
+  }
+}
+
+fn hkeys(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  let values = match db.read_hash(&args[0])? {
+    Some(hash) if cmd == "HKEYS" => hash.keys().cloned().map(blob).collect(),
+    Some(hash) => hash.values().cloned().map(blob).collect(),
+    None => Vec::new(),
+  };
+
+  Ok(array(values))
+}
+
+fn hlen(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  Ok(int(db.read_hash(&args[0])?.map(|h| h.len()).unwrap_or(0) as i64))
+}
+
+fn hexists(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let exists = db
+    .read_hash(&args[0])?
+    .map(|h| h.contains_key(&args[1]))
+    .unwrap_or(false);
+  Ok(int(exists as i64))
+}
+
+fn hincrby(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, Some(3), 1)?;
+  let amount = parse_i64(&args[2])?;
+  let hash = db.write_hash(&args[0])?;
+  let current = match hash.get(&args[1]) {
+    Some(value) => parse_i64(value)?,
+    None => 0,
+  };
+  let updated = current
+    .checked_add(amount)
+    .ok_or_else(|| error("ERR increment or decrement would overflow"))?;
+
+  hash.insert(args[1].clone(), updated.to_string().into());
+  Ok(int(updated))
+}
+
+fn hincrbyfloat(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, Some(3), 1)?;
+  let amount = parse_f64(&args[2])?;
+  let hash = db.write_hash(&args[0])?;
+  let current = match hash.get(&args[1]) {
+    Some(value) => parse_f64(value)?,
+    None => 0.0,
+  };
+  let updated = current + amount;
+  if !updated.is_finite() {
+    return Err(error("ERR increment would produce NaN or Infinity"));
+  }
+
+  hash.insert(args[1].clone(), f64_to_bytes(updated));
+  Ok(blob(f64_to_bytes(updated)))
+}
+
+fn push_list(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  let list = db.write_list(&args[0])?;
+  for value in args[1 ..].iter() {
+    if cmd == "LPUSH" {
+      list.push_front(value.clone());
+    } else {
+      list.push_back(value.clone());
+    }
+  }
+
+  Ok(int(list.len() as i64))
+}
+
+fn pop_list(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(2), 1)?;
+  let count = match args.get(1) {
+    Some(count) => Some(parse_i64(count)?.max(0) as usize),
+    None => None,
+  };
+  if db.read_list(&args[0])?.is_none() {
+    return Ok(Resp3Frame::Null);
+  }
+
+  let list = db.write_list(&args[0])?;
+  let mut values = Vec::new();
+  for _ in 0 .. count.unwrap_or(1) {
+    let value = if cmd == "LPOP" {
+      list.pop_front()
+    } else {
+      list.pop_back()
+    };
+    match value {
+      Some(value) => values.push(blob(value)),
+      None => break,
+    }
+  }
+  db.remove_if_empty(&args[0]);
+
+  if count.is_some() {
+    Ok(array(values))
+  } else {
+    Ok(values.pop().unwrap_or(Resp3Frame::Null))
+  }
+}
+
+fn lrange(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, Some(3), 1)?;
+  let (start, stop) = (parse_i64(&args[1])?, parse_i64(&args[2])?);
+  let values = match db.read_list(&args[0])? {
+    Some(list) => match resolve_range(start, stop, list.len()) {
+      Some((start, stop)) => list.range(start ..= stop).cloned().map(blob).collect(),
+      None => Vec::new(),
+    },
+    None => Vec::new(),
+  };
+
+  Ok(array(values))
+}
+
+fn llen(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  Ok(int(db.read_list(&args[0])?.map(|l| l.len()).unwrap_or(0) as i64))
+}
+
+fn lindex(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let index = parse_i64(&args[1])?;
+  let value = db.read_list(&args[0])?.and_then(|list| {
+    let index = if index < 0 { list.len() as i64 + index } else { index };
+    usize::try_from(index).ok().and_then(|index| list.get(index)).cloned()
+  });
+
+  Ok(value.map(blob).unwrap_or(Resp3Frame::Null))
+}
+
+fn ltrim(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, Some(3), 1)?;
+  let (start, stop) = (parse_i64(&args[1])?, parse_i64(&args[2])?);
+  if db.read_list(&args[0])?.is_none() {
+    return Ok(ok());
+  }
+
+  let list = db.write_list(&args[0])?;
+  match resolve_range(start, stop, list.len()) {
+    Some((start, stop)) => {
+      list.truncate(stop + 1);
+      list.drain(.. start);
+    },
+    None => list.clear(),
+  };
+  db.remove_if_empty(&args[0]);
+  Ok(ok())
+}
+
+fn sadd(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  let set = db.write_set(&args[0])?;
+  Ok(int(
+    args[1 ..].iter().filter(|m| set.insert((*m).clone())).count() as i64
+  ))
+}
+
+fn srem(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  if db.read_set(&args[0])?.is_none() {
+    return Ok(int(0));
+  }
+
+  let set = db.write_set(&args[0])?;
+  let removed = args[1 ..].iter().filter(|m| set.remove(*m)).count();
+  db.remove_if_empty(&args[0]);
+  Ok(int(removed as i64))
+}
+
+fn smembers(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  let members = db
+    .read_set(&args[0])?
+    .map(|set| set.iter().cloned().map(blob).collect())
+    .unwrap_or_default();
+
+  Ok(array(members))
+}
+
+fn sismember(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let exists = db.read_set(&args[0])?.map(|s| s.contains(&args[1])).unwrap_or(false);
+  Ok(int(exists as i64))
+}
+
+fn scard(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  Ok(int(db.read_set(&args[0])?.map(|s| s.len()).unwrap_or(0) as i64))
+}
+
+fn zadd(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, None, 1)?;
+  let (mut nx, mut xx, mut ch, mut idx) = (false, false, false, 1);
+  while idx < args.len() {
+    match upper(&args[idx]).as_str() {
+      "NX" => nx = true,
+      "XX" => xx = true,
+      "CH" => ch = true,
+      _ => break,
+    };
+    idx += 1;
+  }
+  if (nx && xx) || idx == args.len() || (args.len() - idx) % 2 != 0 {
+    return Err(syntax_error());
+  }
+  let pairs = args[idx ..]
+    .chunks(2)
+    .map(|pair| parse_f64(&pair[0]).map(|score| (score, pair[1].clone())))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let zset = db.write_zset(&args[0])?;
+  let mut changed = 0;
+  for (score, member) in pairs.into_iter() {
+    let previous = zset.get(&member).cloned();
+    if (nx && previous.is_some()) || (xx && previous.is_none()) {
+      continue;
+    }
+    if previous.is_none() || (ch && previous != Some(score)) {
+      changed += 1;
+    }
+    zset.insert(member, score);
+  }
+  db.remove_if_empty(&args[0]);
+
+  Ok(int(changed))
+}
+
+fn zrem(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  if db.read_zset(&args[0])?.is_none() {
+    return Ok(int(0));
+  }
+
+  let zset = db.write_zset(&args[0])?;
+  let removed = args[1 ..].iter().filter(|m| zset.remove(*m).is_some()).count();
+  db.remove_if_empty(&args[0]);
+  Ok(int(removed as i64))
+}
+
+fn zscore(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let score = db.read_zset(&args[0])?.and_then(|zset| zset.get(&args[1])).cloned();
+  Ok(score.map(double).unwrap_or(Resp3Frame::Null))
+}
+
+fn zcard(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, Some(1), 1)?;
+  Ok(int(db.read_zset(&args[0])?.map(|z| z.len()).unwrap_or(0) as i64))
+}
+
+fn zincrby(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 3, Some(3), 1)?;
+  let amount = parse_f64(&args[1])?;
+  let zset = db.write_zset(&args[0])?;
+  let score = zset.get(&args[2]).cloned().unwrap_or(0.0) + amount;
+  if score.is_nan() {
+    return Err(error("ERR resulting score is not a number (NaN)"));
+  }
+
+  zset.insert(args[2].clone(), score);
+  Ok(double(score))
+}
+
+/// Read the members with scores within the provided boundaries.
+fn zset_by_score(db: &mut Database, key: &Bytes, min: &Bytes, max: &Bytes) -> Result<Vec<(Bytes, f64)>, Resp3Frame> {
+  let ((min, min_exclusive), (max, max_exclusive)) = (parse_score_bound(min)?, parse_score_bound(max)?);
+  let members = db.read_zset(key)?.map(sorted_members).unwrap_or_default();
+
+  Ok(
+    members
+      .into_iter()
+      .filter(|(_, score)| if min_exclusive { *score > min } else { *score >= min })
+      .filter(|(_, score)| if max_exclusive { *score < max } else { *score <= max })
+      .collect(),
+  )
+}
+
+/// Parse the trailing `WITHSCORES` and `LIMIT offset count` arguments.
+fn parse_range_options(
+  args: &[Bytes],
+  allow_by_score: bool,
+) -> Result<(bool, bool, bool, Option<(usize, i64)>), Resp3Frame> {
+  let (mut with_scores, mut by_score, mut rev, mut limit, mut idx) = (false, false, false, None, 0);
+  while idx < args.len() {
+    match upper(&args[idx]).as_str() {
+      "WITHSCORES" => with_scores = true,
+      "BYSCORE" if allow_by_score => by_score = true,
+      "REV" if allow_by_score => rev = true,
+      "LIMIT" if idx + 2 < args.len() => {
+        let offset = parse_i64(&args[idx + 1])?.max(0) as usize;
+        limit = Some((offset, parse_i64(&args[idx + 2])?));
+        idx += 2;
+      },
+      _ => return Err(syntax_error()),
+    };
+    idx += 1;
+  }
+
+  Ok((with_scores, by_score, rev, limit))
+}
+
+fn apply_limit(members: Vec<(Bytes, f64)>, limit: Option<(usize, i64)>) -> Vec<(Bytes, f64)> {
+  match limit {
+    Some((offset, count)) if count < 0 => members.into_iter().skip(offset).collect(),
+    Some((offset, count)) => members.into_iter().skip(offset).take(count as usize).collect(),
+    None => members,
+  }
+}
+
+fn zrange(db: &mut Database, cmd: &str, args: &[Bytes], is_resp3: bool) -> Reply {
+  check_args(cmd, args, 3, None, 1)?;
+  let (with_scores, by_score, rev, limit) = parse_range_options(&args[3 ..], true)?;
+
+  let members = if by_score {
+    let (min, max) = if rev {
+      (&args[2], &args[1])
+    } else {
+      (&args[1], &args[2])
+    };
+    let mut members = zset_by_score(db, &args[0], min, max)?;
+    if rev {
+      members.reverse();
+    }
+    apply_limit(members, limit)
+  } else {
+    if limit.is_some() {
+      return Err(syntax_error());
+    }
+    let (start, stop) = (parse_i64(&args[1])?, parse_i64(&args[2])?);
+    let mut members = db.read_zset(&args[0])?.map(sorted_members).unwrap_or_default();
+    if rev {
+      members.reverse();
+    }
+
+    match resolve_range(start, stop, members.len()) {
+      Some((start, stop)) => members.drain(start ..= stop).collect(),
+      None => Vec::new(),
+    }
+  };
+
+  Ok(zset_reply(members, with_scores, is_resp3))
+}
+
+fn zrangebyscore(db: &mut Database, cmd: &str, args: &[Bytes], is_resp3: bool) -> Reply {
+  check_args(cmd, args, 3, None, 1)?;
+  let (with_scores, _, _, limit) = parse_range_options(&args[3 ..], false)?;
+  let members = zset_by_score(db, &args[0], &args[1], &args[2])?;
+
+  Ok(zset_reply(apply_limit(members, limit), with_scores, is_resp3))
+}
+
+fn zrank(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, Some(2), 1)?;
+  let rank = db
+    .read_zset(&args[0])?
+    .map(sorted_members)
+    .and_then(|members| members.iter().position(|(member, _)| member == &args[1]));
+
+  Ok(rank.map(|rank| int(rank as i64)).unwrap_or(Resp3Frame::Null))
+}
+
+/// Parse the `MATCH`, `COUNT`, and `TYPE` options shared by the `SCAN` family of commands.
+fn parse_scan_options(
+  args: &[Bytes],
+  allow_type: bool,
+) -> Result<(Option<Bytes>, usize, Option<String>), Resp3Frame> {
+  let (mut pattern, mut count, mut kind) = (None, 10, None);
+
+  let mut idx = 0;
+  while idx < args.len() {
+    let value = args.get(idx + 1).ok_or_else(syntax_error)?;
+    match upper(&args[idx]).as_str() {
+      "MATCH" => pattern = Some(value.clone()),
+      "COUNT" => {
+        count = parse_i64(value)?;
+        if count < 1 {
+          return Err(syntax_error());
+        }
+      },
+      "TYPE" if allow_type => kind = Some(String::from_utf8_lossy(value).to_lowercase()),
+      _ => return Err(syntax_error()),
+    }
+    idx += 2;
+  }
+
+  Ok((pattern, count as usize, kind))
+}
+
+/// Read the next page of a `SCAN` family iteration over the sorted `items`.
+///
+/// Cursors refer to the last key or member returned by the previous page, so keys that are added or removed between
+/// calls do not cause other keys to be skipped or repeated. Returns the next cursor and the items in the page.
+fn scan_page<T>(
+  db: &mut Database,
+  cursor: &Bytes,
+  items: Vec<(Bytes, T)>,
+  count: usize,
+) -> Result<(u64, Vec<(Bytes, T)>), Resp3Frame> {
+  let cursor = str::from_utf8(cursor)
+    .ok()
+    .and_then(|s| s.parse::<u64>().ok())
+    .ok_or_else(|| error("ERR invalid cursor"))?;
+  let last = match cursor {
+    0 => None,
+    cursor => Some(db.cursors.remove(&cursor).ok_or_else(|| error("ERR invalid cursor"))?),
+  };
+
+  let mut remaining = items
+    .into_iter()
+    .skip_while(|(key, _)| last.as_ref().map(|last| key <= last).unwrap_or(false));
+  let page: Vec<_> = remaining.by_ref().take(count).collect();
+
+  let next = match (remaining.next(), page.last()) {
+    (Some(_), Some((key, _))) => {
+      db.next_cursor += 1;
+      db.cursors.insert(db.next_cursor, key.clone());
+      db.next_cursor
+    },
+    _ => 0,
+  };
+  Ok((next, page))
+}
+
+fn scan_reply(cursor: u64, items: Vec<Resp3Frame>) -> Resp3Frame {
+  array(vec![blob(cursor.to_string()), array(items)])
+}
+
+fn scan(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 1, None, 1)?;
+  let (pattern, count, kind) = parse_scan_options(&args[1 ..], true)?;
+  db.purge_expired();
+
+  let mut keys: Vec<_> = db
+    .entries
+    .iter()
+    .map(|(key, entry)| (key.clone(), entry.value.type_name()))
+    .collect();
+  keys.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+  let (cursor, page) = scan_page(db, &args[0], keys, count)?;
+
+  let keys = page
+    .into_iter()
+    .filter(|(key, _)| pattern.as_ref().map(|p| glob_match(p, key)).unwrap_or(true))
+    .filter(|(_, type_name)| kind.as_ref().map(|kind| kind == type_name).unwrap_or(true))
+    .map(|(key, _)| blob(key))
+    .collect();
+  Ok(scan_reply(cursor, keys))
+}
+
+fn scan_value(db: &mut Database, cmd: &str, args: &[Bytes]) -> Reply {
+  check_args(cmd, args, 2, None, 1)?;
+  let (pattern, count, _) = parse_scan_options(&args[2 ..], false)?;
+
+  let items: Vec<(Bytes, Option<Bytes>)> = match cmd {
+    "HSCAN" => db
+      .read_hash(&args[0])?
+      .map(|hash| hash.iter().map(|(k, v)| (k.clone(), Some(v.clone()))).collect())
+      .unwrap_or_default(),
+    "SSCAN" => db
+      .read_set(&args[0])?
+      .map(|set| set.iter().map(|member| (member.clone(), None)).collect())
+      .unwrap_or_default(),
+    _ => db
+      .read_zset(&args[0])?
+      .map(|zset| zset.iter().map(|(m, s)| (m.clone(), Some(f64_to_bytes(*s)))).collect())
+      .unwrap_or_default(),
+  };
+  let (cursor, page) = scan_page(db, &args[1], items, count)?;
+
+  let items = page
+    .into_iter()
+    .filter(|(key, _)| pattern.as_ref().map(|p| glob_match(p, key)).unwrap_or(true))
+    .flat_map(|(key, value)| [Some(blob(key)), value.map(blob)])
+    .flatten()
+    .collect();
+  Ok(scan_reply(cursor, items))
+}
@@ -0,0 +1,158 @@
+use crate::utils as client_utils;
+use bytes::Bytes;
+use redis_protocol::resp3::types::{Frame as Resp3Frame, FrameMap};
+use std::str;
+
+/// The result of processing a command, where the error variant contains an error frame.
+pub type Reply = Result<Resp3Frame, Resp3Frame>;
+
+pub fn ok() -> Resp3Frame {
+  simple("OK")
+}
+
+pub fn simple(data: &'static str) -> Resp3Frame {
+  Resp3Frame::SimpleString {
+    data:       Bytes::from_static(data.as_bytes()),
+    attributes: None,
+  }
+}
+
+pub fn blob<B: Into<Bytes>>(data: B) -> Resp3Frame {
+  Resp3Frame::BlobString {
+    data:       data.into(),
+    attributes: None,
+  }
+}
+
+pub fn int(data: i64) -> Resp3Frame {
+  Resp3Frame::Number { data, attributes: None }
+}
+
+pub fn double(data: f64) -> Resp3Frame {
+  Resp3Frame::Double { data, attributes: None }
+}
+
+pub fn array(data: Vec<Resp3Frame>) -> Resp3Frame {
+  Resp3Frame::Array { data, attributes: None }
+}
+
+#[cfg(feature = "fake")]
+pub fn push(data: Vec<Resp3Frame>) -> Resp3Frame {
+  Resp3Frame::Push { data, attributes: None }
+}
+
+pub fn map(data: Vec<(Resp3Frame, Resp3Frame)>) -> Resp3Frame {
+  Resp3Frame::Map {
+    data:       data.into_iter().collect::<FrameMap>(),
+    attributes: None,
+  }
+}
+
+pub fn error<S: Into<String>>(data: S) -> Resp3Frame {
+  Resp3Frame::SimpleError {
+    data:       data.into().into(),
+    attributes: None,
+  }
+}
+
+pub fn wrong_type() -> Resp3Frame {
+  error("WRONGTYPE Operation against a key holding the wrong kind of value")
+}
+
+pub fn syntax_error() -> Resp3Frame {
+  error("ERR syntax error")
+}
+
+pub fn wrong_args(cmd: &str) -> Resp3Frame {
+  error(format!(
+    "ERR wrong number of arguments for '{}' command",
+    cmd.to_lowercase()
+  ))
+}
+
+pub fn unknown_command(cmd: &str) -> Resp3Frame {
+  error(format!("ERR unknown command '{}'", cmd))
+}
+
+/// Return an error if the number of arguments is not within `min` and `max` (inclusive), or if the number of
+/// arguments after `min` is not a multiple of `step`.
+pub fn check_args(cmd: &str, args: &[Bytes], min: usize, max: Option<usize>, step: usize) -> Result<(), Resp3Frame> {
+  let too_many = max.map(|max| args.len() > max).unwrap_or(false);
+  if args.len() < min || too_many || (step > 1 && (args.len() - min) % step != 0) {
+    Err(wrong_args(cmd))
+  } else {
+    Ok(())
+  }
+}
+
+/// Read an argument as an uppercase string.
+pub fn upper(arg: &Bytes) -> String {
+  String::from_utf8_lossy(arg).to_uppercase()
+}
+
+pub fn parse_i64(arg: &Bytes) -> Result<i64, Resp3Frame> {
+  str::from_utf8(arg)
+    .ok()
+    .and_then(|s| s.parse::<i64>().ok())
+    .ok_or_else(|| error("ERR value is not an integer or out of range"))
+}
+
+pub fn parse_f64(arg: &Bytes) -> Result<f64, Resp3Frame> {
+  str::from_utf8(arg)
+    .ok()
+    .and_then(|s| client_utils::redis_string_to_f64(s).ok())
+    .filter(|f| !f.is_nan())
+    .ok_or_else(|| error("ERR value is not a valid float"))
+}
+
+/// Format a float the way Redis does in string replies.
+pub fn f64_to_bytes(value: f64) -> Bytes {
+  if value.is_infinite() && value.is_sign_negative() {
+    Bytes::from_static(b"-inf")
+  } else if value.is_infinite() {
+    Bytes::from_static(b"inf")
+  } else {
+    value.to_string().into()
+  }
+}
+
+/// Whether `value` matches the glob-style `pattern` used by `KEYS` and `PSUBSCRIBE`.
+pub fn glob_match(pattern: &[u8], value: &[u8]) -> bool {
+  match pattern.first() {
+    None => value.is_empty(),
+    Some(b'*') => (0 ..= value.len()).any(|idx| glob_match(&pattern[1 ..], &value[idx ..])),
+    Some(b'?') => !value.is_empty() && glob_match(&pattern[1 ..], &value[1 ..]),
+    Some(b'[') => {
+      let first = match value.first() {
+        Some(first) => *first,
+        None => return false,
+      };
+      let end = match pattern.iter().skip(1).position(|b| *b == b']') {
+        Some(idx) => idx + 1,
+        None => return pattern == value,
+      };
+      let (negate, class) = match pattern[1 .. end].split_first() {
+        Some((b'^', rest)) => (true, rest),
+        _ => (false, &pattern[1 .. end]),
+      };
+
+      let mut found = false;
+      let mut idx = 0;
+      while idx < class.len() {
+        if idx + 2 < class.len() && class[idx + 1] == b'-' {
+          found |= class[idx] <= first && first <= class[idx + 2];
+          idx += 3;
+        } else {
+          found |= class[idx] == first;
+          idx += 1;
+        }
+      }
+
+      found != negate && glob_match(&pattern[end + 1 ..], &value[1 ..])
+    },
+    Some(b'\\') if pattern.len() > 1 => {
+      value.first() == Some(&pattern[1]) && glob_match(&pattern[2 ..], &value[1 ..])
+    },
+    Some(c) => value.first() == Some(c) && glob_match(&pattern[1 ..], &value[1 ..]),
+  }
+}
@@ -0,0 +1,678 @@
+//! A distributed lock with fencing tokens and optional support for the
+//! [Redlock](https://redis.io/docs/manual/patterns/distributed-locks/) algorithm.
+//!
+//! A [Lock](Lock) is acquired by setting a random token on the lock key with `SET NX PX`. Each time the lock is
+//! acquired a counter stored in a separate key is incremented and returned as a fencing token, which callers can pass
+//! to other systems to reject writes from a previous holder whose lock expired. The lock is released or extended with
+//! Lua scripts that only modify the key if it still contains the holder's token.
+//!
+//! The fencing token is only a safe fence when the lock uses a single server, and only as long as that server does
+//! not lose writes to the counter, such as after a failover to a replica that had not received them.
+//!
+//! ```rust no_run
+//! # use fred::{lock::{Lock, LockConfig}, prelude::*};
+//! async fn example(client: RedisClient) -> Result<(), RedisError> {
+//!   let lock = Lock::new(client, "resource", LockConfig::default());
+//!
+//!   let guard = lock.acquire().await?;
+//!   println!("Acquired lock with fencing token {}", guard.fencing_token());
+//!   // the lock is extended in a background task until it is released
+//!   guard.release().await?;
+//!   Ok(())
+//! }
+//! ```
+//!
+//! In Redlock mode the lock is acquired on several independent servers, and is considered held only if it was
+//! acquired on a majority of them before it could have expired, accounting for the time spent acquiring the lock and
+//! the expected clock drift between servers.
+//!
+//! **The fencing token is not a safe fence in Redlock mode.** Each server keeps its own counter and the token is the
+//! largest value returned by the servers that acquired the lock, so two holders that acquired the lock on different
+//! majorities can receive the same token, and a later holder can receive a smaller token than an earlier one.
+//!
+//! ```rust no_run
+//! # use fred::{lock::{Lock, LockConfig}, prelude::*};
+//! async fn example(clients: Vec<RedisClient>) -> Result<(), RedisError> {
+//!   let lock = Lock::redlock(clients, "resource", LockConfig::default())?;
+//!
+//!   if let Some(guard) = lock.try_acquire().await? {
+//!     // ...
+//!     guard.release().await?;
+//!   }
+//!   Ok(())
+//! }
+//! ```
+
+use crate::{
+  clients::RedisClient,
+  error::{RedisError, RedisErrorKind},
+  interfaces::ClientLike,
+  types::{RedisKey, RedisValue, Script},
+  utils,
+};
+use futures::future::join_all;
+use lazy_static::lazy_static;
+use std::{
+  cmp,
+  fmt,
+  sync::{atomic::AtomicBool, Arc},
+  time::{Duration, Instant},
+};
+use tokio::{
+  task::JoinHandle,
+  time::{sleep, timeout},
+};
+
+/// Set the token on the lock key and increment the fencing token counter, returning the new fencing token.
+const ACQUIRE_LUA: &str = r#"
+if redis.call('SET', KEYS[1], ARGV[1], 'NX', 'PX', ARGV[2]) then
+  return redis.call('INCR', KEYS[2])
+end
+return nil
+"#;
+/// Change the expiration of the lock key if it contains the provided token.
+const EXTEND_LUA: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+"#;
+/// Delete the lock key if it contains the provided token.
+const RELEASE_LUA: &str = r#"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+  return redis.call('DEL', KEYS[1])
+end
+return 0
+"#;
+
+lazy_static! {
+  static ref ACQUIRE_SCRIPT: Script = Script::from_lua(ACQUIRE_LUA);
+  static ref EXTEND_SCRIPT: Script = Script::from_lua(EXTEND_LUA);
+  static ref RELEASE_SCRIPT: Script = Script::from_lua(RELEASE_LUA);
+}
+
+/// The number of random characters in the token that identifies a lock holder.
+const TOKEN_LEN: usize = 32;
+/// A fixed amount of clock drift, in milliseconds, added to the drift calculated from the `clock_drift_factor`.
+const CLOCK_DRIFT_MS: u64 = 2;
+/// The suffix added to the lock key to create the key that stores the fencing token counter.
+const FENCE_SUFFIX: &str = ":fence";
+
+/// Configuration options for a [Lock](Lock).
+#[derive(Clone, Debug, PartialEq)]
+pub struct LockConfig {
+  /// The amount of time, in milliseconds, before the lock expires if it is not extended or released.
+  ///
+  /// Default: 30000
+  pub ttl:                u64,
+  /// The amount of time, in milliseconds, to wait for each server to respond. This should be much smaller than the
+  /// `ttl` so that an unavailable server does not use up the lock's validity time.
+  ///
+  /// Default: 1000
+  pub timeout:            u64,
+  /// The maximum number of attempts made by [acquire](Lock::acquire) before returning a `Timeout` error. If `None`
+  /// the client will try to acquire the lock indefinitely.
+  ///
+  /// Default: `None`
+  pub max_attempts:       Option<u32>,
+  /// The amount of time, in milliseconds, to wait between attempts to acquire the lock. A random jitter of up to the
+  /// same amount is added to each delay.
+  ///
+  /// Default: 100
+  pub retry_delay:        u64,
+  /// Whether to extend the lock in a background task every `ttl / 3` milliseconds until the guard is released or
+  /// dropped.
+  ///
+  /// Default: `true`
+  pub auto_renew:         bool,
+  /// The fraction of the `ttl` that is subtracted from the lock's validity time to account for clock drift between
+  /// servers.
+  ///
+  /// Default: `0.01`
+  pub clock_drift_factor: f64,
+}
+
+impl Default for LockConfig {
+  fn default() -> Self {
+    LockConfig {
+      ttl:                30000,
+      timeout:            1000,
+      max_attempts:       None,
+      retry_delay:        100,
+      auto_renew:         true,
+      clock_drift_factor: 0.01,
+    }
+  }
+}
+
+struct LockInner {
+  clients:    Vec<RedisClient>,
+  key:        RedisKey,
+  /// The fencing token key for each client, which depends on the client's key prefix.
+  fence_keys: Vec<RedisKey>,
+  config:     LockConfig,
+}
+
+impl LockInner {
+  /// The number of servers that must agree before the lock is considered acquired, extended, or released.
+  fn quorum(&self) -> usize {
+    self.clients.len() / 2 + 1
+  }
+
+  /// The amount of time, in milliseconds, that a lock acquired or extended with `ttl` remains valid after `elapsed`
+  /// milliseconds.
+  fn validity(&self, ttl: u64, elapsed: u64) -> u64 {
+    let drift = (ttl as f64 * self.config.clock_drift_factor) as u64 + CLOCK_DRIFT_MS;
+    ttl.saturating_sub(elapsed).saturating_sub(drift)
+  }
+
+  /// Run a script on each server concurrently, waiting at most `timeout` milliseconds for each response.
+  ///
+  /// The keys are the lock key followed by the fencing token key, if `fence` is `true`.
+  async fn call_all(
+    &self,
+    script: &Script,
+    fence: bool,
+    token: &str,
+    ttl: u64,
+  ) -> Vec<Result<RedisValue, RedisError>> {
+    let args: Vec<RedisValue> = vec![token.into(), (ttl as i64).into()];
+    let wait = Duration::from_millis(self.config.timeout);
+
+    join_all(
+      self
+        .clients
+        .iter()
+        .zip(self.fence_keys.iter())
+        .map(|(client, fence_key)| {
+          let mut keys = vec![self.key.clone()];
+          if fence {
+            keys.push(fence_key.clone());
+          }
+          let args = args.clone();
+
+          async move {
+            let result = timeout(wait, script.evalsha_with_reload(client, keys, args)).await;
+
+            match result {
+              Ok(result) => result,
+              Err(_) => Err(RedisError::new(RedisErrorKind::Timeout, "Request timed out.")),
+            }
+          }
+        }),
+    )
+    .await
+  }
+
+  /// Run a script that returns `1` on success on each server, returning whether a quorum succeeded.
+  async fn call_quorum(&self, script: &Script, token: &str, ttl: u64) -> Result<bool, RedisError> {
+    let results = self.call_all(script, false, token, ttl).await;
+    let (mut succeeded, mut errors, mut last_error) = (0, 0, None);
+    for result in results.into_iter() {
+      match result {
+        Ok(value) => {
+          if value.as_u64() == Some(1) {
+            succeeded += 1;
+          }
+        },
+        Err(error) => {
+          errors += 1;
+          last_error = Some(error);
+        },
+      }
+    }
+
+    if succeeded >= self.quorum() {
+      Ok(true)
+    } else {
+      self.check_errors(errors, last_error).map(|_| false)
+    }
+  }
+
+  /// Return the last error if too many servers returned an error to reach a quorum.
+  fn check_errors(&self, errors: usize, last_error: Option<RedisError>) -> Result<(), RedisError> {
+    match last_error {
+      Some(error) if errors > self.clients.len() - self.quorum() => Err(error),
+      _ => Ok(()),
+    }
+  }
+
+  async fn extend(&self, token: &str, ttl: u64) -> Result<bool, RedisError> {
+    let start = Instant::now();
+    let extended = self.call_quorum(&EXTEND_SCRIPT, token, ttl).await?;
+
+    Ok(extended && self.validity(ttl, start.elapsed().as_millis() as u64) > 0)
+  }
+
+  /// Delete the lock key on each server where it still contains `token`, returning whether a quorum of servers
+  /// released the lock.
+  async fn release(&self, token: &str) -> Result<bool, RedisError> {
+    self.call_quorum(&RELEASE_SCRIPT, token, self.config.ttl).await
+  }
+}
+
+/// A distributed lock on a key, stored on one server or on several independent servers with the Redlock algorithm.
+///
+/// See the [module documentation](crate::lock) for more information.
+#[derive(Clone)]
+pub struct Lock {
+  inner: Arc<LockInner>,
+}
+
+impl fmt::Debug for Lock {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Lock")
+      .field("key", &self.inner.key)
+      .field("servers", &self.inner.clients.len())
+      .field("config", &self.inner.config)
+      .finish()
+  }
+}
+
+impl Lock {
+  /// Create a new lock on `key` using a single client.
+  ///
+  /// In a clustered deployment the fencing token key is created with the same hash tag as the lock key, after the
+  /// client's `key_prefix` is added. If neither the prefix nor the key contains a hash tag the prefixed key is used
+  /// as the hash tag, which requires that the prefixed key does not contain `{` or `}` characters.
+  pub fn new<K: Into<RedisKey>>(client: RedisClient, key: K, config: LockConfig) -> Self {
+    let key = key.into();
+    let fence_keys = vec![fence_key(&client, &key)];
+
+    Lock {
+      inner: Arc::new(LockInner {
+        clients: vec![client],
+        key,
+        fence_keys,
+        config,
+      }),
+    }
+  }
+
+  /// Create a new lock on `key` using the Redlock algorithm across several independent servers.
+  ///
+  /// The clients should connect to independent deployments. The lock is acquired, extended, or released when a
+  /// majority of the servers agree. The fencing token is the largest token returned by the servers that acquired the
+  /// lock, which is not unique or increasing across holders and should not be used as a fence.
+  pub fn redlock<K>(clients: Vec<RedisClient>, key: K, config: LockConfig) -> Result<Self, RedisError>
+  where
+    K: Into<RedisKey>,
+  {
+    if clients.is_empty() {
+      return Err(RedisError::new(
+        RedisErrorKind::InvalidArgument,
+        "At least one client is required.",
+      ));
+    }
+    let key = key.into();
+    let fence_keys = clients.iter().map(|client| fence_key(client, &key)).collect();
+
+    Ok(Lock {
+      inner: Arc::new(LockInner {
+        clients,
+        key,
+        fence_keys,
+        config,
+      }),
+    })
+  }
+
+  /// Read the lock key.
+  pub fn key(&self) -> &RedisKey {
+    &self.inner.key
+  }
+
+  /// Read the lock configuration options.
+  pub fn config(&self) -> &LockConfig {
+    &self.inner.config
+  }
+
+  /// Attempt to acquire the lock once, returning `None` if the lock is held by another caller.
+  pub async fn try_acquire(&self) -> Result<Option<LockGuard>, RedisError> {
+    let token = utils::random_string(TOKEN_LEN);
+    let ttl = self.inner.config.ttl;
+    let start = Instant::now();
+    let results = self.inner.call_all(&ACQUIRE_SCRIPT, true, &token, ttl).await;
+    let elapsed = start.elapsed().as_millis() as u64;
+
+    let (mut acquired, mut fencing_token, mut errors, mut last_error) = (0, 0, 0, None);
+    for result in results.into_iter() {
+      match result {
+        Ok(RedisValue::Null) => {},
+        Ok(value) => {
+          acquired += 1;
+          fencing_token = cmp::max(fencing_token, value.as_u64().unwrap_or(0));
+        },
+        Err(error) => {
+          errors += 1;
+          last_error = Some(error);
+        },
+      }
+    }
+
+    if acquired >= self.inner.quorum() && self.inner.validity(ttl, elapsed) > 0 {
+      return Ok(Some(LockGuard::new(self.inner.clone(), token, fencing_token)));
+    }
+
+    // release the lock on every server in case the lock was acquired on a minority of servers, or a response was lost
+    let _ = self.inner.release(&token).await;
+    self.inner.check_errors(errors, last_error).map(|_| None)
+  }
+
+  /// Acquire the lock, waiting for `retry_delay` milliseconds plus a random jitter between attempts.
+  ///
+  /// A `Timeout` error is returned if the lock cannot be acquired within `max_attempts` attempts.
+  pub async fn acquire(&self) -> Result<LockGuard, RedisError> {
+    let mut attempts = 0;
+
+    loop {
+      if let Some(guard) = self.try_acquire().await? {
+        return Ok(guard);
+      }
+
+      attempts += 1;
+      if let Some(max) = self.inner.config.max_attempts {
+        if attempts >= max {
+          return Err(RedisError::new(
+            RedisErrorKind::Timeout,
+            "Could not acquire lock after max attempts.",
+          ));
+        }
+      }
+
+      let delay = self.inner.config.retry_delay;
+      sleep(Duration::from_millis(delay + utils::random_u64(delay + 1))).await;
+    }
+  }
+}
+
+/// A held [Lock](Lock).
+///
+/// If the lock was created with `auto_renew` the lock is extended in a background task until the guard is released
+/// or dropped. Dropping the guard stops the background task but does not release the lock, which will expire after
+/// `ttl` milliseconds.
+pub struct LockGuard {
+  inner:         Arc<LockInner>,
+  token:         String,
+  fencing_token: u64,
+  held:          Arc<AtomicBool>,
+  renew_task:    Option<JoinHandle<()>>,
+}
+
+impl fmt::Debug for LockGuard {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("LockGuard")
+      .field("key", &self.inner.key)
+      .field("fencing_token", &self.fencing_token)
+      .field("held", &self.is_held())
+      .finish()
+  }
+}
+
+impl Drop for LockGuard {
+  fn drop(&mut self) {
+    if let Some(task) = self.renew_task.take() {
+      task.abort();
+    }
+  }
+}
+
+impl LockGuard {
+  fn new(inner: Arc<LockInner>, token: String, fencing_token: u64) -> Self {
+    let held = Arc::new(AtomicBool::new(true));
+    let renew_task = if inner.config.auto_renew {
+      Some(spawn_renew_task(inner.clone(), token.clone(), held.clone()))
+    } else {
+      None
+    };
+
+    LockGuard {
+      inner,
+      token,
+      fencing_token,
+      held,
+      renew_task,
+    }
+  }
+
+  /// The fencing token associated with this lock holder.
+  ///
+  /// With a single server fencing tokens increase each time the lock is acquired. With the Redlock algorithm the
+  /// token is not guaranteed to be unique or increasing and is not a safe fence.
+  pub fn fencing_token(&self) -> u64 {
+    self.fencing_token
+  }
+
+  /// The random token that identifies this lock holder.
+  pub fn token(&self) -> &str {
+    &self.token
+  }
+
+  /// Whether the lock is still held, as of the last time it was extended.
+  pub fn is_held(&self) -> bool {
+    utils::read_bool_atomic(&self.held)
+  }
+
+  /// Set the lock to expire after `ttl` milliseconds, returning whether the lock is still held.
+  pub async fn extend(&self, ttl: u64) -> Result<bool, RedisError> {
+    let extended = self.inner.extend(&self.token, ttl).await?;
+    if !extended {
+      utils::set_bool_atomic(&self.held, false);
+    }
+
+    Ok(extended)
+  }
+
+  /// Release the lock, returning whether the lock was still held.
+  pub async fn release(mut self) -> Result<bool, RedisError> {
+    if let Some(task) = self.renew_task.take() {
+      task.abort();
+    }
+    utils::set_bool_atomic(&self.held, false);
+
+    self.inner.release(&self.token).await
+  }
+}
+
+/// Spawn a task that extends the lock every `ttl / 3` milliseconds until the lock is lost.
+fn spawn_renew_task(inner: Arc<LockInner>, token: String, held: Arc<AtomicBool>) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    let ttl = inner.config.ttl;
+    let interval = Duration::from_millis(cmp::max(ttl / 3, 1));
+
+    loop {
+      sleep(interval).await;
+
+      match inner.extend(&token, ttl).await {
+        Ok(true) => continue,
+        Ok(false) => {
+          let client = &inner.clients[0];
+          let _inner = client.inner();
+          _warn!(_inner, "Lost lock on {:?}. Stopping renewal task.", inner.key);
+          utils::set_bool_atomic(&held, false);
+          break;
+        },
+        Err(error) => {
+          let client = &inner.clients[0];
+          let _inner = client.inner();
+          _warn!(_inner, "Error extending lock on {:?}: {:?}", inner.key, error);
+        },
+      }
+    }
+  })
+}
+
+/// Whether the key contains a non-empty hash tag.
+fn has_hash_tag(key: &[u8]) -> bool {
+  match key.iter().position(|b| *b == b'{') {
+    Some(start) => match key[start + 1 ..].iter().position(|b| *b == b'}') {
+      Some(len) => len > 0,
+      None => false,
+    },
+    None => false,
+  }
+}
+
+/// Create the key that stores the fencing token counter from the unprefixed lock key.
+///
+/// The client adds its key prefix to both keys. If the prefixed lock key contains a hash tag, appending a suffix
+/// keeps the same hash tag. Otherwise the whole prefixed key is hashed, so it is used as the hash tag of the fencing
+/// token key.
+fn fence_key_with_prefix(prefix: &[u8], key: &RedisKey) -> RedisKey {
+  let key = key.as_bytes();
+  let mut prefixed = Vec::with_capacity(prefix.len() + key.len());
+  prefixed.extend_from_slice(prefix);
+  prefixed.extend_from_slice(key);
+
+  let mut out = Vec::with_capacity(prefixed.len() + FENCE_SUFFIX.len() + 2);
+  if has_hash_tag(&prefixed) {
+    out.extend_from_slice(key);
+  } else {
+    out.push(b'{');
+    out.extend_from_slice(&prefixed);
+    out.push(b'}');
+  }
+  out.extend_from_slice(FENCE_SUFFIX.as_bytes());
+
+  out.as_slice().into()
+}
+
+/// Create the key that stores the fencing token counter using the client's key prefix.
+fn fence_key(client: &RedisClient, key: &RedisKey) -> RedisKey {
+  let prefix = client
+    .inner()
+    .config
+    .key_prefix
+    .as_ref()
+    .map(|p| p.as_bytes())
+    .unwrap_or(b"");
+  fence_key_with_prefix(prefix, key)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::protocol::types::ClusterRouting;
+
+  fn with_prefix(prefix: &[u8], key: &[u8]) -> Vec<u8> {
+    let mut out = prefix.to_vec();
+    out.extend_from_slice(key);
+    out
+  }
+
+  #[test]
+  fn should_create_fence_key_in_same_slot() {
+    for key in ["foo", "{foo}bar", "foo{bar}baz", "a{b", "}a{b}"].iter() {
+      let key = RedisKey::from(*key);
+      let fence = fence_key_with_prefix(b"", &key);
+
+      assert_ne!(fence, key);
+      assert_eq!(
+        ClusterRouting::hash_key(fence.as_bytes()),
+        ClusterRouting::hash_key(key.as_bytes())
+      );
+    }
+  }
+
+  #[test]
+  fn should_create_fence_key_in_same_slot_with_prefix() {
+    for (prefix, key) in [
+      (&b"app:"[..], "{foo}"),
+      (&b"app:"[..], "foo{bar}"),
+      (&b"{app}:"[..], "foo"),
+      (&b"app:"[..], "foo"),
+      (&b"app:"[..], "foo:bar"),
+    ]
+    .iter()
+    {
+      let key = RedisKey::from(*key);
+      let fence = fence_key_with_prefix(prefix, &key);
+
+      assert!(!fence.as_bytes().starts_with(prefix));
+      assert_eq!(
+        ClusterRouting::hash_key(&with_prefix(prefix, fence.as_bytes())),
+        ClusterRouting::hash_key(&with_prefix(prefix, key.as_bytes()))
+      );
+    }
+  }
+
+  #[cfg(feature = "mocks")]
+  #[tokio::test]
+  async fn should_verify_token_on_each_server_when_releasing() {
+    use crate::{
+      mocks::{MockCommand, Mocks},
+      types::RedisConfig,
+    };
+    use parking_lot::Mutex;
+
+    /// A server that runs the lock scripts against a single stored token.
+    #[derive(Debug, Default)]
+    struct Server {
+      token: Mutex<Option<String>>,
+    }
+
+    impl Mocks for Server {
+      fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+        let num_keys = command.args[1].as_u64().unwrap() as usize;
+        let token = command.args[2 + num_keys].as_string().unwrap();
+        let mut stored = self.token.lock();
+
+        if num_keys == 2 {
+          if stored.is_some() {
+            Ok(RedisValue::Null)
+          } else {
+            *stored = Some(token);
+            Ok(RedisValue::Integer(1))
+          }
+        } else if stored.as_ref() == Some(&token) {
+          *stored = None;
+          Ok(RedisValue::Integer(1))
+        } else {
+          Ok(RedisValue::Integer(0))
+        }
+      }
+    }
+
+    let servers: Vec<Arc<Server>> = (0 .. 3).map(|_| Arc::new(Server::default())).collect();
+    let mut clients = Vec::with_capacity(servers.len());
+    for server in servers.iter() {
+      let config = RedisConfig {
+        mocks: Some(server.clone()),
+        ..Default::default()
+      };
+      let client = RedisClient::new(config, None, None, None);
+      let _ = client.connect();
+      client.wait_for_connect().await.unwrap();
+      clients.push(client);
+    }
+
+    let config = LockConfig {
+      auto_renew: false,
+      ..Default::default()
+    };
+    let lock = Lock::redlock(clients, "foo", config).unwrap();
+    let guard = lock.try_acquire().await.unwrap().unwrap();
+    for server in servers[1 ..].iter() {
+      *server.token.lock() = Some("other".into());
+    }
+
+    assert!(!guard.release().await.unwrap());
+    assert_eq!(*servers[0].token.lock(), None);
+    for server in servers[1 ..].iter() {
+      assert_eq!(server.token.lock().as_deref(), Some("other"));
+    }
+  }
+
+  #[test]
+  fn should_subtract_clock_drift_from_validity() {
+    let inner = LockInner {
+      clients:    Vec::new(),
+      key:        "foo".into(),
+      fence_keys: Vec::new(),
+      config:     LockConfig::default(),
+    };
+
+    assert_eq!(inner.validity(30000, 100), 30000 - 100 - 300 - CLOCK_DRIFT_MS);
+    assert_eq!(inner.validity(100, 200), 0);
+  }
+}
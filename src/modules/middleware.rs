@@ -0,0 +1,293 @@
+//! An interface for inspecting and modifying commands and responses.
+//!
+//! Middleware is configured as an ordered list on the [RedisConfig](crate::types::RedisConfig). Before a command is
+//! sent to the server the [on_request](Middleware::on_request) function is called on each middleware in order, and
+//! after the command finishes the [on_response](Middleware::on_response) function is called on each middleware in
+//! reverse order.
+//!
+//! ```rust no_run
+//! # use fred::{prelude::*, middleware::{Middleware, MiddlewareCommand}};
+//! # use std::sync::Arc;
+//! #[derive(Debug)]
+//! struct Audit;
+//!
+//! impl Middleware for Audit {
+//!   fn on_request(&self, command: &mut MiddlewareCommand) -> Result<(), RedisError> {
+//!     println!("Sending {} with {} arguments", command.cmd(), command.args.len());
+//!     Ok(())
+//!   }
+//!
+//!   fn on_response(&self, command: &MiddlewareCommand, result: &mut Result<Resp3Frame, RedisError>) {
+//!     if let Err(ref error) = result {
+//!       println!("{} failed: {:?}", command.cmd(), error);
+//!     }
+//!   }
+//! }
+//!
+//! async fn example() -> Result<(), RedisError> {
+//!   let config = RedisConfig {
+//!     middleware: vec![Arc::new(Audit)],
+//!     ..Default::default()
+//!   };
+//!   let client = Builder::from_config(config).build()?;
+//!   let _ = client.connect();
+//!   client.wait_for_connect().await?;
+//!
+//!   let _: () = client.set("foo", "bar", None, None, false).await?;
+//!   Ok(())
+//! }
+//! ```
+
+use crate::{
+  error::RedisError,
+  interfaces::Resp3Frame,
+  modules::inner::RedisClientInner,
+  protocol::command::RedisCommand,
+  types::{Options, RedisValue},
+};
+use bytes_utils::Str;
+use std::{fmt::Debug, mem, sync::Arc};
+
+/// A wrapper type for the parts of a Redis command that can be inspected or modified by a [Middleware].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MiddlewareCommand {
+  cmd:         Str,
+  subcommand:  Option<Str>,
+  buffered:    bool,
+  /// The ordered list of arguments to the command.
+  ///
+  /// The arguments are moved to the command after the last [on_request](Middleware::on_request) call, so this is
+  /// empty in [on_response](Middleware::on_response).
+  pub args:    Vec<RedisValue>,
+  /// The configuration options that will be applied to the command.
+  pub options: Options,
+}
+
+impl MiddlewareCommand {
+  /// Read the first word in the command string. For example:
+  /// * `SET` - `"SET"`
+  /// * `XGROUP CREATE` - `"XGROUP"`
+  pub fn cmd(&self) -> &str {
+    &self.cmd
+  }
+
+  /// Read the optional subcommand string (or second word) in the command string. For example:
+  /// * `SET` - `None`
+  /// * `XGROUP CREATE` - `Some("CREATE")`
+  pub fn subcommand(&self) -> Option<&str> {
+    self.subcommand.as_deref()
+  }
+
+  /// Whether the command was added to a pipeline or transaction, in which case the result provided to
+  /// [on_response](Middleware::on_response) is the `QUEUED` response returned when the command is buffered in memory.
+  pub fn is_buffered(&self) -> bool {
+    self.buffered
+  }
+}
+
+/// An interface for inspecting and modifying commands before they are sent and their results after they finish.
+///
+/// Middleware is applied to commands sent via the public interfaces, including commands added to a pipeline or
+/// transaction. In the pipeline and transaction case the result provided to
+/// [on_response](Self::on_response) is the `QUEUED` response returned when the command is buffered in memory, and
+/// [is_buffered](MiddlewareCommand::is_buffered) returns `true`. Commands with a streamed response, such as
+/// [lrange_stream](crate::clients::RedisClient::lrange_stream), call `on_response` once for each element.
+///
+/// The following commands are not passed to middleware:
+/// * `SCAN`, `HSCAN`, `SSCAN`, and `ZSCAN`, including each page requested by a scan stream.
+/// * `SUBSCRIBE`, `PSUBSCRIBE`, `SSUBSCRIBE`, and the corresponding unsubscribe commands, as well as the messages
+///   received by the publish-subscribe interface.
+/// * Commands sent by the client itself, such as `AUTH`, `HELLO`, `SELECT`, and cluster discovery commands.
+/// * Commands sent on the backchannel connection, such as `CLIENT UNBLOCK` and `FUNCTION KILL`.
+///
+/// Note: errors returned by the server are typically provided as `SimpleError` or `BlobError` frames rather than
+/// `Err(RedisError)` values. Errors that occur within the client (timeouts, connection errors, etc) are provided as
+/// `Err(RedisError)` values.
+#[allow(unused_variables)]
+pub trait Middleware: Debug + Send + Sync + 'static {
+  /// Inspect or modify a command before it is sent to the server.
+  ///
+  /// Returning an error will stop the command from being sent and the error will be returned to the caller. The
+  /// remaining middleware will not be called.
+  ///
+  /// This is called after the client's [Options](crate::types::Options) are applied and before the
+  /// [key prefix](crate::types::RedisConfig::key_prefix) is added, so the key arguments are the values provided by
+  /// the caller. The prefix and cluster hash slot are applied to the arguments after all `on_request` calls.
+  fn on_request(&self, command: &mut MiddlewareCommand) -> Result<(), RedisError> {
+    Ok(())
+  }
+
+  /// Inspect or modify the result of a command.
+  ///
+  /// The provided command contains the options after all `on_request` calls. The arguments are not included.
+  fn on_response(&self, command: &MiddlewareCommand, result: &mut Result<Resp3Frame, RedisError>) {}
+}
+
+/// Run the request hook on each middleware, returning the final command state to use with the response hooks.
+pub(crate) fn process_request(
+  inner: &Arc<RedisClientInner>,
+  command: &mut RedisCommand,
+  buffered: bool,
+) -> Result<Option<MiddlewareCommand>, RedisError> {
+  if inner.config.middleware.is_empty() {
+    return Ok(None);
+  }
+
+  let mut wrapped = MiddlewareCommand {
+    cmd: command.kind.cmd_str(),
+    subcommand: command.kind.subcommand_str(),
+    buffered,
+    args: command.take_args(),
+    options: Options::from_command(command),
+  };
+  for middleware in inner.config.middleware.iter() {
+    if let Err(error) = middleware.on_request(&mut wrapped) {
+      _debug!(
+        inner,
+        "Middleware rejected {}: {:?}",
+        command.kind.to_str_debug(),
+        error
+      );
+      return Err(error);
+    }
+  }
+
+  wrapped.options.apply(command);
+  *command.args_mut() = mem::take(&mut wrapped.args);
+  Ok(Some(wrapped))
+}
+
+/// Run the response hook on each middleware in reverse order.
+pub(crate) fn process_response(
+  inner: &Arc<RedisClientInner>,
+  command: Option<&MiddlewareCommand>,
+  mut result: Result<Resp3Frame, RedisError>,
+) -> Result<Resp3Frame, RedisError> {
+  if let Some(command) = command {
+    for middleware in inner.config.middleware.iter().rev() {
+      middleware.on_response(command, &mut result);
+    }
+  }
+
+  result
+}
+
+#[cfg(test)]
+#[cfg(feature = "mocks")]
+mod tests {
+  use super::*;
+  use crate::{
+    clients::RedisClient,
+    error::RedisErrorKind,
+    interfaces::{ClientLike, KeysInterface},
+    mocks::Echo,
+    types::RedisConfig,
+  };
+  use parking_lot::Mutex;
+  use tokio::task::JoinHandle;
+
+  #[derive(Debug)]
+  struct AppendArg;
+
+  impl Middleware for AppendArg {
+    fn on_request(&self, command: &mut MiddlewareCommand) -> Result<(), RedisError> {
+      command.args.push("baz".into());
+      Ok(())
+    }
+  }
+
+  #[derive(Debug)]
+  struct Reject;
+
+  impl Middleware for Reject {
+    fn on_request(&self, command: &mut MiddlewareCommand) -> Result<(), RedisError> {
+      if command.cmd() == "GET" {
+        Err(RedisError::new(RedisErrorKind::Unknown, "Rejected."))
+      } else {
+        Ok(())
+      }
+    }
+  }
+
+  #[derive(Debug)]
+  struct RenameKey;
+
+  impl Middleware for RenameKey {
+    fn on_request(&self, command: &mut MiddlewareCommand) -> Result<(), RedisError> {
+      assert_eq!(command.args[0].as_str().unwrap(), "foo");
+      command.args[0] = "bar".into();
+      Ok(())
+    }
+  }
+
+  #[derive(Debug, Default)]
+  struct Audit {
+    commands: Mutex<Vec<String>>,
+  }
+
+  impl Middleware for Audit {
+    fn on_response(&self, command: &MiddlewareCommand, result: &mut Result<Resp3Frame, RedisError>) {
+      assert!(result.is_ok());
+      assert!(command.args.is_empty());
+      self.commands.lock().push(command.cmd().to_owned());
+    }
+  }
+
+  async fn create_client(middleware: Vec<Arc<dyn Middleware>>) -> (RedisClient, JoinHandle<Result<(), RedisError>>) {
+    let config = RedisConfig {
+      mocks: Some(Arc::new(Echo)),
+      middleware,
+      ..Default::default()
+    };
+    let client = RedisClient::new(config, None, None, None);
+    let jh = client.connect();
+    let _ = client.wait_for_connect().await.expect("Failed to connect");
+
+    (client, jh)
+  }
+
+  #[tokio::test]
+  async fn should_modify_command_arguments() {
+    let (client, _) = create_client(vec![Arc::new(AppendArg)]).await;
+
+    let actual: Vec<RedisValue> = client.get("foo").await.expect("Failed to call GET");
+    let expected: Vec<RedisValue> = vec!["foo".into(), "baz".into()];
+    assert_eq!(actual, expected);
+  }
+
+  #[tokio::test]
+  async fn should_reject_command_before_sending() {
+    let (client, _) = create_client(vec![Arc::new(Reject), Arc::new(AppendArg)]).await;
+
+    let error = client.get::<RedisValue, _>("foo").await.unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::Unknown);
+    let actual: Vec<RedisValue> = client.del("foo").await.expect("Failed to call DEL");
+    assert_eq!(actual, vec![RedisValue::from("foo"), "baz".into()]);
+  }
+
+  #[tokio::test]
+  async fn should_run_before_the_key_prefix() {
+    let config = RedisConfig {
+      mocks: Some(Arc::new(Echo)),
+      middleware: vec![Arc::new(RenameKey)],
+      key_prefix: Some("app:".into()),
+      ..Default::default()
+    };
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    let _ = client.wait_for_connect().await.expect("Failed to connect");
+
+    let actual: Vec<RedisValue> = client.get("foo").await.expect("Failed to call GET");
+    assert_eq!(actual, vec![RedisValue::from("app:bar")]);
+  }
+
+  #[tokio::test]
+  async fn should_inspect_responses() {
+    let audit = Arc::new(Audit::default());
+    let (client, _) = create_client(vec![audit.clone()]).await;
+
+    let _: RedisValue = client.get("foo").await.expect("Failed to call GET");
+    let _: RedisValue = client.del("foo").await.expect("Failed to call DEL");
+    assert_eq!(*audit.commands.lock(), vec!["GET".to_owned(), "DEL".to_owned()]);
+  }
+}
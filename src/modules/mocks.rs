@@ -9,18 +9,26 @@
 //! * `SimpleMap` - A mocking struct that implements the basic `GET`, `SET`, and `DEL` commands.
 //! * `Buffer` - A mocking struct that buffers commands internally, returning `QUEUED` to each command. Callers can
 //!   then drain or inspect the buffer later.
+//! * `InMemoryMocks` - A mocking struct that stores strings, hashes, lists, sets, and sorted sets in memory and
+//!   implements the common commands for each type.
 //!
 //! The base `Mocks` trait is directly exposed so callers can implement their own mocking layer as well.
 
 use crate::{
   error::{RedisError, RedisErrorKind},
+  modules::keyspace::{utils::*, Keyspace, DATABASES},
+  protocol::utils as protocol_utils,
   types::{RedisKey, RedisValue},
 };
+use bytes::Bytes;
 use bytes_utils::Str;
 use parking_lot::Mutex;
+use redis_protocol::resp3::types::Frame as Resp3Frame;
 use std::{
   collections::{HashMap, VecDeque},
   fmt::Debug,
+  sync::atomic::{AtomicUsize, Ordering},
+  time::Duration,
 };
 
 /// A wrapper type for the parts of an internal Redis command.
@@ -318,6 +326,141 @@ impl Mocks for Buffer {
   }
 }
 
+/// A mocking layer that stores values in memory and implements the common commands for each data type.
+///
+/// The following commands are supported:
+/// * Keys - `DEL`, `UNLINK`, `EXISTS`, `TYPE`, `KEYS`, `SCAN`, `RENAME`, `EXPIRE`, `PEXPIRE`, `EXPIREAT`,
+///   `PEXPIREAT`, `TTL`, `PTTL`, `PERSIST`, `DBSIZE`, `FLUSHDB`, `FLUSHALL`, `SELECT`
+/// * Strings and counters - `GET`, `SET`, `SETNX`, `SETEX`, `PSETEX`, `GETDEL`, `MGET`, `MSET`, `INCR`, `DECR`,
+///   `INCRBY`, `DECRBY`, `INCRBYFLOAT`, `APPEND`, `STRLEN`
+/// * Hashes - `HSET`, `HMSET`, `HSETNX`, `HGET`, `HMGET`, `HDEL`, `HGETALL`, `HKEYS`, `HVALS`, `HLEN`, `HEXISTS`,
+///   `HINCRBY`, `HINCRBYFLOAT`, `HSCAN`
+/// * Lists - `LPUSH`, `RPUSH`, `LPOP`, `RPOP`, `LRANGE`, `LLEN`, `LINDEX`, `LTRIM`
+/// * Sets - `SADD`, `SREM`, `SMEMBERS`, `SISMEMBER`, `SCARD`, `SSCAN`
+/// * Sorted sets - `ZADD`, `ZREM`, `ZSCORE`, `ZCARD`, `ZINCRBY`, `ZRANGE`, `ZRANGEBYSCORE`, `ZRANK`, `ZSCAN`
+///
+/// Errors use the same format as Redis (`WRONGTYPE ...`, `ERR value is not an integer or out of range`, etc) and are
+/// converted to a `RedisError` the same way as errors from a server. Transactions are applied atomically via
+/// [process_transaction](Mocks::process_transaction).
+///
+/// The clock used for expirations only moves forward when the caller calls [advance](Self::advance).
+///
+/// ```rust no_run
+/// #[tokio::test]
+/// async fn should_use_in_memory_mocks() {
+///   let mocks = Arc::new(InMemoryMocks::new());
+///   let config = RedisConfig {
+///     mocks: Some(mocks.clone()),
+///     ..Default::default()
+///   };
+///   let client = Builder::from_config(config).build().unwrap();
+///   let _ = client.connect();
+///   let _ = client.wait_for_connect().await.expect("Failed to connect");
+///
+///   let _: () = client
+///     .set("foo", "bar", Some(Expiration::EX(10)), None, false)
+///     .await
+///     .expect("Failed to call SET");
+///   let actual: i64 = client.ttl("foo").await.expect("Failed to call TTL");
+///   assert_eq!(actual, 10);
+///
+///   mocks.advance(Duration::from_secs(10));
+///   let actual: Option<String> = client.get("foo").await.expect("Failed to call GET");
+///   assert_eq!(actual, None);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct InMemoryMocks {
+  keyspace: Mutex<Keyspace>,
+  db:       AtomicUsize,
+}
+
+impl InMemoryMocks {
+  /// Create a new empty `InMemoryMocks` with a clock that starts at the current system time.
+  pub fn new() -> Self {
+    InMemoryMocks {
+      keyspace: Mutex::new(Keyspace::manual_clock()),
+      db:       AtomicUsize::new(0),
+    }
+  }
+
+  /// Move the clock used for expirations forward by `duration`.
+  pub fn advance(&self, duration: Duration) {
+    self.keyspace.lock().advance(duration);
+  }
+
+  /// Read the current time on the mocked clock, in milliseconds since the unix epoch.
+  pub fn now(&self) -> u64 {
+    self.keyspace.lock().now()
+  }
+
+  /// Remove all keys from all databases.
+  pub fn clear(&self) {
+    self.keyspace.lock().flush_all();
+  }
+
+  fn execute(&self, keyspace: &mut Keyspace, command: MockCommand) -> Result<RedisValue, RedisError> {
+    let cmd = command.cmd.to_uppercase();
+    let mut args = Vec::with_capacity(command.args.len() + 1);
+    if let Some(subcommand) = command.subcommand {
+      args.push(subcommand.into_inner());
+    }
+    for arg in command.args.iter() {
+      let frame = protocol_utils::value_to_outgoing_resp3_frame(arg)?;
+      args.push(protocol_utils::frame_to_bytes(&frame).unwrap_or_default());
+    }
+
+    let result = match cmd.as_str() {
+      "PING" => Ok(args.pop().map(blob).unwrap_or_else(|| simple("PONG"))),
+      "ECHO" => check_args(&cmd, &args, 1, Some(1), 1).map(|_| blob(args[0].clone())),
+      "SELECT" => self.select(&args),
+      "FLUSHALL" => {
+        keyspace.flush_all();
+        Ok(ok())
+      },
+      _ => {
+        let db = self.db.load(Ordering::Acquire);
+        keyspace
+          .execute(db, &cmd, &args, false)
+          .unwrap_or_else(|| Err(unknown_command(&cmd)))
+      },
+    };
+
+    protocol_utils::frame_to_results(result.unwrap_or_else(|e| e))
+  }
+
+  fn select(&self, args: &[Bytes]) -> Result<Resp3Frame, Resp3Frame> {
+    check_args("SELECT", args, 1, Some(1), 1)?;
+    let db = parse_i64(&args[0])?;
+    if db < 0 || db as usize >= DATABASES {
+      return Err(error("ERR DB index is out of range"));
+    }
+
+    self.db.store(db as usize, Ordering::Release);
+    Ok(ok())
+  }
+}
+
+impl Mocks for InMemoryMocks {
+  fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+    let mut keyspace = self.keyspace.lock();
+    self.execute(&mut keyspace, command)
+  }
+
+  fn process_transaction(&self, commands: Vec<MockCommand>) -> Result<RedisValue, RedisError> {
+    let mut keyspace = self.keyspace.lock();
+    let results: Vec<_> = commands
+      .into_iter()
+      .map(|command| self.execute(&mut keyspace, command))
+      .collect();
+
+    results
+      .into_iter()
+      .collect::<Result<Vec<_>, _>>()
+      .map(RedisValue::Array)
+  }
+}
+
 #[cfg(test)]
 #[cfg(feature = "mocks")]
 mod tests {
@@ -325,12 +468,20 @@ mod tests {
   use crate::{
     clients::RedisClient,
     error::RedisError,
-    interfaces::{ClientLike, KeysInterface},
-    mocks::{Buffer, Echo, Mocks, SimpleMap},
+    interfaces::{
+      ClientLike,
+      HashesInterface,
+      KeysInterface,
+      ListInterface,
+      SortedSetsInterface,
+      TransactionInterface,
+    },
+    mocks::{Buffer, Echo, InMemoryMocks, Mocks, SimpleMap},
     prelude::Expiration,
-    types::{RedisConfig, RedisValue, SetOptions},
+    types::{RedisConfig, RedisKey, RedisValue, Scanner, SetOptions},
   };
-  use std::sync::Arc;
+  use futures::StreamExt;
+  use std::{collections::HashMap, sync::Arc};
   use tokio::task::JoinHandle;
 
   async fn create_mock_client(mocks: Arc<dyn Mocks>) -> (RedisClient, JoinHandle<Result<(), RedisError>>) {
@@ -405,4 +556,121 @@ mod tests {
     ];
     assert_eq!(buffer.take(), expected);
   }
+
+  #[tokio::test]
+  async fn should_use_in_memory_mock_data_types() {
+    let (client, _) = create_mock_client(Arc::new(InMemoryMocks::new())).await;
+
+    let _: () = client.hset("hash", ("a", 1)).await.unwrap();
+    let actual: i64 = client.hincrby("hash", "a", 2).await.unwrap();
+    assert_eq!(actual, 3);
+    let actual: HashMap<String, i64> = client.hgetall("hash").await.unwrap();
+    assert_eq!(actual, HashMap::from([("a".to_owned(), 3)]));
+
+    let _: () = client.rpush("list", vec![1, 2, 3]).await.unwrap();
+    let actual: Vec<i64> = client.lrange("list", 0, -1).await.unwrap();
+    assert_eq!(actual, vec![1, 2, 3]);
+
+    let _: () = client
+      .zadd("zset", None, None, false, false, vec![(2.0, "b"), (1.0, "a")])
+      .await
+      .unwrap();
+    let actual: Vec<String> = client.zrange("zset", 0, -1, None, false, None, false).await.unwrap();
+    assert_eq!(actual, vec!["a", "b"]);
+
+    let error = client.get::<RedisValue, _>("hash").await.unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::InvalidArgument);
+    assert_eq!(
+      error.details(),
+      "WRONGTYPE Operation against a key holding the wrong kind of value"
+    );
+    let error = client.incr::<i64, _>("list").await.unwrap_err();
+    assert!(error.details().starts_with("WRONGTYPE"));
+  }
+
+  #[tokio::test]
+  async fn should_expire_in_memory_mock_keys() {
+    let mocks = Arc::new(InMemoryMocks::new());
+    let (client, _) = create_mock_client(mocks.clone()).await;
+
+    let _: () = client
+      .set("foo", "bar", Some(Expiration::EX(10)), None, false)
+      .await
+      .unwrap();
+    mocks.advance(Duration::from_secs(5));
+    let actual: i64 = client.ttl("foo").await.unwrap();
+    assert_eq!(actual, 5);
+    let actual: Option<String> = client.get("foo").await.unwrap();
+    assert_eq!(actual.as_deref(), Some("bar"));
+
+    mocks.advance(Duration::from_secs(5));
+    let actual: Option<String> = client.get("foo").await.unwrap();
+    assert_eq!(actual, None);
+  }
+
+  #[tokio::test]
+  async fn should_scan_in_memory_mock_keys() {
+    let (client, _) = create_mock_client(Arc::new(InMemoryMocks::new())).await;
+    for idx in 0 .. 25 {
+      let _: () = client
+        .set(format!("foo-{}", idx), idx, None, None, false)
+        .await
+        .unwrap();
+    }
+    let _: () = client.set("bar", 1, None, None, false).await.unwrap();
+
+    let mut keys = Vec::new();
+    let mut scanner = client.scan("foo-*", Some(10), None);
+    while let Some(page) = scanner.next().await {
+      let mut page = page.unwrap();
+      keys.extend(page.take_results().unwrap_or_default());
+      // deleting returned keys should not cause other keys to be skipped
+      let _: () = client.del(keys.clone()).await.unwrap();
+      page.next().unwrap();
+    }
+
+    keys.sort();
+    let mut expected: Vec<RedisKey> = (0 .. 25).map(|idx| format!("foo-{}", idx).into()).collect();
+    expected.sort();
+    assert_eq!(keys, expected);
+  }
+
+  #[tokio::test]
+  async fn should_prefix_in_memory_mock_keys_pattern() {
+    let mocks = Arc::new(InMemoryMocks::new());
+    let (client, _) = create_mock_client(mocks.clone()).await;
+    let config = RedisConfig {
+      mocks: Some(mocks),
+      key_prefix: Some("app:".into()),
+      ..Default::default()
+    };
+    let prefixed = RedisClient::new(config, None, None, None);
+    let _ = prefixed.connect();
+    prefixed.wait_for_connect().await.unwrap();
+
+    let _: () = client.set("foo", 1, None, None, false).await.unwrap();
+    let _: () = prefixed.set("foo", 2, None, None, false).await.unwrap();
+    let _: () = prefixed.set("bar", 3, None, None, false).await.unwrap();
+
+    let mut actual: Vec<String> = client.keys("*").await.unwrap();
+    actual.sort();
+    assert_eq!(actual, vec!["app:bar", "app:foo", "foo"]);
+    let mut actual: Vec<String> = prefixed.keys("*").await.unwrap();
+    actual.sort();
+    assert_eq!(actual, vec!["bar", "foo"]);
+    let actual: Vec<String> = prefixed.keys("f*").await.unwrap();
+    assert_eq!(actual, vec!["foo"]);
+  }
+
+  #[tokio::test]
+  async fn should_run_in_memory_mock_transactions() {
+    let (client, _) = create_mock_client(Arc::new(InMemoryMocks::new())).await;
+
+    let trx = client.multi();
+    let _: () = trx.incr("foo").await.unwrap();
+    let _: () = trx.incr_by("foo", 2).await.unwrap();
+    let _: () = trx.get("foo").await.unwrap();
+    let actual: (i64, i64, i64) = trx.exec(true).await.unwrap();
+    assert_eq!(actual, (1, 3, 3));
+  }
 }
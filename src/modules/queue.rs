@@ -0,0 +1,560 @@
+//! A reliable job queue built on lists and sorted sets.
+//!
+//! Each queue uses several keys that share the queue name as a hash tag, so a queue can be used in a clustered
+//! deployment:
+//!
+//! * `{name}:pending` - A list of job IDs waiting to be processed.
+//! * `{name}:processing` - A list of job IDs that were dequeued but not yet acknowledged.
+//! * `{name}:deadlines` - A sorted set of the visibility deadline for each processing job.
+//! * `{name}:delayed` - A sorted set of delayed or retried job IDs, scored by the time they should be processed.
+//! * `{name}:jobs` and `{name}:attempts` - Hashes with the payload and number of attempts for each job.
+//! * `{name}:dead` - A list of job IDs that failed `max_attempts` times.
+//!
+//! Dequeued jobs are moved atomically into the processing list. If a job is not acknowledged before its visibility
+//! timeout expires the [reaper](Queue::reap) moves it back to the pending list so another worker can process it.
+//! Jobs that fail are retried with an exponential backoff, and are moved to the dead letter list once they've been
+//! attempted `max_attempts` times.
+//!
+//! ```rust no_run
+//! # use fred::{prelude::*, queue::{Queue, QueueConfig}};
+//! # use std::time::Duration;
+//! async fn example(pool: RedisPool) -> Result<(), RedisError> {
+//!   let queue = Queue::from_pool(pool, "emails", QueueConfig::default());
+//!   let _reaper = queue.start_reaper(Duration::from_secs(1));
+//!
+//!   queue.enqueue("hello").await?;
+//!   queue.enqueue_delayed("world", Duration::from_secs(10)).await?;
+//!
+//!   while let Some(job) = queue.dequeue_blocking(Duration::from_secs(5)).await? {
+//!     let payload: String = job.payload.clone().convert()?;
+//!     if payload.is_empty() {
+//!       queue.nack(&job).await?;
+//!     } else {
+//!       queue.ack(&job).await?;
+//!     }
+//!   }
+//!   Ok(())
+//! }
+//! ```
+
+use crate::{
+  clients::{RedisClient, RedisPool},
+  error::RedisError,
+  interfaces::{ClientLike, ListInterface, RedisResult},
+  types::{LMoveDirection, RedisKey, RedisValue, Script},
+  utils,
+};
+use lazy_static::lazy_static;
+use std::{
+  cmp,
+  fmt,
+  time::{Duration, Instant},
+};
+use tokio::{task::JoinHandle, time::sleep};
+
+/// Functions shared by each script.
+///
+/// Each script is called with the keys `pending`, `processing`, `delayed`, `deadlines`, `jobs`, `attempts`, and
+/// `dead`, in that order.
+macro_rules! lua_prelude {
+  () => {
+    r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local function promote(count)
+  local due = redis.call('ZRANGEBYSCORE', KEYS[3], '-inf', now, 'LIMIT', 0, count)
+  for _, id in ipairs(due) do
+    redis.call('ZREM', KEYS[3], id)
+    redis.call('LPUSH', KEYS[1], id)
+  end
+  return #due
+end
+
+local function claim(id, visibility)
+  redis.call('ZADD', KEYS[4], now + visibility, id)
+  local attempts = redis.call('HINCRBY', KEYS[6], id, 1)
+  return {id, redis.call('HGET', KEYS[5], id), attempts}
+end
+"#
+  };
+}
+
+/// Store the payload and push the job onto the pending list, or the delayed set if `ARGV[3]` is greater than zero.
+const ENQUEUE_LUA: &str = concat!(
+  lua_prelude!(),
+  r#"
+redis.call('HSET', KEYS[5], ARGV[1], ARGV[2])
+local delay = tonumber(ARGV[3])
+if delay > 0 then
+  redis.call('ZADD', KEYS[3], now + delay, ARGV[1])
+else
+  redis.call('LPUSH', KEYS[1], ARGV[1])
+end
+return ARGV[1]
+"#
+);
+
+/// Promote any due jobs, then move the next job into the processing list.
+const DEQUEUE_LUA: &str = concat!(
+  lua_prelude!(),
+  r#"
+promote(tonumber(ARGV[2]))
+local id = redis.call('LMOVE', KEYS[1], KEYS[2], 'RIGHT', 'LEFT')
+if not id then
+  return nil
+end
+return claim(id, tonumber(ARGV[1]))
+"#
+);
+
+/// Remove a job and all of its state.
+const ACK_LUA: &str = concat!(
+  lua_prelude!(),
+  r#"
+local removed = redis.call('LREM', KEYS[2], 1, ARGV[1])
+redis.call('ZREM', KEYS[4], ARGV[1])
+redis.call('HDEL', KEYS[5], ARGV[1])
+redis.call('HDEL', KEYS[6], ARGV[1])
+return removed
+"#
+);
+
+/// Remove a job from the processing list, then retry it after `ARGV[2]` milliseconds or move it to the dead letter
+/// list if `ARGV[3]` is `1`.
+const NACK_LUA: &str = concat!(
+  lua_prelude!(),
+  r#"
+local removed = redis.call('LREM', KEYS[2], 1, ARGV[1])
+redis.call('ZREM', KEYS[4], ARGV[1])
+if removed == 0 then
+  return 0
+end
+
+if ARGV[3] == '1' then
+  redis.call('LPUSH', KEYS[7], ARGV[1])
+else
+  redis.call('ZADD', KEYS[3], now + tonumber(ARGV[2]), ARGV[1])
+end
+return 1
+"#
+);
+
+/// Promote any due jobs, and move up to `ARGV[2]` jobs whose visibility deadline passed back to the pending list, or
+/// to the dead letter list if they were attempted `ARGV[1]` times.
+const REAP_LUA: &str = concat!(
+  lua_prelude!(),
+  r#"
+promote(tonumber(ARGV[2]))
+local expired = redis.call('ZRANGEBYSCORE', KEYS[4], '-inf', now, 'LIMIT', 0, tonumber(ARGV[2]))
+for _, id in ipairs(expired) do
+  redis.call('ZREM', KEYS[4], id)
+  if redis.call('LREM', KEYS[2], 1, id) > 0 then
+    local attempts = tonumber(redis.call('HGET', KEYS[6], id)) or 0
+    if attempts >= tonumber(ARGV[1]) then
+      redis.call('LPUSH', KEYS[7], id)
+    else
+      redis.call('RPUSH', KEYS[1], id)
+    end
+  end
+end
+return #expired
+"#
+);
+
+lazy_static! {
+  static ref ENQUEUE_SCRIPT: Script = Script::from_lua(ENQUEUE_LUA);
+  static ref DEQUEUE_SCRIPT: Script = Script::from_lua(DEQUEUE_LUA);
+  static ref ACK_SCRIPT: Script = Script::from_lua(ACK_LUA);
+  static ref NACK_SCRIPT: Script = Script::from_lua(NACK_LUA);
+  static ref REAP_SCRIPT: Script = Script::from_lua(REAP_LUA);
+}
+
+/// The suffix of each queue key, in the order the keys are passed to each script.
+const KEY_SUFFIXES: [&str; 7] = [
+  "pending",
+  "processing",
+  "delayed",
+  "deadlines",
+  "jobs",
+  "attempts",
+  "dead",
+];
+/// The number of random characters in each job ID.
+const JOB_ID_LEN: usize = 24;
+/// The maximum number of delayed jobs promoted to the pending list by each script call.
+const PROMOTE_COUNT: u64 = 100;
+/// The maximum number of expired jobs returned to the pending list by each call to the reap script.
+const REAP_COUNT: u64 = 100;
+
+/// Configuration options for a [Queue](Queue).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueueConfig {
+  /// The amount of time, in milliseconds, that a dequeued job can be processed before it is returned to the pending
+  /// list by the reaper.
+  ///
+  /// Default: 30000
+  pub visibility_timeout: u64,
+  /// The maximum number of times a job is attempted before it is moved to the dead letter list.
+  ///
+  /// Default: 5
+  pub max_attempts:       u64,
+  /// The amount of time, in milliseconds, to wait before retrying a job after the first failed attempt. The delay
+  /// doubles after each failed attempt.
+  ///
+  /// Default: 1000
+  pub retry_delay:        u64,
+  /// The maximum amount of time, in milliseconds, to wait before retrying a job.
+  ///
+  /// Default: 60000
+  pub max_retry_delay:    u64,
+}
+
+impl Default for QueueConfig {
+  fn default() -> Self {
+    QueueConfig {
+      visibility_timeout: 30000,
+      max_attempts:       5,
+      retry_delay:        1000,
+      max_retry_delay:    60000,
+    }
+  }
+}
+
+impl QueueConfig {
+  /// Calculate the delay, in milliseconds, before retrying a job that failed `attempts` times.
+  fn retry_delay(&self, attempts: u64) -> u64 {
+    let exponent = cmp::min(attempts.saturating_sub(1), 32) as u32;
+    cmp::min(
+      self.retry_delay.saturating_mul(2_u64.saturating_pow(exponent)),
+      self.max_retry_delay,
+    )
+  }
+}
+
+/// A job read from a [Queue](Queue).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Job {
+  /// The ID of the job.
+  pub id:       String,
+  /// The payload provided when the job was enqueued.
+  pub payload:  RedisValue,
+  /// The number of times the job has been dequeued, including the current attempt.
+  pub attempts: u64,
+}
+
+impl Job {
+  /// Parse the response from the dequeue or claim scripts.
+  fn from_response(value: RedisValue) -> Result<Option<Self>, RedisError> {
+    if value.is_null() {
+      return Ok(None);
+    }
+    let (id, payload, attempts) = value.convert()?;
+
+    Ok(Some(Job { id, payload, attempts }))
+  }
+}
+
+#[derive(Clone)]
+enum Clients {
+  Client(RedisClient),
+  Pool(RedisPool),
+}
+
+impl Clients {
+  fn next(&self) -> &RedisClient {
+    match self {
+      Clients::Client(ref client) => client,
+      Clients::Pool(ref pool) => pool.next(),
+    }
+  }
+}
+
+/// A reliable job queue.
+///
+/// See the [module documentation](crate::queue) for more information.
+#[derive(Clone)]
+pub struct Queue {
+  clients: Clients,
+  name:    String,
+  config:  QueueConfig,
+  keys:    Vec<RedisKey>,
+}
+
+impl fmt::Debug for Queue {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Queue")
+      .field("name", &self.name)
+      .field("config", &self.config)
+      .finish()
+  }
+}
+
+impl Queue {
+  /// Create a new queue that uses a single client.
+  ///
+  /// **Note: [dequeue_blocking](Self::dequeue_blocking) will block the client's connection while waiting for a job.**
+  pub fn new<S: Into<String>>(client: RedisClient, name: S, config: QueueConfig) -> Self {
+    Queue::from_clients(Clients::Client(client), name.into(), config)
+  }
+
+  /// Create a new queue that uses a pool of clients.
+  ///
+  /// Each command, including each blocking dequeue, runs on the next client in the pool, so concurrent workers that
+  /// use [dequeue_blocking](Self::dequeue_blocking) only block one connection each. Callers should use a pool that
+  /// is larger than the number of concurrent blocking workers.
+  pub fn from_pool<S: Into<String>>(pool: RedisPool, name: S, config: QueueConfig) -> Self {
+    Queue::from_clients(Clients::Pool(pool), name.into(), config)
+  }
+
+  fn from_clients(clients: Clients, name: String, config: QueueConfig) -> Self {
+    let keys = KEY_SUFFIXES.iter().map(|suffix| queue_key(&name, suffix)).collect();
+
+    Queue {
+      clients,
+      name,
+      config,
+      keys,
+    }
+  }
+
+  /// Read the name of the queue.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Read the queue configuration options.
+  pub fn config(&self) -> &QueueConfig {
+    &self.config
+  }
+
+  /// Read the key of the list that contains the IDs of jobs that failed `max_attempts` times.
+  ///
+  /// The payload for each job remains in the `{name}:jobs` hash until the job is [acknowledged](Self::ack).
+  pub fn dead_letter_key(&self) -> &RedisKey {
+    &self.keys[6]
+  }
+
+  async fn call(&self, script: &Script, args: Vec<RedisValue>) -> RedisResult<RedisValue> {
+    script
+      .evalsha_with_reload(self.clients.next(), self.keys.clone(), args)
+      .await
+  }
+
+  /// Add a job to the queue, returning the job ID.
+  pub async fn enqueue<V>(&self, payload: V) -> RedisResult<String>
+  where
+    V: TryInto<RedisValue> + Send,
+    V::Error: Into<RedisError> + Send,
+  {
+    self.enqueue_delayed(payload, Duration::from_millis(0)).await
+  }
+
+  /// Add a job to the queue that can be dequeued after `delay`, returning the job ID.
+  ///
+  /// Delayed jobs are moved to the pending list by [dequeue](Self::dequeue) or [reap](Self::reap).
+  pub async fn enqueue_delayed<V>(&self, payload: V, delay: Duration) -> RedisResult<String>
+  where
+    V: TryInto<RedisValue> + Send,
+    V::Error: Into<RedisError> + Send,
+  {
+    try_into!(payload);
+    let id = utils::random_string(JOB_ID_LEN);
+    let args = vec![id.as_str().into(), payload, (delay.as_millis() as i64).into()];
+
+    self.call(&ENQUEUE_SCRIPT, args).await?;
+    Ok(id)
+  }
+
+  /// Move the next job into the processing list, returning `None` if the queue is empty.
+  pub async fn dequeue(&self) -> RedisResult<Option<Job>> {
+    let args = vec![
+      (self.config.visibility_timeout as i64).into(),
+      (PROMOTE_COUNT as i64).into(),
+    ];
+
+    Job::from_response(self.call(&DEQUEUE_SCRIPT, args).await?)
+  }
+
+  /// Move the next job into the processing list, waiting up to `timeout` for a job if the queue is empty. A
+  /// `timeout` of zero waits indefinitely.
+  ///
+  /// The client waits for a job with a `BLMOVE` call that moves the next job back to the end of the pending list, and
+  /// then moves the job into the processing list with [dequeue](Self::dequeue). Jobs are therefore never in the
+  /// processing list without a visibility deadline, even if the worker exits while waiting.
+  ///
+  /// Delayed and retried jobs are only moved to the pending list by [dequeue](Self::dequeue) or the
+  /// [reaper](Self::start_reaper), so callers that only use this function should run a reaper task.
+  pub async fn dequeue_blocking(&self, timeout: Duration) -> RedisResult<Option<Job>> {
+    let deadline = Instant::now() + timeout;
+
+    loop {
+      if let Some(job) = self.dequeue().await? {
+        return Ok(Some(job));
+      }
+
+      let wait = if timeout.is_zero() {
+        0.0
+      } else {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        // the server treats a timeout that rounds down to 0 milliseconds as an indefinite timeout
+        if remaining.as_millis() == 0 {
+          return Ok(None);
+        }
+        remaining.as_secs_f64()
+      };
+
+      let id: Option<String> = self
+        .clients
+        .next()
+        .blmove(
+          self.keys[0].clone(),
+          self.keys[0].clone(),
+          LMoveDirection::Right,
+          LMoveDirection::Right,
+          wait,
+        )
+        .await?;
+
+      if id.is_none() {
+        return Ok(None);
+      }
+    }
+  }
+
+  /// Acknowledge that a job finished, removing it from the queue. Returns whether the job was still processing.
+  pub async fn ack(&self, job: &Job) -> RedisResult<bool> {
+    let args = vec![job.id.as_str().into()];
+    Ok(self.call(&ACK_SCRIPT, args).await?.as_u64() == Some(1))
+  }
+
+  /// Indicate that a job failed, retrying it after the backoff delay or moving it to the dead letter list if it was
+  /// attempted `max_attempts` times. Returns whether the job was still processing.
+  pub async fn nack(&self, job: &Job) -> RedisResult<bool> {
+    let dead = job.attempts >= self.config.max_attempts;
+    let delay = self.config.retry_delay(job.attempts);
+    let args = vec![
+      job.id.as_str().into(),
+      (delay as i64).into(),
+      if dead { "1" } else { "0" }.into(),
+    ];
+
+    Ok(self.call(&NACK_SCRIPT, args).await?.as_u64() == Some(1))
+  }
+
+  /// Move delayed jobs that are due to the pending list, and return jobs whose visibility timeout expired to the
+  /// pending list. Returns the number of expired jobs.
+  ///
+  /// Expired jobs are read from the deadline index in batches of at most 100 jobs, so a single call may not return
+  /// every expired job. The [reaper](Self::start_reaper) calls this function until a batch is not full.
+  pub async fn reap(&self) -> RedisResult<u64> {
+    let args = vec![(self.config.max_attempts as i64).into(), (REAP_COUNT as i64).into()];
+
+    Ok(self.call(&REAP_SCRIPT, args).await?.as_u64().unwrap_or(0))
+  }
+
+  /// Spawn a task that calls [reap](Self::reap) every `interval`. Errors are logged and the task runs until it is
+  /// aborted.
+  pub fn start_reaper(&self, interval: Duration) -> JoinHandle<()> {
+    let queue = self.clone();
+
+    tokio::spawn(async move {
+      loop {
+        sleep(interval).await;
+
+        loop {
+          match queue.reap().await {
+            Ok(count) if count >= REAP_COUNT => continue,
+            Ok(_) => break,
+            Err(error) => {
+              let inner = queue.clients.next().inner();
+              _warn!(inner, "Error reaping queue {}: {:?}", queue.name, error);
+              break;
+            },
+          }
+        }
+      }
+    })
+  }
+}
+
+/// Create a queue key that uses the queue name as a hash tag.
+fn queue_key(name: &str, suffix: &str) -> RedisKey {
+  format!("{{{}}}:{}", name, suffix).into()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_calculate_retry_delay() {
+    let config = QueueConfig::default();
+
+    assert_eq!(config.retry_delay(1), 1000);
+    assert_eq!(config.retry_delay(2), 2000);
+    assert_eq!(config.retry_delay(4), 8000);
+    assert_eq!(config.retry_delay(100), config.max_retry_delay);
+  }
+
+  #[test]
+  fn should_parse_dequeued_job() {
+    assert_eq!(Job::from_response(RedisValue::Null).unwrap(), None);
+
+    let value = RedisValue::Array(vec!["foo".into(), "bar".into(), 2.into()]);
+    let expected = Job {
+      id:       "foo".into(),
+      payload:  "bar".into(),
+      attempts: 2,
+    };
+    assert_eq!(Job::from_response(value).unwrap(), Some(expected));
+  }
+
+  #[cfg(feature = "mocks")]
+  #[tokio::test]
+  async fn should_wait_on_pending_list_before_dequeue() {
+    use crate::{
+      mocks::{MockCommand, Mocks},
+      types::RedisConfig,
+    };
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[derive(Debug, Default)]
+    struct Pending {
+      commands: Mutex<Vec<MockCommand>>,
+    }
+
+    impl Mocks for Pending {
+      fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+        let mut commands = self.commands.lock();
+        commands.push(command.clone());
+
+        match &*command.cmd {
+          "BLMOVE" => Ok("foo".into()),
+          _ if commands.len() == 1 => Ok(RedisValue::Null),
+          _ => Ok(RedisValue::Array(vec!["foo".into(), "bar".into(), 1.into()])),
+        }
+      }
+    }
+
+    let mocks = Arc::new(Pending::default());
+    let config = RedisConfig {
+      mocks: Some(mocks.clone()),
+      ..Default::default()
+    };
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    client.wait_for_connect().await.unwrap();
+
+    let queue = Queue::new(client, "jobs", QueueConfig::default());
+    let job = queue.dequeue_blocking(Duration::from_secs(1)).await.unwrap().unwrap();
+    assert_eq!(job.id, "foo");
+
+    let commands = mocks.commands.lock();
+    let cmds: Vec<&str> = commands.iter().map(|command| &*command.cmd).collect();
+    assert_eq!(cmds, vec!["EVALSHA", "BLMOVE", "EVALSHA"]);
+    assert_eq!(commands[1].args[0], commands[1].args[1]);
+    assert_eq!(commands[1].args[2], commands[1].args[3]);
+  }
+}
@@ -0,0 +1,291 @@
+//! Rate limiting algorithms implemented with Lua scripts.
+//!
+//! Each algorithm stores its state in a single key, so limits on different keys can be checked in a clustered
+//! deployment without any cross slot restrictions. The scripts read the current time from the server with `TIME`,
+//! which avoids any clock drift between clients.
+//!
+//! * [Gcra](RateLimitAlgorithm::Gcra) - The [generic cell rate algorithm](https://en.wikipedia.org/wiki/Generic_cell_rate_algorithm).
+//!   Requests are spaced evenly across the window, with a burst of up to `limit` requests. This uses one string key
+//!   per limit.
+//! * [SlidingWindow](RateLimitAlgorithm::SlidingWindow) - A sliding log of request timestamps. This is the most
+//!   precise algorithm but uses one sorted set entry per allowed request.
+//! * [TokenBucket](RateLimitAlgorithm::TokenBucket) - A bucket with `limit` tokens that refills at `limit` tokens per
+//!   window. This uses one hash key per limit.
+//!
+//! ```rust no_run
+//! # use fred::{prelude::*, rate_limit::{RateLimitAlgorithm, RateLimiter}};
+//! # use std::time::Duration;
+//! async fn example(client: RedisClient) -> Result<(), RedisError> {
+//!   let limiter = RateLimiter::new(client, RateLimitAlgorithm::Gcra);
+//!   // optionally load the scripts ahead of time
+//!   limiter.load().await?;
+//!
+//!   // allow 100 requests per minute for each user
+//!   let result = limiter.check("ratelimit:user:1", 100, Duration::from_secs(60)).await?;
+//!   if !result.allowed {
+//!     println!("Rate limited. Retry after {:?}", result.retry_after);
+//!   }
+//!   Ok(())
+//! }
+//! ```
+
+use crate::{
+  clients::RedisClient,
+  error::{RedisError, RedisErrorKind},
+  interfaces::RedisResult,
+  types::{RedisKey, RedisValue, Script},
+  utils,
+};
+use lazy_static::lazy_static;
+use std::time::Duration;
+
+/// Read the server time in milliseconds. Shared by each script.
+macro_rules! lua_now {
+  () => {
+    r#"
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+local limit = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local cost = tonumber(ARGV[3])
+"#
+  };
+}
+
+/// The GCRA script. The key stores the theoretical arrival time (TAT) of the next request.
+const GCRA_LUA: &str = concat!(
+  lua_now!(),
+  r#"
+local interval = window / limit
+local tat = tonumber(redis.call('GET', KEYS[1])) or now
+if tat < now then
+  tat = now
+end
+
+local new_tat = tat + interval * cost
+local allow_at = new_tat - window
+if allow_at > now then
+  local remaining = math.max(math.floor((now - (tat - window)) / interval), 0)
+  return {0, remaining, math.ceil(allow_at - now)}
+end
+
+redis.call('SET', KEYS[1], new_tat, 'PX', math.max(math.ceil(new_tat - now), 1))
+return {1, math.max(math.floor((now - allow_at) / interval), 0), 0}
+"#
+);
+
+/// The sliding log script. The key is a sorted set of request timestamps.
+const SLIDING_WINDOW_LUA: &str = concat!(
+  lua_now!(),
+  r#"
+redis.call('ZREMRANGEBYSCORE', KEYS[1], '-inf', now - window)
+local count = redis.call('ZCARD', KEYS[1])
+if count + cost <= limit then
+  for i = 1, cost do
+    redis.call('ZADD', KEYS[1], now, now .. ':' .. ARGV[4] .. ':' .. i)
+  end
+  redis.call('PEXPIRE', KEYS[1], window)
+  return {1, limit - count - cost, 0}
+end
+
+local retry_after = window
+local expiring = redis.call('ZRANGE', KEYS[1], count + cost - limit - 1, count + cost - limit - 1, 'WITHSCORES')
+if expiring[2] then
+  retry_after = tonumber(expiring[2]) + window - now
+end
+return {0, math.max(limit - count, 0), math.max(retry_after, 1)}
+"#
+);
+
+/// The token bucket script. The key is a hash with the number of tokens and the time they were last updated.
+const TOKEN_BUCKET_LUA: &str = concat!(
+  lua_now!(),
+  r#"
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(state[1]) or limit
+local ts = tonumber(state[2]) or now
+if now > ts then
+  tokens = math.min(limit, tokens + (now - ts) * limit / window)
+end
+
+local allowed, retry_after = 0, 0
+if tokens >= cost then
+  tokens = tokens - cost
+  allowed = 1
+else
+  retry_after = math.ceil((cost - tokens) * window / limit)
+end
+
+redis.call('HSET', KEYS[1], 'tokens', tokens, 'ts', now)
+redis.call('PEXPIRE', KEYS[1], window)
+return {allowed, math.floor(tokens), retry_after}
+"#
+);
+
+lazy_static! {
+  static ref GCRA_SCRIPT: Script = Script::from_lua(GCRA_LUA);
+  static ref SLIDING_WINDOW_SCRIPT: Script = Script::from_lua(SLIDING_WINDOW_LUA);
+  static ref TOKEN_BUCKET_SCRIPT: Script = Script::from_lua(TOKEN_BUCKET_LUA);
+}
+
+/// The number of random characters used to create unique sorted set members in the sliding window script.
+const NONCE_LEN: usize = 16;
+
+/// A rate limiting algorithm.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RateLimitAlgorithm {
+  /// The generic cell rate algorithm.
+  Gcra,
+  /// A sliding log of request timestamps.
+  SlidingWindow,
+  /// A token bucket that refills continuously.
+  TokenBucket,
+}
+
+impl RateLimitAlgorithm {
+  /// Read the script that implements the algorithm.
+  pub fn script(&self) -> &'static Script {
+    match *self {
+      RateLimitAlgorithm::Gcra => &GCRA_SCRIPT,
+      RateLimitAlgorithm::SlidingWindow => &SLIDING_WINDOW_SCRIPT,
+      RateLimitAlgorithm::TokenBucket => &TOKEN_BUCKET_SCRIPT,
+    }
+  }
+}
+
+/// The result of a rate limit check.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RateLimitResult {
+  /// Whether the request is allowed.
+  pub allowed:     bool,
+  /// The number of requests that would be allowed immediately after this request.
+  pub remaining:   u64,
+  /// The amount of time to wait before the request would be allowed. This is zero if the request is allowed.
+  pub retry_after: Duration,
+}
+
+impl TryFrom<RedisValue> for RateLimitResult {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let (allowed, remaining, retry_after): (i64, i64, i64) = value.convert()?;
+
+    Ok(RateLimitResult {
+      allowed:     allowed == 1,
+      remaining:   remaining.max(0) as u64,
+      retry_after: Duration::from_millis(retry_after.max(0) as u64),
+    })
+  }
+}
+
+/// A rate limiter that runs one of the [RateLimitAlgorithm](RateLimitAlgorithm) scripts.
+///
+/// See the [module documentation](crate::rate_limit) for more information.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+  client:    RedisClient,
+  algorithm: RateLimitAlgorithm,
+}
+
+impl RateLimiter {
+  /// Create a new rate limiter with the provided algorithm.
+  pub fn new(client: RedisClient, algorithm: RateLimitAlgorithm) -> Self {
+    RateLimiter { client, algorithm }
+  }
+
+  /// Read the rate limiting algorithm.
+  pub fn algorithm(&self) -> RateLimitAlgorithm {
+    self.algorithm
+  }
+
+  /// Load the script on all the associated servers with `SCRIPT LOAD`.
+  ///
+  /// This is optional. Scripts are loaded automatically the first time they're used on each server.
+  pub async fn load(&self) -> RedisResult<()> {
+    self.algorithm.script().load(&self.client).await
+  }
+
+  /// Check whether a request is allowed, allowing up to `limit` requests per `window`.
+  pub async fn check<K>(&self, key: K, limit: u64, window: Duration) -> RedisResult<RateLimitResult>
+  where
+    K: Into<RedisKey> + Send,
+  {
+    self.check_n(key, limit, window, 1).await
+  }
+
+  /// Check whether a request with a cost of `cost` units is allowed, allowing up to `limit` units per `window`.
+  ///
+  /// The cost must not be greater than the limit.
+  pub async fn check_n<K>(&self, key: K, limit: u64, window: Duration, cost: u64) -> RedisResult<RateLimitResult>
+  where
+    K: Into<RedisKey> + Send,
+  {
+    let window = window_ms(limit, window, cost)?;
+    let args: Vec<RedisValue> = vec![
+      limit.try_into()?,
+      window.try_into()?,
+      cost.try_into()?,
+      utils::random_string(NONCE_LEN).into(),
+    ];
+
+    let result: RedisValue = self
+      .algorithm
+      .script()
+      .evalsha_with_reload(&self.client, key.into(), args)
+      .await?;
+    RateLimitResult::try_from(result)
+  }
+}
+
+/// Load the scripts for all the rate limiting algorithms on all the associated servers with `SCRIPT LOAD`.
+pub async fn load_scripts(client: &RedisClient) -> RedisResult<()> {
+  for script in [&*GCRA_SCRIPT, &*SLIDING_WINDOW_SCRIPT, &*TOKEN_BUCKET_SCRIPT].iter() {
+    script.load(client).await?;
+  }
+
+  Ok(())
+}
+
+/// Check the rate limit arguments, returning the window in milliseconds.
+fn window_ms(limit: u64, window: Duration, cost: u64) -> Result<u64, RedisError> {
+  let window = window.as_millis() as u64;
+
+  if limit == 0 || window == 0 {
+    Err(RedisError::new(
+      RedisErrorKind::InvalidArgument,
+      "Limit and window must be greater than zero.",
+    ))
+  } else if cost > limit {
+    Err(RedisError::new(
+      RedisErrorKind::InvalidArgument,
+      "Cost cannot be greater than the limit.",
+    ))
+  } else {
+    Ok(window)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn should_check_rate_limit_arguments() {
+    assert_eq!(window_ms(10, Duration::from_secs(1), 1).unwrap(), 1000);
+    assert!(window_ms(0, Duration::from_secs(1), 0).is_err());
+    assert!(window_ms(10, Duration::from_micros(10), 1).is_err());
+    assert!(window_ms(10, Duration::from_secs(1), 11).is_err());
+  }
+
+  #[test]
+  fn should_convert_rate_limit_result() {
+    let value = RedisValue::Array(vec![0.into(), 0.into(), 250.into()]);
+    let expected = RateLimitResult {
+      allowed:     false,
+      remaining:   0,
+      retry_after: Duration::from_millis(250),
+    };
+
+    assert_eq!(RateLimitResult::try_from(value).unwrap(), expected);
+  }
+}
@@ -0,0 +1,797 @@
+//! A record-and-replay mocking layer.
+//!
+//! The [Recorder] captures each command and its result from a real session and writes them to a file (or any other
+//! writer) as JSON lines. The [Replayer] reads those recordings back and implements the [Mocks](crate::mocks::Mocks)
+//! interface, serving the recorded responses without a server.
+//!
+//! Commands are recorded as they are sent to the server or mocking layer, after the [key
+//! prefix](crate::types::RedisConfig::key_prefix) is applied and any cross-slot commands are split, so the
+//! recordings contain the same commands that the [Replayer] receives.
+//!
+//! ```rust no_run
+//! # use fred::{prelude::*, replay::{Recorder, Replayer, ReplayMode}};
+//! # use std::sync::Arc;
+//! async fn record() -> Result<(), RedisError> {
+//!   let recorder = Arc::new(Recorder::create("session.jsonl")?);
+//!   let config = RedisConfig {
+//!     recorder: Some(recorder.clone()),
+//!     ..Default::default()
+//!   };
+//!   let client = Builder::from_config(config).build()?;
+//!   let _ = client.connect();
+//!   client.wait_for_connect().await?;
+//!
+//!   let _: () = client.set("foo", "bar", None, None, false).await?;
+//!   let _: String = client.get("foo").await?;
+//!   recorder.flush()
+//! }
+//!
+//! async fn replay() -> Result<(), RedisError> {
+//!   let replayer = Arc::new(Replayer::from_file("session.jsonl", ReplayMode::InOrder)?);
+//!   let config = RedisConfig {
+//!     mocks: Some(replayer.clone()),
+//!     ..Default::default()
+//!   };
+//!   let client = Builder::from_config(config).build()?;
+//!   let _ = client.connect();
+//!   client.wait_for_connect().await?;
+//!
+//!   let _: () = client.set("foo", "bar", None, None, false).await?;
+//!   assert_eq!(client.get::<String, _>("foo").await?, "bar");
+//!   // check that every recording was used and nothing diverged
+//!   replayer.finish()
+//! }
+//! ```
+//!
+//! Commands in a transaction are recorded with their results from the `EXEC` response, so they can be replayed by
+//! the default [process_transaction](crate::mocks::Mocks::process_transaction) implementation. Errors that do not
+//! come from the server or mocking layer, such as timeouts, are not recorded.
+
+use crate::{
+  error::{RedisError, RedisErrorKind},
+  mocks::{MockCommand, Mocks},
+  protocol::command::RedisCommand,
+  types::{RedisKey, RedisMap, RedisValue},
+};
+use bytes::Bytes;
+use bytes_utils::Str;
+use parking_lot::Mutex;
+use serde_json::{json, Map, Value};
+use std::{
+  collections::{HashMap, VecDeque},
+  fmt,
+  fs::File,
+  io::{BufRead, BufReader, BufWriter, Read, Write},
+  path::Path,
+};
+
+use crate::types::VerbatimStringFormat;
+
+fn parse_error<T: Into<std::borrow::Cow<'static, str>>>(details: T) -> RedisError {
+  RedisError::new(RedisErrorKind::Parse, details)
+}
+
+const ERROR_KINDS: &[RedisErrorKind] = &[
+  RedisErrorKind::Config,
+  RedisErrorKind::Auth,
+  RedisErrorKind::IO,
+  RedisErrorKind::InvalidCommand,
+  RedisErrorKind::InvalidArgument,
+  RedisErrorKind::Url,
+  RedisErrorKind::Protocol,
+  #[cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))]
+  RedisErrorKind::Tls,
+  RedisErrorKind::Canceled,
+  RedisErrorKind::Unknown,
+  RedisErrorKind::Timeout,
+  RedisErrorKind::Cluster,
+  RedisErrorKind::Parse,
+  RedisErrorKind::Sentinel,
+  RedisErrorKind::NotFound,
+  RedisErrorKind::Backpressure,
+  RedisErrorKind::CircuitOpen,
+  #[cfg(feature = "replicas")]
+  RedisErrorKind::Replica,
+];
+
+fn kind_from_str(s: &str) -> RedisErrorKind {
+  ERROR_KINDS
+    .iter()
+    .find(|kind| kind.to_str() == s)
+    .cloned()
+    .unwrap_or(RedisErrorKind::Unknown)
+}
+
+/// Encode bytes as a string when possible, or as an array of numbers otherwise.
+fn bytes_to_json(bytes: &[u8]) -> Value {
+  match std::str::from_utf8(bytes) {
+    Ok(s) => Value::String(s.to_owned()),
+    Err(_) => Value::Array(bytes.iter().map(|b| Value::from(*b)).collect()),
+  }
+}
+
+fn json_to_bytes(value: &Value) -> Result<Bytes, RedisError> {
+  match value {
+    Value::String(s) => Ok(Bytes::from(s.clone())),
+    Value::Array(values) => values
+      .iter()
+      .map(|v| {
+        v.as_u64()
+          .filter(|b| *b <= u8::MAX as u64)
+          .map(|b| b as u8)
+          .ok_or_else(|| parse_error("Invalid byte."))
+      })
+      .collect::<Result<Vec<u8>, _>>()
+      .map(Bytes::from),
+    _ => Err(parse_error("Expected string or byte array.")),
+  }
+}
+
+fn map_to_json(map: &RedisMap) -> Value {
+  Value::Array(
+    map
+      .iter()
+      .map(|(key, value)| Value::Array(vec![bytes_to_json(key.as_bytes()), value_to_json(value)]))
+      .collect(),
+  )
+}
+
+fn json_to_map(value: &Value) -> Result<RedisMap, RedisError> {
+  let mut out = RedisMap::new();
+  for pair in value.as_array().ok_or_else(|| parse_error("Expected map entries."))? {
+    match pair.as_array().map(|p| p.as_slice()) {
+      Some([key, value]) => {
+        out.insert(RedisKey::from(json_to_bytes(key)?), json_to_value(value)?);
+      },
+      _ => return Err(parse_error("Expected key/value pair.")),
+    }
+  }
+
+  Ok(out)
+}
+
+fn double_to_json(d: f64) -> Value {
+  if d.is_nan() {
+    Value::from("nan")
+  } else if d.is_infinite() {
+    Value::from(if d > 0.0 { "inf" } else { "-inf" })
+  } else {
+    Value::from(d)
+  }
+}
+
+fn json_to_double(value: &Value) -> Result<f64, RedisError> {
+  match value {
+    Value::Number(n) => n.as_f64().ok_or_else(|| parse_error("Invalid double.")),
+    Value::String(s) => match s.as_str() {
+      "nan" => Ok(f64::NAN),
+      "inf" => Ok(f64::INFINITY),
+      "-inf" => Ok(f64::NEG_INFINITY),
+      _ => Err(parse_error("Invalid double.")),
+    },
+    _ => Err(parse_error("Invalid double.")),
+  }
+}
+
+/// Encode a `RedisValue` as JSON, tagging each value with its type so it can be decoded without loss.
+///
+/// `Null` and `Queued` are encoded as `null` and `"queued"`. All other values are encoded as an object with one
+/// field, such as `{"integer": 1}` or `{"bytes": "foo"}`.
+pub fn value_to_json(value: &RedisValue) -> Value {
+  match value {
+    RedisValue::Null => Value::Null,
+    RedisValue::Queued => Value::from("queued"),
+    RedisValue::Boolean(b) => json!({ "boolean": b }),
+    RedisValue::Integer(i) => json!({ "integer": i }),
+    RedisValue::Double(d) => json!({ "double": double_to_json(*d) }),
+    RedisValue::String(s) => json!({ "string": &**s }),
+    RedisValue::Bytes(b) => json!({ "bytes": bytes_to_json(b) }),
+    RedisValue::Array(values) => json!({ "array": values.iter().map(value_to_json).collect::<Vec<_>>() }),
+    RedisValue::Map(map) => json!({ "map": map_to_json(map) }),
+    RedisValue::BigNumber(s) => json!({ "big_number": &**s }),
+    RedisValue::VerbatimString { format, data } => {
+      let format = match format {
+        VerbatimStringFormat::Text => "txt",
+        VerbatimStringFormat::Markdown => "mkd",
+      };
+      json!({ "verbatim_string": { "format": format, "data": bytes_to_json(data) } })
+    },
+    RedisValue::Set(values) => json!({ "set": values.iter().map(value_to_json).collect::<Vec<_>>() }),
+    RedisValue::Attributed { value, attributes } => json!({
+      "attributed": { "value": value_to_json(value), "attributes": map_to_json(attributes) }
+    }),
+  }
+}
+
+/// Decode a `RedisValue` from the format used by [value_to_json].
+pub fn json_to_value(value: &Value) -> Result<RedisValue, RedisError> {
+  let object = match value {
+    Value::Null => return Ok(RedisValue::Null),
+    Value::String(s) if s == "queued" => return Ok(RedisValue::Queued),
+    Value::Object(object) if object.len() == 1 => object,
+    _ => return Err(parse_error(format!("Invalid recorded value: {}", value))),
+  };
+  let (tag, inner) = object.iter().next().unwrap();
+
+  let value = match tag.as_str() {
+    "boolean" => RedisValue::Boolean(inner.as_bool().ok_or_else(|| parse_error("Invalid boolean."))?),
+    "integer" => RedisValue::Integer(inner.as_i64().ok_or_else(|| parse_error("Invalid integer."))?),
+    "double" => RedisValue::Double(json_to_double(inner)?),
+    "string" => RedisValue::String(Str::from(inner.as_str().ok_or_else(|| parse_error("Invalid string."))?)),
+    "bytes" => RedisValue::Bytes(json_to_bytes(inner)?),
+    "array" => RedisValue::Array(
+      inner
+        .as_array()
+        .ok_or_else(|| parse_error("Invalid array."))?
+        .iter()
+        .map(json_to_value)
+        .collect::<Result<_, _>>()?,
+    ),
+    "map" => RedisValue::Map(json_to_map(inner)?),
+    "big_number" => RedisValue::BigNumber(Str::from(
+      inner.as_str().ok_or_else(|| parse_error("Invalid big number."))?,
+    )),
+    "verbatim_string" => {
+      let format = match inner.get("format").and_then(|f| f.as_str()) {
+        Some("txt") => VerbatimStringFormat::Text,
+        Some("mkd") => VerbatimStringFormat::Markdown,
+        _ => return Err(parse_error("Invalid verbatim string format.")),
+      };
+      let data = json_to_bytes(inner.get("data").unwrap_or(&Value::Null))?;
+      RedisValue::VerbatimString { format, data }
+    },
+    "set" => RedisValue::Set(
+      inner
+        .as_array()
+        .ok_or_else(|| parse_error("Invalid set."))?
+        .iter()
+        .map(json_to_value)
+        .collect::<Result<_, _>>()?,
+    ),
+    "attributed" => RedisValue::Attributed {
+      value:      Box::new(json_to_value(inner.get("value").unwrap_or(&Value::Null))?),
+      attributes: json_to_map(inner.get("attributes").unwrap_or(&Value::Null))?,
+    },
+    _ => return Err(parse_error(format!("Unknown recorded value type: {}", tag))),
+  };
+
+  Ok(value)
+}
+
+/// A recorded command and the result returned to the caller.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recording {
+  /// The command, including the arguments after the key prefix is applied.
+  pub command: MockCommand,
+  /// The result returned by the server or client.
+  pub result:  Result<RedisValue, RedisError>,
+}
+
+impl Recording {
+  /// Encode the recording as a single line of JSON.
+  pub fn to_json(&self) -> String {
+    let mut out = Map::new();
+    out.insert("cmd".into(), Value::from(&*self.command.cmd));
+    out.insert(
+      "subcommand".into(),
+      self
+        .command
+        .subcommand
+        .as_ref()
+        .map(|s| Value::from(&**s))
+        .unwrap_or(Value::Null),
+    );
+    out.insert(
+      "args".into(),
+      Value::Array(self.command.args.iter().map(value_to_json).collect()),
+    );
+    let result = match self.result {
+      Ok(ref value) => json!({ "ok": value_to_json(value) }),
+      Err(ref error) => json!({ "err": { "kind": error.kind().to_str(), "details": error.details() } }),
+    };
+    out.insert("result".into(), result);
+
+    Value::Object(out).to_string()
+  }
+
+  /// Decode a recording from a line of JSON.
+  pub fn from_json(line: &str) -> Result<Self, RedisError> {
+    let value: Value = serde_json::from_str(line)?;
+    let cmd = value
+      .get("cmd")
+      .and_then(|v| v.as_str())
+      .ok_or_else(|| parse_error("Missing recorded command."))?;
+    let subcommand = value.get("subcommand").and_then(|v| v.as_str()).map(Str::from);
+    let args = value
+      .get("args")
+      .and_then(|v| v.as_array())
+      .ok_or_else(|| parse_error("Missing recorded arguments."))?
+      .iter()
+      .map(json_to_value)
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let result = value
+      .get("result")
+      .ok_or_else(|| parse_error("Missing recorded result."))?;
+    let result = if let Some(value) = result.get("ok") {
+      Ok(json_to_value(value)?)
+    } else if let Some(error) = result.get("err") {
+      let kind = error.get("kind").and_then(|v| v.as_str()).unwrap_or_default();
+      let details = error.get("details").and_then(|v| v.as_str()).unwrap_or_default();
+      Err(RedisError::new(kind_from_str(kind), details.to_owned()))
+    } else {
+      return Err(parse_error("Invalid recorded result."));
+    };
+
+    Ok(Recording {
+      command: MockCommand {
+        cmd: Str::from(cmd),
+        subcommand,
+        args,
+      },
+      result,
+    })
+  }
+}
+
+/// A recorder that writes each command and its result as a line of JSON.
+///
+/// The recorder is provided via the [RedisConfig](crate::types::RedisConfig::recorder) and can be used with a real
+/// server or a mocking layer.
+pub struct Recorder {
+  writer:       Mutex<Box<dyn Write + Send>>,
+  transactions: Mutex<HashMap<u64, Vec<MockCommand>>>,
+}
+
+impl fmt::Debug for Recorder {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Recorder").finish()
+  }
+}
+
+impl Recorder {
+  /// Create a new recorder that writes to the provided writer.
+  pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+    Recorder {
+      writer:       Mutex::new(Box::new(writer)),
+      transactions: Mutex::new(HashMap::new()),
+    }
+  }
+
+  /// Create a new recorder that writes to a file at `path`, truncating the file if it exists.
+  pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, RedisError> {
+    Ok(Recorder::new(BufWriter::new(File::create(path)?)))
+  }
+
+  /// Write a recording to the underlying writer.
+  pub fn record(&self, recording: &Recording) -> Result<(), RedisError> {
+    let mut writer = self.writer.lock();
+    writer.write_all(recording.to_json().as_bytes())?;
+    writer.write_all(b"\n")?;
+    Ok(())
+  }
+
+  /// Flush any buffered recordings to the underlying writer.
+  pub fn flush(&self) -> Result<(), RedisError> {
+    self.writer.lock().flush().map_err(|e| e.into())
+  }
+
+  fn record_or_warn(&self, command: MockCommand, result: Result<RedisValue, RedisError>) {
+    let recording = Recording { command, result };
+    if let Err(error) = self.record(&recording) {
+      warn!("Failed to record {}: {:?}", recording.command.cmd, error);
+    }
+  }
+
+  /// Record the commands in a transaction, excluding `MULTI` and `EXEC`, with the result of the transaction.
+  ///
+  /// Each command is recorded with the corresponding value from the `EXEC` response. If the transaction fails the
+  /// first command is recorded with the error.
+  pub(crate) fn record_transaction(&self, commands: Vec<MockCommand>, result: Result<RedisValue, RedisError>) {
+    match result {
+      Ok(RedisValue::Array(values)) if values.len() == commands.len() => {
+        for (command, value) in commands.into_iter().zip(values) {
+          self.record_or_warn(command, Ok(value));
+        }
+      },
+      Ok(value) => {
+        warn!("Cannot record transaction result: {:?}", value);
+      },
+      Err(error) => {
+        if let Some(command) = commands.into_iter().next() {
+          self.record_or_warn(command, Err(error));
+        }
+      },
+    }
+  }
+
+  /// Record the result of a command sent to the server or mocking layer.
+  ///
+  /// Commands in a transaction are buffered until the transaction finishes.
+  pub(crate) fn record_command(&self, command: &RedisCommand, result: Result<RedisValue, RedisError>) {
+    let id = match command.transaction_id {
+      Some(id) => id,
+      None => return self.record_or_warn(command.to_mocked(), result),
+    };
+    if command.kind.is_multi() {
+      return;
+    }
+
+    if command.kind.is_exec() {
+      let commands = self.transactions.lock().remove(&id).unwrap_or_default();
+      self.record_transaction(commands, result);
+    } else if command.kind.ends_transaction() {
+      self.transactions.lock().remove(&id);
+    } else if let Err(error) = result {
+      // the transaction is aborted, so the replayed transaction should stop at the same command
+      let commands = self.transactions.lock().remove(&id).unwrap_or_default();
+      for queued in commands.into_iter() {
+        self.record_or_warn(queued, Ok(RedisValue::Queued));
+      }
+      self.record_or_warn(command.to_mocked(), Err(error));
+    } else {
+      self
+        .transactions
+        .lock()
+        .entry(id)
+        .or_default()
+        .push(command.to_mocked());
+    }
+  }
+}
+
+/// The strategy used by a [Replayer] to select the recording used for each command.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ReplayMode {
+  /// Commands must arrive in the same order they were recorded.
+  InOrder,
+  /// Each command uses the first unused recording with the same command and arguments.
+  Match,
+}
+
+#[derive(Debug, Default)]
+struct ReplayState {
+  recordings: VecDeque<Recording>,
+  divergence: Option<String>,
+}
+
+/// A mocking layer that serves recorded responses.
+///
+/// Commands that do not match a recording return an error to the caller, and the first divergence is stored so it
+/// can be reported later via [finish](Self::finish). Once a divergence occurs all subsequent commands also return
+/// an error.
+#[derive(Debug)]
+pub struct Replayer {
+  mode:  ReplayMode,
+  state: Mutex<ReplayState>,
+}
+
+impl Replayer {
+  /// Create a new replayer from a list of recordings.
+  pub fn new(recordings: Vec<Recording>, mode: ReplayMode) -> Self {
+    Replayer {
+      mode,
+      state: Mutex::new(ReplayState {
+        recordings: recordings.into(),
+        divergence: None,
+      }),
+    }
+  }
+
+  /// Read JSON line recordings from the provided reader.
+  pub fn from_reader<R: Read>(reader: R, mode: ReplayMode) -> Result<Self, RedisError> {
+    let mut recordings = Vec::new();
+    for line in BufReader::new(reader).lines() {
+      let line = line?;
+      if !line.trim().is_empty() {
+        recordings.push(Recording::from_json(&line)?);
+      }
+    }
+
+    Ok(Replayer::new(recordings, mode))
+  }
+
+  /// Read JSON line recordings from the file at `path`.
+  pub fn from_file<P: AsRef<Path>>(path: P, mode: ReplayMode) -> Result<Self, RedisError> {
+    Replayer::from_reader(File::open(path)?, mode)
+  }
+
+  /// Read the number of recordings that have not been used.
+  pub fn remaining(&self) -> usize {
+    self.state.lock().recordings.len()
+  }
+
+  /// Check that every recording was used and that no commands diverged from the recordings.
+  pub fn finish(&self) -> Result<(), RedisError> {
+    let state = self.state.lock();
+    if let Some(ref divergence) = state.divergence {
+      Err(RedisError::new(RedisErrorKind::Unknown, divergence.clone()))
+    } else if let Some(next) = state.recordings.front() {
+      Err(RedisError::new(
+        RedisErrorKind::Unknown,
+        format!(
+          "{} recording(s) were not replayed. Next: {}",
+          state.recordings.len(),
+          describe(&next.command)
+        ),
+      ))
+    } else {
+      Ok(())
+    }
+  }
+}
+
+fn describe(command: &MockCommand) -> String {
+  let mut out = command.cmd.to_string();
+  if let Some(ref subcommand) = command.subcommand {
+    out.push(' ');
+    out.push_str(subcommand);
+  }
+  format!("{} {:?}", out, command.args)
+}
+
+impl Mocks for Replayer {
+  fn process_command(&self, command: MockCommand) -> Result<RedisValue, RedisError> {
+    let mut state = self.state.lock();
+    if let Some(ref divergence) = state.divergence {
+      return Err(RedisError::new(
+        RedisErrorKind::Unknown,
+        format!("Replay already diverged: {}", divergence),
+      ));
+    }
+
+    let position = match self.mode {
+      ReplayMode::InOrder => state.recordings.front().filter(|r| r.command == command).map(|_| 0),
+      ReplayMode::Match => state.recordings.iter().position(|r| r.command == command),
+    };
+
+    match position.and_then(|idx| state.recordings.remove(idx)) {
+      Some(recording) => recording.result,
+      None => {
+        let divergence = match (self.mode, state.recordings.front()) {
+          (ReplayMode::InOrder, Some(next)) => {
+            format!("Expected {}, received {}", describe(&next.command), describe(&command))
+          },
+          _ => format!("No recording for {}", describe(&command)),
+        };
+        state.divergence = Some(divergence.clone());
+
+        Err(RedisError::new(RedisErrorKind::Unknown, divergence))
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    clients::RedisClient,
+    interfaces::{ClientLike, KeysInterface, TransactionInterface},
+    mocks::SimpleMap,
+    types::RedisConfig,
+  };
+  use std::sync::Arc;
+
+  #[derive(Clone, Default)]
+  struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+  impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.0.lock().extend_from_slice(buf);
+      Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  async fn create_client(config: RedisConfig) -> RedisClient {
+    let client = RedisClient::new(config, None, None, None);
+    let _ = client.connect();
+    client.wait_for_connect().await.unwrap();
+    client
+  }
+
+  async fn record_session() -> Vec<u8> {
+    let buffer = SharedBuffer::default();
+    let client = create_client(RedisConfig {
+      mocks: Some(Arc::new(SimpleMap::new())),
+      recorder: Some(Arc::new(Recorder::new(buffer.clone()))),
+      ..Default::default()
+    })
+    .await;
+
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    let _: String = client.get("foo").await.unwrap();
+    let _: i64 = client.del("foo").await.unwrap();
+
+    let out = buffer.0.lock().clone();
+    out
+  }
+
+  #[test]
+  fn should_round_trip_recordings() {
+    let mut map = RedisMap::new();
+    map.insert("a".into(), RedisValue::Double(f64::INFINITY));
+    let recordings = vec![
+      Recording {
+        command: MockCommand {
+          cmd:        "HGETALL".into(),
+          subcommand: None,
+          args:       vec![RedisValue::Bytes(Bytes::from_static(&[0xff, 0x00]))],
+        },
+        result:  Ok(RedisValue::Array(vec![
+          RedisValue::Map(map),
+          RedisValue::Null,
+          RedisValue::Queued,
+          RedisValue::Boolean(true),
+          RedisValue::String("foo".into()),
+        ])),
+      },
+      Recording {
+        command: MockCommand {
+          cmd:        "CLIENT".into(),
+          subcommand: Some("KILL".into()),
+          args:       vec![1.into()],
+        },
+        result:  Err(RedisError::new(RedisErrorKind::InvalidArgument, "WRONGTYPE")),
+      },
+    ];
+
+    for recording in recordings.into_iter() {
+      assert_eq!(Recording::from_json(&recording.to_json()).unwrap(), recording);
+    }
+  }
+
+  #[tokio::test]
+  async fn should_replay_pipelines_and_transactions() {
+    let buffer = SharedBuffer::default();
+    let client = create_client(RedisConfig {
+      mocks: Some(Arc::new(SimpleMap::new())),
+      recorder: Some(Arc::new(Recorder::new(buffer.clone()))),
+      ..Default::default()
+    })
+    .await;
+    let pipeline = client.pipeline();
+    let _: () = pipeline.set("foo", "bar", None, None, false).await.unwrap();
+    let _: () = pipeline.get("foo").await.unwrap();
+    let _: Vec<RedisValue> = pipeline.all().await.unwrap();
+    let transaction = client.multi();
+    let _: () = transaction.get("foo").await.unwrap();
+    let _: () = transaction.del("foo").await.unwrap();
+    let _: Vec<RedisValue> = transaction.exec(true).await.unwrap();
+
+    let recorded = buffer.0.lock().clone();
+    let replayer = Arc::new(Replayer::from_reader(&recorded[..], ReplayMode::InOrder).unwrap());
+    assert_eq!(replayer.remaining(), 4);
+    let client = create_client(RedisConfig {
+      mocks: Some(replayer.clone()),
+      ..Default::default()
+    })
+    .await;
+    let pipeline = client.pipeline();
+    let _: () = pipeline.set("foo", "bar", None, None, false).await.unwrap();
+    let _: () = pipeline.get("foo").await.unwrap();
+    let _: RedisValue = pipeline.all().await.unwrap();
+    let transaction = client.multi();
+    let _: () = transaction.get("foo").await.unwrap();
+    let _: () = transaction.del("foo").await.unwrap();
+    let (value, deleted): (String, i64) = transaction.exec(true).await.unwrap();
+    assert_eq!((value.as_str(), deleted), ("bar", 1));
+    assert!(replayer.finish().is_ok());
+  }
+
+  #[tokio::test]
+  async fn should_record_prefixed_keys() {
+    let buffer = SharedBuffer::default();
+    let client = create_client(RedisConfig {
+      mocks: Some(Arc::new(SimpleMap::new())),
+      recorder: Some(Arc::new(Recorder::new(buffer.clone()))),
+      key_prefix: Some("app:".into()),
+      ..Default::default()
+    })
+    .await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+
+    let recorded = buffer.0.lock().clone();
+    let recording = Recording::from_json(std::str::from_utf8(&recorded).unwrap().trim()).unwrap();
+    assert_eq!(recording.command.args[0], RedisValue::Bytes("app:foo".into()));
+
+    let replayer = Arc::new(Replayer::from_reader(&recorded[..], ReplayMode::InOrder).unwrap());
+    let client = create_client(RedisConfig {
+      mocks: Some(replayer.clone()),
+      key_prefix: Some("app:".into()),
+      ..Default::default()
+    })
+    .await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    assert!(replayer.finish().is_ok());
+  }
+
+  #[tokio::test]
+  #[cfg(feature = "fake")]
+  async fn should_replay_session_recorded_from_server() {
+    let server = crate::fake::Server::start().await.unwrap();
+    let buffer = SharedBuffer::default();
+    let client = create_client(RedisConfig {
+      recorder: Some(Arc::new(Recorder::new(buffer.clone()))),
+      key_prefix: Some("app:".into()),
+      ..server.redis_config().unwrap()
+    })
+    .await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    let transaction = client.multi();
+    let _: () = transaction.get("foo").await.unwrap();
+    let _: () = transaction.del("foo").await.unwrap();
+    let _: Vec<RedisValue> = transaction.exec(true).await.unwrap();
+
+    let recorded = buffer.0.lock().clone();
+    let replayer = Arc::new(Replayer::from_reader(&recorded[..], ReplayMode::InOrder).unwrap());
+    let client = create_client(RedisConfig {
+      mocks: Some(replayer.clone()),
+      key_prefix: Some("app:".into()),
+      ..Default::default()
+    })
+    .await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    let transaction = client.multi();
+    let _: () = transaction.get("foo").await.unwrap();
+    let _: () = transaction.del("foo").await.unwrap();
+    let (value, deleted): (String, i64) = transaction.exec(true).await.unwrap();
+    assert_eq!((value.as_str(), deleted), ("bar", 1));
+    replayer.finish().unwrap();
+  }
+
+  #[tokio::test]
+  async fn should_replay_recorded_session_in_order() {
+    let recorded = record_session().await;
+    let replayer = Arc::new(Replayer::from_reader(&recorded[..], ReplayMode::InOrder).unwrap());
+    assert_eq!(replayer.remaining(), 3);
+
+    let client = create_client(RedisConfig {
+      mocks: Some(replayer.clone()),
+      ..Default::default()
+    })
+    .await;
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    assert!(replayer.finish().is_err());
+    assert_eq!(client.del::<i64, _>("foo").await.unwrap(), 1);
+    assert!(replayer.finish().is_ok());
+  }
+
+  #[tokio::test]
+  async fn should_replay_recorded_session_by_match() {
+    let recorded = record_session().await;
+    let replayer = Arc::new(Replayer::from_reader(&recorded[..], ReplayMode::Match).unwrap());
+    let client = create_client(RedisConfig {
+      mocks: Some(replayer.clone()),
+      ..Default::default()
+    })
+    .await;
+
+    assert_eq!(client.del::<i64, _>("foo").await.unwrap(), 1);
+    assert_eq!(client.get::<String, _>("foo").await.unwrap(), "bar");
+    let _: () = client.set("foo", "bar", None, None, false).await.unwrap();
+    assert!(replayer.finish().is_ok());
+  }
+
+  #[tokio::test]
+  async fn should_fail_on_divergence() {
+    let recorded = record_session().await;
+    let replayer = Arc::new(Replayer::from_reader(&recorded[..], ReplayMode::InOrder).unwrap());
+    let client = create_client(RedisConfig {
+      mocks: Some(replayer.clone()),
+      ..Default::default()
+    })
+    .await;
+
+    assert!(client.get::<String, _>("foo").await.is_err());
+    // later commands fail even if they match
+    assert!(client.set::<(), _, _>("foo", "bar", None, None, false).await.is_err());
+    let error = replayer.finish().unwrap_err();
+    assert!(error.details().starts_with("Expected SET"));
+  }
+}
@@ -1,9 +1,30 @@
 use crate::{
   error::{RedisError, RedisErrorKind},
-  types::{ClusterInfo, DatabaseMemoryStats, GeoPosition, MemoryStats, RedisKey, RedisValue, SlowlogEntry, QUEUED},
+  protocol::utils as protocol_utils,
+  types::{
+    ClusterInfo,
+    DatabaseMemoryStats,
+    GeoPosition,
+    MemoryStats,
+    PendingDelivery,
+    PendingEntry,
+    PendingSummary,
+    RedisKey,
+    RedisValue,
+    SlowlogEntry,
+    StreamConsumerInfo,
+    StreamConsumerInfoFull,
+    StreamGroupInfo,
+    StreamGroupInfoFull,
+    StreamInfo,
+    StreamInfoFull,
+    XEntry,
+    QUEUED,
+  },
 };
 use bytes::Bytes;
 use bytes_utils::Str;
+use redis_protocol::resp3::types::Frame as Resp3Frame;
 use std::{
   collections::{BTreeMap, BTreeSet, HashMap, HashSet},
   hash::{BuildHasher, Hash},
@@ -12,6 +33,8 @@ use std::{
 #[allow(unused_imports)]
 use std::any::type_name;
 
+#[cfg(feature = "serde")]
+use crate::types::{from_redis_value, Serde};
 #[cfg(feature = "serde-json")]
 use serde_json::{Map, Value};
 
@@ -178,14 +201,39 @@ macro_rules! impl_unsigned_number (
 /// * `impl FromRedis` for any integer or float type returns `0`
 /// * `impl FromRedis` for `bool` returns `false`
 /// * `impl FromRedis` for map or set types return an empty map or set.
+///
+/// ## Parsing Frames
+///
+/// Most commands convert the response frame to a `RedisValue` before calling [from_value](Self::from_value). Some
+/// commands that often return large responses (`MGET`, `HGETALL`, `LRANGE`, etc) call
+/// [from_frame](Self::from_frame) instead, which allows implementations to read directly from the decoded frame. The
+/// `Bytes`, `Vec<T>`, `HashMap<K, V>`, and `Option<T>` implementations use this to move the `Bytes` in the frame into
+/// the output without allocating an intermediate `RedisValue` tree.
 pub trait FromRedis: Sized {
   fn from_value(value: RedisValue) -> Result<Self, RedisError>;
 
+  /// Convert a decoded response frame into `Self`.
+  ///
+  /// The default implementation converts the frame to a `RedisValue` and calls [from_value](Self::from_value).
+  fn from_frame(frame: Resp3Frame) -> Result<Self, RedisError> {
+    frame_to_type(frame)
+  }
+
   #[doc(hidden)]
   fn from_values(values: Vec<RedisValue>) -> Result<Vec<Self>, RedisError> {
     values.into_iter().map(|v| Self::from_value(v)).collect()
   }
 
+  #[doc(hidden)]
+  fn from_frames(frames: Vec<Resp3Frame>) -> Result<Vec<Self>, RedisError> {
+    let values = frames
+      .into_iter()
+      .map(protocol_utils::frame_to_results)
+      .collect::<Result<Vec<RedisValue>, RedisError>>()?;
+
+    Self::from_values(values)
+  }
+
   #[doc(hidden)]
   // FIXME if/when specialization is stable
   fn from_owned_bytes(_: Vec<u8>) -> Option<Vec<Self>> {
@@ -195,12 +243,61 @@ pub trait FromRedis: Sized {
   fn is_tuple() -> bool {
     false
   }
+
+  #[doc(hidden)]
+  fn preserves_resp3_types() -> bool {
+    false
+  }
+}
+
+/// Convert a frame to a `RedisValue` before converting it to the target type.
+fn frame_to_type<T: FromRedis>(frame: Resp3Frame) -> Result<T, RedisError> {
+  protocol_utils::frame_to_results(frame)?.convert()
+}
+
+/// Take the key and value frames from a `Map` frame or an `Array` frame with an even number of elements, if every
+/// key is a bulk string and every value is a scalar.
+fn frame_to_scalar_pairs(frame: Resp3Frame) -> Result<Vec<(Resp3Frame, Resp3Frame)>, Resp3Frame> {
+  let is_scalar_pair = |key: &Resp3Frame, value: &Resp3Frame| {
+    matches!(key, Resp3Frame::BlobString { attributes: None, .. }) && protocol_utils::frame_is_scalar(value)
+  };
+
+  match frame {
+    Resp3Frame::Map { data, attributes } => {
+      if data.iter().all(|(key, value)| is_scalar_pair(key, value)) {
+        Ok(data.into_iter().collect())
+      } else {
+        Err(Resp3Frame::Map { data, attributes })
+      }
+    },
+    Resp3Frame::Array { data, attributes } => {
+      if data.len() % 2 == 0 && data.chunks_exact(2).all(|pair| is_scalar_pair(&pair[0], &pair[1])) {
+        let mut data = data.into_iter();
+        Ok(std::iter::from_fn(|| Some((data.next()?, data.next()?))).collect())
+      } else {
+        Err(Resp3Frame::Array { data, attributes })
+      }
+    },
+    _ => Err(frame),
+  }
+}
+
+/// Convert a bulk string frame to a key without copying or validating the contents.
+fn frame_to_key(frame: Resp3Frame) -> Result<RedisKey, RedisError> {
+  match frame {
+    Resp3Frame::BlobString { data, .. } => Ok(data.into()),
+    _ => protocol_utils::frame_to_results(frame)?.try_into(),
+  }
 }
 
 impl FromRedis for RedisValue {
   fn from_value(value: RedisValue) -> Result<Self, RedisError> {
     Ok(value)
   }
+
+  fn preserves_resp3_types() -> bool {
+    true
+  }
 }
 
 impl FromRedis for () {
@@ -313,6 +410,19 @@ where
       Ok(Some(T::from_value(value)?))
     }
   }
+
+  fn from_frame(frame: Resp3Frame) -> Result<Option<T>, RedisError> {
+    match frame {
+      Resp3Frame::Null => Ok(None),
+      Resp3Frame::BlobString { .. } | Resp3Frame::Map { .. } => Ok(Some(T::from_frame(frame)?)),
+      Resp3Frame::Array { ref data, .. } if !data.is_empty() => Ok(Some(T::from_frame(frame)?)),
+      _ => frame_to_type(frame),
+    }
+  }
+
+  fn preserves_resp3_types() -> bool {
+    T::preserves_resp3_types()
+  }
 }
 
 impl FromRedis for Bytes {
@@ -324,6 +434,17 @@ impl FromRedis for Bytes {
       .into_bytes()
       .ok_or(RedisError::new_parse("Cannot parse into bytes."))
   }
+
+  fn from_frame(frame: Resp3Frame) -> Result<Self, RedisError> {
+    match frame {
+      Resp3Frame::BlobString { data, attributes: None } => Ok(data),
+      _ => frame_to_type(frame),
+    }
+  }
+
+  fn from_frames(frames: Vec<Resp3Frame>) -> Result<Vec<Self>, RedisError> {
+    frames.into_iter().map(Self::from_frame).collect()
+  }
 }
 
 impl<T> FromRedis for Vec<T>
@@ -377,6 +498,25 @@ where
       RedisValue::Boolean(b) => Ok(vec![T::from_value(RedisValue::Boolean(b))?]),
       RedisValue::Queued => Ok(vec![T::from_value(RedisValue::from_static_str(QUEUED))?]),
       RedisValue::Null => Ok(Vec::new()),
+      _ => Self::from_value(value.into_lossy()),
+    }
+  }
+
+  fn from_frame(frame: Resp3Frame) -> Result<Vec<T>, RedisError> {
+    match frame {
+      Resp3Frame::Array { data, .. } | Resp3Frame::Push { data, .. }
+        if data.iter().all(protocol_utils::frame_is_scalar) =>
+      {
+        if data.is_empty() {
+          Ok(Vec::new())
+        } else {
+          T::from_frames(data)
+        }
+      },
+      Resp3Frame::Set { data, .. } if data.iter().all(protocol_utils::frame_is_scalar) => {
+        T::from_frames(data.into_iter().collect())
+      },
+      _ => frame_to_type(frame),
     }
   }
 }
@@ -425,6 +565,16 @@ where
       .map(|(k, v)| Ok((K::from_key(k)?, V::from_value(v)?)))
       .collect()
   }
+
+  fn from_frame(frame: Resp3Frame) -> Result<Self, RedisError> {
+    match frame_to_scalar_pairs(frame) {
+      Ok(pairs) => pairs
+        .into_iter()
+        .map(|(k, v)| Ok((K::from_key(frame_to_key(k)?)?, V::from_frame(v)?)))
+        .collect(),
+      Err(frame) => frame_to_type(frame),
+    }
+  }
 }
 
 impl<V, S> FromRedis for HashSet<V, S>
@@ -589,12 +739,21 @@ impl FromRedis for Value {
         }
         Value::Object(out)
       },
+      _ => Self::from_value(value.into_lossy())?,
     };
 
     Ok(value)
   }
 }
 
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<T: serde::de::DeserializeOwned> FromRedis for Serde<T> {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    from_redis_value(value).map(Serde)
+  }
+}
+
 impl FromRedis for GeoPosition {
   fn from_value(value: RedisValue) -> Result<Self, RedisError> {
     GeoPosition::try_from(value)
@@ -613,6 +772,66 @@ impl FromRedis for ClusterInfo {
   }
 }
 
+impl FromRedis for StreamInfo {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    StreamInfo::try_from(value)
+  }
+}
+
+impl FromRedis for StreamInfoFull {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    StreamInfoFull::try_from(value)
+  }
+}
+
+impl FromRedis for StreamGroupInfo {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    StreamGroupInfo::try_from(value)
+  }
+}
+
+impl FromRedis for StreamGroupInfoFull {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    StreamGroupInfoFull::try_from(value)
+  }
+}
+
+impl FromRedis for StreamConsumerInfo {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    StreamConsumerInfo::try_from(value)
+  }
+}
+
+impl FromRedis for StreamConsumerInfoFull {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    StreamConsumerInfoFull::try_from(value)
+  }
+}
+
+impl FromRedis for PendingDelivery {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    PendingDelivery::try_from(value)
+  }
+}
+
+impl FromRedis for PendingSummary {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    PendingSummary::try_from(value)
+  }
+}
+
+impl FromRedis for PendingEntry {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    PendingEntry::try_from(value)
+  }
+}
+
+impl<T: FromRedis> FromRedis for XEntry<T> {
+  fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+    XEntry::try_from(value)
+  }
+}
+
 impl FromRedis for MemoryStats {
   fn from_value(value: RedisValue) -> Result<Self, RedisError> {
     MemoryStats::try_from(value)
@@ -638,6 +857,7 @@ impl FromRedis for RedisKey {
         return Err(RedisError::new_parse("Cannot convert aggregate type to key."))
       },
       RedisValue::Null => return Err(RedisError::new(RedisErrorKind::NotFound, "Cannot convert nil to key.")),
+      _ => return Self::from_value(value.into_lossy()),
     };
 
     Ok(key)
@@ -970,4 +1190,331 @@ mod tests {
       .is_err());
     assert!(RedisValue::Array(vec![]).convert::<[i64; 3]>().is_err());
   }
+
+  #[test]
+  fn should_convert_stream_group_info() {
+    use crate::types::StreamGroupInfo;
+
+    let value = RedisValue::Array(vec![RedisValue::Array(vec![
+      "name".into(),
+      "mygroup".into(),
+      "consumers".into(),
+      2.into(),
+      "pending".into(),
+      2.into(),
+      "last-delivered-id".into(),
+      "1638126030001-0".into(),
+      "entries-read".into(),
+      2.into(),
+      "lag".into(),
+      RedisValue::Null,
+    ])]);
+    let expected = StreamGroupInfo {
+      name:              "mygroup".into(),
+      consumers:         2,
+      pending:           2,
+      last_delivered_id: "1638126030001-0".into(),
+      entries_read:      Some(2),
+      lag:               None,
+    };
+
+    assert_eq!(value.convert::<Vec<StreamGroupInfo>>().unwrap(), vec![expected]);
+  }
+
+  #[test]
+  fn should_convert_pending_summary_and_entries() {
+    use crate::types::{PendingEntry, PendingSummary};
+
+    let value = RedisValue::Array(vec![
+      2.into(),
+      "1526569498055-0".into(),
+      "1526569506935-0".into(),
+      RedisValue::Array(vec![RedisValue::Array(vec!["Bob".into(), "2".into()])]),
+    ]);
+    let expected = PendingSummary {
+      count:     2,
+      min_id:    Some("1526569498055-0".into()),
+      max_id:    Some("1526569506935-0".into()),
+      consumers: vec![("Bob".into(), 2)],
+    };
+    assert_eq!(value.convert::<PendingSummary>().unwrap(), expected);
+
+    let value = RedisValue::Array(vec![0.into(), RedisValue::Null, RedisValue::Null, RedisValue::Null]);
+    assert!(value.convert::<PendingSummary>().unwrap().consumers.is_empty());
+
+    let value = RedisValue::Array(vec![RedisValue::Array(vec![
+      "1526569498055-0".into(),
+      "Bob".into(),
+      74170458.into(),
+      1.into(),
+    ])]);
+    let expected = PendingEntry {
+      id:         "1526569498055-0".into(),
+      consumer:   "Bob".into(),
+      idle:       74170458,
+      deliveries: 1,
+    };
+    assert_eq!(value.convert::<Vec<PendingEntry>>().unwrap(), vec![expected]);
+  }
+
+  #[test]
+  fn should_convert_stream_entry_fields_to_map() {
+    use crate::types::XEntry;
+
+    let value = RedisValue::Array(vec![RedisValue::Array(vec![
+      "1-0".into(),
+      RedisValue::Array(vec!["foo".into(), "bar".into()]),
+    ])]);
+    let entries: Vec<XEntry<HashMap<String, String>>> = value.convert().unwrap();
+
+    let mut expected = HashMap::new();
+    expected.insert("foo".to_owned(), "bar".to_owned());
+    assert_eq!(entries, vec![XEntry {
+      id:     "1-0".into(),
+      fields: expected,
+    }]);
+  }
+
+  #[test]
+  fn should_convert_stream_info_frames() {
+    use crate::{modules::response::FromRedis, types::StreamInfo};
+    use bytes::Bytes;
+    use redis_protocol::resp3::types::Frame;
+
+    let blob = |s: &'static str| Frame::BlobString {
+      data:       Bytes::from_static(s.as_bytes()),
+      attributes: None,
+    };
+    let int = |i: i64| Frame::Number {
+      data:       i,
+      attributes: None,
+    };
+    let array = |data: Vec<Frame>| Frame::Array { data, attributes: None };
+    let entry = |id: &'static str| array(vec![blob(id), array(vec![blob("foo"), blob("bar")])]);
+
+    let frame = Frame::Map {
+      data:       vec![
+        (blob("length"), int(2)),
+        (blob("radix-tree-keys"), int(1)),
+        (blob("radix-tree-nodes"), int(2)),
+        (blob("last-generated-id"), blob("1638125141232-0")),
+        (blob("max-deleted-entry-id"), blob("0-0")),
+        (blob("entries-added"), int(2)),
+        (blob("recorded-first-entry-id"), blob("1638125133432-0")),
+        (blob("groups"), int(1)),
+        (blob("first-entry"), entry("1638125133432-0")),
+        (blob("last-entry"), Frame::Null),
+      ]
+      .into_iter()
+      .collect(),
+      attributes: None,
+    };
+    let mut fields = HashMap::new();
+    fields.insert("foo".to_owned(), RedisValue::from("bar"));
+    let expected = StreamInfo {
+      length:                  2,
+      radix_tree_keys:         1,
+      radix_tree_nodes:        2,
+      last_generated_id:       "1638125141232-0".into(),
+      max_deleted_entry_id:    Some("0-0".into()),
+      entries_added:           Some(2),
+      recorded_first_entry_id: Some("1638125133432-0".into()),
+      groups:                  1,
+      first_entry:             Some(("1638125133432-0".into(), fields)),
+      last_entry:              None,
+    };
+    assert_eq!(StreamInfo::from_frame(frame).unwrap(), expected);
+
+    // RESP2 servers older than 7.0 send a flat array without the 7.0 fields.
+    let frame = array(vec![
+      blob("length"),
+      int(0),
+      blob("radix-tree-keys"),
+      int(0),
+      blob("radix-tree-nodes"),
+      int(1),
+      blob("last-generated-id"),
+      blob("0-0"),
+      blob("groups"),
+      int(0),
+      blob("first-entry"),
+      Frame::Null,
+      blob("last-entry"),
+      Frame::Null,
+    ]);
+    let info = StreamInfo::from_frame(frame).unwrap();
+    assert_eq!(info.length, 0);
+    assert_eq!(info.max_deleted_entry_id, None);
+    assert_eq!(info.entries_added, None);
+    assert_eq!(info.first_entry, None);
+
+    let frame = array(vec![blob("length"), int(0)]);
+    assert!(StreamInfo::from_frame(frame).is_err());
+  }
+
+  #[test]
+  fn should_convert_stream_info_full_frames() {
+    use crate::{
+      modules::response::FromRedis,
+      types::{PendingDelivery, StreamConsumerInfoFull, StreamGroupInfoFull, StreamInfoFull},
+    };
+    use bytes::Bytes;
+    use redis_protocol::resp3::types::Frame;
+
+    let blob = |s: &'static str| Frame::BlobString {
+      data:       Bytes::from_static(s.as_bytes()),
+      attributes: None,
+    };
+    let int = |i: i64| Frame::Number {
+      data:       i,
+      attributes: None,
+    };
+    let array = |data: Vec<Frame>| Frame::Array { data, attributes: None };
+    let map = |data: Vec<(Frame, Frame)>| Frame::Map {
+      data:       data.into_iter().collect(),
+      attributes: None,
+    };
+
+    let consumer = map(vec![
+      (blob("name"), blob("Alice")),
+      (blob("seen-time"), int(1638127657203)),
+      (blob("active-time"), int(1638127657203)),
+      (blob("pel-count"), int(1)),
+      (
+        blob("pending"),
+        array(vec![array(vec![blob("1638126030001-0"), int(1638127657203), int(1)])]),
+      ),
+    ]);
+    let group = map(vec![
+      (blob("name"), blob("mygroup")),
+      (blob("last-delivered-id"), blob("1638126030001-0")),
+      (blob("entries-read"), int(2)),
+      (blob("lag"), int(0)),
+      (blob("pel-count"), int(1)),
+      (
+        blob("pending"),
+        array(vec![array(vec![
+          blob("1638126030001-0"),
+          blob("Alice"),
+          int(1638127657203),
+          int(1),
+        ])]),
+      ),
+      (blob("consumers"), array(vec![consumer])),
+    ]);
+    let frame = map(vec![
+      (blob("length"), int(1)),
+      (blob("radix-tree-keys"), int(1)),
+      (blob("radix-tree-nodes"), int(2)),
+      (blob("last-generated-id"), blob("1638126030001-0")),
+      (blob("max-deleted-entry-id"), blob("0-0")),
+      (blob("entries-added"), int(1)),
+      (blob("recorded-first-entry-id"), blob("1638126030001-0")),
+      (
+        blob("entries"),
+        array(vec![array(vec![
+          blob("1638126030001-0"),
+          array(vec![blob("foo"), blob("bar")]),
+        ])]),
+      ),
+      (blob("groups"), array(vec![group])),
+    ]);
+
+    let mut fields = HashMap::new();
+    fields.insert("foo".to_owned(), RedisValue::from("bar"));
+    let expected = StreamInfoFull {
+      length:                  1,
+      radix_tree_keys:         1,
+      radix_tree_nodes:        2,
+      last_generated_id:       "1638126030001-0".into(),
+      max_deleted_entry_id:    Some("0-0".into()),
+      entries_added:           Some(1),
+      recorded_first_entry_id: Some("1638126030001-0".into()),
+      entries:                 vec![("1638126030001-0".into(), fields)],
+      groups:                  vec![StreamGroupInfoFull {
+        name:              "mygroup".into(),
+        last_delivered_id: "1638126030001-0".into(),
+        entries_read:      Some(2),
+        lag:               Some(0),
+        pel_count:         1,
+        pending:           vec![PendingDelivery {
+          id:            "1638126030001-0".into(),
+          consumer:      Some("Alice".into()),
+          delivery_time: 1638127657203,
+          deliveries:    1,
+        }],
+        consumers:         vec![StreamConsumerInfoFull {
+          name:        "Alice".into(),
+          seen_time:   1638127657203,
+          active_time: Some(1638127657203),
+          pel_count:   1,
+          pending:     vec![PendingDelivery {
+            id:            "1638126030001-0".into(),
+            consumer:      None,
+            delivery_time: 1638127657203,
+            deliveries:    1,
+          }],
+        }],
+      }],
+    };
+    assert_eq!(StreamInfoFull::from_frame(frame).unwrap(), expected);
+
+    let frame = map(vec![
+      (blob("length"), int(0)),
+      (blob("radix-tree-keys"), int(0)),
+      (blob("radix-tree-nodes"), int(1)),
+      (blob("last-generated-id"), blob("0-0")),
+      (blob("entries"), array(vec![])),
+      (blob("groups"), array(vec![])),
+    ]);
+    let info = StreamInfoFull::from_frame(frame).unwrap();
+    assert!(info.entries.is_empty());
+    assert!(info.groups.is_empty());
+    assert_eq!(info.recorded_first_entry_id, None);
+  }
+
+  #[test]
+  fn should_convert_frames_without_intermediate_values() {
+    use crate::modules::response::FromRedis;
+    use bytes::Bytes;
+    use redis_protocol::resp3::types::Frame;
+
+    let blob = |s: &'static str| Frame::BlobString {
+      data:       Bytes::from_static(s.as_bytes()),
+      attributes: None,
+    };
+    let array = |data: Vec<Frame>| Frame::Array { data, attributes: None };
+
+    let values: Vec<Bytes> = FromRedis::from_frame(array(vec![blob("a"), blob("b")])).unwrap();
+    assert_eq!(values, vec![Bytes::from_static(b"a"), Bytes::from_static(b"b")]);
+    let values: Vec<Option<Bytes>> = FromRedis::from_frame(array(vec![blob("a"), Frame::Null])).unwrap();
+    assert_eq!(values, vec![Some(Bytes::from_static(b"a")), None]);
+    let values: Vec<(String, i64)> = FromRedis::from_frame(array(vec![blob("a"), blob("1")])).unwrap();
+    assert_eq!(values, vec![("a".to_owned(), 1)]);
+
+    let mut expected = HashMap::new();
+    expected.insert(Bytes::from_static(b"a"), Bytes::from_static(b"1"));
+    expected.insert(Bytes::from_static(b"b"), Bytes::from_static(b"2"));
+    let map: HashMap<Bytes, Bytes> =
+      FromRedis::from_frame(array(vec![blob("a"), blob("1"), blob("b"), blob("2")])).unwrap();
+    assert_eq!(map, expected);
+    let map: HashMap<Bytes, Bytes> = FromRedis::from_frame(Frame::Map {
+      data:       vec![(blob("a"), blob("1")), (blob("b"), blob("2"))]
+        .into_iter()
+        .collect(),
+      attributes: None,
+    })
+    .unwrap();
+    assert_eq!(map, expected);
+
+    let nested: Vec<Vec<String>> = FromRedis::from_frame(array(vec![array(vec![blob("a")])])).unwrap();
+    assert_eq!(nested, vec![vec!["a".to_owned()]]);
+    let value: Option<String> = FromRedis::from_frame(array(vec![])).unwrap();
+    assert_eq!(value, None);
+    let result: Result<Vec<Bytes>, _> = FromRedis::from_frame(Frame::SimpleError {
+      data:       "ERR foo".into(),
+      attributes: None,
+    });
+    assert!(result.is_err());
+  }
 }
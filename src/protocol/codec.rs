@@ -1,10 +1,10 @@
 use crate::{
   error::{RedisError, RedisErrorKind},
   modules::inner::RedisClientInner,
-  protocol::{types::ProtocolFrame, utils as protocol_utils},
+  protocol::{connection::SharedBuffer, responders::ResponseKind, types::ProtocolFrame, utils as protocol_utils},
   utils,
 };
-use bytes::BytesMut;
+use bytes::{Buf, BytesMut};
 use bytes_utils::Str;
 use redis_protocol::{
   resp2::{decode::decode_mut as resp2_decode, encode::encode_bytes as resp2_encode, types::Frame as Resp2Frame},
@@ -14,7 +14,10 @@ use redis_protocol::{
     types::{Frame as Resp3Frame, StreamedFrame},
   },
 };
-use std::sync::{atomic::AtomicBool, Arc};
+use std::{
+  str,
+  sync::{atomic::AtomicBool, Arc},
+};
 use tokio_util::codec::{Decoder, Encoder};
 
 #[cfg(feature = "blocking-encoding")]
@@ -165,6 +168,182 @@ fn resp3_decode_frame(codec: &mut RedisCodec, src: &mut BytesMut) -> Result<Opti
   }
 }
 
+/// The maximum number of bytes to search for the end of an aggregate type header.
+const MAX_HEADER_LEN: usize = 24;
+/// The first element of RESP2 pubsub messages and subscription confirmations.
+const RESP2_PUBSUB_KINDS: [&[u8]; 9] = [
+  b"message",
+  b"pmessage",
+  b"smessage",
+  b"subscribe",
+  b"psubscribe",
+  b"ssubscribe",
+  b"unsubscribe",
+  b"punsubscribe",
+  b"sunsubscribe",
+];
+
+/// An aggregate response whose elements are emitted as separate frames as they're decoded.
+pub struct StreamedReply {
+  /// The number of elements that have not been decoded yet, or `None` for streamed RESP3 aggregate types.
+  pub remaining: Option<usize>,
+}
+
+/// Read the length of the line at the front of `src`, not including the trailing CRLF.
+fn read_line(src: &[u8]) -> Option<usize> {
+  src[.. src.len().min(MAX_HEADER_LEN)]
+    .windows(2)
+    .position(|window| window == b"\r\n")
+}
+
+/// Read the header length and number of elements in the array, set, or map at the front of `src`.
+///
+/// The number of elements is `None` for streamed RESP3 aggregate types. Nil arrays are not included.
+fn parse_aggregate_header(src: &[u8], is_resp3: bool) -> Option<(usize, Option<usize>)> {
+  let multiplier = match src.first() {
+    Some(b'*') => 1,
+    Some(b'~') if is_resp3 => 1,
+    Some(b'%') if is_resp3 => 2,
+    _ => return None,
+  };
+  let end = read_line(src)?;
+  let len = &src[1 .. end];
+  if is_resp3 && len == b"?" {
+    return Some((end + 2, None));
+  }
+  let len: usize = str::from_utf8(len).ok()?.parse().ok()?;
+
+  Some((end + 2, Some(len * multiplier)))
+}
+
+/// Whether the first element of a RESP2 array may be the start of a pubsub message, or `None` if more data is needed.
+fn resp2_may_be_pubsub(src: &[u8]) -> Option<bool> {
+  if src.first()? != &b'$' {
+    return Some(false);
+  }
+  let end = read_line(src)?;
+  let len: usize = match str::from_utf8(&src[1 .. end]).ok().and_then(|s| s.parse().ok()) {
+    Some(len) => len,
+    None => return Some(false),
+  };
+  let data = src.get(end + 2 .. end + 2 + len)?;
+
+  Some(RESP2_PUBSUB_KINDS.contains(&data))
+}
+
+/// Check whether the elements of the next response should be streamed to the caller, switching the command at the
+/// front of the shared buffer to `StreamElements` if so.
+fn check_streamed_reply(codec: &mut RedisCodec, src: &mut BytesMut) -> bool {
+  if codec.streamed_reply.is_some() {
+    return true;
+  }
+  if codec.streaming_state.is_some() {
+    return false;
+  }
+  let buffer = match codec.buffer {
+    Some(ref buffer) => buffer,
+    None => return false,
+  };
+  let is_resp3 = codec.is_resp3();
+  let (header_len, remaining) = match parse_aggregate_header(src, is_resp3) {
+    Some(header) => header,
+    None => return false,
+  };
+  if !is_resp3 && remaining != Some(0) && resp2_may_be_pubsub(&src[header_len ..]).unwrap_or(true) {
+    return false;
+  }
+
+  {
+    let mut guard = buffer.lock();
+    let command = match guard.front_mut() {
+      Some(command) if matches!(command.response, ResponseKind::Stream(_)) => command,
+      _ => return false,
+    };
+    if let ResponseKind::Stream(tx) = command.take_response() {
+      command.response = ResponseKind::StreamElements(tx);
+    }
+  }
+
+  trace!(
+    "{}: Streaming {:?} elements from {}",
+    codec.name,
+    remaining,
+    codec.server
+  );
+  src.advance(header_len);
+  codec.streamed_reply = Some(StreamedReply { remaining });
+  true
+}
+
+/// Decode the next complete RESP3 frame, including streamed frames whose chunks are already buffered.
+fn resp3_decode_buffered(codec: &mut RedisCodec, src: &mut BytesMut) -> Result<Option<Resp3Frame>, RedisError> {
+  loop {
+    let len = src.len();
+    match resp3_decode_frame(codec, src)? {
+      Some(frame) => return Ok(Some(frame)),
+      None if src.len() == len => return Ok(None),
+      None => continue,
+    }
+  }
+}
+
+/// Decode the next element of a streamed reply.
+///
+/// Once the last element is decoded this switches the command at the front of the shared buffer to `Skip`, closing
+/// the response stream, and returns an empty array so the reader task can finish the command.
+fn decode_streamed_reply(codec: &mut RedisCodec, src: &mut BytesMut) -> Result<Option<ProtocolFrame>, RedisError> {
+  let is_resp3 = codec.is_resp3();
+  let remaining = match codec.streamed_reply {
+    Some(ref reply) => reply.remaining,
+    None => return Ok(None),
+  };
+
+  if remaining != Some(0) {
+    let frame: ProtocolFrame = if is_resp3 {
+      match resp3_decode_buffered(codec, src)? {
+        Some(frame) => frame.into(),
+        None => return Ok(None),
+      }
+    } else {
+      match resp2_decode_frame(codec, src)? {
+        Some(frame) => frame.into(),
+        None => return Ok(None),
+      }
+    };
+    let is_end = matches!(frame, ProtocolFrame::Resp3(ref frame) if frame.is_end_stream_frame());
+
+    if !(remaining.is_none() && is_end) {
+      if let Some(StreamedReply {
+        remaining: Some(ref mut remaining),
+      }) = codec.streamed_reply
+      {
+        *remaining -= 1;
+      }
+      return Ok(Some(frame));
+    }
+  }
+
+  trace!("{}: Finished streaming response from {}", codec.name, codec.server);
+  codec.streamed_reply = None;
+  if let Some(ref buffer) = codec.buffer {
+    if let Some(command) = buffer.lock().front_mut() {
+      if matches!(command.response, ResponseKind::StreamElements(_)) {
+        let _ = command.take_response();
+      }
+    }
+  }
+
+  Ok(Some(if is_resp3 {
+    Resp3Frame::Array {
+      data:       Vec::new(),
+      attributes: None,
+    }
+    .into()
+  } else {
+    Resp2Frame::Array(Vec::new()).into()
+  }))
+}
+
 /// Attempt to decode with RESP2, and if that fails try once with RESP3.
 ///
 /// This is useful when handling HELLO commands sent in the middle of a RESP2 command sequence.
@@ -190,6 +369,8 @@ pub struct RedisCodec {
   pub server:          Server,
   pub resp3:           Arc<AtomicBool>,
   pub streaming_state: Option<StreamedFrame>,
+  pub buffer:          Option<SharedBuffer>,
+  pub streamed_reply:  Option<StreamedReply>,
   #[cfg(feature = "metrics")]
   pub req_size_stats:  Arc<RwLock<MovingStats>>,
   #[cfg(feature = "metrics")]
@@ -203,6 +384,8 @@ impl RedisCodec {
       name:                                       inner.id.clone(),
       resp3:                                      inner.shared_resp3(),
       streaming_state:                            None,
+      buffer:                                     None,
+      streamed_reply:                             None,
       #[cfg(feature = "metrics")]
       req_size_stats:                             inner.req_size_stats.clone(),
       #[cfg(feature = "metrics")]
@@ -252,6 +435,10 @@ impl Decoder for RedisCodec {
 
   #[cfg(not(feature = "blocking-encoding"))]
   fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+    if check_streamed_reply(self, src) {
+      return decode_streamed_reply(self, src);
+    }
+
     if self.is_resp3() {
       resp3_decode_frame(self, src).map(|f| f.map(|f| f.into()))
     } else {
@@ -261,6 +448,10 @@ impl Decoder for RedisCodec {
 
   #[cfg(feature = "blocking-encoding")]
   fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+    if check_streamed_reply(self, src) {
+      return decode_streamed_reply(self, src);
+    }
+
     if src.len() >= globals().blocking_encode_threshold() {
       trace!("{}: Decoding in blocking task with size {}", self.name, src.len());
 
@@ -280,3 +471,122 @@ impl Decoder for RedisCodec {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    clients::RedisClient,
+    protocol::{
+      command::{RedisCommand, RedisCommandKind, RESPONSE_STREAM_CAPACITY},
+      types::Server,
+    },
+    types::RedisValue,
+  };
+  use parking_lot::Mutex;
+  use std::{collections::VecDeque, sync::atomic::Ordering};
+  use tokio::sync::mpsc::{channel, error::TryRecvError};
+
+  fn create_codec(resp3: bool) -> (RedisCodec, SharedBuffer) {
+    let client = RedisClient::default();
+    client.inner.shared_resp3().store(resp3, Ordering::SeqCst);
+    let buffer = Arc::new(Mutex::new(VecDeque::new()));
+
+    let mut codec = RedisCodec::new(&client.inner, &Server::new("localhost", 6379));
+    codec.buffer = Some(buffer.clone());
+    (codec, buffer)
+  }
+
+  fn decode_value(codec: &mut RedisCodec, src: &mut BytesMut) -> RedisValue {
+    let frame = codec.decode(src).unwrap().unwrap().into_resp3();
+    protocol_utils::frame_to_results(frame).unwrap()
+  }
+
+  #[test]
+  fn should_parse_aggregate_headers() {
+    assert_eq!(parse_aggregate_header(b"*3\r\n$1\r\na", false), Some((4, Some(3))));
+    assert_eq!(parse_aggregate_header(b"%2\r\n", true), Some((4, Some(4))));
+    assert_eq!(parse_aggregate_header(b"%2\r\n", false), None);
+    assert_eq!(parse_aggregate_header(b"*-1\r\n", false), None);
+    assert_eq!(parse_aggregate_header(b"*?\r\n", true), Some((4, None)));
+    assert_eq!(parse_aggregate_header(b"*?\r\n", false), None);
+    assert_eq!(parse_aggregate_header(b"*12", false), None);
+    assert_eq!(parse_aggregate_header(b"$3\r\nfoo\r\n", false), None);
+
+    assert_eq!(resp2_may_be_pubsub(b"$7\r\nmessage\r\n"), Some(true));
+    assert_eq!(resp2_may_be_pubsub(b"$3\r\nfoo\r\n"), Some(false));
+    assert_eq!(resp2_may_be_pubsub(b":1\r\n"), Some(false));
+    assert_eq!(resp2_may_be_pubsub(b"$7\r\nmess"), None);
+  }
+
+  #[test]
+  fn should_stream_elements_as_they_are_decoded() {
+    for resp3 in [false, true] {
+      let (mut codec, buffer) = create_codec(resp3);
+      let (tx, mut rx) = channel(RESPONSE_STREAM_CAPACITY);
+      let command: RedisCommand = (RedisCommandKind::LRange, vec![], ResponseKind::Stream(tx)).into();
+      buffer.lock().push_back(command);
+
+      let mut src = BytesMut::from(&b"*3\r\n$1\r\na\r\n$1\r\nb\r\n:"[..]);
+      assert_eq!(decode_value(&mut codec, &mut src), RedisValue::from("a"));
+      assert!(matches!(
+        buffer.lock().front().unwrap().response,
+        ResponseKind::StreamElements(_)
+      ));
+      assert_eq!(decode_value(&mut codec, &mut src), RedisValue::from("b"));
+      assert!(codec.decode(&mut src).unwrap().is_none());
+
+      src.extend_from_slice(b"3\r\n");
+      assert_eq!(decode_value(&mut codec, &mut src), RedisValue::Integer(3));
+      let frame = codec.decode(&mut src).unwrap().unwrap().into_resp3();
+      assert_eq!(frame, Resp3Frame::Array {
+        data:       vec![],
+        attributes: None,
+      });
+      assert!(matches!(buffer.lock().front().unwrap().response, ResponseKind::Skip));
+      assert_eq!(rx.try_recv().unwrap_err(), TryRecvError::Disconnected);
+      assert!(codec.streamed_reply.is_none());
+      assert!(src.is_empty());
+    }
+  }
+
+  #[test]
+  fn should_stream_resp3_streamed_aggregates() {
+    let (mut codec, buffer) = create_codec(true);
+    let (tx, _rx) = channel(RESPONSE_STREAM_CAPACITY);
+    let command: RedisCommand = (RedisCommandKind::LRange, vec![], ResponseKind::Stream(tx)).into();
+    buffer.lock().push_back(command);
+
+    let mut src = BytesMut::from(&b"*?\r\n:1\r\n$?\r\n;2\r\nab\r\n;0\r\n"[..]);
+    assert_eq!(decode_value(&mut codec, &mut src), RedisValue::Integer(1));
+    assert_eq!(decode_value(&mut codec, &mut src), RedisValue::from("ab"));
+    assert!(codec.decode(&mut src).unwrap().is_none());
+    assert!(codec.streamed_reply.is_some());
+
+    src.extend_from_slice(b".\r\n");
+    let frame = codec.decode(&mut src).unwrap().unwrap().into_resp3();
+    assert_eq!(frame, Resp3Frame::Array {
+      data:       vec![],
+      attributes: None,
+    });
+    assert!(matches!(buffer.lock().front().unwrap().response, ResponseKind::Skip));
+    assert!(codec.streamed_reply.is_none());
+    assert!(src.is_empty());
+  }
+
+  #[test]
+  fn should_not_stream_resp2_pubsub_messages() {
+    let (mut codec, buffer) = create_codec(false);
+    let (tx, _rx) = channel(RESPONSE_STREAM_CAPACITY);
+    let command: RedisCommand = (RedisCommandKind::LRange, vec![], ResponseKind::Stream(tx)).into();
+    buffer.lock().push_back(command);
+
+    let mut src = BytesMut::from(&b"*3\r\n$7\r\nmessage\r\n$3\r\nfoo\r\n$3\r\nbar\r\n"[..]);
+    let frame = codec.decode(&mut src).unwrap().unwrap().into_resp3();
+    assert!(matches!(frame, Resp3Frame::Push { .. }));
+    assert!(matches!(
+      buffer.lock().front().unwrap().response,
+      ResponseKind::Stream(_)
+    ));
+  }
+}
@@ -4,12 +4,13 @@ use crate::{
   modules::inner::RedisClientInner,
   protocol::{
     hashers::ClusterHash,
-    responders::ResponseKind,
+    key_specs,
+    responders::{self, ResponseKind},
     types::{ProtocolFrame, Server},
     utils as protocol_utils,
   },
   trace,
-  types::{CustomCommand, RedisValue},
+  types::{CustomCommand, RedisValue, RetryPolicy},
   utils as client_utils,
   utils,
 };
@@ -25,7 +26,10 @@ use std::{
   sync::{atomic::AtomicBool, Arc},
   time::{Duration, Instant},
 };
-use tokio::sync::oneshot::{channel as oneshot_channel, Receiver as OneshotReceiver, Sender as OneshotSender};
+use tokio::sync::{
+  mpsc::Sender,
+  oneshot::{channel as oneshot_channel, Receiver as OneshotReceiver, Sender as OneshotSender},
+};
 
 #[cfg(feature = "blocking-encoding")]
 use crate::globals::globals;
@@ -84,6 +88,11 @@ pub enum RouterResponse {
 
 /// A channel for communication between connection reader tasks and futures returned to the caller.
 pub type ResponseSender = OneshotSender<Result<Resp3Frame, RedisError>>;
+/// A sender channel for streaming the elements of a response to the caller.
+pub type ResponseStreamSender = Sender<Result<Resp3Frame, RedisError>>;
+/// The number of response elements that can be buffered for the caller before the reader task stops reading from the
+/// socket.
+pub const RESPONSE_STREAM_CAPACITY: usize = 64;
 /// A sender channel for communication between connection reader tasks and the router.
 pub type RouterSender = OneshotSender<RouterResponse>;
 /// A receiver channel for communication between connection reader tasks and the router.
@@ -1395,6 +1404,89 @@ impl RedisCommandKind {
       RedisCommandKind::EvalSha | RedisCommandKind::Eval | RedisCommandKind::Fcall | RedisCommandKind::FcallRO
     )
   }
+
+  /// Whether the command can be written more than once without changing the outcome.
+  ///
+  /// Commands that modify a value relative to its current state (`INCR`, `LPUSH`, `JSON.ARRTRIM`, etc), remove and
+  /// return values (`LPOP`, `SPOP`, etc), add stream entries, run scripts, or publish messages are considered
+  /// non-idempotent. Custom commands are always considered non-idempotent.
+  pub fn is_idempotent(&self) -> bool {
+    !matches!(
+      *self,
+      RedisCommandKind::Append
+        | RedisCommandKind::BitField
+        | RedisCommandKind::BlPop
+        | RedisCommandKind::BlMove
+        | RedisCommandKind::BrPop
+        | RedisCommandKind::BrPopLPush
+        | RedisCommandKind::BzPopMin
+        | RedisCommandKind::BzPopMax
+        | RedisCommandKind::BlmPop
+        | RedisCommandKind::BzmPop
+        | RedisCommandKind::Decr
+        | RedisCommandKind::DecrBy
+        | RedisCommandKind::Eval
+        | RedisCommandKind::EvalSha
+        | RedisCommandKind::Fcall
+        | RedisCommandKind::GetDel
+        | RedisCommandKind::GetSet
+        | RedisCommandKind::HIncrBy
+        | RedisCommandKind::HIncrByFloat
+        | RedisCommandKind::Incr
+        | RedisCommandKind::IncrBy
+        | RedisCommandKind::IncrByFloat
+        | RedisCommandKind::LInsert
+        | RedisCommandKind::LMove
+        | RedisCommandKind::LPop
+        | RedisCommandKind::LPush
+        | RedisCommandKind::LPushX
+        | RedisCommandKind::LMPop
+        | RedisCommandKind::LRem
+        | RedisCommandKind::Rename
+        | RedisCommandKind::Renamenx
+        | RedisCommandKind::Restore
+        | RedisCommandKind::Rpop
+        | RedisCommandKind::Rpoplpush
+        | RedisCommandKind::Rpush
+        | RedisCommandKind::Rpushx
+        | RedisCommandKind::Spop
+        | RedisCommandKind::Xadd
+        | RedisCommandKind::Xreadgroup
+        | RedisCommandKind::Xclaim
+        | RedisCommandKind::Xautoclaim
+        | RedisCommandKind::Zincrby
+        | RedisCommandKind::Zpopmax
+        | RedisCommandKind::Zpopmin
+        | RedisCommandKind::Zmpop
+        | RedisCommandKind::Zremrangebyrank
+        | RedisCommandKind::Publish
+        | RedisCommandKind::Spublish
+        | RedisCommandKind::JsonArrAppend
+        | RedisCommandKind::JsonArrInsert
+        | RedisCommandKind::JsonArrPop
+        | RedisCommandKind::JsonArrTrim
+        | RedisCommandKind::JsonNumIncrBy
+        | RedisCommandKind::JsonStrAppend
+        | RedisCommandKind::JsonToggle
+        | RedisCommandKind::_Custom(_)
+    )
+  }
+
+  /// Read the indexes of the key arguments in the provided arguments, or `None` if the command does not have a known
+  /// key layout.
+  ///
+  /// The key layouts are read from a table of command key specifications (see `COMMAND INFO`), which also covers
+  /// custom commands with a known name. Callers should use `ClusterHash::key_indexes` to include the key positions
+  /// declared by the command's hashing policy.
+  pub fn key_indexes(&self, args: &[RedisValue]) -> Option<Vec<usize>> {
+    match *self {
+      RedisCommandKind::_Custom(ref kind) => {
+        let name = kind.cmd.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase();
+        key_specs::key_indexes(&name, args)
+      },
+      _ => key_specs::key_indexes(self.to_str_debug(), args),
+    }
+  }
 }
 
 pub struct RedisCommand {
@@ -1428,12 +1520,21 @@ pub struct RedisCommand {
   pub timeout_dur:            Option<Duration>,
   /// Whether the command has timed out from the perspective of the caller.
   pub timed_out:              Arc<AtomicBool>,
+  /// The server that most recently received the command, shared with the caller when a circuit breaker is
+  /// configured so that timeouts can be recorded against the server.
+  pub written_to:             Option<Arc<Mutex<Option<Server>>>>,
   /// A timestamp of when the command was last written to the socket.
   pub network_start:          Option<Instant>,
   /// Whether to route the command to a replica, if possible.
   pub use_replica:            bool,
   /// Only send the command to the provided server.
   pub cluster_node:           Option<Server>,
+  /// A prefix to add to each key argument.
+  pub key_prefix:             Option<Str>,
+  /// The policy used to decide whether the command should be retried after a connection error.
+  pub retry_policy:           Option<RetryPolicy>,
+  /// Whether to split a multi-key command into one command per hash slot.
+  pub split_cross_slot:       bool,
   /// A timestamp of when the command was first created from the public interface.
   #[cfg(feature = "metrics")]
   pub created:                Instant,
@@ -1485,6 +1586,7 @@ impl Default for RedisCommand {
       kind:                                        RedisCommandKind::Ping,
       arguments:                                   Vec::new(),
       timed_out:                                   Arc::new(AtomicBool::new(false)),
+      written_to:                                  None,
       timeout_dur:                                 None,
       response:                                    ResponseKind::Respond(None),
       hasher:                                      ClusterHash::default(),
@@ -1496,6 +1598,9 @@ impl Default for RedisCommand {
       transaction_id:                              None,
       use_replica:                                 false,
       cluster_node:                                None,
+      key_prefix:                                  None,
+      retry_policy:                                None,
+      split_cross_slot:                            false,
       network_start:                               None,
       write_attempts:                              0,
       #[cfg(feature = "metrics")]
@@ -1583,7 +1688,44 @@ impl RedisCommand {
 
   /// Whether errors writing the command should be returned to the caller.
   pub fn should_finish_with_error(&self, inner: &Arc<RedisClientInner>) -> bool {
-    self.attempts_remaining == 0 || inner.policy.read().is_none()
+    self.attempts_remaining == 0 || inner.policy.read().is_none() || !self.check_retry_policy(inner)
+  }
+
+  /// Check whether the retry policy allows the command to be written again, recording the decision if not.
+  pub fn check_retry_policy(&self, inner: &Arc<RedisClientInner>) -> bool {
+    let policy = match self.retry_policy {
+      Some(ref policy) if self.write_attempts > 0 => policy,
+      _ => return true,
+    };
+    let idempotent = self.kind.is_idempotent();
+    let retry = policy.should_retry(idempotent);
+
+    if !retry {
+      trace::retry_event(self, false, idempotent);
+      _debug!(
+        inner,
+        "Skip retrying {} ({}) due to retry policy.",
+        self.kind.to_str_debug(),
+        self.debug_id()
+      );
+      inner.counters.incr_retry_skipped_count();
+    }
+    retry
+  }
+
+  /// Record that the command will be written again after a connection error.
+  pub fn record_retry(&self) {
+    if self.write_attempts > 0 {
+      trace::retry_event(self, true, self.kind.is_idempotent());
+    }
+  }
+
+  /// Read the amount of time to wait before writing the command again, if any.
+  pub fn retry_delay(&self) -> Option<Duration> {
+    self
+      .retry_policy
+      .as_ref()
+      .and_then(|policy| policy.delay(self.write_attempts))
   }
 
   /// Increment and check the number of write attempts.
@@ -1618,6 +1760,15 @@ impl RedisCommand {
     }
   }
 
+  /// Read a mutable reference to the arguments associated with the command.
+  pub fn args_mut(&mut self) -> &mut Vec<RedisValue> {
+    match self.response {
+      ResponseKind::ValueScan(ref mut inner) => &mut inner.args,
+      ResponseKind::KeyScan(ref mut inner) => &mut inner.args,
+      _ => &mut self.arguments,
+    }
+  }
+
   /// Whether the command blocks the connection.
   pub fn blocks_connection(&self) -> bool {
     self.transaction_id.is_none()
@@ -1689,6 +1840,7 @@ impl RedisCommand {
   pub fn duplicate(&self, response: ResponseKind) -> Self {
     RedisCommand {
       timed_out: Arc::new(AtomicBool::new(false)),
+      written_to: None,
       kind: self.kind.clone(),
       arguments: self.arguments.clone(),
       hasher: self.hasher.clone(),
@@ -1700,6 +1852,9 @@ impl RedisCommand {
       skip_backpressure: self.skip_backpressure,
       router_tx: self.router_tx.clone(),
       cluster_node: self.cluster_node.clone(),
+      key_prefix: self.key_prefix.clone(),
+      retry_policy: self.retry_policy.clone(),
+      split_cross_slot: self.split_cross_slot,
       response,
       use_replica: self.use_replica,
       write_attempts: self.write_attempts,
@@ -1729,6 +1884,32 @@ impl RedisCommand {
         self.timeout_dur = Some(default_dur);
       }
     }
+    if self.key_prefix.is_none() {
+      self.key_prefix = inner.config.key_prefix.clone();
+    }
+  }
+
+  /// Prepend the key prefix, if any, to each key argument.
+  pub fn apply_key_prefix(&mut self) {
+    let prefix = match self.key_prefix {
+      Some(ref prefix) if !prefix.is_empty() => prefix.clone(),
+      _ => return,
+    };
+
+    if self.kind == RedisCommandKind::Keys {
+      if let Some(pattern) = self.args_mut().get_mut(0) {
+        utils::add_key_pattern_prefix(prefix.as_bytes(), pattern);
+      }
+      return;
+    }
+
+    let indexes = self.hasher.key_indexes(&self.kind, self.args());
+    let args = self.args_mut();
+    for idx in indexes.into_iter() {
+      if let Some(arg) = args.get_mut(idx) {
+        utils::add_key_prefix(prefix.as_bytes(), arg);
+      }
+    }
   }
 
   /// Take the command tracing state for the `queued` span.
@@ -1767,7 +1948,10 @@ impl RedisCommand {
 
   /// Respond to the caller, taking the response channel in the process.
   pub fn respond_to_caller(&mut self, result: Result<Resp3Frame, RedisError>) {
-    if let Some(tx) = self.take_responder() {
+    if let ResponseKind::Stream(ref tx) | ResponseKind::StreamElements(ref tx) = self.response {
+      responders::send_stream_result(tx, result);
+      self.response = ResponseKind::Skip;
+    } else if let Some(tx) = self.take_responder() {
       let _ = tx.send(result);
     }
   }
@@ -1789,7 +1973,7 @@ impl RedisCommand {
       .kind
       .custom_hash_slot()
       .or(self.scan_hash_slot())
-      .or(self.hasher.hash(self.args()))
+      .or(self.hasher.hash_command(&self.kind, self.args()))
   }
 
   /// Read the custom hash slot assigned to a scan operation.
@@ -7,7 +7,7 @@ use crate::{
     types::{ProtocolFrame, Server},
     utils as protocol_utils,
   },
-  types::InfoKind,
+  types::{CustomCommand, InfoKind, RedisValue, RespNegotiation},
   utils as client_utils,
   utils,
 };
@@ -37,10 +37,7 @@ use tokio_util::codec::Framed;
 #[cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))]
 use crate::protocol::tls::TlsConnector;
 #[cfg(feature = "replicas")]
-use crate::{
-  protocol::{connection, responders::ResponseKind},
-  types::RedisValue,
-};
+use crate::protocol::{connection, responders::ResponseKind};
 use bytes_utils::Str;
 #[cfg(feature = "enable-rustls")]
 use std::{convert::TryInto, ops::Deref};
@@ -116,6 +113,17 @@ pub enum ConnectionKind {
 }
 
 impl ConnectionKind {
+  /// Read a mutable reference to the codec.
+  pub fn codec_mut(&mut self) -> &mut RedisCodec {
+    match self {
+      ConnectionKind::Tcp(ref mut conn) => conn.codec_mut(),
+      #[cfg(feature = "enable-rustls")]
+      ConnectionKind::Rustls(ref mut conn) => conn.codec_mut(),
+      #[cfg(feature = "enable-native-tls")]
+      ConnectionKind::NativeTls(ref mut conn) => conn.codec_mut(),
+    }
+  }
+
   /// Split the connection.
   pub fn split(self) -> (SplitSinkKind, SplitStreamKind) {
     match self {
@@ -586,34 +594,73 @@ impl RedisTransport {
     Ok(())
   }
 
+  /// Build a `HELLO 3` command with the provided credentials and the client name, if enabled.
+  fn hello_command(
+    inner: &Arc<RedisClientInner>,
+    username: Option<String>,
+    password: Option<String>,
+  ) -> RedisCommand {
+    let mut args: Vec<RedisValue> = vec![3.into()];
+    if let Some(password) = password {
+      args.extend([
+        "AUTH".into(),
+        username.unwrap_or_else(|| "default".into()).into(),
+        password.into(),
+      ]);
+    }
+    if cfg!(feature = "auto-client-setname") {
+      args.extend(["SETNAME".into(), inner.id.clone().into()]);
+    }
+
+    let kind = RedisCommandKind::_Custom(CustomCommand::new_static("HELLO", None, false));
+    RedisCommand::new(kind, args)
+  }
+
   /// Authenticate via HELLO in RESP3 mode or AUTH in RESP2 mode, then set the client name.
+  ///
+  /// If the config specifies `RespNegotiation::Auto` and the server rejects `HELLO` the connection falls back to
+  /// RESP2 and `AUTH`. The negotiated version is cleared when the connections are closed, so each reconnection
+  /// negotiates again.
   pub async fn switch_protocols_and_authenticate(&mut self, inner: &Arc<RedisClientInner>) -> Result<(), RedisError> {
-    // reset the protocol version to the one specified by the config when we create new connections
+    // reset the protocol version to the configured or negotiated one when we create new connections
     inner.reset_protocol_version();
     let username = inner.config.username.clone();
     let password = inner.config.password.clone();
 
     if inner.is_resp3() {
       _debug!(inner, "Switching to RESP3 protocol with HELLO...");
-      let args = if let Some(password) = password {
-        if let Some(username) = username {
-          vec![username.into(), password.into()]
-        } else {
-          vec!["default".into(), password.into()]
-        }
-      } else {
-        vec![]
-      };
-
-      let cmd = RedisCommand::new(RedisCommandKind::_Hello(RespVersion::RESP3), args);
+      let cmd = Self::hello_command(inner, username.clone(), password.clone());
       let response = self.request_response(cmd, true).await?;
-      let response = protocol_utils::frame_to_results(response)?;
-      inner.switch_protocol_versions(RespVersion::RESP3);
-      _trace!(inner, "Recv HELLO response {:?}", response);
 
-      Ok(())
+      match protocol_utils::frame_to_results(response) {
+        Ok(response) => {
+          _trace!(inner, "Recv HELLO response {:?}", response);
+          if inner.negotiate_protocol_version(RespVersion::RESP3) {
+            Ok(())
+          } else {
+            Err(RedisError::new(
+              RedisErrorKind::Protocol,
+              "Connection switched to RESP3 after the client negotiated RESP2.",
+            ))
+          }
+        },
+        Err(e)
+          if inner.config.negotiation == RespNegotiation::Auto
+            && inner.negotiated_version().is_none()
+            && *e.kind() != RedisErrorKind::Auth =>
+        {
+          _debug!(inner, "Falling back to RESP2 after HELLO error: {:?}", e);
+          if !inner.negotiate_protocol_version(RespVersion::RESP2) {
+            return Err(e);
+          }
+          self.authenticate(&inner.id, username, password, false).await?;
+          self.set_client_name(inner).await
+        },
+        Err(e) => Err(e),
+      }
     } else {
-      self.authenticate(&inner.id, username, password, false).await
+      self.authenticate(&inner.id, username, password, false).await?;
+      self.set_client_name(inner).await
     }
   }
 
@@ -703,7 +750,7 @@ impl RedisTransport {
     ))
   }
 
-  /// Authenticate, set the protocol version, set the client name, select the provided database, cache the
+  /// Authenticate, set the protocol version and client name, select the provided database, cache the
   /// connection ID and server version, and check the cluster state (if applicable).
   pub async fn setup(&mut self, inner: &Arc<RedisClientInner>, timeout: Option<Duration>) -> Result<(), RedisError> {
     let timeout = timeout.unwrap_or(inner.internal_command_timeout());
@@ -712,7 +759,6 @@ impl RedisTransport {
       async {
         self.switch_protocols_and_authenticate(inner).await?;
         self.select_database(inner).await?;
-        self.set_client_name(inner).await?;
         self.cache_connection_id(inner).await?;
         self.cache_server_version(inner).await?;
         self.check_cluster_state(inner).await?;
@@ -792,7 +838,10 @@ impl RedisTransport {
     let len = protocol_utils::initial_buffer_size(inner);
     let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(len)));
     let (server, addr, default_host) = (self.server, self.addr, self.default_host);
-    let (sink, stream) = self.transport.split();
+    let mut transport = self.transport;
+    // the codec reads the command at the front of the buffer to decide whether to stream the response
+    transport.codec_mut().buffer = Some(buffer.clone());
+    let (sink, stream) = transport.split();
     let (id, version, counters) = (self.id, self.version, self.counters);
 
     let writer = RedisWriter {
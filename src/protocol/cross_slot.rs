@@ -0,0 +1,332 @@
+use crate::{
+  interfaces::Resp3Frame,
+  modules::inner::RedisClientInner,
+  protocol::{
+    command::{RedisCommand, RedisCommandKind, ResponseSender},
+    hashers::ClusterHash,
+    responders::ResponseKind,
+    types::ClusterRouting,
+  },
+  types::RedisKey,
+};
+use std::{collections::VecDeque, convert::TryFrom, sync::Arc};
+use tokio::sync::oneshot::channel as oneshot_channel;
+
+/// How the responses from each piece of a split command are combined.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum MergeKind {
+  /// Reassemble an array with one value per key, in the original key order.
+  Values,
+  /// Add the integer responses.
+  Sum,
+  /// Respond with the first response if no piece returned an error.
+  First,
+}
+
+impl MergeKind {
+  /// Read the merge policy and the number of arguments associated with each key, if the command can be split.
+  fn from_kind(kind: &RedisCommandKind) -> Option<(Self, usize)> {
+    match *kind {
+      RedisCommandKind::Mget => Some((MergeKind::Values, 1)),
+      RedisCommandKind::Del | RedisCommandKind::Unlink | RedisCommandKind::Exists | RedisCommandKind::Touch => {
+        Some((MergeKind::Sum, 1))
+      },
+      RedisCommandKind::Mset => Some((MergeKind::First, 2)),
+      _ => None,
+    }
+  }
+}
+
+/// State used to merge the responses from the pieces of a command that was split by hash slot.
+#[derive(Debug)]
+pub struct Split {
+  kind:      MergeKind,
+  /// The original position of each key, for each piece.
+  positions: Vec<Vec<usize>>,
+}
+
+impl Split {
+  /// The number of pieces, and therefore response frames, associated with the original command.
+  pub fn len(&self) -> usize {
+    self.positions.len()
+  }
+
+  /// Merge the response frames from each piece, in the same order as the pieces returned by [split].
+  pub fn merge(&self, frames: Vec<Resp3Frame>) -> Resp3Frame {
+    if let Some(frame) = frames.iter().find(|frame| frame.is_error()) {
+      return frame.clone();
+    }
+
+    match self.kind {
+      MergeKind::Values => {
+        let total = self
+          .positions
+          .iter()
+          .fold(0, |count, positions| count + positions.len());
+        let mut out = vec![Resp3Frame::Null; total];
+
+        for (positions, frame) in self.positions.iter().zip(frames) {
+          let values = match frame {
+            Resp3Frame::Array { data, .. } => data,
+            _ => return unexpected_response(),
+          };
+
+          for (position, value) in positions.iter().zip(values) {
+            out[*position] = value;
+          }
+        }
+
+        Resp3Frame::Array {
+          data:       out,
+          attributes: None,
+        }
+      },
+      MergeKind::Sum => {
+        let mut total = 0;
+        for frame in frames.into_iter() {
+          match frame {
+            Resp3Frame::Number { data, .. } => total += data,
+            _ => return unexpected_response(),
+          };
+        }
+
+        Resp3Frame::Number {
+          data:       total,
+          attributes: None,
+        }
+      },
+      MergeKind::First => frames.into_iter().next().unwrap_or(Resp3Frame::Null),
+    }
+  }
+}
+
+fn unexpected_response() -> Resp3Frame {
+  Resp3Frame::SimpleError {
+    data:       "Unexpected response to cross slot command.".into(),
+    attributes: None,
+  }
+}
+
+/// Split a multi-key command into one command per hash slot.
+///
+/// Returns `None` if the command cannot be split or if all the keys belong to the same hash slot.
+pub fn split(command: &RedisCommand) -> Option<(Vec<RedisCommand>, Split)> {
+  let (kind, step) = MergeKind::from_kind(&command.kind)?;
+  if command.cluster_node.is_some() || command.hasher != ClusterHash::FirstKey {
+    return None;
+  }
+  let args = command.args();
+  if args.is_empty() {
+    return None;
+  }
+
+  let (mut slots, mut positions): (Vec<u16>, Vec<Vec<usize>>) = (Vec::new(), Vec::new());
+  for (position, chunk) in args.chunks(step).enumerate() {
+    if chunk.len() != step {
+      return None;
+    }
+    // integer and other non-byte keys are sent as strings, so they're hashed the same way
+    let key = RedisKey::try_from(chunk[0].clone()).ok()?;
+    let slot = ClusterRouting::hash_key(key.as_bytes());
+
+    match slots.iter().position(|s| *s == slot) {
+      Some(idx) => positions[idx].push(position),
+      None => {
+        slots.push(slot);
+        positions.push(vec![position]);
+      },
+    }
+  }
+  if positions.len() < 2 {
+    return None;
+  }
+
+  let pieces = slots
+    .iter()
+    .zip(positions.iter())
+    .map(|(slot, positions)| {
+      let mut piece = command.duplicate(ResponseKind::Skip);
+      *piece.args_mut() = positions
+        .iter()
+        .flat_map(|position| args[position * step .. (position + 1) * step].iter().cloned())
+        .collect();
+      piece.hasher = ClusterHash::Custom(*slot);
+      piece
+    })
+    .collect();
+
+  Some((pieces, Split { kind, positions }))
+}
+
+/// Split any cross slot commands in a pipeline, returning the new commands and the state needed to merge the
+/// responses.
+pub fn split_pipeline(
+  inner: &Arc<RedisClientInner>,
+  commands: VecDeque<RedisCommand>,
+) -> (VecDeque<RedisCommand>, Vec<Option<Split>>) {
+  if !inner.config.server.is_clustered() {
+    return (commands, Vec::new());
+  }
+
+  let mut out = VecDeque::with_capacity(commands.len());
+  let mut splits = Vec::with_capacity(commands.len());
+  for command in commands.into_iter() {
+    if let Some((pieces, split)) = split(&command) {
+      _debug!(
+        inner,
+        "Splitting {} command in pipeline into {} pieces.",
+        command.kind.to_str_debug(),
+        split.len()
+      );
+      out.extend(pieces);
+      splits.push(Some(split));
+    } else {
+      out.push_back(command);
+      splits.push(None);
+    }
+  }
+
+  (out, splits)
+}
+
+/// Merge the buffered response frames from a pipeline created by [split_pipeline].
+pub fn merge_pipeline(splits: &[Option<Split>], frame: Resp3Frame) -> Resp3Frame {
+  if splits.iter().all(|split| split.is_none()) {
+    return frame;
+  }
+  let data = match frame {
+    Resp3Frame::Array { data, .. } => data,
+    frame => return frame,
+  };
+
+  let mut frames = data.into_iter();
+  let data = splits
+    .iter()
+    .map(|split| match split {
+      Some(split) => split.merge(frames.by_ref().take(split.len()).collect()),
+      None => frames.next().unwrap_or(Resp3Frame::Null),
+    })
+    .collect();
+
+  Resp3Frame::Array { data, attributes: None }
+}
+
+/// Buffer the responses from each piece, sending the merged response to `tx` once all the pieces finish.
+pub fn buffer_pieces(mut pieces: Vec<RedisCommand>, split: Split, tx: ResponseSender) -> Vec<RedisCommand> {
+  let (buffer_tx, buffer_rx) = oneshot_channel();
+  let mut response = ResponseKind::new_buffer_with_size(pieces.len(), buffer_tx);
+  response.set_error_early(false);
+
+  for (idx, piece) in pieces.iter_mut().enumerate() {
+    piece.response = response.duplicate().unwrap_or(ResponseKind::Skip);
+    piece.response.set_expected_index(idx);
+  }
+
+  tokio::spawn(async move {
+    let result = match buffer_rx.await {
+      Ok(Ok(Resp3Frame::Array { data, .. })) => Ok(split.merge(data)),
+      Ok(result) => result,
+      Err(e) => Err(e.into()),
+    };
+
+    let _ = tx.send(result);
+  });
+  pieces
+}
+
+/// Split a cross slot command, returning the pieces to send to the router with a response policy that merges the
+/// responses.
+pub fn split_command(inner: &Arc<RedisClientInner>, command: &mut RedisCommand) -> Option<Vec<RedisCommand>> {
+  if !command.split_cross_slot || !inner.config.server.is_clustered() {
+    return None;
+  }
+  let (pieces, split) = split(command)?;
+  _debug!(
+    inner,
+    "Splitting {} command into {} pieces.",
+    command.kind.to_str_debug(),
+    split.len()
+  );
+
+  Some(match command.take_responder() {
+    Some(tx) => buffer_pieces(pieces, split, tx),
+    None => pieces,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::types::RedisValue;
+
+  fn keys_in_different_slots() -> (&'static str, &'static str) {
+    assert_ne!(ClusterRouting::hash_key(b"foo"), ClusterRouting::hash_key(b"bar"));
+    ("foo", "bar")
+  }
+
+  fn number(data: i64) -> Resp3Frame {
+    Resp3Frame::Number { data, attributes: None }
+  }
+
+  fn array(data: Vec<Resp3Frame>) -> Resp3Frame {
+    Resp3Frame::Array { data, attributes: None }
+  }
+
+  #[test]
+  fn should_not_split_same_slot_command() {
+    let args = vec!["{foo}1".into(), "{foo}2".into()];
+    let command = RedisCommand::new(RedisCommandKind::Mget, args);
+    assert!(split(&command).is_none());
+  }
+
+  #[test]
+  fn should_split_mget_and_merge_in_order() {
+    let (foo, bar) = keys_in_different_slots();
+    let args: Vec<RedisValue> = vec![foo.into(), bar.into(), foo.into()];
+    let command = RedisCommand::new(RedisCommandKind::Mget, args);
+
+    let (pieces, split) = split(&command).unwrap();
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(pieces[0].args(), &vec![RedisValue::from(foo), RedisValue::from(foo)]);
+    assert_eq!(pieces[1].args(), &vec![RedisValue::from(bar)]);
+
+    let frames = vec![array(vec![number(1), number(3)]), array(vec![number(2)])];
+    let expected = array(vec![number(1), number(2), number(3)]);
+    assert_eq!(split.merge(frames), expected);
+  }
+
+  #[test]
+  fn should_split_integer_keys() {
+    assert_ne!(ClusterRouting::hash_key(b"1"), ClusterRouting::hash_key(b"2"));
+    let args: Vec<RedisValue> = vec![1.into(), 2.into()];
+    let command = RedisCommand::new(RedisCommandKind::Del, args);
+
+    let (pieces, _) = split(&command).unwrap();
+    assert_eq!(pieces.len(), 2);
+    assert_eq!(pieces[0].args(), &vec![RedisValue::from(1)]);
+    assert_eq!(pieces[0].cluster_hash(), Some(ClusterRouting::hash_key(b"1")));
+    assert_eq!(pieces[1].cluster_hash(), Some(ClusterRouting::hash_key(b"2")));
+  }
+
+  #[test]
+  fn should_split_mset_pairs() {
+    let (foo, bar) = keys_in_different_slots();
+    let args: Vec<RedisValue> = vec![foo.into(), 1.into(), bar.into(), 2.into()];
+    let command = RedisCommand::new(RedisCommandKind::Mset, args);
+
+    let (pieces, _) = split(&command).unwrap();
+    assert_eq!(pieces[0].args(), &vec![RedisValue::from(foo), RedisValue::from(1)]);
+    assert_eq!(pieces[1].args(), &vec![RedisValue::from(bar), RedisValue::from(2)]);
+  }
+
+  #[test]
+  fn should_merge_split_pipeline_responses() {
+    let (foo, bar) = keys_in_different_slots();
+    let command = RedisCommand::new(RedisCommandKind::Del, vec![foo.into(), bar.into()]);
+    let (_, split) = split(&command).unwrap();
+
+    let frame = array(vec![number(0), number(1), number(1)]);
+    let expected = array(vec![number(0), number(2)]);
+    assert_eq!(merge_pipeline(&[None, Some(split)], frame), expected);
+  }
+}
@@ -1,4 +1,4 @@
-use crate::types::RedisValue;
+use crate::{protocol::command::RedisCommandKind, types::RedisValue};
 use redis_protocol::redis_keyslot;
 
 fn hash_value(value: &RedisValue) -> Option<u16> {
@@ -28,7 +28,8 @@ fn hash_key(value: &RedisValue) -> Option<u16> {
 /// A cluster hashing policy.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum ClusterHash {
-  /// Hash the first string or bytes value in the arguments. (Default)
+  /// Hash the first key argument of commands with a known key layout, or otherwise the first string or bytes value
+  /// in the arguments. (Default)
   FirstKey,
   /// Hash the first argument regardless of type.
   FirstValue,
@@ -85,6 +86,48 @@ impl ClusterHash {
     }
   }
 
+  /// Hash the arguments of a command.
+  ///
+  /// The `FirstKey` policy hashes the first key argument of commands with a known key layout, and otherwise the first
+  /// string or bytes argument.
+  pub(crate) fn hash_command(&self, kind: &RedisCommandKind, args: &[RedisValue]) -> Option<u16> {
+    match self {
+      ClusterHash::FirstKey => match self.key_indexes(kind, args).first() {
+        Some(idx) => args.get(*idx).and_then(hash_value),
+        None => self.hash(args),
+      },
+      _ => self.hash(args),
+    }
+  }
+
+  /// Read the indexes of the key arguments of a command.
+  ///
+  /// Commands with a known key layout use that layout. Other custom commands use the key selected by the hashing
+  /// policy, if any.
+  pub(crate) fn key_indexes(&self, kind: &RedisCommandKind, args: &[RedisValue]) -> Vec<usize> {
+    if let Some(indexes) = kind.key_indexes(args) {
+      return indexes;
+    }
+    if !kind.is_custom() {
+      return Vec::new();
+    }
+
+    let idx = match self {
+      ClusterHash::FirstValue => 0,
+      ClusterHash::FirstKey => match args.iter().position(|arg| read_redis_key(arg).is_some()) {
+        Some(idx) => idx,
+        None => return Vec::new(),
+      },
+      ClusterHash::Offset(idx) => *idx,
+      ClusterHash::Random | ClusterHash::Custom(_) => return Vec::new(),
+    };
+    if idx < args.len() {
+      vec![idx]
+    } else {
+      Vec::new()
+    }
+  }
+
   /// Find the key to hash with the provided arguments.
   pub fn find_key<'a>(&self, args: &'a [RedisValue]) -> Option<&'a [u8]> {
     match self {
@@ -0,0 +1,631 @@
+use crate::types::RedisValue;
+use std::cmp;
+
+/// The location of the key arguments of a command, based on the key specifications returned by `COMMAND INFO`.
+///
+/// Indexes are relative to the arguments that follow the command name and subcommand, if any.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum KeySpec {
+  /// Keys from `first` to `last` (inclusive), with `step` arguments between each key. A negative `last` value is
+  /// relative to the end of the arguments, where `-1` is the last argument.
+  Range { first: usize, last: isize, step: usize },
+  /// A key count at the provided index followed by the keys, such as `EVAL script numkeys key [key ...]`.
+  NumKeys(usize),
+  /// A destination key followed by a key count and the keys, such as `ZUNIONSTORE dest numkeys key [key ...]`.
+  DestinationNumKeys,
+  /// The keys following the `STREAMS` keyword, such as `XREAD ... STREAMS key [key ...] id [id ...]`.
+  Streams,
+  /// A key followed by an optional destination key after `STORE` or `STOREDIST`, such as `SORT` or `GEORADIUS`.
+  Store,
+  /// The `MIGRATE` key argument, or the keys after the `KEYS` keyword if the key argument is empty.
+  Migrate,
+}
+
+const fn single(first: usize) -> KeySpec {
+  KeySpec::Range {
+    first,
+    last: first as isize,
+    step: 1,
+  }
+}
+
+const fn all(first: usize) -> KeySpec {
+  KeySpec::Range {
+    first,
+    last: -1,
+    step: 1,
+  }
+}
+
+/// Read the key specification for the provided uppercase command name, including the subcommand if any.
+fn key_spec(name: &str) -> Option<KeySpec> {
+  let spec = match name {
+    "APPEND"
+    | "BITCOUNT"
+    | "BITFIELD"
+    | "BITFIELD_RO"
+    | "BITPOS"
+    | "DECR"
+    | "DECRBY"
+    | "DUMP"
+    | "EXPIRE"
+    | "EXPIREAT"
+    | "EXPIRETIME"
+    | "GEOADD"
+    | "GEODIST"
+    | "GEOHASH"
+    | "GEOPOS"
+    | "GEORADIUS_RO"
+    | "GEORADIUSBYMEMBER_RO"
+    | "GEOSEARCH"
+    | "GET"
+    | "GETBIT"
+    | "GETDEL"
+    | "GETEX"
+    | "GETRANGE"
+    | "GETSET"
+    | "HDEL"
+    | "HEXISTS"
+    | "HEXPIRE"
+    | "HEXPIREAT"
+    | "HEXPIRETIME"
+    | "HGET"
+    | "HGETALL"
+    | "HINCRBY"
+    | "HINCRBYFLOAT"
+    | "HKEYS"
+    | "HLEN"
+    | "HMGET"
+    | "HMSET"
+    | "HPERSIST"
+    | "HPEXPIRE"
+    | "HPEXPIREAT"
+    | "HPEXPIRETIME"
+    | "HPTTL"
+    | "HRANDFIELD"
+    | "HSCAN"
+    | "HSET"
+    | "HSETNX"
+    | "HSTRLEN"
+    | "HTTL"
+    | "HVALS"
+    | "INCR"
+    | "INCRBY"
+    | "INCRBYFLOAT"
+    | "LINDEX"
+    | "LINSERT"
+    | "LLEN"
+    | "LPOP"
+    | "LPOS"
+    | "LPUSH"
+    | "LPUSHX"
+    | "LRANGE"
+    | "LREM"
+    | "LSET"
+    | "LTRIM"
+    | "MEMORY USAGE"
+    | "MOVE"
+    | "OBJECT ENCODING"
+    | "OBJECT FREQ"
+    | "OBJECT IDLETIME"
+    | "OBJECT REFCOUNT"
+    | "PERSIST"
+    | "PEXPIRE"
+    | "PEXPIREAT"
+    | "PEXPIRETIME"
+    | "PFADD"
+    | "PSETEX"
+    | "PTTL"
+    | "RESTORE"
+    | "RPOP"
+    | "RPUSH"
+    | "RPUSHX"
+    | "SADD"
+    | "SCARD"
+    | "SET"
+    | "SETBIT"
+    | "SETEX"
+    | "SETNX"
+    | "SETRANGE"
+    | "SISMEMBER"
+    | "SMEMBERS"
+    | "SMISMEMBER"
+    | "SPOP"
+    | "SRANDMEMBER"
+    | "SREM"
+    | "SSCAN"
+    | "STRLEN"
+    | "SUBSTR"
+    | "TTL"
+    | "TYPE"
+    | "XACK"
+    | "XADD"
+    | "XAUTOCLAIM"
+    | "XCLAIM"
+    | "XDEL"
+    | "XGROUP CREATE"
+    | "XGROUP CREATECONSUMER"
+    | "XGROUP DELCONSUMER"
+    | "XGROUP DESTROY"
+    | "XGROUP SETID"
+    | "XINFO CONSUMERS"
+    | "XINFO GROUPS"
+    | "XINFO STREAM"
+    | "XLEN"
+    | "XPENDING"
+    | "XRANGE"
+    | "XREVRANGE"
+    | "XSETID"
+    | "XTRIM"
+    | "ZADD"
+    | "ZCARD"
+    | "ZCOUNT"
+    | "ZINCRBY"
+    | "ZLEXCOUNT"
+    | "ZMSCORE"
+    | "ZPOPMAX"
+    | "ZPOPMIN"
+    | "ZRANDMEMBER"
+    | "ZRANGE"
+    | "ZRANGEBYLEX"
+    | "ZRANGEBYSCORE"
+    | "ZRANK"
+    | "ZREM"
+    | "ZREMRANGEBYLEX"
+    | "ZREMRANGEBYRANK"
+    | "ZREMRANGEBYSCORE"
+    | "ZREVRANGE"
+    | "ZREVRANGEBYLEX"
+    | "ZREVRANGEBYSCORE"
+    | "ZREVRANK"
+    | "ZSCAN"
+    | "ZSCORE"
+    | "JSON.ARRAPPEND"
+    | "JSON.ARRINDEX"
+    | "JSON.ARRINSERT"
+    | "JSON.ARRLEN"
+    | "JSON.ARRPOP"
+    | "JSON.ARRTRIM"
+    | "JSON.CLEAR"
+    | "JSON.DEBUG MEMORY"
+    | "JSON.DEL"
+    | "JSON.FORGET"
+    | "JSON.GET"
+    | "JSON.MERGE"
+    | "JSON.NUMINCRBY"
+    | "JSON.NUMMULTBY"
+    | "JSON.OBJKEYS"
+    | "JSON.OBJLEN"
+    | "JSON.RESP"
+    | "JSON.SET"
+    | "JSON.STRAPPEND"
+    | "JSON.STRLEN"
+    | "JSON.TOGGLE"
+    | "JSON.TYPE" => single(0),
+    "PFDEBUG" => single(1),
+    "DEL" | "EXISTS" | "MGET" | "PFCOUNT" | "PFMERGE" | "SDIFF" | "SDIFFSTORE" | "SINTER" | "SINTERSTORE"
+    | "SUNION" | "SUNIONSTORE" | "TOUCH" | "UNLINK" | "WATCH" => all(0),
+    "BITOP" => all(1),
+    "BLMOVE" | "BRPOPLPUSH" | "COPY" | "GEOSEARCHSTORE" | "LCS" | "LMOVE" | "RENAME" | "RENAMENX" | "RPOPLPUSH"
+    | "SMOVE" | "ZRANGESTORE" => KeySpec::Range {
+      first: 0,
+      last:  1,
+      step:  1,
+    },
+    "BLPOP" | "BRPOP" | "BZPOPMAX" | "BZPOPMIN" | "JSON.MGET" => KeySpec::Range {
+      first: 0,
+      last:  -2,
+      step:  1,
+    },
+    "MSET" | "MSETNX" => KeySpec::Range {
+      first: 0,
+      last:  -1,
+      step:  2,
+    },
+    "JSON.MSET" => KeySpec::Range {
+      first: 0,
+      last:  -1,
+      step:  3,
+    },
+    "LMPOP" | "SINTERCARD" | "ZDIFF" | "ZINTER" | "ZINTERCARD" | "ZMPOP" | "ZUNION" => KeySpec::NumKeys(0),
+    "BLMPOP" | "BZMPOP" | "EVAL" | "EVAL_RO" | "EVALSHA" | "EVALSHA_RO" | "FCALL" | "FCALL_RO" => KeySpec::NumKeys(1),
+    "ZDIFFSTORE" | "ZINTERSTORE" | "ZUNIONSTORE" => KeySpec::DestinationNumKeys,
+    "XREAD" | "XREADGROUP" => KeySpec::Streams,
+    "GEORADIUS" | "GEORADIUSBYMEMBER" | "SORT" | "SORT_RO" => KeySpec::Store,
+    "MIGRATE" => KeySpec::Migrate,
+    _ => return None,
+  };
+
+  Some(spec)
+}
+
+/// Whether the command name is a container command, such as `OBJECT`, where the subcommand is the first argument.
+fn is_container(name: &str) -> bool {
+  matches!(name, "JSON.DEBUG" | "MEMORY" | "OBJECT" | "XGROUP" | "XINFO")
+}
+
+fn is_keyword(arg: &RedisValue, keyword: &[u8]) -> bool {
+  arg.as_bytes().map(|b| b.eq_ignore_ascii_case(keyword)).unwrap_or(false)
+}
+
+impl KeySpec {
+  fn indexes(&self, args: &[RedisValue]) -> Vec<usize> {
+    let len = args.len();
+    let numkeys = |idx: usize| {
+      let count = args.get(idx).and_then(|v| v.as_usize()).unwrap_or(0);
+      (idx + 1 .. cmp::min(idx + 1 + count, len)).collect::<Vec<_>>()
+    };
+
+    match *self {
+      KeySpec::Range { first, last, step } => {
+        let last = if last < 0 {
+          len as isize + last
+        } else {
+          cmp::min(last, len as isize - 1)
+        };
+        if last < first as isize {
+          return Vec::new();
+        }
+
+        (first ..= last as usize).step_by(step).collect()
+      },
+      KeySpec::NumKeys(idx) => numkeys(idx),
+      KeySpec::DestinationNumKeys => {
+        let mut indexes = numkeys(1);
+        if len > 0 {
+          indexes.insert(0, 0);
+        }
+        indexes
+      },
+      KeySpec::Streams => match args.iter().position(|arg| is_keyword(arg, b"STREAMS")) {
+        Some(idx) => {
+          let count = (len - idx - 1) / 2;
+          (idx + 1 .. idx + 1 + count).collect()
+        },
+        None => Vec::new(),
+      },
+      KeySpec::Store => {
+        let mut indexes = if len > 0 { vec![0] } else { Vec::new() };
+        for (idx, arg) in args.iter().enumerate() {
+          if (is_keyword(arg, b"STORE") || is_keyword(arg, b"STOREDIST")) && idx + 1 < len {
+            indexes.push(idx + 1);
+          }
+        }
+        indexes
+      },
+      KeySpec::Migrate => {
+        let has_key = args
+          .get(2)
+          .and_then(|arg| arg.as_bytes())
+          .map(|b| !b.is_empty())
+          .unwrap_or(false);
+        if has_key {
+          return vec![2];
+        }
+
+        match args.iter().skip(5).position(|arg| is_keyword(arg, b"KEYS")) {
+          Some(idx) => (idx + 6 .. len).collect(),
+          None => Vec::new(),
+        }
+      },
+    }
+  }
+}
+
+/// Read the indexes of the key arguments for the command with the provided name, or `None` if the command does not
+/// have a known key layout.
+///
+/// The name may include a subcommand, such as `XINFO STREAM`. If the name is a container command without a
+/// subcommand, such as `OBJECT`, the first argument is used as the subcommand.
+pub fn key_indexes(name: &str, args: &[RedisValue]) -> Option<Vec<usize>> {
+  if let Some(spec) = key_spec(name) {
+    return Some(spec.indexes(args));
+  }
+  if !is_container(name) {
+    return None;
+  }
+
+  let subcommand = match args.first().and_then(|arg| arg.as_str()) {
+    Some(subcommand) => subcommand.to_uppercase(),
+    None => return Some(Vec::new()),
+  };
+  let indexes = match key_spec(&format!("{} {}", name, subcommand)) {
+    Some(spec) => spec.indexes(&args[1 ..]).into_iter().map(|idx| idx + 1).collect(),
+    None => Vec::new(),
+  };
+  Some(indexes)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The commands with key arguments, from the `COMMAND INFO` output of Redis 7.4 and RedisJSON 2.6.
+  const KEY_COMMANDS: &[&str] = &[
+    "APPEND",
+    "BITCOUNT",
+    "BITFIELD",
+    "BITFIELD_RO",
+    "BITOP",
+    "BITPOS",
+    "BLMOVE",
+    "BLMPOP",
+    "BLPOP",
+    "BRPOP",
+    "BRPOPLPUSH",
+    "BZMPOP",
+    "BZPOPMAX",
+    "BZPOPMIN",
+    "COPY",
+    "DECR",
+    "DECRBY",
+    "DEL",
+    "DUMP",
+    "EVAL",
+    "EVALSHA",
+    "EVALSHA_RO",
+    "EVAL_RO",
+    "EXISTS",
+    "EXPIRE",
+    "EXPIREAT",
+    "EXPIRETIME",
+    "FCALL",
+    "FCALL_RO",
+    "GEOADD",
+    "GEODIST",
+    "GEOHASH",
+    "GEOPOS",
+    "GEORADIUS",
+    "GEORADIUSBYMEMBER",
+    "GEORADIUSBYMEMBER_RO",
+    "GEORADIUS_RO",
+    "GEOSEARCH",
+    "GEOSEARCHSTORE",
+    "GET",
+    "GETBIT",
+    "GETDEL",
+    "GETEX",
+    "GETRANGE",
+    "GETSET",
+    "HDEL",
+    "HEXISTS",
+    "HEXPIRE",
+    "HEXPIREAT",
+    "HEXPIRETIME",
+    "HGET",
+    "HGETALL",
+    "HINCRBY",
+    "HINCRBYFLOAT",
+    "HKEYS",
+    "HLEN",
+    "HMGET",
+    "HMSET",
+    "HPERSIST",
+    "HPEXPIRE",
+    "HPEXPIREAT",
+    "HPEXPIRETIME",
+    "HPTTL",
+    "HRANDFIELD",
+    "HSCAN",
+    "HSET",
+    "HSETNX",
+    "HSTRLEN",
+    "HTTL",
+    "HVALS",
+    "INCR",
+    "INCRBY",
+    "INCRBYFLOAT",
+    "LCS",
+    "LINDEX",
+    "LINSERT",
+    "LLEN",
+    "LMOVE",
+    "LMPOP",
+    "LPOP",
+    "LPOS",
+    "LPUSH",
+    "LPUSHX",
+    "LRANGE",
+    "LREM",
+    "LSET",
+    "LTRIM",
+    "MEMORY USAGE",
+    "MGET",
+    "MIGRATE",
+    "MOVE",
+    "MSET",
+    "MSETNX",
+    "OBJECT ENCODING",
+    "OBJECT FREQ",
+    "OBJECT IDLETIME",
+    "OBJECT REFCOUNT",
+    "PERSIST",
+    "PEXPIRE",
+    "PEXPIREAT",
+    "PEXPIRETIME",
+    "PFADD",
+    "PFCOUNT",
+    "PFDEBUG",
+    "PFMERGE",
+    "PSETEX",
+    "PTTL",
+    "RENAME",
+    "RENAMENX",
+    "RESTORE",
+    "RPOP",
+    "RPOPLPUSH",
+    "RPUSH",
+    "RPUSHX",
+    "SADD",
+    "SCARD",
+    "SDIFF",
+    "SDIFFSTORE",
+    "SET",
+    "SETBIT",
+    "SETEX",
+    "SETNX",
+    "SETRANGE",
+    "SINTER",
+    "SINTERCARD",
+    "SINTERSTORE",
+    "SISMEMBER",
+    "SMEMBERS",
+    "SMISMEMBER",
+    "SMOVE",
+    "SORT",
+    "SORT_RO",
+    "SPOP",
+    "SRANDMEMBER",
+    "SREM",
+    "SSCAN",
+    "STRLEN",
+    "SUBSTR",
+    "SUNION",
+    "SUNIONSTORE",
+    "TOUCH",
+    "TTL",
+    "TYPE",
+    "UNLINK",
+    "WATCH",
+    "XACK",
+    "XADD",
+    "XAUTOCLAIM",
+    "XCLAIM",
+    "XDEL",
+    "XGROUP CREATE",
+    "XGROUP CREATECONSUMER",
+    "XGROUP DELCONSUMER",
+    "XGROUP DESTROY",
+    "XGROUP SETID",
+    "XINFO CONSUMERS",
+    "XINFO GROUPS",
+    "XINFO STREAM",
+    "XLEN",
+    "XPENDING",
+    "XRANGE",
+    "XREAD",
+    "XREADGROUP",
+    "XREVRANGE",
+    "XSETID",
+    "XTRIM",
+    "ZADD",
+    "ZCARD",
+    "ZCOUNT",
+    "ZDIFF",
+    "ZDIFFSTORE",
+    "ZINCRBY",
+    "ZINTER",
+    "ZINTERCARD",
+    "ZINTERSTORE",
+    "ZLEXCOUNT",
+    "ZMPOP",
+    "ZMSCORE",
+    "ZPOPMAX",
+    "ZPOPMIN",
+    "ZRANDMEMBER",
+    "ZRANGE",
+    "ZRANGEBYLEX",
+    "ZRANGEBYSCORE",
+    "ZRANGESTORE",
+    "ZRANK",
+    "ZREM",
+    "ZREMRANGEBYLEX",
+    "ZREMRANGEBYRANK",
+    "ZREMRANGEBYSCORE",
+    "ZREVRANGE",
+    "ZREVRANGEBYLEX",
+    "ZREVRANGEBYSCORE",
+    "ZREVRANK",
+    "ZSCAN",
+    "ZSCORE",
+    "ZUNION",
+    "ZUNIONSTORE",
+    "JSON.ARRAPPEND",
+    "JSON.ARRINDEX",
+    "JSON.ARRINSERT",
+    "JSON.ARRLEN",
+    "JSON.ARRPOP",
+    "JSON.ARRTRIM",
+    "JSON.CLEAR",
+    "JSON.DEBUG MEMORY",
+    "JSON.DEL",
+    "JSON.FORGET",
+    "JSON.GET",
+    "JSON.MERGE",
+    "JSON.MGET",
+    "JSON.MSET",
+    "JSON.NUMINCRBY",
+    "JSON.NUMMULTBY",
+    "JSON.OBJKEYS",
+    "JSON.OBJLEN",
+    "JSON.RESP",
+    "JSON.SET",
+    "JSON.STRAPPEND",
+    "JSON.STRLEN",
+    "JSON.TOGGLE",
+    "JSON.TYPE",
+  ];
+
+  fn args(values: &[&str]) -> Vec<RedisValue> {
+    values.iter().map(|v| RedisValue::from(*v)).collect()
+  }
+
+  fn keys(name: &str, args: &[RedisValue]) -> Vec<usize> {
+    key_indexes(name, args).unwrap_or_default()
+  }
+
+  #[test]
+  fn should_have_key_spec_for_each_key_command() {
+    let missing: Vec<&str> = KEY_COMMANDS
+      .iter()
+      .filter(|name| key_spec(name).is_none())
+      .copied()
+      .collect();
+    assert!(missing.is_empty(), "Missing key specs: {:?}", missing);
+  }
+
+  #[test]
+  fn should_read_range_key_indexes() {
+    assert_eq!(keys("GET", &args(&["foo"])), vec![0]);
+    assert_eq!(keys("DEL", &args(&["a", "b", "c"])), vec![0, 1, 2]);
+    assert_eq!(keys("MSET", &args(&["a", "1", "b", "2"])), vec![0, 2]);
+    assert_eq!(keys("BLPOP", &args(&["a", "b", "0"])), vec![0, 1]);
+    assert_eq!(keys("RENAME", &args(&["a", "b"])), vec![0, 1]);
+    assert_eq!(keys("BITOP", &args(&["AND", "dest", "a", "b"])), vec![1, 2, 3]);
+    assert_eq!(keys("GET", &[]), Vec::<usize>::new());
+    assert_eq!(key_indexes("PING", &args(&["foo"])), None);
+  }
+
+  #[test]
+  fn should_read_numkeys_key_indexes() {
+    assert_eq!(keys("SINTERCARD", &args(&["2", "a", "b", "LIMIT", "1"])), vec![1, 2]);
+    assert_eq!(keys("EVAL", &args(&["return 1", "1", "a", "b"])), vec![2]);
+    assert_eq!(keys("ZUNIONSTORE", &args(&["dest", "2", "a", "b"])), vec![0, 2, 3]);
+  }
+
+  #[test]
+  fn should_read_subcommand_key_indexes() {
+    assert_eq!(keys("OBJECT ENCODING", &args(&["foo"])), vec![0]);
+    assert_eq!(keys("OBJECT", &args(&["encoding", "foo"])), vec![1]);
+    assert_eq!(keys("XINFO", &args(&["STREAM", "foo", "FULL"])), vec![1]);
+    assert_eq!(keys("OBJECT", &args(&["HELP"])), Vec::<usize>::new());
+    assert_eq!(keys("MEMORY", &args(&["STATS"])), Vec::<usize>::new());
+  }
+
+  #[test]
+  fn should_read_keyword_key_indexes() {
+    assert_eq!(
+      keys("XREAD", &args(&["COUNT", "1", "STREAMS", "a", "b", "0", "0"])),
+      vec![3, 4]
+    );
+    assert_eq!(keys("SORT", &args(&["foo", "STORE", "bar"])), vec![0, 2]);
+    assert_eq!(keys("MIGRATE", &args(&["host", "6379", "foo", "0", "10"])), vec![2]);
+    assert_eq!(
+      keys(
+        "MIGRATE",
+        &args(&["host", "6379", "", "0", "10", "COPY", "KEYS", "a", "b"])
+      ),
+      vec![7, 8]
+    );
+  }
+}
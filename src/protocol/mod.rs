@@ -2,9 +2,11 @@ pub mod cluster;
 pub mod codec;
 pub mod command;
 pub mod connection;
+pub mod cross_slot;
 #[cfg(feature = "network-logs")]
 pub mod debug;
 pub mod hashers;
+pub mod key_specs;
 pub mod responders;
 /// TLS configuration types.
 #[cfg(any(feature = "enable-rustls", feature = "enable-native-tls"))]
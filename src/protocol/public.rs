@@ -1,5 +1,8 @@
-use crate::error::{RedisError, RedisErrorKind};
-use bytes::BytesMut;
+use crate::{
+  error::{RedisError, RedisErrorKind},
+  utils,
+};
+use bytes::{Bytes, BytesMut};
 use redis_protocol::{
   resp2::{decode::decode_mut as resp2_decode, encode::encode_bytes as resp2_encode},
   resp3::{
@@ -208,3 +211,194 @@ impl Decoder for Resp3 {
     Ok(result)
   }
 }
+
+/// Convert a RESP3 frame to the RESP2 frame that Redis would send to a RESP2 client.
+///
+/// Maps and sets are flattened into arrays, push frames become arrays, booleans become integers, and doubles, big
+/// numbers, and verbatim strings become bulk strings.
+pub fn resp3_frame_to_resp2(frame: Resp3Frame) -> Result<Resp2Frame, RedisError> {
+  let frame = match frame {
+    Resp3Frame::Null => Resp2Frame::Null,
+    Resp3Frame::SimpleString { data, .. } => Resp2Frame::SimpleString(data),
+    Resp3Frame::SimpleError { data, .. } => Resp2Frame::Error(data),
+    Resp3Frame::BlobError { data, .. } => Resp2Frame::Error(String::from_utf8_lossy(&data).into_owned().into()),
+    Resp3Frame::BlobString { data, .. }
+    | Resp3Frame::BigNumber { data, .. }
+    | Resp3Frame::VerbatimString { data, .. } => Resp2Frame::BulkString(data),
+    Resp3Frame::Number { data, .. } => Resp2Frame::Integer(data),
+    Resp3Frame::Boolean { data, .. } => Resp2Frame::Integer(data as i64),
+    Resp3Frame::Double { data, .. } => match utils::f64_to_redis_string(data)?.into_string() {
+      Some(s) => Resp2Frame::BulkString(s.into()),
+      None => return Err(RedisError::new(RedisErrorKind::Protocol, "Invalid double.")),
+    },
+    Resp3Frame::Array { data, .. } | Resp3Frame::Push { data, .. } => Resp2Frame::Array(
+      data
+        .into_iter()
+        .map(resp3_frame_to_resp2)
+        .collect::<Result<Vec<_>, _>>()?,
+    ),
+    Resp3Frame::Set { data, .. } => Resp2Frame::Array(
+      data
+        .into_iter()
+        .map(resp3_frame_to_resp2)
+        .collect::<Result<Vec<_>, _>>()?,
+    ),
+    Resp3Frame::Map { data, .. } => {
+      let mut out = Vec::with_capacity(data.len() * 2);
+      for (key, value) in data.into_iter() {
+        out.push(resp3_frame_to_resp2(key)?);
+        out.push(resp3_frame_to_resp2(value)?);
+      }
+      Resp2Frame::Array(out)
+    },
+    Resp3Frame::Hello { .. } | Resp3Frame::ChunkedString(_) => {
+      return Err(RedisError::new(
+        RedisErrorKind::Protocol,
+        "Cannot convert HELLO or chunked string frames to RESP2.",
+      ))
+    },
+  };
+
+  Ok(frame)
+}
+
+/// Read the arguments from a command frame sent by a client.
+fn command_frame_to_args(frame: Resp2Frame) -> Result<Vec<Bytes>, RedisError> {
+  let frames = match frame {
+    Resp2Frame::Array(frames) => frames,
+    _ => return Err(RedisError::new(RedisErrorKind::Protocol, "Expected command array.")),
+  };
+
+  frames
+    .into_iter()
+    .map(|frame| match frame {
+      Resp2Frame::BulkString(data) | Resp2Frame::SimpleString(data) => Ok(data),
+      Resp2Frame::Integer(i) => Ok(i.to_string().into()),
+      _ => Err(RedisError::new(
+        RedisErrorKind::Protocol,
+        "Expected bulk string command argument.",
+      )),
+    })
+    .collect()
+}
+
+/// A framed server-side codec that decodes commands and encodes RESP2 or RESP3 replies.
+///
+/// Commands are decoded into their arguments, including the command name. Both multi-bulk commands (the format used
+/// by client libraries) and inline commands (`HELLO 3`, `PING`, etc) are supported. Replies are written as RESP3
+/// frames and converted to RESP2 if the connection uses RESP2. Callers should switch versions via
+/// [set_version](Self::set_version) when the client sends `HELLO`.
+///
+/// ```rust
+/// use fred::{
+///   codec::{Resp3Frame, RespServer},
+///   prelude::*,
+/// };
+/// use futures::{SinkExt, StreamExt};
+/// use tokio::net::TcpListener;
+/// use tokio_util::codec::Framed;
+///
+/// // respond to every command with `OK`
+/// async fn example() -> Result<(), RedisError> {
+///   let listener = TcpListener::bind("127.0.0.1:6379").await?;
+///   let (socket, _) = listener.accept().await?;
+///   let mut framed = Framed::new(socket, RespServer::default());
+///
+///   while let Some(args) = framed.next().await {
+///     println!("Recv command: {:?}", args?);
+///     framed
+///       .send(Resp3Frame::SimpleString {
+///         data:       "OK".into(),
+///         attributes: None,
+///       })
+///       .await?;
+///   }
+///   Ok(())
+/// }
+/// ```
+pub struct RespServer {
+  version: RespVersion,
+}
+
+impl Default for RespServer {
+  fn default() -> Self {
+    RespServer {
+      version: RespVersion::RESP2,
+    }
+  }
+}
+
+impl RespServer {
+  /// Create a new codec that writes replies with the provided protocol version.
+  pub fn new(version: RespVersion) -> Self {
+    RespServer { version }
+  }
+
+  /// Read the protocol version used to encode replies.
+  pub fn version(&self) -> &RespVersion {
+    &self.version
+  }
+
+  /// Change the protocol version used to encode replies.
+  pub fn set_version(&mut self, version: RespVersion) {
+    self.version = version;
+  }
+}
+
+impl Encoder<Resp3Frame> for RespServer {
+  type Error = RedisError;
+
+  fn encode(&mut self, item: Resp3Frame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+    #[cfg(feature = "network-logs")]
+    trace!("Server codec encode: {:?}", item);
+
+    if self.version == RespVersion::RESP3 {
+      resp3_encode(dst, &item).map(|_| ()).map_err(RedisError::from)
+    } else {
+      let frame = resp3_frame_to_resp2(item)?;
+      resp2_encode(dst, &frame).map(|_| ()).map_err(RedisError::from)
+    }
+  }
+}
+
+impl Decoder for RespServer {
+  type Error = RedisError;
+  type Item = Vec<Bytes>;
+
+  fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+    loop {
+      if src.is_empty() {
+        return Ok(None);
+      }
+
+      if src[0] == b'*' {
+        let parsed = match resp2_decode(src)? {
+          Some((frame, _, _)) => command_frame_to_args(frame)?,
+          None => return Ok(None),
+        };
+        #[cfg(feature = "network-logs")]
+        trace!("Server codec decode: {:?}", parsed);
+
+        return Ok(Some(parsed));
+      } else {
+        let end = match src.iter().position(|b| *b == b'\n') {
+          Some(idx) => idx,
+          None => return Ok(None),
+        };
+        let line = src.split_to(end + 1).freeze();
+        let parsed: Vec<Bytes> = line
+          .split(|b| b.is_ascii_whitespace())
+          .filter(|part| !part.is_empty())
+          .map(|part| line.slice_ref(part))
+          .collect();
+
+        if !parsed.is_empty() {
+          #[cfg(feature = "network-logs")]
+          trace!("Server codec decode: {:?}", parsed);
+
+          return Ok(Some(parsed));
+        }
+      }
+    }
+  }
+}
@@ -3,7 +3,7 @@ use crate::{
   interfaces::Resp3Frame,
   modules::inner::RedisClientInner,
   protocol::{
-    command::{RedisCommand, RedisCommandKind, ResponseSender, RouterResponse},
+    command::{RedisCommand, RedisCommandKind, ResponseSender, ResponseStreamSender, RouterResponse},
     types::{KeyScanInner, Server, ValueScanInner, ValueScanResult},
     utils as protocol_utils,
   },
@@ -20,6 +20,7 @@ use std::{
   ops::DerefMut,
   sync::{atomic::AtomicUsize, Arc},
 };
+use tokio::sync::mpsc::error::TrySendError;
 
 #[cfg(feature = "metrics")]
 use crate::modules::metrics::MovingStats;
@@ -78,6 +79,16 @@ pub enum ResponseKind {
   ValueScan(ValueScanInner),
   /// Handle the response as a page of keys from a SCAN command.
   KeyScan(KeyScanInner),
+  /// Send each element of the response to the caller as a separate message.
+  ///
+  /// The codec will switch this to `StreamElements` when it finds an aggregate response, and other responses are
+  /// split into elements here.
+  Stream(ResponseStreamSender),
+  /// Send each frame read from the socket to the caller as a separate element.
+  ///
+  /// The codec emits the elements of an aggregate response as separate frames, and then switches the command to
+  /// `Skip` before emitting an empty array frame to finish the command.
+  StreamElements(ResponseStreamSender),
 }
 
 impl fmt::Debug for ResponseKind {
@@ -89,6 +100,8 @@ impl fmt::Debug for ResponseKind {
       ResponseKind::Respond(_) => "Respond",
       ResponseKind::KeyScan(_) => "KeyScan",
       ResponseKind::ValueScan(_) => "ValueScan",
+      ResponseKind::Stream(_) => "Stream",
+      ResponseKind::StreamElements(_) => "StreamElements",
     })
   }
 }
@@ -122,7 +135,10 @@ impl ResponseKind {
         tx:       tx.clone(),
         expected: *expected,
       },
-      ResponseKind::KeyScan(_) | ResponseKind::ValueScan(_) => return None,
+      ResponseKind::KeyScan(_)
+      | ResponseKind::ValueScan(_)
+      | ResponseKind::Stream(_)
+      | ResponseKind::StreamElements(_) => return None,
     })
   }
 
@@ -193,7 +209,9 @@ impl ResponseKind {
 
   /// Respond with an error to the caller.
   pub fn respond_with_error(&mut self, error: RedisError) {
-    if let Some(tx) = self.take_response_tx() {
+    if let ResponseKind::Stream(ref tx) | ResponseKind::StreamElements(ref tx) = self {
+      send_stream_result(tx, Err(error));
+    } else if let Some(tx) = self.take_response_tx() {
       let _ = tx.send(Err(error));
     }
   }
@@ -204,7 +222,10 @@ impl ResponseKind {
       ResponseKind::Skip | ResponseKind::Respond(_) => 1,
       ResponseKind::Multiple { ref expected, .. } => *expected,
       ResponseKind::Buffer { ref expected, .. } => *expected,
-      ResponseKind::ValueScan(_) | ResponseKind::KeyScan(_) => 1,
+      ResponseKind::ValueScan(_)
+      | ResponseKind::KeyScan(_)
+      | ResponseKind::Stream(_)
+      | ResponseKind::StreamElements(_) => 1,
     }
   }
 }
@@ -239,7 +260,7 @@ fn update_protocol_version(inner: &Arc<RedisClientInner>, command: &RedisCommand
 
     _debug!(inner, "Changing RESP version to {:?}", version);
     // HELLO cannot be pipelined so this is safe
-    inner.switch_protocol_versions(version.clone());
+    inner.record_protocol_version(version.clone());
   }
 }
 
@@ -478,12 +499,106 @@ pub fn respond_to_caller(
   if command.kind.is_hello() {
     update_protocol_version(inner, &command, &frame);
   }
+  let frame = strip_keys_prefix(inner, &command, frame);
 
   let _ = tx.send(Ok(frame));
   command.respond_to_router(inner, RouterResponse::Continue);
   Ok(())
 }
 
+/// Remove the key prefix from the keys in a `KEYS` response.
+pub fn strip_keys_prefix(inner: &Arc<RedisClientInner>, command: &RedisCommand, frame: Resp3Frame) -> Resp3Frame {
+  let prefix = match inner.config.key_prefix {
+    Some(ref prefix) if !prefix.is_empty() && command.kind == RedisCommandKind::Keys => prefix.as_bytes(),
+    _ => return frame,
+  };
+
+  match frame {
+    Resp3Frame::Array { data, attributes } => Resp3Frame::Array {
+      data: data
+        .into_iter()
+        .map(|frame| match frame {
+          Resp3Frame::BlobString { data, attributes } if data.starts_with(prefix) => Resp3Frame::BlobString {
+            data: data.slice(prefix.len() ..),
+            attributes,
+          },
+          frame => frame,
+        })
+        .collect(),
+      attributes,
+    },
+    frame => frame,
+  }
+}
+
+/// Split an aggregate response frame into elements, or treat any other frame as a single element.
+///
+/// Map responses are split into a flat sequence of keys and values.
+fn stream_elements(frame: Resp3Frame) -> Vec<Resp3Frame> {
+  match frame {
+    Resp3Frame::Null => Vec::new(),
+    Resp3Frame::Array { data, .. } | Resp3Frame::Push { data, .. } => data,
+    Resp3Frame::Set { data, .. } => data.into_iter().collect(),
+    Resp3Frame::Map { data, .. } => data.into_iter().flat_map(|(key, value)| [key, value]).collect(),
+    frame => vec![frame],
+  }
+}
+
+/// Send the elements of the response to the caller without waiting.
+///
+/// If the response stream is full the remaining elements are sent from a separate task.
+pub fn send_stream_result(tx: &ResponseStreamSender, result: Result<Resp3Frame, RedisError>) {
+  let elements: Vec<Result<Resp3Frame, RedisError>> = match result {
+    Ok(frame) => stream_elements(frame).into_iter().map(Ok).collect(),
+    Err(error) => vec![Err(error)],
+  };
+  let mut elements = elements.into_iter();
+
+  while let Some(element) = elements.next() {
+    match tx.try_send(element) {
+      Ok(_) => continue,
+      Err(TrySendError::Full(element)) => {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+          for element in Some(element).into_iter().chain(elements) {
+            if tx.send(element).await.is_err() {
+              break;
+            }
+          }
+        });
+        return;
+      },
+      Err(TrySendError::Closed(_)) => return,
+    }
+  }
+}
+
+/// Send the elements of the response to the caller, closing the response stream.
+pub async fn respond_stream(
+  inner: &Arc<RedisClientInner>,
+  server: &Server,
+  mut command: RedisCommand,
+  tx: ResponseStreamSender,
+  frame: Resp3Frame,
+) -> Result<(), RedisError> {
+  sample_command_latencies(inner, &mut command);
+  _trace!(
+    inner,
+    "Respond to stream from {} for {} with {:?}",
+    server,
+    command.kind.to_str_debug(),
+    frame.kind()
+  );
+
+  for element in stream_elements(frame).into_iter() {
+    if tx.send(Ok(element)).await.is_err() {
+      break;
+    }
+  }
+  command.respond_to_router(inner, RouterResponse::Continue);
+  Ok(())
+}
+
 /// Respond to the caller, assuming multiple response frames from the last command.
 ///
 /// This interface may return the last command to be put back at the front of the shared buffer if more responses are
@@ -569,6 +684,7 @@ pub fn respond_buffer(
   );
 
   // errors are buffered like normal frames and are not returned early
+  let frame = strip_keys_prefix(inner, &command, frame);
   if let Err(e) = add_buffered_frame(server, &frames, index, frame) {
     respond_locked(inner, &tx, Err(e));
     command.respond_to_router(inner, RouterResponse::Continue);
@@ -648,6 +764,13 @@ pub fn respond_key_scan(
       return Ok(());
     },
   };
+  let keys = match inner.config.key_prefix {
+    Some(ref prefix) => keys
+      .into_iter()
+      .map(|key| client_utils::strip_key_prefix(Some(prefix), key))
+      .collect(),
+    None => keys,
+  };
   let scan_stream = scanner.tx.clone();
   let can_continue = next_cursor != LAST_CURSOR;
   scanner.update_cursor(next_cursor);
@@ -388,6 +388,11 @@ pub fn check_null_timeout(frame: &Resp3Frame) -> Result<(), RedisError> {
 
 /// Parse the protocol frame into a redis value, with support for arbitrarily nested arrays.
 pub fn frame_to_results(frame: Resp3Frame) -> Result<RedisValue, RedisError> {
+  #[cfg(feature = "resp3-types")]
+  let mut frame = frame;
+  #[cfg(feature = "resp3-types")]
+  let attributes = frame.take_attributes();
+
   let value = match frame {
     Resp3Frame::Null => RedisValue::Null,
     Resp3Frame::SimpleString { data, .. } => {
@@ -405,9 +410,18 @@ pub fn frame_to_results(frame: Resp3Frame) -> Result<RedisValue, RedisError> {
       let parsed = String::from_utf8_lossy(&data);
       return Err(pretty_error(parsed.as_ref()));
     },
+    #[cfg(feature = "resp3-types")]
+    Resp3Frame::VerbatimString { data, format, .. } => RedisValue::VerbatimString { format, data },
+    #[cfg(not(feature = "resp3-types"))]
     Resp3Frame::VerbatimString { data, .. } => string_or_bytes(data),
     Resp3Frame::Number { data, .. } => data.into(),
     Resp3Frame::Double { data, .. } => data.into(),
+    #[cfg(feature = "resp3-types")]
+    Resp3Frame::BigNumber { data, .. } => match string_or_bytes(data) {
+      RedisValue::String(s) => RedisValue::BigNumber(s),
+      value => value,
+    },
+    #[cfg(not(feature = "resp3-types"))]
     Resp3Frame::BigNumber { data, .. } => string_or_bytes(data),
     Resp3Frame::Boolean { data, .. } => data.into(),
     Resp3Frame::Array { data, .. } | Resp3Frame::Push { data, .. } => RedisValue::Array(
@@ -416,6 +430,14 @@ pub fn frame_to_results(frame: Resp3Frame) -> Result<RedisValue, RedisError> {
         .map(frame_to_results)
         .collect::<Result<Vec<RedisValue>, _>>()?,
     ),
+    #[cfg(feature = "resp3-types")]
+    Resp3Frame::Set { data, .. } => RedisValue::Set(
+      data
+        .into_iter()
+        .map(frame_to_results)
+        .collect::<Result<Vec<RedisValue>, _>>()?,
+    ),
+    #[cfg(not(feature = "resp3-types"))]
     Resp3Frame::Set { data, .. } => RedisValue::Array(
       data
         .into_iter()
@@ -441,6 +463,13 @@ pub fn frame_to_results(frame: Resp3Frame) -> Result<RedisValue, RedisError> {
     },
   };
 
+  #[cfg(feature = "resp3-types")]
+  if let Some(attributes) = attributes {
+    return Ok(RedisValue::Attributed {
+      value:      Box::new(value),
+      attributes: parse_nested_map(attributes)?,
+    });
+  }
   Ok(value)
 }
 
@@ -499,6 +528,45 @@ pub fn flatten_frame(frame: Resp3Frame) -> Resp3Frame {
   }
 }
 
+/// Whether the frame is a scalar value without any attributes.
+pub fn frame_is_scalar(frame: &Resp3Frame) -> bool {
+  matches!(
+    frame,
+    Resp3Frame::Null
+      | Resp3Frame::BlobString { attributes: None, .. }
+      | Resp3Frame::SimpleString { attributes: None, .. }
+      | Resp3Frame::Number { attributes: None, .. }
+      | Resp3Frame::Double { attributes: None, .. }
+      | Resp3Frame::Boolean { attributes: None, .. }
+  )
+}
+
+/// Convert an array frame with an even number of elements to a map frame, leaving any other frames unchanged.
+pub fn array_frame_to_map_frame(frame: Resp3Frame) -> Result<Resp3Frame, RedisError> {
+  match frame {
+    Resp3Frame::Array { data, attributes } => {
+      if data.len() % 2 != 0 {
+        return Err(RedisError::new(
+          RedisErrorKind::Protocol,
+          "Expected an even number of frames.",
+        ));
+      }
+
+      let mut inner = FrameMap::with_capacity(data.len() / 2);
+      let mut data = data.into_iter();
+      while let (Some(key), Some(value)) = (data.next(), data.next()) {
+        inner.insert(key, value);
+      }
+
+      Ok(Resp3Frame::Map {
+        data: inner,
+        attributes,
+      })
+    },
+    _ => Ok(frame),
+  }
+}
+
 /// Convert a frame to a nested RedisMap.
 pub fn frame_to_map(frame: Resp3Frame) -> Result<RedisMap, RedisError> {
   match frame {
@@ -557,6 +625,8 @@ pub fn value_to_outgoing_resp2_frame(value: &RedisValue) -> Result<Resp2Frame, R
     RedisValue::Bytes(ref b) => Resp2Frame::BulkString(b.clone()),
     RedisValue::Queued => Resp2Frame::BulkString(Bytes::from_static(QUEUED.as_bytes())),
     RedisValue::Null => Resp2Frame::Null,
+    RedisValue::BigNumber(ref s) => Resp2Frame::BulkString(s.inner().clone()),
+    RedisValue::VerbatimString { ref data, .. } => Resp2Frame::BulkString(data.clone()),
     _ => {
       return Err(RedisError::new(
         RedisErrorKind::InvalidArgument,
@@ -595,6 +665,14 @@ pub fn value_to_outgoing_resp3_frame(value: &RedisValue) -> Result<Resp3Frame, R
       attributes: None,
     },
     RedisValue::Null => Resp3Frame::Null,
+    RedisValue::BigNumber(ref s) => Resp3Frame::BlobString {
+      data:       s.inner().clone(),
+      attributes: None,
+    },
+    RedisValue::VerbatimString { ref data, .. } => Resp3Frame::BlobString {
+      data:       data.clone(),
+      attributes: None,
+    },
     _ => {
       return Err(RedisError::new(
         RedisErrorKind::InvalidArgument,
@@ -646,6 +724,31 @@ pub fn mocked_value_to_frame(value: RedisValue) -> Resp3Frame {
       data:       value.into_inner(),
       attributes: None,
     },
+    RedisValue::BigNumber(value) => Resp3Frame::BigNumber {
+      data:       value.into_inner(),
+      attributes: None,
+    },
+    RedisValue::VerbatimString { format, data } => Resp3Frame::VerbatimString {
+      data,
+      format,
+      attributes: None,
+    },
+    RedisValue::Set(values) => Resp3Frame::Set {
+      data:       values.into_iter().map(mocked_value_to_frame).collect(),
+      attributes: None,
+    },
+    RedisValue::Attributed { value, attributes } => {
+      let mut frame = mocked_value_to_frame(*value);
+      let attributes = attributes
+        .inner()
+        .into_iter()
+        .map(|(key, value)| (mocked_value_to_frame(key.into()), mocked_value_to_frame(value)))
+        .collect();
+
+      // null frames cannot have attributes
+      let _ = frame.add_attributes(attributes);
+      frame
+    },
   }
 }
 
@@ -795,6 +898,10 @@ pub fn arg_size(value: &RedisValue) -> usize {
       .iter()
       .fold(0, |c, (k, v)| c + k.as_bytes().len() + arg_size(v)),
     RedisValue::Queued => 0,
+    RedisValue::BigNumber(ref s) => s.len(),
+    RedisValue::VerbatimString { ref data, .. } => data.len(),
+    RedisValue::Set(ref values) => args_size(values),
+    RedisValue::Attributed { ref value, .. } => arg_size(value),
   }
 }
 
@@ -1270,4 +1377,58 @@ cluster_stats_messages_received:1483968"
 
     assert_eq!(actual, expected);
   }
+
+  #[test]
+  #[cfg(feature = "resp3-types")]
+  fn should_preserve_resp3_types() {
+    let mut attributes = FrameMap::new();
+    attributes.insert(str_to_bs("popularity"), int_to_f(10));
+    let frame = Resp3Frame::Array {
+      data:       vec![
+        Resp3Frame::BigNumber {
+          data:       "3492890328409238509324850943850943825024385".into(),
+          attributes: None,
+        },
+        Resp3Frame::VerbatimString {
+          data:       "foo".into(),
+          format:     VerbatimStringFormat::Markdown,
+          attributes: None,
+        },
+        Resp3Frame::Set {
+          data:       vec![str_to_bs("a")].into_iter().collect(),
+          attributes: None,
+        },
+      ],
+      attributes: Some(attributes),
+    };
+
+    let (value, attributes) = frame_to_results(frame).unwrap().take_attributes();
+    let mut expected_attributes = HashMap::new();
+    expected_attributes.insert("popularity".into(), 10.into());
+    let expected = RedisValue::Array(vec![
+      RedisValue::BigNumber("3492890328409238509324850943850943825024385".into()),
+      RedisValue::VerbatimString {
+        format: VerbatimStringFormat::Markdown,
+        data:   "foo".into(),
+      },
+      RedisValue::Set(vec!["a".into()]),
+    ]);
+
+    assert_eq!(value, expected);
+    assert_eq!(attributes.unwrap().inner(), expected_attributes);
+  }
+
+  #[test]
+  fn should_convert_resp3_types_to_basic_types() {
+    let value = RedisValue::Attributed {
+      value:      Box::new(RedisValue::Set(vec![RedisValue::BigNumber("1".into())])),
+      attributes: RedisMap::new(),
+    };
+
+    assert_eq!(value.as_u64(), Some(1));
+    assert_eq!(value.as_bytes_str(), Some("1".into()));
+    assert_eq!(value.clone().convert::<Vec<String>>().unwrap(), vec!["1".to_owned()]);
+    assert_eq!(value.clone().convert::<RedisValue>().unwrap(), value);
+    assert_eq!(value.into_lossy(), RedisValue::Array(vec!["1".into()]));
+  }
 }
@@ -243,7 +243,7 @@ pub fn spawn_reader_task(
   let (buffer, counters) = (buffer.clone(), counters.clone());
 
   tokio::spawn(async move {
-    let mut last_error = None;
+    let (mut last_error, mut stalled) = (None, false);
     let mut rx = utils::reader_subscribe(&inner, &server);
 
     loop {
@@ -258,6 +258,18 @@ pub fn spawn_reader_task(
           break;
         },
       };
+      let frame = match responses::check_stream_element(&buffer, frame).await {
+        Some(frame) => frame,
+        None => continue,
+      };
+      let frame = match utils::inject_faults(&inner, &server, &buffer, &mut stalled, frame).await {
+        Ok(Some(frame)) => frame,
+        Ok(None) => continue,
+        Err(error) => {
+          last_error = Some(error);
+          break;
+        },
+      };
 
       if let Some(error) = responses::check_special_errors(&inner, &frame) {
         last_error = Some(error);
@@ -445,6 +457,7 @@ pub async fn process_response_frame(
     process_cluster_error(inner, server, command, frame);
     return Ok(());
   }
+  responses::record_response(inner, &command, &frame);
 
   if command.transaction_id.is_some() {
     if let Some(error) = protocol_utils::frame_to_error(&frame) {
@@ -500,6 +513,9 @@ pub async fn process_response_frame(
     ),
     ResponseKind::KeyScan(scanner) => responders::respond_key_scan(inner, server, command, scanner, frame),
     ResponseKind::ValueScan(scanner) => responders::respond_value_scan(inner, server, command, scanner, frame),
+    ResponseKind::Stream(tx) | ResponseKind::StreamElements(tx) => {
+      responders::respond_stream(inner, server, command, tx, frame).await
+    },
   }
 }
 
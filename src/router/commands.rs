@@ -73,7 +73,19 @@ async fn handle_router_response(
         };
 
         utils::reconnect_with_policy(inner, router).await?;
-        Ok(command)
+        match command {
+          Some(command) => {
+            command.record_retry();
+            match command.retry_delay() {
+              Some(delay) => {
+                utils::defer_retry(inner, command, delay);
+                Ok(None)
+              },
+              None => Ok(Some(command)),
+            }
+          },
+          None => Ok(None),
+        }
       },
       RouterResponse::TransactionError(_) | RouterResponse::TransactionResult(_) => {
         _error!(inner, "Unexpected transaction response. This is a bug.");
@@ -593,15 +605,72 @@ pub async fn start(inner: &Arc<RedisClientInner>) -> Result<(), RedisError> {
 #[cfg(feature = "mocks")]
 mod mocking {
   use super::*;
-  use crate::{modules::mocks::Mocks, protocol::utils as protocol_utils};
+  use crate::{
+    modules::mocks::{MockCommand, Mocks},
+    protocol::{responders, responders::ResponseKind, utils as protocol_utils},
+    types::RedisValue,
+  };
+
+  /// Record the result of a mocked command, if a recorder is configured.
+  #[cfg(feature = "replay")]
+  fn record(inner: &Arc<RedisClientInner>, command: &RedisCommand, result: &Result<RedisValue, RedisError>) {
+    if let Some(ref recorder) = inner.config.recorder {
+      recorder.record_command(command, result.clone());
+    }
+  }
+
+  #[cfg(not(feature = "replay"))]
+  fn record(_: &Arc<RedisClientInner>, _: &RedisCommand, _: &Result<RedisValue, RedisError>) {}
+
+  /// Record the result of a mocked transaction, if a recorder is configured.
+  #[cfg(feature = "replay")]
+  fn record_transaction(
+    inner: &Arc<RedisClientInner>,
+    commands: &[MockCommand],
+    result: &Result<RedisValue, RedisError>,
+  ) {
+    if let Some(ref recorder) = inner.config.recorder {
+      recorder.record_transaction(commands.to_vec(), result.clone());
+    }
+  }
+
+  #[cfg(not(feature = "replay"))]
+  fn record_transaction(_: &Arc<RedisClientInner>, _: &[MockCommand], _: &Result<RedisValue, RedisError>) {}
+
+  /// Send the result of a mocked command to the caller, including the pages of any scanning operations.
+  fn respond(inner: &Arc<RedisClientInner>, mut command: RedisCommand, result: Result<Resp3Frame, RedisError>) {
+    let server = Server::new("mocks", 0);
+
+    match (command.take_response(), result) {
+      (ResponseKind::KeyScan(scanner), Ok(frame)) => {
+        let _ = responders::respond_key_scan(inner, &server, command, scanner, frame);
+      },
+      (ResponseKind::ValueScan(scanner), Ok(frame)) => {
+        let _ = responders::respond_value_scan(inner, &server, command, scanner, frame);
+      },
+      (ResponseKind::KeyScan(scanner), Err(e)) => scanner.send_error(e),
+      (ResponseKind::ValueScan(scanner), Err(e)) => scanner.send_error(e),
+      (response, result) => {
+        let result = result.map(|frame| responders::strip_keys_prefix(inner, &command, frame));
+        command.response = response;
+        command.respond_to_caller(result);
+      },
+    }
+  }
 
   /// Process any kind of router command.
-  pub fn process_command(mocks: &Arc<dyn Mocks>, command: RouterCommand) -> Result<(), RedisError> {
+  pub fn process_command(
+    inner: &Arc<RedisClientInner>,
+    mocks: &Arc<dyn Mocks>,
+    command: RouterCommand,
+  ) -> Result<(), RedisError> {
     match command {
       RouterCommand::Transaction { commands, tx, .. } => {
-        let mocked = commands.into_iter().skip(1).map(|c| c.to_mocked()).collect();
+        let mocked: Vec<MockCommand> = commands.into_iter().skip(1).map(|c| c.to_mocked()).collect();
+        let result = mocks.process_transaction(mocked.clone());
+        record_transaction(inner, &mocked, &result);
 
-        match mocks.process_transaction(mocked) {
+        match result {
           Ok(result) => {
             let _ = tx.send(Ok(protocol_utils::mocked_value_to_frame(result)));
             Ok(())
@@ -614,21 +683,21 @@ mod mocking {
       },
       RouterCommand::Pipeline { commands } => {
         for mut command in commands.into_iter() {
-          let mocked = command.to_mocked();
-          let result = mocks
-            .process_command(mocked)
-            .map(|result| protocol_utils::mocked_value_to_frame(result));
+          let result = mocks.process_command(command.to_mocked());
+          record(inner, &command, &result);
+          let result = result
+            .map(protocol_utils::mocked_value_to_frame)
+            .map(|frame| responders::strip_keys_prefix(inner, &command, frame));
 
           let _ = command.respond_to_caller(result);
         }
 
         Ok(())
       },
-      RouterCommand::Command(mut command) => {
-        let result = mocks
-          .process_command(command.to_mocked())
-          .map(|result| protocol_utils::mocked_value_to_frame(result));
-        let _ = command.respond_to_caller(result);
+      RouterCommand::Command(command) => {
+        let result = mocks.process_command(command.to_mocked());
+        record(inner, &command, &result);
+        respond(inner, command, result.map(protocol_utils::mocked_value_to_frame));
 
         Ok(())
       },
@@ -645,7 +714,7 @@ mod mocking {
       inner.counters.decr_cmd_buffer_len();
 
       _trace!(inner, "Recv mock command: {:?}", command);
-      if let Err(e) = process_command(mocks, command) {
+      if let Err(e) = process_command(inner, mocks, command) {
         // errors on this interface end the client connection task
         _error!(inner, "Ending early after error processing mock command: {:?}", e);
         if e.is_canceled() {
@@ -17,7 +17,7 @@ use std::{
   fmt,
   fmt::Formatter,
   sync::Arc,
-  time::Duration,
+  time::{Duration, Instant},
 };
 use tokio::sync::oneshot::channel as oneshot_channel;
 
@@ -320,7 +320,7 @@ impl Connections {
 
   /// Disconnect and clear local state for all connections, returning all in-flight commands.
   pub async fn disconnect_all(&mut self, inner: &Arc<RedisClientInner>) -> CommandBuffer {
-    match self {
+    let commands = match self {
       Connections::Centralized { ref mut writer } => {
         if let Some(writer) = writer.take() {
           _debug!(inner, "Disconnecting from {}", writer.server);
@@ -346,7 +346,11 @@ impl Connections {
           VecDeque::new()
         }
       },
-    }
+    };
+
+    // the next connection negotiates the protocol version again
+    inner.reset_negotiated_version();
+    commands
   }
 
   /// Read a map of connection IDs (via `CLIENT ID`) for each inner connections.
@@ -642,7 +646,7 @@ impl Router {
         return Written::NotFound(command);
       },
     };
-    let frame = match utils::prepare_command(&self.inner, &writer.counters, &mut command) {
+    let frame = match utils::prepare_command(&self.inner, &writer.counters, &writer.server, &mut command) {
       Ok((frame, _)) => frame,
       Err(e) => {
         warn!(
@@ -817,6 +821,7 @@ impl Router {
     let mut commands: VecDeque<_> = self.buffer.drain(..).collect();
     #[cfg(feature = "replicas")]
     commands.extend(self.replicas.take_retry_buffer());
+    let started = Instant::now();
 
     for mut command in commands.drain(..) {
       if client_utils::read_bool_atomic(&command.timed_out) {
@@ -827,6 +832,25 @@ impl Router {
         );
         continue;
       }
+      if !command.check_retry_policy(&self.inner) {
+        command.finish(
+          &self.inner,
+          Err(RedisError::new(
+            RedisErrorKind::IO,
+            "Connection closed before the command finished. The retry policy does not allow retrying the command.",
+          )),
+        );
+        continue;
+      }
+      command.record_retry();
+      // the delay is relative to the start of the retry loop so that each buffered command does not add to the total
+      if let Some(delay) = command.retry_delay() {
+        let elapsed = started.elapsed();
+        if delay > elapsed {
+          utils::defer_retry(&self.inner, command, delay - elapsed);
+          continue;
+        }
+      }
 
       if let Err(e) = command.decr_check_attempted() {
         command.finish(&self.inner, Err(e));
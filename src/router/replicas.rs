@@ -462,7 +462,15 @@ impl Replicas {
         }
       },
     };
-    let (frame, should_flush) = match utils::prepare_command(inner, &writer.counters, &mut command) {
+    if let Err(e) = inner.circuit_breakers.check(inner, &writer.server) {
+      return if inner.connection.replica.primary_fallback {
+        Written::Fallback(command)
+      } else {
+        command.finish(inner, Err(e));
+        Written::Ignore
+      };
+    }
+    let (frame, should_flush) = match utils::prepare_command(inner, &writer.counters, &writer.server, &mut command) {
       Ok((frame, should_flush)) => (frame, should_flush || force_flush),
       Err(e) => {
         _warn!(inner, "Frame encoding error for {}", command.kind.to_str_debug());
@@ -491,6 +499,7 @@ impl Replicas {
         },
       };
 
+      inner.circuit_breakers.record_failure(inner, &writer.server);
       _debug!(
         inner,
         "Error sending replica command {}: {:?}",
@@ -1,7 +1,14 @@
 use crate::{
   error::{RedisError, RedisErrorKind},
   modules::inner::RedisClientInner,
-  protocol::{command::RedisCommand, types::Server, utils as protocol_utils, utils::pretty_error},
+  protocol::{
+    command::RedisCommand,
+    connection::SharedBuffer,
+    responders::ResponseKind,
+    types::Server,
+    utils as protocol_utils,
+    utils::pretty_error,
+  },
   trace,
   types::{ClientState, KeyspaceEvent, Message, RedisKey, RedisValue},
   utils,
@@ -216,7 +223,10 @@ pub fn check_pubsub_message(inner: &Arc<RedisClientInner>, server: &Server, fram
 
   if is_pubsub_invalidation(&message) {
     broadcast_pubsub_invalidation(inner, message, server);
-  } else if let Some(event) = parse_keyspace_notification(&message.channel, &message.value) {
+  } else if let Some(mut event) = parse_keyspace_notification(&message.channel, &message.value) {
+    if inner.config.key_prefix.is_some() {
+      event.key = utils::strip_key_prefix(inner.config.key_prefix.as_ref(), event.key);
+    }
     inner.notifications.broadcast_keyspace(event);
   } else {
     inner.notifications.broadcast_pubsub(message);
@@ -231,6 +241,23 @@ pub async fn check_and_set_unblocked_flag(inner: &Arc<RedisClientInner>, command
   }
 }
 
+/// Send the frame to the caller if it's an element of a streamed aggregate response, waiting for space in the
+/// response stream.
+///
+/// Returns the frame if it should be handled as a response to the command at the front of the buffer.
+pub async fn check_stream_element(buffer: &SharedBuffer, frame: Resp3Frame) -> Option<Resp3Frame> {
+  let tx = match buffer.lock().front() {
+    Some(RedisCommand {
+      response: ResponseKind::StreamElements(ref tx),
+      ..
+    }) => tx.clone(),
+    _ => return Some(frame),
+  };
+
+  let _ = tx.send(Ok(frame)).await;
+  None
+}
+
 #[cfg(feature = "reconnect-on-auth-error")]
 /// Parse the response frame to see if it's an auth error.
 fn parse_redis_auth_error(frame: &Resp3Frame) -> Option<RedisError> {
@@ -316,6 +343,9 @@ pub fn check_special_errors(inner: &Arc<RedisClientInner>, frame: &Resp3Frame) -
 /// Handle an error in the reader task that should end the connection.
 pub fn broadcast_reader_error(inner: &Arc<RedisClientInner>, server: &Server, error: Option<RedisError>) {
   _warn!(inner, "Ending reader task from {} due to {:?}", server, error);
+  if error.is_some() {
+    inner.circuit_breakers.record_failure(inner, server);
+  }
 
   if inner.should_reconnect() {
     inner.send_reconnect(Some(server.clone()), false, None);
@@ -335,6 +365,9 @@ pub fn broadcast_replica_error(inner: &Arc<RedisClientInner>, server: &Server, e
 #[cfg(feature = "replicas")]
 pub fn broadcast_replica_error(inner: &Arc<RedisClientInner>, server: &Server, error: Option<RedisError>) {
   _warn!(inner, "Ending replica reader task from {} due to {:?}", server, error);
+  if error.is_some() {
+    inner.circuit_breakers.record_failure(inner, server);
+  }
 
   if inner.should_reconnect() {
     inner.send_replica_reconnect(server);
@@ -345,3 +378,32 @@ pub fn broadcast_replica_error(inner: &Arc<RedisClientInner>, server: &Server, e
       .broadcast_error(error.unwrap_or(RedisError::new_canceled()));
   }
 }
+
+/// Record the response to a command, if a [recorder](crate::replay::Recorder) is configured.
+#[cfg(feature = "replay")]
+pub fn record_response(inner: &Arc<RedisClientInner>, command: &RedisCommand, frame: &Resp3Frame) {
+  use crate::protocol::responders::ResponseKind;
+  use std::sync::atomic::Ordering;
+
+  let recorder = match inner.config.recorder {
+    Some(ref recorder) => recorder,
+    None => return,
+  };
+  // the mocking layer receives these commands once, so only one of the response frames is recorded
+  let should_record = match command.response {
+    ResponseKind::Multiple {
+      ref received, expected, ..
+    } => frame.is_error() || received.load(Ordering::Acquire) + 1 >= expected,
+    ResponseKind::Buffer { ref received, .. } if command.kind.is_all_cluster_nodes() => {
+      received.load(Ordering::Acquire) == 0
+    },
+    _ => true,
+  };
+
+  if should_record {
+    recorder.record_command(command, protocol_utils::frame_to_results(frame.clone()));
+  }
+}
+
+#[cfg(not(feature = "replay"))]
+pub fn record_response(_: &Arc<RedisClientInner>, _: &RedisCommand, _: &Resp3Frame) {}
@@ -1,9 +1,10 @@
 use crate::{
   error::{RedisError, RedisErrorKind},
+  interfaces,
   modules::inner::RedisClientInner,
   prelude::Resp3Frame,
   protocol::{
-    command::{ClusterErrorKind, RedisCommand, RedisCommandKind, RouterResponse},
+    command::{ClusterErrorKind, RedisCommand, RedisCommandKind, RouterCommand, RouterResponse},
     connection::{RedisWriter, SharedBuffer, SplitStreamKind},
     responders::ResponseKind,
     types::*,
@@ -22,10 +23,11 @@ use std::{
 use tokio::{
   self,
   sync::{mpsc::UnboundedReceiver, oneshot::channel as oneshot_channel},
+  time::sleep,
 };
 
-#[cfg(feature = "replicas")]
-use crate::{interfaces, protocol::command::RouterCommand};
+#[cfg(feature = "faults")]
+use crate::modules::faults;
 #[cfg(feature = "check-unresponsive")]
 use futures::future::Either;
 #[cfg(feature = "check-unresponsive")]
@@ -70,14 +72,14 @@ pub fn check_backpressure(
 }
 
 #[cfg(feature = "partial-tracing")]
-fn set_command_trace(inner: &Arc<RedisClientInner>, command: &mut RedisCommand) {
+fn set_command_trace(inner: &Arc<RedisClientInner>, command: &mut RedisCommand, server: &Server) {
   if inner.should_trace() {
-    crate::trace::set_network_span(inner, command, true);
+    crate::trace::set_network_span(inner, command, server, true);
   }
 }
 
 #[cfg(not(feature = "partial-tracing"))]
-fn set_command_trace(_inner: &Arc<RedisClientInner>, _: &mut RedisCommand) {}
+fn set_command_trace(_inner: &Arc<RedisClientInner>, _: &mut RedisCommand, _: &Server) {}
 
 /// Prepare the command, updating flags in place.
 ///
@@ -85,6 +87,7 @@ fn set_command_trace(_inner: &Arc<RedisClientInner>, _: &mut RedisCommand) {}
 pub fn prepare_command(
   inner: &Arc<RedisClientInner>,
   counters: &Counters,
+  server: &Server,
   command: &mut RedisCommand,
 ) -> Result<(ProtocolFrame, bool), RedisError> {
   let frame = command.to_frame(inner.is_resp3())?;
@@ -103,7 +106,7 @@ pub fn prepare_command(
     || command.has_router_channel();
 
   command.network_start = Some(Instant::now());
-  set_command_trace(inner, command);
+  set_command_trace(inner, command, server);
 
   Ok((frame, should_flush))
 }
@@ -131,6 +134,15 @@ pub async fn write_command(
     );
     return Written::Ignore;
   }
+  if let Err(e) = inner.circuit_breakers.check(inner, &writer.server) {
+    _debug!(
+      inner,
+      "Failing {} due to open circuit breaker.",
+      command.kind.to_str_debug()
+    );
+    command.finish(inner, Err(e));
+    return Written::Ignore;
+  }
 
   match check_backpressure(inner, &writer.counters, &command) {
     Ok(Some(backpressure)) => {
@@ -145,7 +157,7 @@ pub async fn write_command(
     _ => {},
   };
 
-  let (frame, should_flush) = match prepare_command(inner, &writer.counters, &mut command) {
+  let (frame, should_flush) = match prepare_command(inner, &writer.counters, &writer.server, &mut command) {
     Ok((frame, should_flush)) => (frame, should_flush || force_flush),
     Err(e) => {
       _warn!(inner, "Frame encoding error for {}", command.kind.to_str_debug());
@@ -155,6 +167,11 @@ pub async fn write_command(
     },
   };
 
+  let mut command = match intercept_faults(inner, writer, command).await {
+    Ok(command) => command,
+    Err(written) => return written,
+  };
+
   _trace!(
     inner,
     "Sending command {} ({}) to {}",
@@ -163,10 +180,14 @@ pub async fn write_command(
     writer.server
   );
   command.write_attempts += 1;
+  if let Some(ref written_to) = command.written_to {
+    *written_to.lock() = Some(writer.server.clone());
+  }
   writer.push_command(inner, command);
   if let Err(e) = writer.write_frame(frame, should_flush).await {
     let command = writer.pop_recent_command();
     _debug!(inner, "Error sending command: {:?}", e);
+    inner.circuit_breakers.record_failure(inner, &writer.server);
     Written::Disconnected((Some(writer.server.clone()), command, e))
   } else {
     Written::Sent((writer.server.clone(), should_flush))
@@ -530,6 +551,27 @@ pub fn defer_replica_sync(inner: &Arc<RedisClientInner>) {
   }
 }
 
+/// Send the command back to the router after `delay` without blocking the router task.
+///
+/// Commands retried this way may be written after other commands that were sent later.
+pub fn defer_retry(inner: &Arc<RedisClientInner>, mut command: RedisCommand, delay: Duration) {
+  _debug!(
+    inner,
+    "Retrying {} ({}) after {} ms",
+    command.kind.to_str_debug(),
+    command.debug_id(),
+    delay.as_millis()
+  );
+  command.skip_backpressure = true;
+  let inner = inner.clone();
+
+  tokio::spawn(async move {
+    sleep(delay).await;
+    // the caller receives an error if the router is closed
+    let _ = interfaces::send_to_router(&inner, RouterCommand::Command(command));
+  });
+}
+
 #[cfg(feature = "check-unresponsive")]
 pub async fn next_frame(
   inner: &Arc<RedisClientInner>,
@@ -570,6 +612,48 @@ pub async fn next_frame(
   conn.try_next().await
 }
 
+/// Apply any configured network faults to a response frame before it is processed.
+#[cfg(feature = "faults")]
+pub async fn inject_faults(
+  inner: &Arc<RedisClientInner>,
+  server: &Server,
+  buffer: &SharedBuffer,
+  stalled: &mut bool,
+  frame: Resp3Frame,
+) -> Result<Option<Resp3Frame>, RedisError> {
+  faults::apply(inner, server, buffer, stalled, frame).await
+}
+
+#[cfg(not(feature = "faults"))]
+pub async fn inject_faults(
+  _: &Arc<RedisClientInner>,
+  _: &Server,
+  _: &SharedBuffer,
+  _: &mut bool,
+  frame: Resp3Frame,
+) -> Result<Option<Resp3Frame>, RedisError> {
+  Ok(Some(frame))
+}
+
+/// Apply any configured error faults to a command instead of writing it to the server.
+#[cfg(feature = "faults")]
+pub async fn intercept_faults(
+  inner: &Arc<RedisClientInner>,
+  writer: &RedisWriter,
+  command: RedisCommand,
+) -> Result<RedisCommand, Written> {
+  faults::intercept(inner, writer, command).await
+}
+
+#[cfg(not(feature = "faults"))]
+pub async fn intercept_faults(
+  _: &Arc<RedisClientInner>,
+  _: &RedisWriter,
+  command: RedisCommand,
+) -> Result<RedisCommand, Written> {
+  Ok(command)
+}
+
 #[cfg(feature = "check-unresponsive")]
 pub fn reader_subscribe(inner: &Arc<RedisClientInner>, server: &Server) -> Option<UnboundedReceiver<()>> {
   Some(inner.network_timeouts.state().subscribe(inner, server))
@@ -0,0 +1,1672 @@
+//! Blocking versions of the [command interfaces](crate::interfaces).
+//!
+//! Each trait mirrors the async trait with the same name. Each function calls the async function with the same name
+//! and arguments via [block_on](ClientLike::block_on), which polls the future on the calling thread while the
+//! client's runtime thread drives the connections. The functions are generated from their signatures, so see the
+//! async interfaces for the documentation of each command.
+//!
+//! The following parts of the async interfaces do not have blocking versions:
+//!
+//! * Functions that are not `async`, such as
+//!   [cached_cluster_state](crate::interfaces::ClusterInterface::cached_cluster_state) or
+//!   [sentinel_primary](crate::interfaces::ServerInterface::sentinel_primary).
+//! * Functions that return a receiver or spawn a task, such as
+//!   [on_message](crate::interfaces::PubsubInterface::on_message),
+//!   [on_keyspace_event](crate::interfaces::PubsubInterface::on_keyspace_event),
+//!   [on_invalidation](crate::interfaces::TrackingInterface::on_invalidation), or
+//!   [invalidation_rx](crate::interfaces::TrackingInterface::invalidation_rx).
+//! * Functions that take async callbacks, such as [get_or_compute](crate::interfaces::KeysInterface::get_or_compute)
+//!   or [transaction_with_retry](crate::interfaces::TransactionInterface::transaction_with_retry).
+//! * The [HeartbeatInterface](crate::interfaces::HeartbeatInterface),
+//!   [MetricsInterface](crate::interfaces::MetricsInterface), and [EventInterface](crate::interfaces::EventInterface)
+//!   traits.
+//!
+//! These can be called on the [async_client](ClientLike::async_client), using [block_on](ClientLike::block_on) with
+//! any futures.
+
+use crate::{
+  error::RedisError,
+  interfaces::{self, RedisResult, Resp3Frame},
+  types::*,
+};
+use bytes::Bytes;
+use bytes_utils::Str;
+use semver::Version;
+use std::{collections::HashMap, convert::TryInto, future::Future, hash::Hash};
+
+#[cfg(feature = "redis-json")]
+use serde_json::Value;
+
+/// The base trait for blocking clients, used to run futures while the client's runtime thread drives the connections.
+pub trait ClientLike: Sized {
+  /// The async client used to send commands.
+  type Client: interfaces::ClientLike + Sync;
+
+  /// Read the async client used to send commands.
+  fn async_client(&self) -> &Self::Client;
+
+  /// Run a future to completion, blocking the calling thread until it finishes.
+  ///
+  /// The future is polled on the calling thread, while the connections and any spawned tasks run on the client's
+  /// runtime thread. This can be used to call any function on the [async_client](Self::async_client).
+  ///
+  /// # Panics
+  ///
+  /// This function panics if called from within an async context.
+  fn block_on<F: Future>(&self, ft: F) -> F::Output;
+
+  /// The unique ID identifying this client and underlying connections.
+  fn id(&self) -> &str {
+    interfaces::ClientLike::id(self.async_client())
+  }
+
+  /// Read the config used to initialize the client.
+  fn client_config(&self) -> RedisConfig {
+    interfaces::ClientLike::client_config(self.async_client())
+  }
+
+  /// Read the RESP version used by the client when communicating with the server.
+  fn protocol_version(&self) -> RespVersion {
+    interfaces::ClientLike::protocol_version(self.async_client())
+  }
+
+  /// Whether the client is connected to a cluster.
+  fn is_clustered(&self) -> bool {
+    interfaces::ClientLike::is_clustered(self.async_client())
+  }
+
+  /// Read the state of the underlying connection(s).
+  fn state(&self) -> ClientState {
+    interfaces::ClientLike::state(self.async_client())
+  }
+
+  /// Whether all underlying connections are healthy.
+  fn is_connected(&self) -> bool {
+    interfaces::ClientLike::is_connected(self.async_client())
+  }
+
+  /// Read the server version, if known.
+  fn server_version(&self) -> Option<Version> {
+    interfaces::ClientLike::server_version(self.async_client())
+  }
+
+  /// Read the set of active connections managed by the client.
+  fn active_connections(&self) -> RedisResult<Vec<Server>> {
+    self.block_on(interfaces::ClientLike::active_connections(self.async_client()))
+  }
+
+  /// Force a reconnection to the server(s).
+  ///
+  /// See [force_reconnection](crate::interfaces::ClientLike::force_reconnection).
+  fn force_reconnection(&self) -> RedisResult<()> {
+    self.block_on(interfaces::ClientLike::force_reconnection(self.async_client()))
+  }
+
+  /// Wait for the result of the next connection attempt.
+  ///
+  /// See [wait_for_connect](crate::interfaces::ClientLike::wait_for_connect).
+  fn wait_for_connect(&self) -> RedisResult<()> {
+    self.block_on(interfaces::ClientLike::wait_for_connect(self.async_client()))
+  }
+
+  /// Close the connection to the Redis server.
+  ///
+  /// See [quit](crate::interfaces::ClientLike::quit).
+  fn quit(&self) -> RedisResult<()> {
+    self.block_on(interfaces::ClientLike::quit(self.async_client()))
+  }
+
+  /// Shut down the server and quit the client.
+  ///
+  /// See [shutdown](crate::interfaces::ClientLike::shutdown).
+  fn shutdown(&self, flags: Option<ShutdownFlags>) -> RedisResult<()> {
+    self.block_on(interfaces::ClientLike::shutdown(self.async_client(), flags))
+  }
+
+  /// Ping the Redis server.
+  ///
+  /// See [ping](crate::interfaces::ClientLike::ping).
+  fn ping<R>(&self) -> RedisResult<R>
+  where
+    R: FromRedis,
+  {
+    self.block_on(interfaces::ClientLike::ping(self.async_client()))
+  }
+
+  /// Read info about the server.
+  ///
+  /// See [info](crate::interfaces::ClientLike::info).
+  fn info<R>(&self, section: Option<InfoKind>) -> RedisResult<R>
+  where
+    R: FromRedis,
+  {
+    self.block_on(interfaces::ClientLike::info(self.async_client(), section))
+  }
+
+  /// Run a custom command that is not yet supported via another interface on this client.
+  ///
+  /// See [custom](crate::interfaces::ClientLike::custom).
+  fn custom<R, T>(&self, cmd: CustomCommand, args: Vec<T>) -> RedisResult<R>
+  where
+    R: FromRedis,
+    T: TryInto<RedisValue> + Send,
+    T::Error: Into<RedisError> + Send,
+  {
+    self.block_on(interfaces::ClientLike::custom(self.async_client(), cmd, args))
+  }
+
+  /// Run a custom command, returning the response frame directly without any parsing.
+  ///
+  /// See [custom_raw](crate::interfaces::ClientLike::custom_raw).
+  fn custom_raw<T>(&self, cmd: CustomCommand, args: Vec<T>) -> RedisResult<Resp3Frame>
+  where
+    T: TryInto<RedisValue> + Send,
+    T::Error: Into<RedisError> + Send,
+  {
+    self.block_on(interfaces::ClientLike::custom_raw(self.async_client(), cmd, args))
+  }
+}
+
+/// Define a blocking version of an async interface trait with the same name.
+///
+/// Each function calls the async function with the same name and arguments via [block_on](ClientLike::block_on).
+/// Where clauses are wrapped in brackets so the macro can find the end of each signature.
+macro_rules! blocking_interface {
+  (
+    $(#[$trait_meta:meta])*
+    pub trait $name:ident {
+      $(
+        $(#[$meta:meta])*
+        fn $fn_name:ident $(<$($gen:ident),+>)? (&self $(, $arg:ident: $ty:ty)* $(,)?) -> $ret:ty
+        $(where [$($bounds:tt)+])?;
+      )*
+    }
+  ) => {
+    $(#[$trait_meta])*
+    #[doc = concat!(
+      "A blocking version of [", stringify!($name), "](crate::interfaces::", stringify!($name), ")."
+    )]
+    pub trait $name: ClientLike
+    where
+      Self::Client: interfaces::$name,
+    {
+      $(
+        $(#[$meta])*
+        #[doc = concat!(
+          "A blocking version of [", stringify!($fn_name), "](crate::interfaces::", stringify!($name), "::",
+          stringify!($fn_name), ")."
+        )]
+        fn $fn_name $(<$($gen),+>)? (&self $(, $arg: $ty)*) -> $ret
+        $(where $($bounds)+)?
+        {
+          self.block_on(interfaces::$name::$fn_name $(::<$($gen),+>)? (self.async_client() $(, $arg)*))
+        }
+      )*
+    }
+  };
+}
+
+blocking_interface! {
+  pub trait AclInterface {
+    fn acl_setuser<S, V>(&self, username: S, rules: V) -> RedisResult<()>
+    where [S: Into<Str> + Send, V: TryInto<MultipleValues> + Send, V::Error: Into<RedisError> + Send];
+
+    fn acl_load(&self) -> RedisResult<()>;
+
+    fn acl_save(&self) -> RedisResult<()>;
+
+    fn acl_list<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn acl_users<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn acl_getuser<R, S>(&self, username: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn acl_deluser<R, S>(&self, usernames: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<MultipleStrings> + Send];
+
+    fn acl_cat<R>(&self, category: Option<Str>) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn acl_genpass<R>(&self, bits: Option<u16>) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn acl_whoami<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn acl_log_count<R>(&self, count: Option<u32>) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn acl_log_reset(&self) -> RedisResult<()>;
+  }
+}
+
+blocking_interface! {
+  pub trait ClientInterface {
+    fn client_id<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn connection_ids(&self) -> HashMap<Server, i64>;
+
+    fn client_info<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn client_kill<R>(&self, filters: Vec<ClientKillFilter>) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn client_list<R, I>(&self, r#type: Option<ClientKillType>, ids: Option<Vec<String>>) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn client_getname<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn client_setname<S>(&self, name: S) -> RedisResult<()>
+    where [S: Into<Str> + Send];
+
+    fn client_pause(&self, timeout: i64, mode: Option<ClientPauseKind>) -> RedisResult<()>;
+
+    fn client_unpause(&self) -> RedisResult<()>;
+
+    fn client_reply(&self, flag: ClientReplyFlag) -> RedisResult<()>;
+
+    fn client_unblock<R, S>(&self, id: S, flag: Option<ClientUnblockFlag>) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisValue> + Send];
+
+    fn unblock_self(&self, flag: Option<ClientUnblockFlag>) -> RedisResult<()>;
+
+    #[cfg(feature = "client-tracking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "client-tracking")))]
+    fn client_tracking<R, T, P>(
+      &self,
+      toggle: T,
+      redirect: Option<i64>,
+      prefixes: P,
+      bcast: bool,
+      optin: bool,
+      optout: bool,
+      noloop: bool,
+    ) -> RedisResult<R>
+    where [R: FromRedis, T: TryInto<Toggle> + Send, T::Error: Into<RedisError> + Send, P: Into<MultipleStrings> + Send];
+
+    #[cfg(feature = "client-tracking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "client-tracking")))]
+    fn client_trackinginfo<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    #[cfg(feature = "client-tracking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "client-tracking")))]
+    fn client_getredir<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    #[cfg(feature = "client-tracking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "client-tracking")))]
+    fn client_caching<R>(&self, enabled: bool) -> RedisResult<R>
+    where [R: FromRedis];
+  }
+}
+
+blocking_interface! {
+  pub trait ClusterInterface {
+    fn sync_cluster(&self) -> Result<(), RedisError>;
+
+    fn cluster_bumpepoch<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn cluster_flushslots(&self) -> RedisResult<()>;
+
+    fn cluster_myid<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn cluster_nodes<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn cluster_saveconfig(&self) -> RedisResult<()>;
+
+    fn cluster_slots<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn cluster_info<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn cluster_add_slots<S>(&self, slots: S) -> RedisResult<()>
+    where [S: Into<MultipleHashSlots> + Send];
+
+    fn cluster_count_failure_reports<R, S>(&self, node_id: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn cluster_count_keys_in_slot<R>(&self, slot: u16) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn cluster_del_slots<S>(&self, slots: S) -> RedisResult<()>
+    where [S: Into<MultipleHashSlots> + Send];
+
+    fn cluster_failover(&self, flag: Option<ClusterFailoverFlag>) -> RedisResult<()>;
+
+    fn cluster_forget<S>(&self, node_id: S) -> RedisResult<()>
+    where [S: Into<Str> + Send];
+
+    fn cluster_get_keys_in_slot<R>(&self, slot: u16, count: u64) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn cluster_keyslot<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn cluster_meet<S>(&self, ip: S, port: u16) -> RedisResult<()>
+    where [S: Into<Str> + Send];
+
+    fn cluster_replicate<S>(&self, node_id: S) -> RedisResult<()>
+    where [S: Into<Str> + Send];
+
+    fn cluster_replicas<R, S>(&self, node_id: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn cluster_reset(&self, mode: Option<ClusterResetFlag>) -> RedisResult<()>;
+
+    fn cluster_set_config_epoch(&self, epoch: u64) -> RedisResult<()>;
+
+    fn cluster_setslot(&self, slot: u16, state: ClusterSetSlotState) -> RedisResult<()>;
+  }
+}
+
+blocking_interface! {
+  pub trait ConfigInterface {
+    fn config_resetstat(&self) -> RedisResult<()>;
+
+    fn config_rewrite(&self) -> RedisResult<()>;
+
+    fn config_get<R, S>(&self, parameter: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn config_set<P, V>(&self, parameter: P, value: V) -> RedisResult<()>
+    where [P: Into<Str> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait GeoInterface {
+    fn geoadd<R, K, V>(&self, key: K, options: Option<SetOptions>, changed: bool, values: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: Into<MultipleGeoValues> + Send];
+
+    fn geohash<R, K, V>(&self, key: K, members: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn geopos<R, K, V>(&self, key: K, members: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn geodist<R, K, S, D>(&self, key: K, src: S, dest: D, unit: Option<GeoUnit>) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      S: TryInto<RedisValue> + Send,
+      S::Error: Into<RedisError> + Send,
+      D: TryInto<RedisValue> + Send,
+      D::Error: Into<RedisError> + Send,
+    ];
+
+    fn georadius<R, K, P>(
+      &self,
+      key: K,
+      position: P,
+      radius: f64,
+      unit: GeoUnit,
+      withcoord: bool,
+      withdist: bool,
+      withhash: bool,
+      count: Option<(u64, Any)>,
+      ord: Option<SortOrder>,
+      store: Option<RedisKey>,
+      storedist: Option<RedisKey>,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<GeoPosition> + Send];
+
+    fn georadiusbymember<R, K, V>(
+      &self,
+      key: K,
+      member: V,
+      radius: f64,
+      unit: GeoUnit,
+      withcoord: bool,
+      withdist: bool,
+      withhash: bool,
+      count: Option<(u64, Any)>,
+      ord: Option<SortOrder>,
+      store: Option<RedisKey>,
+      storedist: Option<RedisKey>,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn geosearch<R, K>(
+      &self,
+      key: K,
+      from_member: Option<RedisValue>,
+      from_lonlat: Option<GeoPosition>,
+      by_radius: Option<(f64, GeoUnit)>,
+      by_box: Option<(f64, f64, GeoUnit)>,
+      ord: Option<SortOrder>,
+      count: Option<(u64, Any)>,
+      withcoord: bool,
+      withdist: bool,
+      withhash: bool,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn geosearchstore<R, D, S>(
+      &self,
+      dest: D,
+      source: S,
+      from_member: Option<RedisValue>,
+      from_lonlat: Option<GeoPosition>,
+      by_radius: Option<(f64, GeoUnit)>,
+      by_box: Option<(f64, f64, GeoUnit)>,
+      ord: Option<SortOrder>,
+      count: Option<(u64, Any)>,
+      storedist: bool,
+    ) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, S: Into<RedisKey> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait HashesInterface {
+    fn hgetall<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn hdel<R, K, F>(&self, key: K, fields: F) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, F: Into<MultipleKeys> + Send];
+
+    fn hexists<R, K, F>(&self, key: K, field: F) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, F: Into<RedisKey> + Send];
+
+    fn hget<R, K, F>(&self, key: K, field: F) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, F: Into<RedisKey> + Send];
+
+    fn hincrby<R, K, F>(&self, key: K, field: F, increment: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, F: Into<RedisKey> + Send];
+
+    fn hincrbyfloat<R, K, F>(&self, key: K, field: F, increment: f64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, F: Into<RedisKey> + Send];
+
+    fn hkeys<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn hlen<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn hmget<R, K, F>(&self, key: K, fields: F) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, F: Into<MultipleKeys> + Send];
+
+    fn hmset<R, K, V>(&self, key: K, values: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisMap> + Send, V::Error: Into<RedisError> + Send];
+
+    fn hset<R, K, V>(&self, key: K, values: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisMap> + Send, V::Error: Into<RedisError> + Send];
+
+    fn hsetnx<R, K, F, V>(&self, key: K, field: F, value: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      F: Into<RedisKey> + Send,
+      V: TryInto<RedisValue> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn hrandfield<R, K>(&self, key: K, count: Option<(i64, bool)>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn hstrlen<R, K, F>(&self, key: K, field: F) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, F: Into<RedisKey> + Send];
+
+    fn hvals<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait HyperloglogInterface {
+    fn pfadd<R, K, V>(&self, key: K, elements: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn pfcount<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn pfmerge<R, D, S>(&self, dest: D, sources: S) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, S: Into<MultipleKeys> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait KeysInterface {
+    fn watch<K>(&self, keys: K) -> RedisResult<()>
+    where [K: Into<MultipleKeys> + Send];
+
+    fn unwatch(&self) -> RedisResult<()>;
+
+    fn randomkey<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn keys<R, P>(&self, pattern: P) -> RedisResult<R>
+    where [R: FromRedis, P: Into<Str> + Send];
+
+    fn copy<R, S, D>(&self, source: S, destination: D, db: Option<u8>, replace: bool) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisKey> + Send, D: Into<RedisKey> + Send];
+
+    fn dump<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn restore<R, K>(
+      &self,
+      key: K,
+      ttl: i64,
+      serialized: RedisValue,
+      replace: bool,
+      absttl: bool,
+      idletime: Option<i64>,
+      frequency: Option<i64>,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn set<R, K, V>(
+      &self,
+      key: K,
+      value: V,
+      expire: Option<Expiration>,
+      options: Option<SetOptions>,
+      get: bool,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn get<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn getrange<R, K>(&self, key: K, start: usize, end: usize) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn setrange<R, K, V>(&self, key: K, offset: u32, value: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn getset<R, K, V>(&self, key: K, value: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn getdel<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn strlen<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn del<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn unlink<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn rename<R, S, D>(&self, source: S, destination: D) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisKey> + Send, D: Into<RedisKey> + Send];
+
+    fn renamenx<R, S, D>(&self, source: S, destination: D) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisKey> + Send, D: Into<RedisKey> + Send];
+
+    fn append<R, K, V>(&self, key: K, value: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn mget<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn mset<V>(&self, values: V) -> RedisResult<()>
+    where [V: TryInto<RedisMap> + Send, V::Error: Into<RedisError> + Send];
+
+    fn msetnx<R, V>(&self, values: V) -> RedisResult<R>
+    where [R: FromRedis, V: TryInto<RedisMap> + Send, V::Error: Into<RedisError> + Send];
+
+    fn incr<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn incr_by<R, K>(&self, key: K, val: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn incr_by_float<R, K>(&self, key: K, val: f64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn decr<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn decr_by<R, K>(&self, key: K, val: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn ttl<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn pttl<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn persist<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn expire<R, K>(&self, key: K, seconds: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn expire_at<R, K>(&self, key: K, timestamp: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn exists<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn lcs<R, K1, K2>(
+      &self,
+      key1: K1,
+      key2: K2,
+      len: bool,
+      idx: bool,
+      minmatchlen: Option<i64>,
+      withmatchlen: bool,
+    ) -> Result<R, RedisError>
+    where [R: FromRedis, K1: Into<RedisKey> + Send, K2: Into<RedisKey> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait ListInterface {
+    fn blmpop<R, K>(&self, timeout: f64, keys: K, direction: LMoveDirection, count: Option<i64>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn blpop<R, K>(&self, keys: K, timeout: f64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn brpop<R, K>(&self, keys: K, timeout: f64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn brpoplpush<R, S, D>(&self, source: S, destination: D, timeout: f64) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisKey> + Send, D: Into<RedisKey> + Send];
+
+    fn blmove<R, S, D>(
+      &self,
+      source: S,
+      destination: D,
+      source_direction: LMoveDirection,
+      destination_direction: LMoveDirection,
+      timeout: f64,
+    ) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisKey> + Send, D: Into<RedisKey> + Send];
+
+    fn lmpop<R, K>(&self, keys: K, direction: LMoveDirection, count: Option<i64>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn lindex<R, K>(&self, key: K, index: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn linsert<R, K, P, V>(&self, key: K, location: ListLocation, pivot: P, element: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      P: TryInto<RedisValue> + Send,
+      P::Error: Into<RedisError> + Send,
+      V: TryInto<RedisValue> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn llen<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn lpop<R, K>(&self, key: K, count: Option<usize>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn lpos<R, K, V>(
+      &self,
+      key: K,
+      element: V,
+      rank: Option<i64>,
+      count: Option<i64>,
+      maxlen: Option<i64>,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn lpush<R, K, V>(&self, key: K, elements: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn lpushx<R, K, V>(&self, key: K, elements: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn lrange<R, K>(&self, key: K, start: i64, stop: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn lrem<R, K, V>(&self, key: K, count: i64, element: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn lset<R, K, V>(&self, key: K, index: i64, element: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn ltrim<R, K>(&self, key: K, start: i64, stop: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn rpop<R, K>(&self, key: K, count: Option<usize>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn rpoplpush<R, S, D>(&self, source: S, dest: D) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisKey> + Send, D: Into<RedisKey> + Send];
+
+    fn lmove<R, S, D>(
+      &self,
+      source: S,
+      dest: D,
+      source_direction: LMoveDirection,
+      dest_direction: LMoveDirection,
+    ) -> RedisResult<R>
+    where [R: FromRedis, S: Into<RedisKey> + Send, D: Into<RedisKey> + Send];
+
+    fn rpush<R, K, V>(&self, key: K, elements: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn rpushx<R, K, V>(&self, key: K, elements: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+  }
+}
+
+blocking_interface! {
+  pub trait LuaInterface {
+    fn script_load<R, S>(&self, script: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    #[cfg(feature = "sha-1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "sha-1")))]
+    fn script_load_cluster<R, S>(&self, script: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn script_kill(&self) -> RedisResult<()>;
+
+    fn script_kill_cluster(&self) -> RedisResult<()>;
+
+    fn script_flush(&self, r#async: bool) -> RedisResult<()>;
+
+    fn script_flush_cluster(&self, r#async: bool) -> RedisResult<()>;
+
+    fn script_exists<R, H>(&self, hashes: H) -> RedisResult<R>
+    where [R: FromRedis, H: Into<MultipleStrings> + Send];
+
+    fn script_debug(&self, flag: ScriptDebugFlag) -> RedisResult<()>;
+
+    fn evalsha<R, S, K, V>(&self, hash: S, keys: K, args: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      S: Into<Str> + Send,
+      K: Into<MultipleKeys> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn eval<R, S, K, V>(&self, script: S, keys: K, args: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      S: Into<Str> + Send,
+      K: Into<MultipleKeys> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+  }
+}
+
+blocking_interface! {
+  pub trait FunctionInterface {
+    fn fcall<R, F, K, V>(&self, func: F, keys: K, args: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      F: Into<Str> + Send,
+      K: Into<MultipleKeys> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn fcall_ro<R, F, K, V>(&self, func: F, keys: K, args: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      F: Into<Str> + Send,
+      K: Into<MultipleKeys> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn function_delete<R, S>(&self, library_name: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn function_delete_cluster<S>(&self, library_name: S) -> RedisResult<()>
+    where [S: Into<Str> + Send];
+
+    fn function_dump<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn function_flush<R>(&self, r#async: bool) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn function_flush_cluster(&self, r#async: bool) -> RedisResult<()>;
+
+    fn function_kill<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn function_list<R, S>(&self, library_name: Option<S>, withcode: bool) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn function_load<R, S>(&self, replace: bool, code: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn function_load_cluster<R, S>(&self, replace: bool, code: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn function_restore<R, B, P>(&self, serialized: B, policy: P) -> RedisResult<R>
+    where [R: FromRedis, B: Into<Bytes> + Send, P: TryInto<FnPolicy> + Send, P::Error: Into<RedisError> + Send];
+
+    fn function_restore_cluster<B, P>(&self, serialized: B, policy: P) -> RedisResult<()>
+    where [B: Into<Bytes> + Send, P: TryInto<FnPolicy> + Send, P::Error: Into<RedisError> + Send];
+
+    fn function_stats<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+  }
+}
+
+blocking_interface! {
+  pub trait MemoryInterface {
+    fn memory_doctor<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn memory_malloc_stats<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn memory_purge(&self) -> RedisResult<()>;
+
+    fn memory_stats<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn memory_usage<R, K>(&self, key: K, samples: Option<u32>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait PubsubInterface {
+    fn subscribe<R, S>(&self, channels: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<MultipleStrings> + Send];
+
+    fn unsubscribe<S>(&self, channels: S) -> RedisResult<()>
+    where [S: Into<MultipleStrings> + Send];
+
+    fn psubscribe<R, S>(&self, patterns: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<MultipleStrings> + Send];
+
+    fn punsubscribe<S>(&self, patterns: S) -> RedisResult<()>
+    where [S: Into<MultipleStrings> + Send];
+
+    fn publish<R, S, V>(&self, channel: S, message: V) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn ssubscribe<R, C>(&self, channels: C) -> RedisResult<R>
+    where [R: FromRedis, C: Into<MultipleStrings> + Send];
+
+    fn sunsubscribe<C>(&self, channels: C) -> RedisResult<()>
+    where [C: Into<MultipleStrings> + Send];
+
+    fn spublish<R, S, V>(&self, channel: S, message: V) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn pubsub_channels<R, S>(&self, pattern: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn pubsub_numpat<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn pubsub_numsub<R, S>(&self, channels: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<MultipleStrings> + Send];
+
+    fn pubsub_shardchannels<R, S>(&self, pattern: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<Str> + Send];
+
+    fn pubsub_shardnumsub<R, S>(&self, channels: S) -> RedisResult<R>
+    where [R: FromRedis, S: Into<MultipleStrings> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait AuthInterface {
+    fn auth<S>(&self, username: Option<String>, password: S) -> RedisResult<()>
+    where [S: Into<Str> + Send];
+
+    fn hello(&self, version: RespVersion, auth: Option<(String, String)>) -> RedisResult<()>;
+  }
+}
+
+blocking_interface! {
+  pub trait ServerInterface {
+    fn bgrewriteaof<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn bgsave<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn dbsize<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn flushall<R>(&self, r#async: bool) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn flushall_cluster(&self) -> RedisResult<()>;
+
+    fn select(&self, db: u8) -> RedisResult<()>;
+
+    fn failover(&self, to: Option<(String, u16)>, force: bool, abort: bool, timeout: Option<u32>) -> RedisResult<()>;
+
+    fn lastsave<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn wait<R>(&self, numreplicas: i64, timeout: i64) -> Result<R, RedisError>
+    where [R: FromRedis];
+  }
+}
+
+blocking_interface! {
+  pub trait SetsInterface {
+    fn sadd<R, K, V>(&self, key: K, members: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn scard<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn sdiff<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn sdiffstore<R, D, K>(&self, dest: D, keys: K) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, K: Into<MultipleKeys> + Send];
+
+    fn sinter<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn sinterstore<R, D, K>(&self, dest: D, keys: K) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, K: Into<MultipleKeys> + Send];
+
+    fn sismember<R, K, V>(&self, key: K, member: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn smismember<R, K, V>(&self, key: K, members: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn smembers<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn smove<R, S, D, V>(&self, source: S, dest: D, member: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      S: Into<RedisKey> + Send,
+      D: Into<RedisKey> + Send,
+      V: TryInto<RedisValue> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn spop<R, K>(&self, key: K, count: Option<usize>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn srandmember<R, K>(&self, key: K, count: Option<usize>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn srem<R, K, V>(&self, key: K, members: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn sunion<R, K>(&self, keys: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn sunionstore<R, D, K>(&self, dest: D, keys: K) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, K: Into<MultipleKeys> + Send];
+  }
+}
+
+blocking_interface! {
+  pub trait SlowlogInterface {
+    fn slowlog_get<R>(&self, count: Option<i64>) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn slowlog_length<R>(&self) -> RedisResult<R>
+    where [R: FromRedis];
+
+    fn slowlog_reset(&self) -> RedisResult<()>;
+  }
+}
+
+blocking_interface! {
+  pub trait SortedSetsInterface {
+    fn bzmpop<R, K>(&self, timeout: f64, keys: K, sort: ZCmp, count: Option<i64>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn bzpopmin<R, K>(&self, keys: K, timeout: f64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn bzpopmax<R, K>(&self, keys: K, timeout: f64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn zadd<R, K, V>(
+      &self,
+      key: K,
+      options: Option<SetOptions>,
+      ordering: Option<Ordering>,
+      changed: bool,
+      incr: bool,
+      values: V,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleZaddValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn zcard<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn zcount<R, K>(&self, key: K, min: f64, max: f64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn zdiff<R, K>(&self, keys: K, withscores: bool) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn zdiffstore<R, D, K>(&self, dest: D, keys: K) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, K: Into<MultipleKeys> + Send];
+
+    fn zincrby<R, K, V>(&self, key: K, increment: f64, member: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn zinter<R, K, W>(
+      &self,
+      keys: K,
+      weights: W,
+      aggregate: Option<AggregateOptions>,
+      withscores: bool,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send, W: Into<MultipleWeights> + Send];
+
+    fn zinterstore<R, D, K, W>(
+      &self,
+      dest: D,
+      keys: K,
+      weights: W,
+      aggregate: Option<AggregateOptions>,
+    ) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, K: Into<MultipleKeys> + Send, W: Into<MultipleWeights> + Send];
+
+    fn zlexcount<R, K, M, N>(&self, key: K, min: M, max: N) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zpopmax<R, K>(&self, key: K, count: Option<usize>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn zpopmin<R, K>(&self, key: K, count: Option<usize>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn zmpop<R, K>(&self, keys: K, sort: ZCmp, count: Option<i64>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send];
+
+    fn zrandmember<R, K>(&self, key: K, count: Option<(i64, bool)>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn zrangestore<R, D, S, M, N>(
+      &self,
+      dest: D,
+      source: S,
+      min: M,
+      max: N,
+      sort: Option<ZSort>,
+      rev: bool,
+      limit: Option<Limit>,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      D: Into<RedisKey> + Send,
+      S: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zrange<R, K, M, N>(
+      &self,
+      key: K,
+      min: M,
+      max: N,
+      sort: Option<ZSort>,
+      rev: bool,
+      limit: Option<Limit>,
+      withscores: bool,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zrangebylex<R, K, M, N>(&self, key: K, min: M, max: N, limit: Option<Limit>) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zrevrangebylex<R, K, M, N>(&self, key: K, max: M, min: N, limit: Option<Limit>) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zrangebyscore<R, K, M, N>(
+      &self,
+      key: K,
+      min: M,
+      max: N,
+      withscores: bool,
+      limit: Option<Limit>,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zrevrangebyscore<R, K, M, N>(
+      &self,
+      key: K,
+      max: M,
+      min: N,
+      withscores: bool,
+      limit: Option<Limit>,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zrank<R, K, V>(&self, key: K, member: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn zrem<R, K, V>(&self, key: K, members: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+
+    fn zremrangebylex<R, K, M, N>(&self, key: K, min: M, max: N) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zremrangebyrank<R, K>(&self, key: K, start: i64, stop: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn zremrangebyscore<R, K, M, N>(&self, key: K, min: M, max: N) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      M: TryInto<ZRange> + Send,
+      M::Error: Into<RedisError> + Send,
+      N: TryInto<ZRange> + Send,
+      N::Error: Into<RedisError> + Send,
+    ];
+
+    fn zrevrange<R, K>(&self, key: K, start: i64, stop: i64, withscores: bool) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn zrevrank<R, K, V>(&self, key: K, member: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn zscore<R, K, V>(&self, key: K, member: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, V: TryInto<RedisValue> + Send, V::Error: Into<RedisError> + Send];
+
+    fn zunion<R, K, W>(
+      &self,
+      keys: K,
+      weights: W,
+      aggregate: Option<AggregateOptions>,
+      withscores: bool,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send, W: Into<MultipleWeights> + Send];
+
+    fn zunionstore<R, D, K, W>(
+      &self,
+      dest: D,
+      keys: K,
+      weights: W,
+      aggregate: Option<AggregateOptions>,
+    ) -> RedisResult<R>
+    where [R: FromRedis, D: Into<RedisKey> + Send, K: Into<MultipleKeys> + Send, W: Into<MultipleWeights> + Send];
+
+    fn zmscore<R, K, V>(&self, key: K, members: V) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      V: TryInto<MultipleValues> + Send,
+      V::Error: Into<RedisError> + Send,
+    ];
+  }
+}
+
+blocking_interface! {
+  pub trait StreamsInterface {
+    fn xinfo_consumers<R, K, S>(&self, key: K, groupname: S) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, S: Into<Str> + Send];
+
+    fn xinfo_groups<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn xinfo_stream<R, K>(&self, key: K, full: bool, count: Option<u64>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn xadd<R, K, C, I, F>(&self, key: K, nomkstream: bool, cap: C, id: I, fields: F) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      I: Into<XID> + Send,
+      F: TryInto<MultipleOrderedPairs> + Send,
+      F::Error: Into<RedisError> + Send,
+      C: TryInto<XCap> + Send,
+      C::Error: Into<RedisError> + Send,
+    ];
+
+    fn xtrim<R, K, C>(&self, key: K, cap: C) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, C: TryInto<XCap> + Send, C::Error: Into<RedisError> + Send];
+
+    fn xdel<R, K, S>(&self, key: K, ids: S) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, S: Into<MultipleStrings> + Send];
+
+    fn xrange_values<Ri, Rk, Rv, K, S, E>(
+      &self,
+      key: K,
+      start: S,
+      end: E,
+      count: Option<u64>,
+    ) -> RedisResult<Vec<XReadValue<Ri, Rk, Rv>>>
+    where [
+      Ri: FromRedis,
+      Rk: FromRedisKey + Hash + Eq,
+      Rv: FromRedis,
+      K: Into<RedisKey> + Send,
+      S: TryInto<RedisValue> + Send,
+      S::Error: Into<RedisError> + Send,
+      E: TryInto<RedisValue> + Send,
+      E::Error: Into<RedisError> + Send,
+    ];
+
+    fn xrange<R, K, S, E>(&self, key: K, start: S, end: E, count: Option<u64>) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      S: TryInto<RedisValue> + Send,
+      S::Error: Into<RedisError> + Send,
+      E: TryInto<RedisValue> + Send,
+      E::Error: Into<RedisError> + Send,
+    ];
+
+    fn xrevrange_values<Ri, Rk, Rv, K, E, S>(
+      &self,
+      key: K,
+      end: E,
+      start: S,
+      count: Option<u64>,
+    ) -> RedisResult<Vec<XReadValue<Ri, Rk, Rv>>>
+    where [
+      Ri: FromRedis,
+      Rk: FromRedisKey + Hash + Eq,
+      Rv: FromRedis,
+      K: Into<RedisKey> + Send,
+      S: TryInto<RedisValue> + Send,
+      S::Error: Into<RedisError> + Send,
+      E: TryInto<RedisValue> + Send,
+      E::Error: Into<RedisError> + Send,
+    ];
+
+    fn xrevrange<R, K, S, E>(&self, key: K, end: E, start: S, count: Option<u64>) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      S: TryInto<RedisValue> + Send,
+      S::Error: Into<RedisError> + Send,
+      E: TryInto<RedisValue> + Send,
+      E::Error: Into<RedisError> + Send,
+    ];
+
+    fn xlen<R, K>(&self, key: K) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send];
+
+    fn xread_map<Rk1, Rk2, Rk3, Rv, K, I>(
+      &self,
+      count: Option<u64>,
+      block: Option<u64>,
+      keys: K,
+      ids: I,
+    ) -> RedisResult<XReadResponse<Rk1, Rk2, Rk3, Rv>>
+    where [
+      Rk1: FromRedisKey + Hash + Eq,
+      Rk2: FromRedis,
+      Rk3: FromRedisKey + Hash + Eq,
+      Rv: FromRedis,
+      K: Into<MultipleKeys> + Send,
+      I: Into<MultipleIDs> + Send,
+    ];
+
+    fn xread<R, K, I>(&self, count: Option<u64>, block: Option<u64>, keys: K, ids: I) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send, I: Into<MultipleIDs> + Send];
+
+    fn xgroup_create<R, K, S, I>(&self, key: K, groupname: S, id: I, mkstream: bool) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, S: Into<Str> + Send, I: Into<XID> + Send];
+
+    fn xgroup_createconsumer<R, K, G, C>(&self, key: K, groupname: G, consumername: C) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, G: Into<Str> + Send, C: Into<Str> + Send];
+
+    fn xgroup_delconsumer<R, K, G, C>(&self, key: K, groupname: G, consumername: C) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, G: Into<Str> + Send, C: Into<Str> + Send];
+
+    fn xgroup_destroy<R, K, S>(&self, key: K, groupname: S) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, S: Into<Str> + Send];
+
+    fn xgroup_setid<R, K, S, I>(&self, key: K, groupname: S, id: I) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, S: Into<Str> + Send, I: Into<XID> + Send];
+
+    fn xreadgroup_map<Rk1, Rk2, Rk3, Rv, G, C, K, I>(
+      &self,
+      group: G,
+      consumer: C,
+      count: Option<u64>,
+      block: Option<u64>,
+      noack: bool,
+      keys: K,
+      ids: I,
+    ) -> RedisResult<XReadResponse<Rk1, Rk2, Rk3, Rv>>
+    where [
+      Rk1: FromRedisKey + Hash + Eq,
+      Rk2: FromRedis,
+      Rk3: FromRedisKey + Hash + Eq,
+      Rv: FromRedis,
+      G: Into<Str> + Send,
+      C: Into<Str> + Send,
+      K: Into<MultipleKeys> + Send,
+      I: Into<MultipleIDs> + Send,
+    ];
+
+    fn xreadgroup<R, G, C, K, I>(
+      &self,
+      group: G,
+      consumer: C,
+      count: Option<u64>,
+      block: Option<u64>,
+      noack: bool,
+      keys: K,
+      ids: I,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      G: Into<Str> + Send,
+      C: Into<Str> + Send,
+      K: Into<MultipleKeys> + Send,
+      I: Into<MultipleIDs> + Send,
+    ];
+
+    fn xack<R, K, G, I>(&self, key: K, group: G, ids: I) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, G: Into<Str> + Send, I: Into<MultipleIDs> + Send];
+
+    fn xclaim_values<Ri, Rk, Rv, K, G, C, I>(
+      &self,
+      key: K,
+      group: G,
+      consumer: C,
+      min_idle_time: u64,
+      ids: I,
+      idle: Option<u64>,
+      time: Option<u64>,
+      retry_count: Option<u64>,
+      force: bool,
+      justid: bool,
+    ) -> RedisResult<Vec<XReadValue<Ri, Rk, Rv>>>
+    where [
+      Ri: FromRedis,
+      Rk: FromRedisKey + Hash + Eq,
+      Rv: FromRedis,
+      K: Into<RedisKey> + Send,
+      G: Into<Str> + Send,
+      C: Into<Str> + Send,
+      I: Into<MultipleIDs> + Send,
+    ];
+
+    fn xclaim<R, K, G, C, I>(
+      &self,
+      key: K,
+      group: G,
+      consumer: C,
+      min_idle_time: u64,
+      ids: I,
+      idle: Option<u64>,
+      time: Option<u64>,
+      retry_count: Option<u64>,
+      force: bool,
+      justid: bool,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      G: Into<Str> + Send,
+      C: Into<Str> + Send,
+      I: Into<MultipleIDs> + Send,
+    ];
+
+    fn xautoclaim_values<Ri, Rk, Rv, K, G, C, I>(
+      &self,
+      key: K,
+      group: G,
+      consumer: C,
+      min_idle_time: u64,
+      start: I,
+      count: Option<u64>,
+      justid: bool,
+    ) -> RedisResult<(String, Vec<XReadValue<Ri, Rk, Rv>>)>
+    where [
+      Ri: FromRedis,
+      Rk: FromRedisKey + Hash + Eq,
+      Rv: FromRedis,
+      K: Into<RedisKey> + Send,
+      G: Into<Str> + Send,
+      C: Into<Str> + Send,
+      I: Into<XID> + Send,
+    ];
+
+    fn xautoclaim<R, K, G, C, I>(
+      &self,
+      key: K,
+      group: G,
+      consumer: C,
+      min_idle_time: u64,
+      start: I,
+      count: Option<u64>,
+      justid: bool,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, G: Into<Str> + Send, C: Into<Str> + Send, I: Into<XID> + Send];
+
+    fn xpending<R, K, G, A>(&self, key: K, group: G, args: A) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, G: Into<Str> + Send, A: Into<XPendingArgs> + Send];
+  }
+}
+
+blocking_interface! {
+  #[cfg(feature = "redis-json")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "redis-json")))]
+  pub trait RedisJsonInterface {
+    fn json_arrappend<R, K, P, V>(&self, key: K, path: P, values: Vec<V>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_arrindex<R, K, P, V>(
+      &self,
+      key: K,
+      path: P,
+      value: V,
+      start: Option<i64>,
+      stop: Option<i64>,
+    ) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_arrinsert<R, K, P, V>(&self, key: K, path: P, index: i64, values: Vec<V>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_arrlen<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_arrpop<R, K, P>(&self, key: K, path: Option<P>, index: Option<i64>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_arrtrim<R, K, P>(&self, key: K, path: P, start: i64, stop: i64) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_clear<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_debug_memory<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_del<R, K, P>(&self, key: K, path: P) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_get<R, K, I, N, S, P>(
+      &self,
+      key: K,
+      indent: Option<I>,
+      newline: Option<N>,
+      space: Option<S>,
+      paths: P,
+    ) -> RedisResult<R>
+    where [
+      R: FromRedis,
+      K: Into<RedisKey> + Send,
+      I: Into<Str> + Send,
+      N: Into<Str> + Send,
+      S: Into<Str> + Send,
+      P: Into<MultipleStrings> + Send,
+    ];
+
+    fn json_merge<R, K, P, V>(&self, key: K, path: P, value: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_mget<R, K, P>(&self, keys: K, path: P) -> RedisResult<R>
+    where [R: FromRedis, K: Into<MultipleKeys> + Send, P: Into<Str> + Send];
+
+    fn json_mset<R, K, P, V>(&self, values: Vec<(K, P, V)>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_numincrby<R, K, P, V>(&self, key: K, path: P, value: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_objkeys<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_objlen<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_resp<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_set<R, K, P, V>(&self, key: K, path: P, value: V, options: Option<SetOptions>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_strappend<R, K, P, V>(&self, key: K, path: Option<P>, value: V) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send, V: Into<Value> + Send];
+
+    fn json_strlen<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_toggle<R, K, P>(&self, key: K, path: P) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+
+    fn json_type<R, K, P>(&self, key: K, path: Option<P>) -> RedisResult<R>
+    where [R: FromRedis, K: Into<RedisKey> + Send, P: Into<Str> + Send];
+  }
+}
+
+blocking_interface! {
+  #[cfg(feature = "client-tracking")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "client-tracking")))]
+  pub trait TrackingInterface {
+    fn start_tracking<P>(&self, prefixes: P, bcast: bool, optin: bool, optout: bool, noloop: bool) -> RedisResult<()>
+    where [P: Into<MultipleStrings> + Send];
+
+    fn stop_tracking(&self) -> RedisResult<()>;
+  }
+}
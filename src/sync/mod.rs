@@ -0,0 +1,483 @@
+//! A blocking client interface for callers that cannot use `async` functions.
+//!
+//! The [RedisClient] in this module wraps an async [RedisClient](crate::clients::RedisClient) and a dedicated Tokio
+//! runtime thread. Each command is sent through the async interfaces and the calling thread blocks until the
+//! response arrives. The blocking command interfaces are in the [interfaces] module, and mirror the async
+//! [interfaces](crate::interfaces) with the same function names and arguments.
+//!
+//! ```rust no_run
+//! use fred::{
+//!   sync::{interfaces::*, RedisClient},
+//!   types::RedisConfig,
+//! };
+//!
+//! fn main() -> Result<(), fred::error::RedisError> {
+//!   let client = RedisClient::new(RedisConfig::default(), None, None, None)?;
+//!   client.connect()?;
+//!
+//!   let _: () = client.set("foo", "bar", None, None, false)?;
+//!   assert_eq!(client.get::<String, _>("foo")?, "bar");
+//!
+//!   let pipeline = client.pipeline();
+//!   let _: () = pipeline.incr("bar")?;
+//!   let _: () = pipeline.incr("bar")?;
+//!   assert_eq!(pipeline.all::<Vec<i64>>()?, vec![1, 2]);
+//!
+//!   let trx = client.multi();
+//!   let _: () = trx.get("foo")?;
+//!   let _: () = trx.del("foo")?;
+//!   let (value, deleted): (String, i64) = trx.exec(true)?;
+//!
+//!   client.quit()
+//! }
+//! ```
+//!
+//! Functions on the blocking interfaces must not be called from within an async context, such as a Tokio task,
+//! since they block the calling thread. Interfaces that take async callbacks, such as
+//! [transaction_with_retry](crate::interfaces::TransactionInterface::transaction_with_retry), can be called with
+//! [block_on](interfaces::ClientLike::block_on) on the async client.
+
+use crate::{
+  clients::{Pipeline as AsyncPipeline, RedisClient as AsyncRedisClient, Transaction as AsyncTransaction},
+  error::RedisError,
+  interfaces::{ClientLike as AsyncClientLike, RedisResult, TransactionInterface as AsyncTransactionInterface},
+  types::{ClusterTransactionResult, ConnectionConfig, FromRedis, PerformanceConfig, ReconnectPolicy, RedisConfig},
+};
+use std::{
+  fmt,
+  future::Future,
+  sync::Arc,
+  thread::{self, JoinHandle as ThreadJoinHandle},
+};
+use tokio::{
+  runtime::{Builder as RuntimeBuilder, Handle},
+  sync::oneshot::{channel as oneshot_channel, Sender as OneshotSender},
+};
+
+pub mod interfaces;
+
+use interfaces::*;
+
+/// A Tokio runtime running on a dedicated thread, shared by a client and its pipelines and transactions.
+struct Runtime {
+  handle:   Handle,
+  shutdown: Option<OneshotSender<()>>,
+  thread:   Option<ThreadJoinHandle<()>>,
+}
+
+impl Runtime {
+  fn new() -> Result<Self, RedisError> {
+    let runtime = RuntimeBuilder::new_current_thread().enable_all().build()?;
+    let handle = runtime.handle().clone();
+    let (tx, rx) = oneshot_channel();
+    let thread = thread::Builder::new().name("fred-sync".into()).spawn(move || {
+      runtime.block_on(async {
+        let _ = rx.await;
+      });
+    })?;
+
+    Ok(Runtime {
+      handle,
+      shutdown: Some(tx),
+      thread: Some(thread),
+    })
+  }
+
+  fn block_on<F: Future>(&self, ft: F) -> F::Output {
+    self.handle.block_on(ft)
+  }
+}
+
+impl Drop for Runtime {
+  fn drop(&mut self) {
+    if let Some(tx) = self.shutdown.take() {
+      let _ = tx.send(());
+    }
+    if let Some(thread) = self.thread.take() {
+      // the last reference may be dropped by a task on the runtime thread
+      if thread.thread().id() != thread::current().id() {
+        let _ = thread.join();
+      }
+    }
+  }
+}
+
+/// A blocking Redis client.
+///
+/// Clones share the same connections and runtime thread. The runtime thread stops when the last clone, pipeline, or
+/// transaction is dropped.
+#[derive(Clone)]
+pub struct RedisClient {
+  client:  AsyncRedisClient,
+  runtime: Arc<Runtime>,
+}
+
+impl fmt::Debug for RedisClient {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("RedisClient").field("id", &self.client.id()).finish()
+  }
+}
+
+impl ClientLike for RedisClient {
+  type Client = AsyncRedisClient;
+
+  fn async_client(&self) -> &AsyncRedisClient {
+    &self.client
+  }
+
+  fn block_on<F: Future>(&self, ft: F) -> F::Output {
+    self.runtime.block_on(ft)
+  }
+}
+
+impl AclInterface for RedisClient {}
+impl ClientInterface for RedisClient {}
+impl ClusterInterface for RedisClient {}
+impl PubsubInterface for RedisClient {}
+impl ConfigInterface for RedisClient {}
+impl GeoInterface for RedisClient {}
+impl HashesInterface for RedisClient {}
+impl HyperloglogInterface for RedisClient {}
+impl KeysInterface for RedisClient {}
+impl LuaInterface for RedisClient {}
+impl ListInterface for RedisClient {}
+impl MemoryInterface for RedisClient {}
+impl AuthInterface for RedisClient {}
+impl ServerInterface for RedisClient {}
+impl SlowlogInterface for RedisClient {}
+impl SetsInterface for RedisClient {}
+impl SortedSetsInterface for RedisClient {}
+impl StreamsInterface for RedisClient {}
+impl FunctionInterface for RedisClient {}
+#[cfg(feature = "redis-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "redis-json")))]
+impl RedisJsonInterface for RedisClient {}
+#[cfg(feature = "client-tracking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "client-tracking")))]
+impl TrackingInterface for RedisClient {}
+
+impl RedisClient {
+  /// Create a new client instance and runtime thread without connecting to the server.
+  ///
+  /// See [RedisClient::new](crate::clients::RedisClient::new) for more information.
+  pub fn new(
+    config: RedisConfig,
+    perf: Option<PerformanceConfig>,
+    connection: Option<ConnectionConfig>,
+    policy: Option<ReconnectPolicy>,
+  ) -> Result<Self, RedisError> {
+    let runtime = Runtime::new()?;
+    let client = {
+      let _guard = runtime.handle.enter();
+      AsyncRedisClient::new(config, perf, connection, policy)
+    };
+
+    Ok(RedisClient {
+      client,
+      runtime: Arc::new(runtime),
+    })
+  }
+
+  /// Connect to the server, blocking until the first connection attempt finishes.
+  ///
+  /// The connection task runs on the runtime thread and will follow the reconnection policy if the connection
+  /// closes unexpectedly.
+  pub fn connect(&self) -> RedisResult<()> {
+    self.block_on(async {
+      let _connection = self.client.connect();
+      self.client.wait_for_connect().await
+    })
+  }
+
+  /// Send a series of commands in a [pipeline](https://redis.io/docs/manual/pipelining/).
+  pub fn pipeline(&self) -> Pipeline {
+    Pipeline {
+      pipeline: self.client.pipeline(),
+      runtime:  self.runtime.clone(),
+    }
+  }
+
+  /// Enter a MULTI block, executing subsequent commands as a transaction.
+  ///
+  /// <https://redis.io/commands/multi>
+  pub fn multi(&self) -> Transaction {
+    Transaction {
+      transaction: self.client.multi(),
+      runtime:     self.runtime.clone(),
+    }
+  }
+}
+
+/// A blocking client that buffers commands and sends them in a pipeline.
+///
+/// Commands return `QUEUED` when they are buffered. See [Pipeline](crate::clients::Pipeline) for more information.
+#[derive(Clone)]
+pub struct Pipeline {
+  pipeline: AsyncPipeline<AsyncRedisClient>,
+  runtime:  Arc<Runtime>,
+}
+
+impl fmt::Debug for Pipeline {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Pipeline").field("id", &self.pipeline.id()).finish()
+  }
+}
+
+impl ClientLike for Pipeline {
+  type Client = AsyncPipeline<AsyncRedisClient>;
+
+  fn async_client(&self) -> &AsyncPipeline<AsyncRedisClient> {
+    &self.pipeline
+  }
+
+  fn block_on<F: Future>(&self, ft: F) -> F::Output {
+    self.runtime.block_on(ft)
+  }
+}
+
+impl AclInterface for Pipeline {}
+impl ClientInterface for Pipeline {}
+impl ClusterInterface for Pipeline {}
+impl PubsubInterface for Pipeline {}
+impl ConfigInterface for Pipeline {}
+impl GeoInterface for Pipeline {}
+impl HashesInterface for Pipeline {}
+impl HyperloglogInterface for Pipeline {}
+impl KeysInterface for Pipeline {}
+impl ListInterface for Pipeline {}
+impl MemoryInterface for Pipeline {}
+impl AuthInterface for Pipeline {}
+impl ServerInterface for Pipeline {}
+impl SlowlogInterface for Pipeline {}
+impl SetsInterface for Pipeline {}
+impl SortedSetsInterface for Pipeline {}
+impl StreamsInterface for Pipeline {}
+impl FunctionInterface for Pipeline {}
+#[cfg(feature = "redis-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "redis-json")))]
+impl RedisJsonInterface for Pipeline {}
+
+impl Pipeline {
+  /// Send the pipeline and respond with an array of all the responses.
+  ///
+  /// See [Pipeline::all](crate::clients::Pipeline::all) for more information.
+  pub fn all<R>(&self) -> RedisResult<R>
+  where
+    R: FromRedis,
+  {
+    self.block_on(self.pipeline.all())
+  }
+
+  /// Send the pipeline and respond with each individual result.
+  ///
+  /// See [Pipeline::try_all](crate::clients::Pipeline::try_all) for more information.
+  pub fn try_all<R>(&self) -> Vec<RedisResult<R>>
+  where
+    R: FromRedis,
+  {
+    self.block_on(self.pipeline.try_all())
+  }
+
+  /// Send the pipeline and respond with only the result of the last command.
+  ///
+  /// See [Pipeline::last](crate::clients::Pipeline::last) for more information.
+  pub fn last<R>(&self) -> RedisResult<R>
+  where
+    R: FromRedis,
+  {
+    self.block_on(self.pipeline.last())
+  }
+}
+
+/// A blocking client that buffers commands and sends them in a transaction.
+///
+/// Commands return `QUEUED` when they are buffered. See [Transaction](crate::clients::Transaction) for more
+/// information.
+#[derive(Clone)]
+pub struct Transaction {
+  transaction: AsyncTransaction,
+  runtime:     Arc<Runtime>,
+}
+
+impl fmt::Debug for Transaction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("Transaction")
+      .field("id", &self.transaction.id())
+      .field("length", &self.transaction.len())
+      .finish()
+  }
+}
+
+impl ClientLike for Transaction {
+  type Client = AsyncTransaction;
+
+  fn async_client(&self) -> &AsyncTransaction {
+    &self.transaction
+  }
+
+  fn block_on<F: Future>(&self, ft: F) -> F::Output {
+    self.runtime.block_on(ft)
+  }
+}
+
+impl AclInterface for Transaction {}
+impl ClientInterface for Transaction {}
+impl PubsubInterface for Transaction {}
+impl ConfigInterface for Transaction {}
+impl GeoInterface for Transaction {}
+impl HashesInterface for Transaction {}
+impl HyperloglogInterface for Transaction {}
+impl KeysInterface for Transaction {}
+impl ListInterface for Transaction {}
+impl MemoryInterface for Transaction {}
+impl AuthInterface for Transaction {}
+impl ServerInterface for Transaction {}
+impl SetsInterface for Transaction {}
+impl SortedSetsInterface for Transaction {}
+impl StreamsInterface for Transaction {}
+impl FunctionInterface for Transaction {}
+#[cfg(feature = "redis-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "redis-json")))]
+impl RedisJsonInterface for Transaction {}
+
+impl Transaction {
+  /// Read the number of commands queued to run.
+  pub fn len(&self) -> usize {
+    self.transaction.len()
+  }
+
+  /// Whether the transaction contains no commands.
+  pub fn is_empty(&self) -> bool {
+    self.transaction.len() == 0
+  }
+
+  /// Clear the internal command buffer and watched keys.
+  pub fn reset(&self) {
+    self.transaction.reset();
+  }
+
+  /// Executes all previously queued commands in a transaction.
+  ///
+  /// See [Transaction::exec](crate::clients::Transaction::exec) for more information.
+  pub fn exec<R>(&self, abort_on_error: bool) -> RedisResult<R>
+  where
+    R: FromRedis,
+  {
+    self.block_on(self.transaction.exec(abort_on_error))
+  }
+
+  /// Execute a transaction that contains keys from different hash slots.
+  ///
+  /// See [Transaction::exec_cross_slot](crate::clients::Transaction::exec_cross_slot) for more information.
+  pub fn exec_cross_slot(&self, abort_on_error: bool) -> RedisResult<ClusterTransactionResult> {
+    self.block_on(self.transaction.exec_cross_slot(abort_on_error))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{error::RedisErrorKind, types::RedisValue};
+  use std::time::Duration;
+
+  #[cfg(feature = "fake")]
+  use crate::fake::Server as FakeServer;
+
+  /// Create a client that is not connected, so commands time out after 10 milliseconds.
+  fn create_disconnected_client() -> RedisClient {
+    let perf = PerformanceConfig {
+      default_command_timeout: Duration::from_millis(10),
+      ..Default::default()
+    };
+    RedisClient::new(RedisConfig::default(), Some(perf), None, None).unwrap()
+  }
+
+  #[test]
+  fn should_return_errors_from_blocking_commands() {
+    let client = create_disconnected_client();
+
+    let error = client.get::<String, _>("foo").unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::Timeout);
+    let error = client.ping::<String>().unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::Timeout);
+    assert!(!client.is_connected());
+  }
+
+  #[test]
+  fn should_buffer_blocking_pipeline_and_transaction_commands() {
+    let client = create_disconnected_client();
+
+    let pipeline = client.pipeline();
+    assert_eq!(pipeline.incr::<RedisValue, _>("foo").unwrap(), RedisValue::Queued);
+    let error = pipeline.all::<Vec<i64>>().unwrap_err();
+    assert_eq!(*error.kind(), RedisErrorKind::Timeout);
+
+    let trx = client.multi();
+    assert_eq!(trx.incr::<RedisValue, _>("foo").unwrap(), RedisValue::Queued);
+    let _: () = trx.get("foo").unwrap();
+    assert_eq!(trx.len(), 2);
+    trx.reset();
+    assert!(trx.is_empty());
+  }
+
+  #[cfg(feature = "fake")]
+  fn start_server() -> (tokio::runtime::Runtime, FakeServer) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let server = runtime.block_on(FakeServer::start()).unwrap();
+    (runtime, server)
+  }
+
+  #[cfg(feature = "fake")]
+  fn create_client(server: &FakeServer) -> RedisClient {
+    let client = RedisClient::new(server.redis_config().unwrap(), None, None, None).unwrap();
+    client.connect().unwrap();
+    client
+  }
+
+  #[cfg(feature = "fake")]
+  #[test]
+  fn should_run_blocking_commands() {
+    let (_runtime, server) = start_server();
+    let client = create_client(&server);
+
+    let _: () = client.set("foo", "bar", None, None, false).unwrap();
+    assert_eq!(client.get::<String, _>("foo").unwrap(), "bar");
+    assert_eq!(client.incr::<i64, _>("count").unwrap(), 1);
+    assert_eq!(client.ping::<String>().unwrap(), "PONG");
+    assert!(client.is_connected());
+
+    // clones share the runtime thread
+    let clone = client.clone();
+    drop(client);
+    assert_eq!(clone.get::<String, _>("foo").unwrap(), "bar");
+    clone.quit().unwrap();
+  }
+
+  #[cfg(feature = "fake")]
+  #[test]
+  fn should_run_blocking_pipeline() {
+    let (_runtime, server) = start_server();
+    let client = create_client(&server);
+
+    let pipeline = client.pipeline();
+    let _: () = pipeline.incr("foo").unwrap();
+    let _: () = pipeline.incr("foo").unwrap();
+    let _: () = pipeline.get("foo").unwrap();
+    assert_eq!(pipeline.all::<Vec<i64>>().unwrap(), vec![1, 2, 2]);
+    assert_eq!(pipeline.last::<i64>().unwrap(), 4);
+  }
+
+  #[cfg(feature = "fake")]
+  #[test]
+  fn should_run_blocking_transaction() {
+    let (_runtime, server) = start_server();
+    let client = create_client(&server);
+
+    let trx = client.multi();
+    let _: () = trx.set("foo", "bar", None, None, false).unwrap();
+    let _: () = trx.get("foo").unwrap();
+    assert_eq!(trx.len(), 2);
+    let results: Vec<RedisValue> = trx.exec(true).unwrap();
+    assert_eq!(results, vec!["OK".into(), "bar".into()]);
+  }
+}
@@ -3,11 +3,11 @@
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
 use crate::modules::inner::RedisClientInner;
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
-use crate::protocol::command::RedisCommand;
+use crate::protocol::{command::RedisCommand, types::Server};
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
 use redis_protocol::resp3::types::Frame;
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 
 /// Fake span for mocking tracing functions.
 #[cfg(not(feature = "full-tracing"))]
@@ -23,12 +23,34 @@ impl Span {
 }
 
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
-pub fn set_network_span(_inner: &Arc<RedisClientInner>, _command: &mut RedisCommand, _flush: bool) {}
+pub fn set_network_span(_inner: &Arc<RedisClientInner>, _command: &mut RedisCommand, _server: &Server, _flush: bool) {
+}
 
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
 pub fn create_pubsub_span(_inner: &Arc<RedisClientInner>, _frame: &Frame) -> Option<Span> {
   Some(Span {})
 }
 
+#[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
+pub fn retry_event(_cmd: &RedisCommand, _: bool, _: bool) {}
+
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
 pub fn backpressure_event(_cmd: &RedisCommand, _: Option<u128>) {}
+
+#[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
+pub fn create_pipeline_span(_inner: &Arc<RedisClientInner>, _len: usize) -> Span {
+  Span {}
+}
+
+#[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
+pub fn create_transaction_span(_inner: &Arc<RedisClientInner>, _id: u64, _len: usize) -> Span {
+  Span {}
+}
+
+#[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
+pub fn set_child_command_span(_inner: &Arc<RedisClientInner>, _parent: &Span, _command: &mut RedisCommand) {}
+
+#[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
+pub fn instrument<F: Future>(_span: Span, ft: F) -> F {
+  ft
+}
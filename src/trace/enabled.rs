@@ -1,11 +1,12 @@
 use crate::{
   modules::inner::RedisClientInner,
-  protocol::{command::RedisCommand, utils as protocol_utils},
+  protocol::{command::RedisCommand, types::Server, utils as protocol_utils},
 };
 use redis_protocol::resp3::types::Frame;
-use std::{fmt, ops::Deref, sync::Arc};
+use std::{fmt, future::Future, ops::Deref, sync::Arc};
 pub use tracing::span::Span;
 use tracing::{event, field::Empty, Id as TraceId, Level};
+use tracing_futures::{Instrument, Instrumented};
 
 #[cfg(not(feature = "full-tracing"))]
 use crate::trace::disabled::Span as FakeSpan;
@@ -45,8 +46,15 @@ impl fmt::Debug for CommandTraces {
   }
 }
 
-pub fn set_network_span(inner: &Arc<RedisClientInner>, command: &mut RedisCommand, flush: bool) {
+pub fn set_network_span(inner: &Arc<RedisClientInner>, command: &mut RedisCommand, server: &Server, flush: bool) {
   trace!("Setting network span from command {}", command.debug_id());
+  if inner.config.tracing.semantic_conventions {
+    if let Some(span) = command.traces.cmd.as_ref() {
+      span.record("net.peer.name", server.host.deref());
+      span.record("net.peer.port", server.port);
+    }
+  }
+
   let span = fspan!(command, inner.tracing_span_level(), "wait_for_response", flush);
   let _ = span.in_scope(|| {});
   command.traces.network = Some(span);
@@ -56,16 +64,140 @@ pub fn record_response_size(span: &Span, frame: &Frame) {
   span.record("res_size", &protocol_utils::resp3_frame_size(frame));
 }
 
-pub fn create_command_span(inner: &Arc<RedisClientInner>) -> Span {
-  span_lvl!(
+/// Record the `db.system`, `db.redis.database_index`, and `otel.kind` fields on a span.
+fn record_db_fields(inner: &Arc<RedisClientInner>, span: &Span) {
+  span.record("otel.kind", "client");
+  span.record("db.system", "redis");
+  span.record("db.redis.database_index", inner.config.database.unwrap_or(0));
+}
+
+/// Create the `db.statement` value for a command, optionally replacing each argument with `?`.
+fn command_statement(command: &RedisCommand, redact: bool) -> String {
+  let args = command.args();
+  let mut out = String::from(command.kind.to_str_debug());
+
+  for arg in args.iter() {
+    out.push(' ');
+    if redact {
+      out.push('?');
+    } else {
+      match arg.as_str_lossy() {
+        Some(arg) => out.push_str(&arg),
+        None => out.push('?'),
+      };
+    }
+  }
+
+  out
+}
+
+fn command_span(inner: &Arc<RedisClientInner>, parent: Option<TraceId>) -> Span {
+  let span = span_lvl!(
     inner.tracing_span_level(),
+    parent: parent,
     "redis_command",
     module = "fred",
     client_id = &inner.id.deref(),
     cmd = Empty,
     req_size = Empty,
-    res_size = Empty
-  )
+    res_size = Empty,
+    otel.name = Empty,
+    otel.kind = Empty,
+    db.system = Empty,
+    db.statement = Empty,
+    db.redis.database_index = Empty,
+    net.peer.name = Empty,
+    net.peer.port = Empty
+  );
+
+  if inner.config.tracing.semantic_conventions {
+    record_db_fields(inner, &span);
+  }
+  span
+}
+
+pub fn create_command_span(inner: &Arc<RedisClientInner>) -> Span {
+  command_span(inner, Span::current().id())
+}
+
+/// Record the `otel.name` and `db.statement` fields on a command span.
+pub fn record_statement(inner: &Arc<RedisClientInner>, span: &Span, command: &RedisCommand) {
+  if inner.config.tracing.semantic_conventions {
+    span.record("otel.name", command.kind.to_str_debug());
+    span.record(
+      "db.statement",
+      command_statement(command, inner.config.tracing.redact_statement).as_str(),
+    );
+  }
+}
+
+/// Create a span for a pipeline that will be used as the parent for each command in the pipeline.
+pub fn create_pipeline_span(inner: &Arc<RedisClientInner>, len: usize) -> Span {
+  if !inner.should_trace() {
+    return Span::none();
+  }
+
+  let span = span_lvl!(
+    inner.tracing_span_level(),
+    "redis_pipeline",
+    module = "fred",
+    client_id = &inner.id.deref(),
+    num_cmds = len,
+    otel.name = Empty,
+    otel.kind = Empty,
+    db.system = Empty,
+    db.redis.database_index = Empty
+  );
+
+  if inner.config.tracing.semantic_conventions {
+    span.record("otel.name", "PIPELINE");
+    record_db_fields(inner, &span);
+  }
+  span
+}
+
+/// Create a span for a transaction that will be used as the parent for each command in the transaction.
+pub fn create_transaction_span(inner: &Arc<RedisClientInner>, id: u64, len: usize) -> Span {
+  if !inner.should_trace() {
+    return Span::none();
+  }
+
+  let span = span_lvl!(
+    inner.tracing_span_level(),
+    "redis_transaction",
+    module = "fred",
+    client_id = &inner.id.deref(),
+    trx_id = id,
+    num_cmds = len,
+    otel.name = Empty,
+    otel.kind = Empty,
+    db.system = Empty,
+    db.redis.database_index = Empty
+  );
+
+  if inner.config.tracing.semantic_conventions {
+    span.record("otel.name", "MULTI");
+    record_db_fields(inner, &span);
+  }
+  span
+}
+
+/// Enter the pipeline or transaction span each time the future is polled.
+pub fn instrument<F: Future>(span: Span, ft: F) -> Instrumented<F> {
+  ft.instrument(span)
+}
+
+/// Attach a new command span to a command inside a pipeline or transaction.
+pub fn set_child_command_span(inner: &Arc<RedisClientInner>, parent: &Span, command: &mut RedisCommand) {
+  if !inner.should_trace() {
+    return;
+  }
+
+  let span = command_span(inner, parent.id());
+  span.record("cmd", command.kind.to_str_debug());
+  span.record("req_size", protocol_utils::args_size(command.args()));
+  record_statement(inner, &span, command);
+  command.traces.cmd = Some(span);
 }
 
 #[cfg(feature = "full-tracing")]
@@ -113,6 +245,15 @@ pub fn create_pubsub_span(_inner: &Arc<RedisClientInner>, _frame: &Frame) -> Opt
   Some(FakeSpan {})
 }
 
+pub fn retry_event(cmd: &RedisCommand, retry: bool, idempotent: bool) {
+  let id = cmd.traces.cmd.as_ref().and_then(|c| c.id());
+  if retry {
+    event!(parent: id, Level::INFO, "retry attempt={} idempotent={}", cmd.write_attempts, idempotent);
+  } else {
+    event!(parent: id, Level::INFO, "retry skipped idempotent={}", idempotent);
+  }
+}
+
 pub fn backpressure_event(cmd: &RedisCommand, duration: Option<u128>) {
   let id = cmd.traces.cmd.as_ref().and_then(|c| c.id());
   if let Some(duration) = duration {
@@ -121,3 +262,181 @@ pub fn backpressure_event(cmd: &RedisCommand, duration: Option<u128>) {
     event!(parent: id, Level::INFO, "backpressure drain");
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    clients::RedisClient,
+    interfaces::ClientLike,
+    protocol::command::RedisCommandKind,
+    types::{RedisConfig, TracingConfig},
+  };
+  use parking_lot::Mutex;
+  use std::collections::HashMap;
+  use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Record},
+    Event,
+    Id,
+    Metadata,
+    Subscriber,
+  };
+
+  #[derive(Debug, Default)]
+  struct RecordedSpan {
+    name:   &'static str,
+    parent: Option<u64>,
+    fields: HashMap<String, String>,
+  }
+
+  struct Fields<'a>(&'a mut HashMap<String, String>);
+
+  impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+      self.0.insert(field.name().to_owned(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+      self.0.insert(field.name().to_owned(), format!("{:?}", value));
+    }
+  }
+
+  /// A subscriber that stores each span and its recorded fields.
+  #[derive(Clone, Default)]
+  struct Recorder {
+    spans: Arc<Mutex<Vec<RecordedSpan>>>,
+  }
+
+  impl Recorder {
+    fn find(&self, name: &str) -> Vec<(u64, Option<u64>, HashMap<String, String>)> {
+      self
+        .spans
+        .lock()
+        .iter()
+        .enumerate()
+        .filter(|(_, span)| span.name == name)
+        .map(|(idx, span)| (idx as u64 + 1, span.parent, span.fields.clone()))
+        .collect()
+    }
+  }
+
+  impl Subscriber for Recorder {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+      true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+      let mut span = RecordedSpan {
+        name: attrs.metadata().name(),
+        parent: attrs.parent().map(|id| id.into_u64()),
+        ..Default::default()
+      };
+      attrs.record(&mut Fields(&mut span.fields));
+
+      let mut spans = self.spans.lock();
+      spans.push(span);
+      Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, id: &Id, values: &Record<'_>) {
+      if let Some(span) = self.spans.lock().get_mut(id.into_u64() as usize - 1) {
+        values.record(&mut Fields(&mut span.fields));
+      }
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+  }
+
+  fn create_inner(semantic_conventions: bool, redact_statement: bool) -> Arc<RedisClientInner> {
+    let mut tracing = TracingConfig::new(true);
+    tracing
+      .set_semantic_conventions(semantic_conventions)
+      .set_redact_statement(redact_statement);
+    let config = RedisConfig {
+      database: Some(2),
+      tracing,
+      ..Default::default()
+    };
+
+    RedisClient::new(config, None, None, None).inner().clone()
+  }
+
+  fn set_command() -> RedisCommand {
+    RedisCommand::new(RedisCommandKind::Set, vec!["foo".into(), "bar".into()])
+  }
+
+  #[test]
+  fn should_redact_statement_arguments() {
+    let command = set_command();
+    assert_eq!(command_statement(&command, true), "SET ? ?");
+    assert_eq!(command_statement(&command, false), "SET foo bar");
+  }
+
+  #[test]
+  fn should_record_semantic_convention_fields() {
+    let recorder = Recorder::default();
+    let inner = create_inner(true, false);
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+      let mut command = set_command();
+      let span = create_command_span(&inner);
+      record_statement(&inner, &span, &command);
+      command.traces.cmd = Some(span);
+      set_network_span(&inner, &mut command, &Server::new("127.0.0.1", 6379), true);
+    });
+
+    let (_, _, fields) = recorder.find("redis_command").pop().unwrap();
+    assert_eq!(fields["otel.name"], "SET");
+    assert_eq!(fields["otel.kind"], "client");
+    assert_eq!(fields["db.system"], "redis");
+    assert_eq!(fields["db.statement"], "SET foo bar");
+    assert_eq!(fields["db.redis.database_index"], "2");
+    assert_eq!(fields["net.peer.name"], "127.0.0.1");
+    assert_eq!(fields["net.peer.port"], "6379");
+  }
+
+  #[test]
+  fn should_skip_semantic_convention_fields() {
+    let recorder = Recorder::default();
+    let inner = create_inner(false, true);
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+      let span = create_command_span(&inner);
+      record_statement(&inner, &span, &set_command());
+    });
+
+    let (_, _, fields) = recorder.find("redis_command").pop().unwrap();
+    assert!(!fields.contains_key("db.system"));
+    assert!(!fields.contains_key("db.statement"));
+  }
+
+  #[test]
+  fn should_attach_command_spans_to_pipeline_and_transaction_spans() {
+    let recorder = Recorder::default();
+    let inner = create_inner(true, true);
+
+    tracing::subscriber::with_default(recorder.clone(), || {
+      let pipeline = create_pipeline_span(&inner, 1);
+      set_child_command_span(&inner, &pipeline, &mut set_command());
+      let transaction = create_transaction_span(&inner, 1, 1);
+      set_child_command_span(&inner, &transaction, &mut set_command());
+    });
+
+    let (pipeline_id, _, pipeline) = recorder.find("redis_pipeline").pop().unwrap();
+    let (transaction_id, _, transaction) = recorder.find("redis_transaction").pop().unwrap();
+    assert_eq!(pipeline["otel.name"], "PIPELINE");
+    assert_eq!(transaction["otel.name"], "MULTI");
+
+    let commands = recorder.find("redis_command");
+    assert_eq!(commands[0].1, Some(pipeline_id));
+    assert_eq!(commands[1].1, Some(transaction_id));
+    assert_eq!(commands[1].2["db.statement"], "SET ? ?");
+  }
+}
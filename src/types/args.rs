@@ -13,7 +13,7 @@ use std::{
 use bytes::Bytes;
 use bytes_utils::Str;
 use float_cmp::approx_eq;
-use redis_protocol::resp2::types::NULL;
+use redis_protocol::{resp2::types::NULL, resp3::types::VerbatimStringFormat};
 #[cfg(feature = "serde-json")]
 use serde_json::Value;
 
@@ -504,6 +504,9 @@ pub enum RedisValueKind {
   Queued,
   Map,
   Array,
+  BigNumber,
+  VerbatimString,
+  Set,
 }
 
 impl fmt::Display for RedisValueKind {
@@ -518,6 +521,9 @@ impl fmt::Display for RedisValueKind {
       RedisValueKind::Queued => "Queued",
       RedisValueKind::Map => "Map",
       RedisValueKind::Array => "Array",
+      RedisValueKind::BigNumber => "BigNumber",
+      RedisValueKind::VerbatimString => "VerbatimString",
+      RedisValueKind::Set => "Set",
     };
 
     write!(f, "{}", s)
@@ -525,6 +531,10 @@ impl fmt::Display for RedisValueKind {
 }
 
 /// A value used in a Redis command.
+///
+/// The `BigNumber`, `VerbatimString`, `Set`, and `Attributed` variants are only produced when parsing responses with
+/// the `resp3-types` feature. Without it these RESP3 types are converted to strings and arrays, and attributes are
+/// dropped.
 #[derive(Clone, Debug)]
 pub enum RedisValue {
   /// A boolean value.
@@ -547,6 +557,20 @@ pub enum RedisValue {
   ///
   /// In RESP2 mode the server usually sends map structures as an array of key/value pairs.
   Array(Vec<RedisValue>),
+  /// A RESP3 big number, stored as a string of digits.
+  BigNumber(Str),
+  /// A RESP3 verbatim string and its format.
+  VerbatimString {
+    format: VerbatimStringFormat,
+    data:   Bytes,
+  },
+  /// A RESP3 set, in the order the values were sent by the server.
+  Set(Vec<RedisValue>),
+  /// A value and the RESP3 attributes the server attached to it.
+  Attributed {
+    value:      Box<RedisValue>,
+    attributes: RedisMap,
+  },
 }
 
 #[allow(clippy::match_like_matches_macro)]
@@ -591,6 +615,31 @@ impl PartialEq for RedisValue {
         Array(ref o) => s == o,
         _ => false,
       },
+      BigNumber(ref s) => match other {
+        BigNumber(ref o) => s == o,
+        _ => false,
+      },
+      VerbatimString { ref format, ref data } => match other {
+        VerbatimString {
+          format: ref other_format,
+          data: ref other_data,
+        } => format == other_format && data == other_data,
+        _ => false,
+      },
+      Set(ref s) => match other {
+        Set(ref o) => s == o,
+        _ => false,
+      },
+      Attributed {
+        ref value,
+        ref attributes,
+      } => match other {
+        Attributed {
+          value: ref other_value,
+          attributes: ref other_attributes,
+        } => value == other_value && attributes == other_attributes,
+        _ => false,
+      },
     }
   }
 }
@@ -645,6 +694,56 @@ impl RedisValue {
       RedisValue::Queued => RedisValueKind::Queued,
       RedisValue::Map(_) => RedisValueKind::Map,
       RedisValue::Array(_) => RedisValueKind::Array,
+      RedisValue::BigNumber(_) => RedisValueKind::BigNumber,
+      RedisValue::VerbatimString { .. } => RedisValueKind::VerbatimString,
+      RedisValue::Set(_) => RedisValueKind::Set,
+      RedisValue::Attributed { ref value, .. } => value.kind(),
+    }
+  }
+
+  /// Whether the value is one of the RESP3 variants that are only produced with the `resp3-types` feature.
+  pub(crate) fn is_resp3_type(&self) -> bool {
+    matches!(
+      *self,
+      RedisValue::BigNumber(_)
+        | RedisValue::VerbatimString { .. }
+        | RedisValue::Set(_)
+        | RedisValue::Attributed { .. }
+    )
+  }
+
+  /// Read the attributes the server attached to the value, if any.
+  pub fn attributes(&self) -> Option<&RedisMap> {
+    match *self {
+      RedisValue::Attributed { ref attributes, .. } => Some(attributes),
+      _ => None,
+    }
+  }
+
+  /// Split the value into the inner value and the attributes the server attached to it, if any.
+  pub fn take_attributes(self) -> (RedisValue, Option<RedisMap>) {
+    match self {
+      RedisValue::Attributed { value, attributes } => (*value, Some(attributes)),
+      _ => (self, None),
+    }
+  }
+
+  /// Recursively convert the RESP3 variants to the values used without the `resp3-types` feature.
+  ///
+  /// Big numbers and verbatim strings are converted to strings, sets are converted to arrays, and attributes are
+  /// dropped.
+  pub fn into_lossy(self) -> RedisValue {
+    match self {
+      RedisValue::BigNumber(s) => RedisValue::String(s),
+      RedisValue::VerbatimString { data, .. } => protocol_utils::string_or_bytes(data),
+      RedisValue::Set(values) | RedisValue::Array(values) => {
+        RedisValue::Array(values.into_iter().map(|v| v.into_lossy()).collect())
+      },
+      RedisValue::Map(map) => RedisValue::Map(RedisMap {
+        inner: map.inner.into_iter().map(|(k, v)| (k, v.into_lossy())).collect(),
+      }),
+      RedisValue::Attributed { value, .. } => value.into_lossy(),
+      _ => self,
     }
   }
 
@@ -701,7 +800,12 @@ impl RedisValue {
 
   /// Whether or not the value is an array or map.
   pub fn is_aggregate_type(&self) -> bool {
-    matches!(*self, RedisValue::Array(_) | RedisValue::Map(_))
+    match *self {
+      RedisValue::Array(_) | RedisValue::Map(_) => true,
+      RedisValue::Set(_) => true,
+      RedisValue::Attributed { ref value, .. } => value.is_aggregate_type(),
+      _ => false,
+    }
   }
 
   /// Whether or not the value is a `RedisMap`.
@@ -721,6 +825,8 @@ impl RedisValue {
     match *self {
       RedisValue::Map(_) => true,
       RedisValue::Array(ref arr) => utils::is_maybe_array_map(arr),
+      RedisValue::Set(ref arr) => utils::is_maybe_array_map(arr),
+      RedisValue::Attributed { ref value, .. } => value.is_maybe_map(),
       _ => false,
     }
   }
@@ -740,8 +846,8 @@ impl RedisValue {
           None
         }
       },
-      RedisValue::String(ref s) => s.parse::<u64>().ok(),
-      RedisValue::Array(ref inner) => {
+      RedisValue::String(ref s) | RedisValue::BigNumber(ref s) => s.parse::<u64>().ok(),
+      RedisValue::Array(ref inner) | RedisValue::Set(ref inner) => {
         if inner.len() == 1 {
           inner.first().and_then(|v| v.as_u64())
         } else {
@@ -752,6 +858,8 @@ impl RedisValue {
       RedisValue::Null => Some(0),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      RedisValue::VerbatimString { ref data, .. } => protocol_utils::string_or_bytes(data.clone()).as_u64(),
+      RedisValue::Attributed { ref value, .. } => value.as_u64(),
       _ => None,
     }
   }
@@ -760,8 +868,8 @@ impl RedisValue {
   pub fn as_i64(&self) -> Option<i64> {
     match self {
       RedisValue::Integer(ref i) => Some(*i),
-      RedisValue::String(ref s) => s.parse::<i64>().ok(),
-      RedisValue::Array(ref inner) => {
+      RedisValue::String(ref s) | RedisValue::BigNumber(ref s) => s.parse::<i64>().ok(),
+      RedisValue::Array(ref inner) | RedisValue::Set(ref inner) => {
         if inner.len() == 1 {
           inner.first().and_then(|v| v.as_i64())
         } else {
@@ -772,6 +880,8 @@ impl RedisValue {
       RedisValue::Null => Some(0),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      RedisValue::VerbatimString { ref data, .. } => protocol_utils::string_or_bytes(data.clone()).as_i64(),
+      RedisValue::Attributed { ref value, .. } => value.as_i64(),
       _ => None,
     }
   }
@@ -786,8 +896,8 @@ impl RedisValue {
           None
         }
       },
-      RedisValue::String(ref s) => s.parse::<usize>().ok(),
-      RedisValue::Array(ref inner) => {
+      RedisValue::String(ref s) | RedisValue::BigNumber(ref s) => s.parse::<usize>().ok(),
+      RedisValue::Array(ref inner) | RedisValue::Set(ref inner) => {
         if inner.len() == 1 {
           inner.first().and_then(|v| v.as_usize())
         } else {
@@ -798,6 +908,8 @@ impl RedisValue {
       RedisValue::Null => Some(0),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      RedisValue::VerbatimString { ref data, .. } => protocol_utils::string_or_bytes(data.clone()).as_usize(),
+      RedisValue::Attributed { ref value, .. } => value.as_usize(),
       _ => None,
     }
   }
@@ -806,9 +918,9 @@ impl RedisValue {
   pub fn as_f64(&self) -> Option<f64> {
     match self {
       RedisValue::Double(ref f) => Some(*f),
-      RedisValue::String(ref s) => utils::redis_string_to_f64(s).ok(),
+      RedisValue::String(ref s) | RedisValue::BigNumber(ref s) => utils::redis_string_to_f64(s).ok(),
       RedisValue::Integer(ref i) => Some(*i as f64),
-      RedisValue::Array(ref inner) => {
+      RedisValue::Array(ref inner) | RedisValue::Set(ref inner) => {
         if inner.len() == 1 {
           inner.first().and_then(|v| v.as_f64())
         } else {
@@ -819,6 +931,8 @@ impl RedisValue {
       RedisValue::Null => Some(0.0),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      RedisValue::VerbatimString { ref data, .. } => protocol_utils::string_or_bytes(data.clone()).as_f64(),
+      RedisValue::Attributed { ref value, .. } => value.as_f64(),
       _ => None,
     }
   }
@@ -843,6 +957,7 @@ impl RedisValue {
       RedisValue::Null => Some(String::new()),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      _ if self.is_resp3_type() => self.into_lossy().into_string(),
       _ => None,
     }
   }
@@ -870,6 +985,7 @@ impl RedisValue {
       RedisValue::Null => Some(Str::new()),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      _ if self.is_resp3_type() => self.into_lossy().into_bytes_str(),
       _ => None,
     }
   }
@@ -882,11 +998,11 @@ impl RedisValue {
         false => Some(FALSE_STR.clone()),
       },
       RedisValue::Double(ref f) => Some(f.to_string().into()),
-      RedisValue::String(ref s) => Some(s.clone()),
+      RedisValue::String(ref s) | RedisValue::BigNumber(ref s) => Some(s.clone()),
       RedisValue::Bytes(ref b) => Str::from_inner(b.clone()).ok(),
       RedisValue::Integer(ref i) => Some(i.to_string().into()),
       RedisValue::Queued => Some(utils::static_str(QUEUED)),
-      RedisValue::Array(ref inner) => {
+      RedisValue::Array(ref inner) | RedisValue::Set(ref inner) => {
         if inner.len() == 1 {
           inner[0].as_bytes_str()
         } else {
@@ -897,6 +1013,8 @@ impl RedisValue {
       RedisValue::Null => Some(Str::new()),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      RedisValue::VerbatimString { ref data, .. } => protocol_utils::string_or_bytes(data.clone()).as_bytes_str(),
+      RedisValue::Attributed { ref value, .. } => value.as_bytes_str(),
       _ => None,
     }
   }
@@ -916,6 +1034,9 @@ impl RedisValue {
       RedisValue::Null => Some(String::new()),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      RedisValue::BigNumber(ref s) => Some(s.to_string()),
+      RedisValue::VerbatimString { ref data, .. } => str::from_utf8(data).ok().map(|s| s.to_owned()),
+      RedisValue::Attributed { ref value, .. } => value.as_string(),
       _ => None,
     }
   }
@@ -935,6 +1056,9 @@ impl RedisValue {
       RedisValue::Null => Cow::Borrowed(""),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => return None,
+      RedisValue::BigNumber(ref s) => Cow::Borrowed(s.deref()),
+      RedisValue::VerbatimString { ref data, .. } => return str::from_utf8(data).ok().map(Cow::Borrowed),
+      RedisValue::Attributed { ref value, .. } => return value.as_str(),
       _ => return None,
     };
 
@@ -954,6 +1078,9 @@ impl RedisValue {
       RedisValue::Null => Cow::Borrowed(""),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => return None,
+      RedisValue::BigNumber(ref s) => Cow::Borrowed(s.deref()),
+      RedisValue::VerbatimString { ref data, .. } => String::from_utf8_lossy(data),
+      RedisValue::Attributed { ref value, .. } => return value.as_str_lossy(),
       _ => return None,
     };
 
@@ -966,6 +1093,9 @@ impl RedisValue {
       RedisValue::String(ref s) => Some(s.as_bytes()),
       RedisValue::Bytes(ref b) => Some(b),
       RedisValue::Queued => Some(QUEUED.as_bytes()),
+      RedisValue::BigNumber(ref s) => Some(s.as_bytes()),
+      RedisValue::VerbatimString { ref data, .. } => Some(data),
+      RedisValue::Attributed { ref value, .. } => value.as_bytes(),
       _ => None,
     }
   }
@@ -979,12 +1109,12 @@ impl RedisValue {
         1 => Some(true),
         _ => None,
       },
-      RedisValue::String(ref s) => match s.as_bytes() {
+      RedisValue::String(ref s) | RedisValue::BigNumber(ref s) => match s.as_bytes() {
         b"true" | b"TRUE" | b"t" | b"T" | b"1" => Some(true),
         b"false" | b"FALSE" | b"f" | b"F" | b"0" => Some(false),
         _ => None,
       },
-      RedisValue::Array(ref inner) => {
+      RedisValue::Array(ref inner) | RedisValue::Set(ref inner) => {
         if inner.len() == 1 {
           inner.first().and_then(|v| v.as_bool())
         } else {
@@ -995,6 +1125,8 @@ impl RedisValue {
       RedisValue::Null => Some(false),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => None,
+      RedisValue::VerbatimString { ref data, .. } => protocol_utils::string_or_bytes(data.clone()).as_bool(),
+      RedisValue::Attributed { ref value, .. } => value.as_bool(),
       _ => None,
     }
   }
@@ -1022,6 +1154,7 @@ impl RedisValue {
       },
       #[cfg(feature = "default-nil-types")]
       RedisValue::Null => Ok(RedisMap::new()),
+      _ if self.is_resp3_type() => self.into_lossy().into_map(),
       _ => Err(RedisError::new(RedisErrorKind::Unknown, "Could not convert to map.")),
     }
   }
@@ -1045,6 +1178,7 @@ impl RedisValue {
       RedisValue::Array(values) => Ok(values.into_iter().collect()),
       #[cfg(feature = "default-nil-types")]
       RedisValue::Null => Ok(HashSet::new()),
+      _ if self.is_resp3_type() => self.into_lossy().into_set(),
       _ => Err(RedisError::new_parse("Could not convert to set.")),
     }
   }
@@ -1069,6 +1203,7 @@ impl RedisValue {
         }
         out
       },
+      _ if self.is_resp3_type() => self.into_lossy().into_array(),
       _ => vec![self],
     }
   }
@@ -1091,6 +1226,7 @@ impl RedisValue {
       RedisValue::Null => Vec::new(),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => return None,
+      _ if self.is_resp3_type() => return self.into_lossy().into_owned_bytes(),
       _ => return None,
     };
 
@@ -1115,6 +1251,7 @@ impl RedisValue {
       RedisValue::Null => Bytes::new(),
       #[cfg(not(feature = "default-nil-types"))]
       RedisValue::Null => return None,
+      _ if self.is_resp3_type() => return self.into_lossy().into_bytes(),
       _ => return None,
     };
 
@@ -1255,10 +1392,18 @@ impl RedisValue {
   }
 
   /// Attempt to convert this value to any value that implements the [FromRedis](crate::types::FromRedis) trait.
+  ///
+  /// With the `resp3-types` feature the RESP3 variants are only preserved when converting to a `RedisValue`. Other
+  /// types receive the value from [into_lossy](Self::into_lossy).
   pub fn convert<R>(self) -> Result<R, RedisError>
   where
     R: FromRedis,
   {
+    #[cfg(feature = "resp3-types")]
+    if !R::preserves_resp3_types() {
+      return R::from_value(self.into_lossy());
+    }
+
     R::from_value(self)
   }
 
@@ -1277,6 +1422,9 @@ impl RedisValue {
         | RedisValueKind::Null
         | RedisValueKind::Array
         | RedisValueKind::Queued
+        | RedisValueKind::BigNumber
+        | RedisValueKind::VerbatimString
+        | RedisValueKind::Set
     )
   }
 
@@ -1301,6 +1449,9 @@ impl Hash for RedisValue {
       RedisValueKind::Array => b'a',
       RedisValueKind::Map => b'm',
       RedisValueKind::Bytes => b'b',
+      RedisValueKind::BigNumber => b'N',
+      RedisValueKind::VerbatimString => b'v',
+      RedisValueKind::Set => b'S',
     };
     prefix.hash(state);
 
@@ -1317,6 +1468,14 @@ impl Hash for RedisValue {
           value.hash(state);
         }
       },
+      RedisValue::BigNumber(ref s) => s.hash(state),
+      RedisValue::VerbatimString { ref data, .. } => data.hash(state),
+      RedisValue::Set(ref values) => {
+        for value in values.iter() {
+          value.hash(state);
+        }
+      },
+      RedisValue::Attributed { ref value, .. } => value.hash(state),
       _ => panic!("Cannot hash aggregate value."),
     }
   }
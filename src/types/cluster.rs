@@ -1,7 +1,8 @@
 pub use crate::protocol::types::{ClusterRouting, SlotRange};
 use crate::{
   error::{RedisError, RedisErrorKind},
-  types::RedisValue,
+  protocol::types::Server,
+  types::{FromRedis, RedisValue},
   utils,
 };
 use bytes_utils::Str;
@@ -151,3 +152,86 @@ impl ClusterSetSlotState {
     (utils::static_str(prefix), value)
   }
 }
+
+/// The result of the `MULTI`/`EXEC` block for one hash slot in a cross slot transaction.
+#[derive(Clone, Debug)]
+pub struct ClusterTransactionGroup {
+  /// The hash slot associated with the commands, if any.
+  pub hash_slot: Option<u16>,
+  /// The cluster node that owned the hash slot when the transaction started, if known.
+  pub server:    Option<Server>,
+  /// The index of each command in the original transaction.
+  pub commands:  Vec<usize>,
+  /// The response to `EXEC`, or `nil` if the transaction was aborted due to a `WATCH` conflict.
+  pub result:    Result<RedisValue, RedisError>,
+}
+
+impl ClusterTransactionGroup {
+  /// Whether the transaction finished without an error and was not aborted.
+  pub fn is_ok(&self) -> bool {
+    matches!(self.result, Ok(ref value) if !value.is_null())
+  }
+}
+
+/// The results from a cross slot transaction, with one group per hash slot.
+///
+/// Each group runs as a separate `MULTI`/`EXEC` block, so some groups may succeed while others fail. Callers are
+/// responsible for deciding how to compensate for partial failures.
+///
+/// See [exec_cross_slot](crate::clients::Transaction::exec_cross_slot) for more information.
+#[derive(Clone, Debug)]
+pub struct ClusterTransactionResult {
+  /// The result from each group.
+  pub groups: Vec<ClusterTransactionGroup>,
+}
+
+impl ClusterTransactionResult {
+  /// Whether every group finished without an error and was not aborted.
+  pub fn is_ok(&self) -> bool {
+    self.groups.iter().all(|group| group.is_ok())
+  }
+
+  /// Read the groups that returned an error or were aborted.
+  pub fn failed(&self) -> impl Iterator<Item = &ClusterTransactionGroup> {
+    self.groups.iter().filter(|group| !group.is_ok())
+  }
+
+  /// Read and convert the result of the command at `index` in the original transaction.
+  ///
+  /// Returns an error if the group containing the command failed or was aborted.
+  pub fn get<R>(&self, index: usize) -> Result<R, RedisError>
+  where
+    R: FromRedis,
+  {
+    for group in self.groups.iter() {
+      let position = match group.commands.iter().position(|idx| *idx == index) {
+        Some(position) => position,
+        None => continue,
+      };
+
+      return match group.result {
+        Ok(RedisValue::Array(ref values)) => match values.get(position) {
+          Some(value) => value.clone().convert(),
+          None => Err(RedisError::new(RedisErrorKind::Unknown, "Missing transaction result.")),
+        },
+        Ok(RedisValue::Null) => Err(RedisError::new(RedisErrorKind::Canceled, "Transaction aborted.")),
+        Ok(ref value) => {
+          if group.commands.len() == 1 {
+            value.clone().convert()
+          } else {
+            Err(RedisError::new(
+              RedisErrorKind::Parse,
+              "Expected array of transaction results.",
+            ))
+          }
+        },
+        Err(ref error) => Err(error.clone()),
+      };
+    }
+
+    Err(RedisError::new(
+      RedisErrorKind::InvalidArgument,
+      format!("Invalid transaction result index: {}", index),
+    ))
+  }
+}
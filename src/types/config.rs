@@ -1,12 +1,19 @@
 pub use crate::protocol::types::Server;
 use crate::{error::RedisError, protocol::command::RedisCommand, types::RespVersion, utils};
+use bytes_utils::Str;
 use socket2::TcpKeepalive;
 use std::{cmp, time::Duration};
 use url::Url;
 
+#[cfg(feature = "faults")]
+use crate::faults::Faults;
+#[cfg(feature = "middleware")]
+use crate::middleware::Middleware;
 #[cfg(feature = "mocks")]
 use crate::mocks::Mocks;
-#[cfg(feature = "mocks")]
+#[cfg(feature = "replay")]
+use crate::replay::Recorder;
+#[cfg(any(feature = "mocks", feature = "middleware", feature = "faults"))]
 use std::sync::Arc;
 
 #[cfg(any(feature = "enable-rustls", feature = "enable-native-tls"))]
@@ -211,6 +218,82 @@ impl ReconnectPolicy {
   }
 }
 
+/// The policy used to decide whether a command should be written again after a connection closes while the command
+/// is in flight.
+///
+/// Retries are also limited by the `max_command_attempts` and `max_attempts` settings, and only occur if the client
+/// has a [ReconnectPolicy]. The delay between attempts starts at `delay` ms and is multiplied by `mult` on each
+/// subsequent attempt, up to `max_delay` ms. Delayed commands are written after the delay without blocking other
+/// commands, so they may be written after commands that were sent later.
+///
+/// This does not apply to commands inside a transaction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RetryPolicy {
+  /// Never write the command more than once.
+  Never,
+  /// Only retry idempotent commands.
+  ///
+  /// Commands such as `INCR`, `LPUSH`, `LPOP`, `XADD`, scripts, and custom commands are not retried.
+  Idempotent {
+    delay:     u32,
+    max_delay: u32,
+    mult:      u32,
+  },
+  /// Retry all commands.
+  Always {
+    delay:     u32,
+    max_delay: u32,
+    mult:      u32,
+  },
+}
+
+impl Default for RetryPolicy {
+  fn default() -> Self {
+    RetryPolicy::Always {
+      delay:     0,
+      max_delay: 0,
+      mult:      1,
+    }
+  }
+}
+
+impl RetryPolicy {
+  /// Create a new retry policy that only retries idempotent commands.
+  pub fn new_idempotent(delay: u32, max_delay: u32, mult: u32) -> RetryPolicy {
+    RetryPolicy::Idempotent { delay, max_delay, mult }
+  }
+
+  /// Create a new retry policy that retries all commands.
+  pub fn new_always(delay: u32, max_delay: u32, mult: u32) -> RetryPolicy {
+    RetryPolicy::Always { delay, max_delay, mult }
+  }
+
+  /// Whether a command with the provided idempotency should be retried.
+  pub fn should_retry(&self, idempotent: bool) -> bool {
+    match *self {
+      RetryPolicy::Never => false,
+      RetryPolicy::Idempotent { .. } => idempotent,
+      RetryPolicy::Always { .. } => true,
+    }
+  }
+
+  /// Calculate the amount of time to wait before the next attempt, given the number of previous write attempts.
+  pub fn delay(&self, attempts: u32) -> Option<Duration> {
+    let (delay, max_delay, mult) = match *self {
+      RetryPolicy::Never => return None,
+      RetryPolicy::Idempotent { delay, max_delay, mult } => (delay, max_delay, mult),
+      RetryPolicy::Always { delay, max_delay, mult } => (delay, max_delay, mult),
+    };
+    if delay == 0 {
+      return None;
+    }
+
+    let factor = cmp::max(mult, 1).saturating_pow(attempts.saturating_sub(1));
+    let delay = cmp::min(delay.saturating_mul(factor), cmp::max(max_delay, delay));
+    Some(Duration::from_millis(delay as u64))
+  }
+}
+
 /// Describes how the client should respond when a command is sent while the client is in a blocked state from a
 /// blocking command.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -229,6 +312,25 @@ impl Default for Blocking {
   }
 }
 
+/// How the client chooses the RESP version on each connection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RespNegotiation {
+  /// Use the configured `version` on every connection.
+  Fixed,
+  /// Try RESP3 via `HELLO 3` on each new connection and fall back to RESP2 and `AUTH` if the server rejects
+  /// `HELLO`.
+  ///
+  /// The version is negotiated again whenever the client reconnects, so failing over to an older server or a
+  /// RESP2-only proxy switches the client to RESP2, and switching back to a newer server upgrades it to RESP3.
+  Auto,
+}
+
+impl Default for RespNegotiation {
+  fn default() -> Self {
+    RespNegotiation::Fixed
+  }
+}
+
 /// Backpressure policies to apply when the max number of in-flight commands is reached on a connection.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum BackpressurePolicy {
@@ -299,6 +401,41 @@ impl Default for BackpressureConfig {
   }
 }
 
+/// Configuration options for the circuit breaker associated with each server.
+///
+/// The circuit opens after `max_errors` connection errors or timeouts occur within `window`. A timeout is counted
+/// when the caller stops waiting on a command that was written to the server. Timeouts on pipelines and
+/// transactions are not counted. While the circuit is open commands routed to the server fail immediately with a
+/// `RedisErrorKind::CircuitOpen` error. After `open_duration` the circuit moves to a half-open state and the client
+/// sends a `PING` to the server. If the `PING` succeeds the circuit closes, otherwise it opens again.
+///
+/// State changes are reported via the [EventInterface](crate::interfaces::EventInterface).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitBreakerConfig {
+  /// The number of errors or timeouts within `window` that will open the circuit.
+  ///
+  /// Default: `5`
+  pub max_errors:    u32,
+  /// The amount of time over which errors and timeouts are counted.
+  ///
+  /// Default: 10 sec
+  pub window:        Duration,
+  /// The amount of time the circuit stays open before sending a probe `PING`.
+  ///
+  /// Default: 5 sec
+  pub open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+  fn default() -> Self {
+    CircuitBreakerConfig {
+      max_errors:    5,
+      window:        Duration::from_millis(10_000),
+      open_duration: Duration::from_millis(5_000),
+    }
+  }
+}
+
 /// TCP configuration options.
 #[derive(Clone, Debug, Default)]
 pub struct TcpConfig {
@@ -363,6 +500,10 @@ pub struct ConnectionConfig {
   pub replica:                    ReplicaConfig,
   /// TCP connection options.
   pub tcp:                        TcpConfig,
+  /// An optional circuit breaker configuration applied to each server.
+  ///
+  /// Default: `None`
+  pub circuit_breaker:            Option<CircuitBreakerConfig>,
 }
 
 impl Default for ConnectionConfig {
@@ -374,6 +515,7 @@ impl Default for ConnectionConfig {
       max_command_attempts: 3,
       cluster_cache_update_delay: Duration::from_millis(0),
       tcp: TcpConfig::default(),
+      circuit_breaker: None,
       #[cfg(feature = "check-unresponsive")]
       unresponsive_timeout: Duration::from_millis(10_000),
       #[cfg(feature = "replicas")]
@@ -434,36 +576,44 @@ pub struct RedisConfig {
   /// difficult.
   ///
   /// Default: `true`
-  pub fail_fast: bool,
+  pub fail_fast:   bool,
   /// The default behavior of the client when a command is sent while the connection is blocked on a blocking
   /// command.
   ///
   /// Default: `Blocking::Block`
-  pub blocking:  Blocking,
+  pub blocking:    Blocking,
   /// An optional ACL username for the client to use when authenticating. If ACL rules are not configured this should
   /// be `None`.
   ///
   /// Default: `None`
-  pub username:  Option<String>,
+  pub username:    Option<String>,
   /// An optional password for the client to use when authenticating.
   ///
   /// Default: `None`
-  pub password:  Option<String>,
+  pub password:    Option<String>,
   /// Connection configuration for the server(s).
   ///
   /// Default: `Centralized(localhost, 6379)`
-  pub server:    ServerConfig,
+  pub server:      ServerConfig,
   /// The protocol version to use when communicating with the server(s).
   ///
   /// If RESP3 is specified the client will automatically use `HELLO` when authenticating. **This requires Redis
-  /// >=6.0.0.** If the `HELLO` command fails this will prevent the client from connecting. Callers should set this
-  /// to RESP2 and use `HELLO` manually to fall back to RESP2 if needed.
+  /// >=6.0.0.** If the `HELLO` command fails this will prevent the client from connecting. Callers should set
+  /// `negotiation` to `Auto` to fall back to RESP2 if needed.
   ///
   /// Note: upgrading an existing codebase from RESP2 to RESP3 may require changing certain type signatures. RESP3
   /// has a slightly different type system than RESP2.
   ///
   /// Default: `RESP2`
-  pub version:   RespVersion,
+  pub version:     RespVersion,
+  /// Whether to negotiate the protocol version with the server instead of always using `version`.
+  ///
+  /// When `Auto` is used `version` is ignored and the negotiated version is available via
+  /// [protocol_version](crate::interfaces::ClientLike::protocol_version). Authentication errors are not retried with
+  /// `AUTH`.
+  ///
+  /// Default: `Fixed`
+  pub negotiation: RespNegotiation,
   /// An optional database number that the client will automatically `SELECT` after connecting or reconnecting.
   ///
   /// It is recommended that callers use this field instead of putting a `select()` call inside the `on_reconnect`
@@ -471,23 +621,54 @@ pub struct RedisConfig {
   /// the `on_reconnect` block.
   ///
   /// Default: `None`
-  pub database:  Option<u8>,
+  pub database:    Option<u8>,
+  /// A prefix added to each key argument before commands are sent to the server.
+  ///
+  /// The prefix is included when calculating cluster hash slots, and is added to `SCAN` and `KEYS` patterns with any
+  /// glob-style characters escaped. It is removed from the keys returned by `SCAN` and `KEYS` and from keyspace
+  /// notifications. Key arguments are found from the key specifications of known Redis commands, which are also used
+  /// by the `FirstKey` [ClusterHash](crate::types::ClusterHash) policy. Other custom commands use the key selected
+  /// by their `ClusterHash`, if any. Lua script bodies and arguments that refer to other keys (such as `SORT ...
+  /// BY` patterns) are not modified.
+  ///
+  /// See [Options::key_prefix](crate::types::Options::key_prefix) to override this on individual commands.
+  ///
+  /// Default: `None`
+  pub key_prefix:  Option<Str>,
   /// TLS configuration options.
   ///
   /// Default: `None`
   #[cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))]
   #[cfg_attr(docsrs, doc(cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))))]
-  pub tls:       Option<TlsConfig>,
+  pub tls:         Option<TlsConfig>,
   /// Tracing configuration options.
   #[cfg(feature = "partial-tracing")]
   #[cfg_attr(docsrs, doc(cfg(feature = "partial-tracing")))]
-  pub tracing:   TracingConfig,
+  pub tracing:     TracingConfig,
   /// An optional [mocking layer](crate::mocks) to intercept and process commands.
   ///
   /// Default: `None`
   #[cfg(feature = "mocks")]
   #[cfg_attr(docsrs, doc(cfg(feature = "mocks")))]
-  pub mocks:     Option<Arc<dyn Mocks>>,
+  pub mocks:       Option<Arc<dyn Mocks>>,
+  /// An ordered list of [middleware](crate::middleware) used to inspect or modify commands and responses.
+  ///
+  /// Default: `Vec::new()`
+  #[cfg(feature = "middleware")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "middleware")))]
+  pub middleware:  Vec<Arc<dyn Middleware>>,
+  /// An optional list of [faults](crate::faults) to inject into responses.
+  ///
+  /// Default: `None`
+  #[cfg(feature = "faults")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "faults")))]
+  pub faults:      Option<Arc<Faults>>,
+  /// An optional [recorder](crate::replay::Recorder) that writes each command and its result.
+  ///
+  /// Default: `None`
+  #[cfg(feature = "replay")]
+  #[cfg_attr(docsrs, doc(cfg(feature = "replay")))]
+  pub recorder:    Option<Arc<Recorder>>,
 }
 
 impl PartialEq for RedisConfig {
@@ -496,9 +677,11 @@ impl PartialEq for RedisConfig {
       && self.database == other.database
       && self.fail_fast == other.fail_fast
       && self.version == other.version
+      && self.negotiation == other.negotiation
       && self.username == other.username
       && self.password == other.password
       && self.blocking == other.blocking
+      && self.key_prefix == other.key_prefix
   }
 }
 
@@ -513,13 +696,21 @@ impl Default for RedisConfig {
       password: None,
       server: ServerConfig::default(),
       version: RespVersion::RESP2,
+      negotiation: RespNegotiation::Fixed,
       database: None,
+      key_prefix: None,
       #[cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))]
       tls: None,
       #[cfg(feature = "partial-tracing")]
       tracing: TracingConfig::default(),
       #[cfg(feature = "mocks")]
       mocks: None,
+      #[cfg(feature = "middleware")]
+      middleware: Vec::new(),
+      #[cfg(feature = "faults")]
+      faults: None,
+      #[cfg(feature = "replay")]
+      recorder: None,
     }
   }
 }
@@ -900,6 +1091,12 @@ impl ServerConfig {
 }
 
 /// Configuration options for tracing.
+///
+/// Callers should use [new](Self::new) or `Default` and the setter methods, or `..Default::default()` in struct
+/// literals, since new fields may be added in minor releases.
+///
+/// The W3C trace context is not sent to the server. `CLIENT SETINFO` only accepts the `LIB-NAME` and `LIB-VER`
+/// attributes, so spans cannot be correlated with server-side logs via the connection.
 #[cfg(feature = "partial-tracing")]
 #[cfg_attr(docsrs, doc(cfg(feature = "partial-tracing")))]
 #[derive(Clone, Debug)]
@@ -920,6 +1117,17 @@ pub struct TracingConfig {
   #[cfg(feature = "full-tracing")]
   #[cfg_attr(docsrs, doc(cfg(feature = "full-tracing")))]
   pub full_tracing_level: tracing::Level,
+
+  /// Whether or not to emit the fields described by the [OpenTelemetry database semantic conventions](https://opentelemetry.io/docs/specs/semconv/database/redis/)
+  /// (`db.system`, `db.statement`, `net.peer.name`, etc) on command, pipeline, and transaction spans.
+  ///
+  /// Default: `false`
+  pub semantic_conventions: bool,
+
+  /// Whether or not to replace command arguments with `?` in the `db.statement` field.
+  ///
+  /// Default: `true`
+  pub redact_statement: bool,
 }
 
 #[cfg(feature = "partial-tracing")]
@@ -931,6 +1139,18 @@ impl TracingConfig {
       ..Self::default()
     }
   }
+
+  /// Set whether to emit the OpenTelemetry database semantic convention fields.
+  pub fn set_semantic_conventions(&mut self, enabled: bool) -> &mut Self {
+    self.semantic_conventions = enabled;
+    self
+  }
+
+  /// Set whether to replace command arguments with `?` in the `db.statement` field.
+  pub fn set_redact_statement(&mut self, redact: bool) -> &mut Self {
+    self.redact_statement = redact;
+    self
+  }
 }
 
 #[cfg(feature = "partial-tracing")]
@@ -942,6 +1162,8 @@ impl Default for TracingConfig {
       default_tracing_level:                               tracing::Level::INFO,
       #[cfg(feature = "full-tracing")]
       full_tracing_level:                                  tracing::Level::DEBUG,
+      semantic_conventions:                                false,
+      redact_statement:                                    true,
     }
   }
 }
@@ -1010,16 +1232,24 @@ impl From<SentinelConfig> for RedisConfig {
       },
       fail_fast: true,
       database: None,
+      key_prefix: None,
       blocking: Blocking::Block,
       username: config.username,
       password: config.password,
       version: RespVersion::RESP2,
+      negotiation: RespNegotiation::Fixed,
       #[cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))]
       tls: config.tls,
       #[cfg(feature = "partial-tracing")]
       tracing: config.tracing,
       #[cfg(feature = "mocks")]
       mocks: None,
+      #[cfg(feature = "middleware")]
+      middleware: Vec::new(),
+      #[cfg(feature = "faults")]
+      faults: None,
+      #[cfg(feature = "replay")]
+      recorder: None,
     }
   }
 }
@@ -1069,6 +1299,20 @@ pub struct Options {
   pub cluster_node:     Option<Server>,
   /// Whether to skip backpressure checks for a command.
   pub no_backpressure:  bool,
+  /// A prefix to add to each key argument, overriding the `key_prefix` on the client's `RedisConfig`.
+  ///
+  /// This does not apply to the scanning interfaces, which always use the prefix from the client's `RedisConfig`.
+  pub key_prefix:       Option<Str>,
+  /// The policy used to decide whether the command should be retried after a connection error.
+  ///
+  /// Default: [RetryPolicy::Always] without a delay.
+  pub retry_policy:     Option<RetryPolicy>,
+  /// Whether to split multi-key commands (`MGET`, `MSET`, `DEL`, `UNLINK`, `EXISTS`, `TOUCH`) into one command per
+  /// hash slot when the keys belong to different cluster nodes.
+  ///
+  /// The pieces are sent to each cluster node in parallel and the responses are merged into one response. Note that
+  /// the split command is not atomic. Pipelines always split cross slot commands when connected to a cluster.
+  pub split_cross_slot: bool,
   /// Whether to send `CLIENT CACHING yes|no` before the command.
   #[cfg(feature = "client-tracking")]
   #[cfg_attr(docsrs, doc(cfg(feature = "client-tracking")))]
@@ -1090,7 +1334,14 @@ impl Options {
     if let Some(ref val) = other.cluster_node {
       self.cluster_node = Some(val.clone());
     }
+    if let Some(ref val) = other.key_prefix {
+      self.key_prefix = Some(val.clone());
+    }
+    if let Some(ref val) = other.retry_policy {
+      self.retry_policy = Some(val.clone());
+    }
     self.no_backpressure |= other.no_backpressure;
+    self.split_cross_slot |= other.split_cross_slot;
 
     #[cfg(feature = "client-tracking")]
     if let Some(val) = other.caching {
@@ -1108,6 +1359,9 @@ impl Options {
       timeout:                                     cmd.timeout_dur,
       no_backpressure:                             cmd.skip_backpressure,
       cluster_node:                                cmd.cluster_node.clone(),
+      key_prefix:                                  cmd.key_prefix.clone(),
+      retry_policy:                                cmd.retry_policy.clone(),
+      split_cross_slot:                            cmd.split_cross_slot,
       #[cfg(feature = "client-tracking")]
       caching:                                     cmd.caching.clone(),
     }
@@ -1118,6 +1372,9 @@ impl Options {
     command.skip_backpressure = self.no_backpressure;
     command.timeout_dur = self.timeout;
     command.cluster_node = self.cluster_node.clone();
+    command.key_prefix = self.key_prefix.clone();
+    command.retry_policy = self.retry_policy.clone();
+    command.split_cross_slot = self.split_cross_slot;
 
     #[cfg(feature = "client-tracking")]
     {
@@ -1381,4 +1638,27 @@ mod tests {
     let actual = RedisConfig::from_url_sentinel(url).unwrap();
     assert_eq!(actual, expected);
   }
+
+  #[test]
+  fn should_calculate_retry_delay() {
+    use super::RetryPolicy;
+    use std::time::Duration;
+
+    let policy = RetryPolicy::new_idempotent(100, 1000, 2);
+    assert_eq!(policy.delay(1), Some(Duration::from_millis(100)));
+    assert_eq!(policy.delay(3), Some(Duration::from_millis(400)));
+    assert_eq!(policy.delay(10), Some(Duration::from_millis(1000)));
+    assert_eq!(RetryPolicy::default().delay(1), None);
+    assert_eq!(RetryPolicy::Never.delay(1), None);
+  }
+
+  #[test]
+  fn should_retry_based_on_idempotency() {
+    use super::RetryPolicy;
+
+    assert!(RetryPolicy::new_idempotent(0, 0, 1).should_retry(true));
+    assert!(!RetryPolicy::new_idempotent(0, 0, 1).should_retry(false));
+    assert!(RetryPolicy::new_always(0, 0, 1).should_retry(false));
+    assert!(!RetryPolicy::Never.should_retry(true));
+  }
 }
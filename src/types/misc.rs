@@ -38,6 +38,26 @@ pub struct KeyspaceEvent {
   pub key:       RedisKey,
 }
 
+/// The state of the circuit breaker associated with a server.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum CircuitBreakerState {
+  /// Commands are sent to the server normally.
+  Closed,
+  /// Commands routed to the server fail immediately.
+  Open,
+  /// The client is sending a `PING` to the server to decide whether to close the circuit.
+  HalfOpen,
+}
+
+/// An event emitted when the state of a server's circuit breaker changes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CircuitBreakerEvent {
+  /// The server associated with the circuit breaker.
+  pub server: Server,
+  /// The new state of the circuit breaker.
+  pub state:  CircuitBreakerState,
+}
+
 /// Aggregate options for the [zinterstore](https://redis.io/commands/zinterstore) (and related) commands.
 pub enum AggregateOptions {
   Sum,
@@ -98,7 +118,8 @@ pub struct CustomCommand {
   pub cmd:          Str,
   /// The cluster hashing policy to use, if any.
   ///
-  /// Cluster clients will use the default policy if not provided.
+  /// Cluster clients will use the default policy if not provided. If the command name is not a known Redis command
+  /// the policy also selects the key argument that receives the [key prefix](crate::types::RedisConfig::key_prefix).
   pub cluster_hash: ClusterHash,
   /// Whether or not the command should block the connection while waiting on a response.
   pub blocking:     bool,
@@ -621,3 +642,53 @@ impl TryFrom<&Str> for FnPolicy {
     FnPolicy::from_str(value)
   }
 }
+
+/// Options for the [get_or_compute](crate::interfaces::KeysInterface::get_or_compute) cache-aside interface.
+#[cfg(feature = "serde-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde-json")))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CacheOptions {
+  /// The amount of time a computed value is considered fresh.
+  pub ttl:       Duration,
+  /// The maximum fraction of `ttl` randomly added to the expiration of each value, spreading out the expiration of
+  /// values written at the same time.
+  ///
+  /// Default: `0.1`
+  pub jitter:    f64,
+  /// The amount of time a value is kept after it expires so it can be served to other callers while one caller
+  /// computes the new value.
+  ///
+  /// Default: 0 seconds
+  pub stale_ttl: Duration,
+  /// The expiration of the lease that allows one caller to compute a value. Callers that fail to take the lease
+  /// on a miss will wait up to this long before computing the value themselves.
+  ///
+  /// Default: 5 seconds
+  pub lease_ttl: Duration,
+  /// The XFetch `beta` parameter, which controls how early values are refreshed before they expire. Values greater
+  /// than `1.0` favor earlier refreshes, and `0.0` disables early refreshes.
+  ///
+  /// Default: `1.0`
+  pub beta:      f64,
+}
+
+#[cfg(feature = "serde-json")]
+impl CacheOptions {
+  /// Create cache options with the provided `ttl` and default values for the other fields.
+  pub fn new(ttl: Duration) -> Self {
+    CacheOptions {
+      ttl,
+      jitter: 0.1,
+      stale_ttl: Duration::from_secs(0),
+      lease_ttl: Duration::from_secs(5),
+      beta: 1.0,
+    }
+  }
+}
+
+#[cfg(feature = "serde-json")]
+impl From<Duration> for CacheOptions {
+  fn from(ttl: Duration) -> Self {
+    CacheOptions::new(ttl)
+  }
+}
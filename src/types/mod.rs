@@ -15,6 +15,8 @@ mod misc;
 mod multiple;
 mod scan;
 mod scripts;
+#[cfg(feature = "serde")]
+mod serde;
 mod sorted_sets;
 mod streams;
 
@@ -37,6 +39,12 @@ pub use streams::*;
 #[cfg_attr(docsrs, doc(cfg(feature = "metrics")))]
 pub use crate::modules::metrics::Stats;
 
+pub use redis_protocol::resp3::types::VerbatimStringFormat;
+
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub use self::serde::{from_redis_value, to_redis_value, Serde};
+
 #[cfg(feature = "dns")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dns")))]
 pub use crate::protocol::types::Resolve;
@@ -0,0 +1,749 @@
+use crate::{
+  error::{RedisError, RedisErrorKind},
+  types::{RedisKey, RedisMap, RedisValue},
+  utils,
+};
+use bytes::Bytes;
+use serde::{
+  de::{
+    self,
+    value::{MapAccessDeserializer, MapDeserializer, SeqDeserializer},
+    DeserializeOwned,
+    Deserializer,
+    IntoDeserializer,
+    Visitor,
+  },
+  ser::{self, Serialize, Serializer},
+};
+use std::{collections::HashMap, convert::TryFrom, fmt::Display, str::FromStr};
+
+/// A wrapper type used to convert values to and from [RedisValue](crate::types::RedisValue) with
+/// [serde](https://serde.rs/).
+///
+/// Responses can be deserialized into any type that implements `Deserialize` without going through JSON:
+///
+/// ```rust no_run
+/// # use fred::{prelude::*, types::Serde};
+/// # use serde::{Deserialize, Serialize};
+/// #[derive(Deserialize, Serialize)]
+/// struct User {
+///   name: String,
+///   age:  u32,
+/// }
+///
+/// async fn example(client: &RedisClient) -> Result<(), RedisError> {
+///   let user = User {
+///     name: "foo".into(),
+///     age:  42,
+///   };
+///   let _: () = client.hset("users:1", Serde(user)).await?;
+///
+///   let user: Serde<User> = client.hgetall("users:1").await?;
+///   println!("{} is {} years old", user.0.name, user.0.age);
+///   Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Serde<T>(pub T);
+
+impl<T> Serde<T> {
+  /// Read the inner value.
+  pub fn into_inner(self) -> T {
+    self.0
+  }
+}
+
+impl<T: Serialize> TryFrom<Serde<T>> for RedisValue {
+  type Error = RedisError;
+
+  fn try_from(value: Serde<T>) -> Result<Self, Self::Error> {
+    to_redis_value(&value.0)
+  }
+}
+
+impl<T: Serialize> TryFrom<Serde<T>> for RedisMap {
+  type Error = RedisError;
+
+  fn try_from(value: Serde<T>) -> Result<Self, Self::Error> {
+    to_redis_value(&value.0)?.into_map()
+  }
+}
+
+/// Deserialize a value from a [RedisValue](crate::types::RedisValue).
+///
+/// Strings are parsed when a number or boolean is expected, and RESP2 arrays of key/value pairs are read as maps when
+/// a map or struct is expected. Note that `Vec<u8>` is deserialized as a sequence by serde, so callers should use a
+/// wrapper such as `serde_bytes::ByteBuf` to read binary values.
+pub fn from_redis_value<T: DeserializeOwned>(value: RedisValue) -> Result<T, RedisError> {
+  T::deserialize(value)
+}
+
+/// Serialize a value to a [RedisValue](crate::types::RedisValue).
+///
+/// Structs and maps are serialized as a `Map`, sequences and tuples as an `Array`, and `None` or unit values as
+/// `Null`. Enum variants with associated data are serialized as a map with one entry whose key is the variant name.
+pub fn to_redis_value<T: Serialize + ?Sized>(value: &T) -> Result<RedisValue, RedisError> {
+  value.serialize(RedisValueSerializer)
+}
+
+/// Convert a map key to a value, using a `String` if the key is valid UTF8.
+fn key_to_value(key: RedisKey) -> RedisValue {
+  match key.as_bytes_str() {
+    Some(s) => RedisValue::String(s),
+    None => RedisValue::Bytes(key.into_bytes()),
+  }
+}
+
+/// Parse a number from a string or byte array value.
+fn parse_value<T: FromStr>(value: &RedisValue) -> Option<T> {
+  match *value {
+    RedisValue::String(ref s) => s.parse::<T>().ok(),
+    RedisValue::BigNumber(ref s) => s.parse::<T>().ok(),
+    RedisValue::Bytes(ref b) => std::str::from_utf8(b).ok().and_then(|s| s.parse::<T>().ok()),
+    _ => None,
+  }
+}
+
+fn visit_array<'de, V: Visitor<'de>>(values: Vec<RedisValue>, visitor: V) -> Result<V::Value, RedisError> {
+  let mut deserializer = SeqDeserializer::new(values.into_iter());
+  let value = visitor.visit_seq(&mut deserializer)?;
+  deserializer.end()?;
+  Ok(value)
+}
+
+fn visit_map<'de, V: Visitor<'de>>(map: RedisMap, visitor: V) -> Result<V::Value, RedisError> {
+  let mut deserializer = MapDeserializer::new(map.inner.into_iter().map(|(k, v)| (key_to_value(k), v)));
+  let value = visitor.visit_map(&mut deserializer)?;
+  deserializer.end()?;
+  Ok(value)
+}
+
+macro_rules! deserialize_number (
+  ($method:ident, $visit:ident, $t:ty) => {
+    fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+      match parse_value::<$t>(&self) {
+        Some(value) => visitor.$visit(value),
+        None => self.deserialize_any(visitor),
+      }
+    }
+  }
+);
+
+macro_rules! deserialize_float (
+  ($method:ident, $visit:ident, $t:ty) => {
+    fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+      let parsed = match self {
+        RedisValue::String(ref s) => utils::redis_string_to_f64(s).ok(),
+        RedisValue::Bytes(ref b) => std::str::from_utf8(b)
+          .ok()
+          .and_then(|s| utils::redis_string_to_f64(s).ok()),
+        _ => None,
+      };
+
+      match parsed {
+        Some(value) => visitor.$visit(value as $t),
+        None => self.deserialize_any(visitor),
+      }
+    }
+  }
+);
+
+impl<'de> Deserializer<'de> for RedisValue {
+  type Error = RedisError;
+
+  deserialize_number!(deserialize_i8, visit_i8, i8);
+
+  deserialize_number!(deserialize_i16, visit_i16, i16);
+
+  deserialize_number!(deserialize_i32, visit_i32, i32);
+
+  deserialize_number!(deserialize_i64, visit_i64, i64);
+
+  deserialize_number!(deserialize_u8, visit_u8, u8);
+
+  deserialize_number!(deserialize_u16, visit_u16, u16);
+
+  deserialize_number!(deserialize_u32, visit_u32, u32);
+
+  deserialize_number!(deserialize_u64, visit_u64, u64);
+
+  deserialize_float!(deserialize_f32, visit_f32, f32);
+
+  deserialize_float!(deserialize_f64, visit_f64, f64);
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    match self {
+      RedisValue::Boolean(b) => visitor.visit_bool(b),
+      RedisValue::Integer(i) => visitor.visit_i64(i),
+      RedisValue::Double(f) => visitor.visit_f64(f),
+      RedisValue::String(s) => visitor.visit_string(s.to_string()),
+      RedisValue::Bytes(b) => visitor.visit_byte_buf(b.to_vec()),
+      RedisValue::Null => visitor.visit_unit(),
+      RedisValue::Queued => visitor.visit_str("QUEUED"),
+      RedisValue::Map(map) => visit_map(map, visitor),
+      RedisValue::Array(values) => visit_array(values, visitor),
+      _ => self.into_lossy().deserialize_any(visitor),
+    }
+  }
+
+  fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    match self.as_bool() {
+      Some(b) => visitor.visit_bool(b),
+      None => self.deserialize_any(visitor),
+    }
+  }
+
+  fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    self.deserialize_string(visitor)
+  }
+
+  fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    self.deserialize_string(visitor)
+  }
+
+  fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    match self {
+      RedisValue::Bytes(b) => match String::from_utf8(b.to_vec()) {
+        Ok(s) => visitor.visit_string(s),
+        Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+      },
+      RedisValue::Integer(i) => visitor.visit_string(i.to_string()),
+      RedisValue::Double(f) => visitor.visit_string(f.to_string()),
+      _ => self.deserialize_any(visitor),
+    }
+  }
+
+  fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    self.deserialize_byte_buf(visitor)
+  }
+
+  fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    match self {
+      RedisValue::String(s) => visitor.visit_byte_buf(s.into_inner().to_vec()),
+      _ => self.deserialize_any(visitor),
+    }
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    match self {
+      RedisValue::Null => visitor.visit_none(),
+      _ => visitor.visit_some(self),
+    }
+  }
+
+  fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    self.deserialize_any(visitor)
+  }
+
+  fn deserialize_unit_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, RedisError> {
+    self.deserialize_unit(visitor)
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(self, _: &'static str, visitor: V) -> Result<V::Value, RedisError> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    match self {
+      // read maps as a sequence of key/value pairs
+      RedisValue::Map(map) => {
+        let pairs = map
+          .inner
+          .into_iter()
+          .map(|(k, v)| RedisValue::Array(vec![key_to_value(k), v]))
+          .collect();
+        visit_array(pairs, visitor)
+      },
+      _ => self.deserialize_any(visitor),
+    }
+  }
+
+  fn deserialize_tuple<V: Visitor<'de>>(self, _: usize, visitor: V) -> Result<V::Value, RedisError> {
+    self.deserialize_seq(visitor)
+  }
+
+  fn deserialize_tuple_struct<V: Visitor<'de>>(
+    self,
+    _: &'static str,
+    _: usize,
+    visitor: V,
+  ) -> Result<V::Value, RedisError> {
+    self.deserialize_seq(visitor)
+  }
+
+  fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    if self.is_maybe_map() {
+      visit_map(self.into_map()?, visitor)
+    } else {
+      self.deserialize_any(visitor)
+    }
+  }
+
+  fn deserialize_struct<V: Visitor<'de>>(
+    self,
+    _: &'static str,
+    _: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, RedisError> {
+    self.deserialize_map(visitor)
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    _: &'static str,
+    _: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, RedisError> {
+    match self {
+      RedisValue::String(s) => visitor.visit_enum(s.to_string().into_deserializer()),
+      RedisValue::Bytes(b) => match String::from_utf8(b.to_vec()) {
+        Ok(s) => visitor.visit_enum(s.into_deserializer()),
+        Err(_) => Err(RedisError::new(RedisErrorKind::Parse, "Invalid enum variant.")),
+      },
+      _ if self.is_maybe_map() => {
+        let map = self.into_map()?;
+        if map.len() != 1 {
+          return Err(RedisError::new(RedisErrorKind::Parse, "Expected a map with one entry."));
+        }
+
+        let entries = map.inner.into_iter().map(|(k, v)| (key_to_value(k), v));
+        visitor.visit_enum(MapAccessDeserializer::new(MapDeserializer::new(entries)))
+      },
+      _ => Err(RedisError::new(RedisErrorKind::Parse, "Invalid enum value.")),
+    }
+  }
+
+  fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    self.deserialize_string(visitor)
+  }
+
+  fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RedisError> {
+    visitor.visit_unit()
+  }
+}
+
+impl<'de> IntoDeserializer<'de, RedisError> for RedisValue {
+  type Deserializer = Self;
+
+  fn into_deserializer(self) -> Self::Deserializer {
+    self
+  }
+}
+
+impl de::Error for RedisError {
+  fn custom<T: Display>(msg: T) -> Self {
+    RedisError::new(RedisErrorKind::Parse, msg.to_string())
+  }
+}
+
+impl ser::Error for RedisError {
+  fn custom<T: Display>(msg: T) -> Self {
+    RedisError::new(RedisErrorKind::InvalidArgument, msg.to_string())
+  }
+}
+
+/// A serializer that converts values to a [RedisValue](crate::types::RedisValue).
+struct RedisValueSerializer;
+
+/// Wrap a value in a map with one entry whose key is the variant name.
+fn variant_map(variant: &'static str, value: RedisValue) -> RedisValue {
+  let mut inner = HashMap::with_capacity(1);
+  inner.insert(RedisKey::from_static_str(variant), value);
+  RedisValue::Map(RedisMap { inner })
+}
+
+impl Serializer for RedisValueSerializer {
+  type Error = RedisError;
+  type Ok = RedisValue;
+  type SerializeMap = SerializeRedisMap;
+  type SerializeSeq = SerializeArray;
+  type SerializeStruct = SerializeRedisMap;
+  type SerializeStructVariant = SerializeRedisMap;
+  type SerializeTuple = SerializeArray;
+  type SerializeTupleStruct = SerializeArray;
+  type SerializeTupleVariant = SerializeArray;
+
+  fn serialize_bool(self, v: bool) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Boolean(v))
+  }
+
+  fn serialize_i8(self, v: i8) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Integer(v as i64))
+  }
+
+  fn serialize_i16(self, v: i16) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Integer(v as i64))
+  }
+
+  fn serialize_i32(self, v: i32) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Integer(v as i64))
+  }
+
+  fn serialize_i64(self, v: i64) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Integer(v))
+  }
+
+  fn serialize_u8(self, v: u8) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Integer(v as i64))
+  }
+
+  fn serialize_u16(self, v: u16) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Integer(v as i64))
+  }
+
+  fn serialize_u32(self, v: u32) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Integer(v as i64))
+  }
+
+  fn serialize_u64(self, v: u64) -> Result<RedisValue, RedisError> {
+    RedisValue::try_from(v)
+  }
+
+  fn serialize_f32(self, v: f32) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Double(v as f64))
+  }
+
+  fn serialize_f64(self, v: f64) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Double(v))
+  }
+
+  fn serialize_char(self, v: char) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::String(v.to_string().into()))
+  }
+
+  fn serialize_str(self, v: &str) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::String(v.into()))
+  }
+
+  fn serialize_bytes(self, v: &[u8]) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Bytes(Bytes::copy_from_slice(v)))
+  }
+
+  fn serialize_none(self) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Null)
+  }
+
+  fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<RedisValue, RedisError> {
+    value.serialize(self)
+  }
+
+  fn serialize_unit(self) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Null)
+  }
+
+  fn serialize_unit_struct(self, _: &'static str) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::Null)
+  }
+
+  fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<RedisValue, RedisError> {
+    Ok(RedisValue::from_static_str(variant))
+  }
+
+  fn serialize_newtype_struct<T: Serialize + ?Sized>(
+    self,
+    _: &'static str,
+    value: &T,
+  ) -> Result<RedisValue, RedisError> {
+    value.serialize(self)
+  }
+
+  fn serialize_newtype_variant<T: Serialize + ?Sized>(
+    self,
+    _: &'static str,
+    _: u32,
+    variant: &'static str,
+    value: &T,
+  ) -> Result<RedisValue, RedisError> {
+    Ok(variant_map(variant, value.serialize(self)?))
+  }
+
+  fn serialize_seq(self, len: Option<usize>) -> Result<SerializeArray, RedisError> {
+    Ok(SerializeArray {
+      variant: None,
+      values:  Vec::with_capacity(len.unwrap_or(0)),
+    })
+  }
+
+  fn serialize_tuple(self, len: usize) -> Result<SerializeArray, RedisError> {
+    self.serialize_seq(Some(len))
+  }
+
+  fn serialize_tuple_struct(self, _: &'static str, len: usize) -> Result<SerializeArray, RedisError> {
+    self.serialize_seq(Some(len))
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _: &'static str,
+    _: u32,
+    variant: &'static str,
+    len: usize,
+  ) -> Result<SerializeArray, RedisError> {
+    Ok(SerializeArray {
+      variant: Some(variant),
+      values:  Vec::with_capacity(len),
+    })
+  }
+
+  fn serialize_map(self, len: Option<usize>) -> Result<SerializeRedisMap, RedisError> {
+    Ok(SerializeRedisMap {
+      variant: None,
+      inner:   HashMap::with_capacity(len.unwrap_or(0)),
+      key:     None,
+    })
+  }
+
+  fn serialize_struct(self, _: &'static str, len: usize) -> Result<SerializeRedisMap, RedisError> {
+    self.serialize_map(Some(len))
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _: &'static str,
+    _: u32,
+    variant: &'static str,
+    len: usize,
+  ) -> Result<SerializeRedisMap, RedisError> {
+    Ok(SerializeRedisMap {
+      variant: Some(variant),
+      inner:   HashMap::with_capacity(len),
+      key:     None,
+    })
+  }
+}
+
+#[doc(hidden)]
+pub struct SerializeArray {
+  variant: Option<&'static str>,
+  values:  Vec<RedisValue>,
+}
+
+impl SerializeArray {
+  fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RedisError> {
+    self.values.push(to_redis_value(value)?);
+    Ok(())
+  }
+
+  fn finish(self) -> Result<RedisValue, RedisError> {
+    let value = RedisValue::Array(self.values);
+
+    Ok(match self.variant {
+      Some(variant) => variant_map(variant, value),
+      None => value,
+    })
+  }
+}
+
+impl ser::SerializeSeq for SerializeArray {
+  type Error = RedisError;
+  type Ok = RedisValue;
+
+  fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RedisError> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<RedisValue, RedisError> {
+    self.finish()
+  }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+  type Error = RedisError;
+  type Ok = RedisValue;
+
+  fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RedisError> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<RedisValue, RedisError> {
+    self.finish()
+  }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+  type Error = RedisError;
+  type Ok = RedisValue;
+
+  fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RedisError> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<RedisValue, RedisError> {
+    self.finish()
+  }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+  type Error = RedisError;
+  type Ok = RedisValue;
+
+  fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RedisError> {
+    self.push(value)
+  }
+
+  fn end(self) -> Result<RedisValue, RedisError> {
+    self.finish()
+  }
+}
+
+#[doc(hidden)]
+pub struct SerializeRedisMap {
+  variant: Option<&'static str>,
+  inner:   HashMap<RedisKey, RedisValue>,
+  key:     Option<RedisKey>,
+}
+
+impl SerializeRedisMap {
+  fn insert<T: Serialize + ?Sized>(&mut self, key: RedisKey, value: &T) -> Result<(), RedisError> {
+    self.inner.insert(key, to_redis_value(value)?);
+    Ok(())
+  }
+
+  fn finish(self) -> Result<RedisValue, RedisError> {
+    let value = RedisValue::Map(RedisMap { inner: self.inner });
+
+    Ok(match self.variant {
+      Some(variant) => variant_map(variant, value),
+      None => value,
+    })
+  }
+}
+
+impl ser::SerializeMap for SerializeRedisMap {
+  type Error = RedisError;
+  type Ok = RedisValue;
+
+  fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), RedisError> {
+    self.key = Some(RedisKey::try_from(to_redis_value(key)?)?);
+    Ok(())
+  }
+
+  fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), RedisError> {
+    match self.key.take() {
+      Some(key) => self.insert(key, value),
+      None => Err(RedisError::new(RedisErrorKind::InvalidArgument, "Missing map key.")),
+    }
+  }
+
+  fn end(self) -> Result<RedisValue, RedisError> {
+    self.finish()
+  }
+}
+
+impl ser::SerializeStruct for SerializeRedisMap {
+  type Error = RedisError;
+  type Ok = RedisValue;
+
+  fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), RedisError> {
+    self.insert(RedisKey::from_static_str(key), value)
+  }
+
+  fn end(self) -> Result<RedisValue, RedisError> {
+    self.finish()
+  }
+}
+
+impl ser::SerializeStructVariant for SerializeRedisMap {
+  type Error = RedisError;
+  type Ok = RedisValue;
+
+  fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), RedisError> {
+    self.insert(RedisKey::from_static_str(key), value)
+  }
+
+  fn end(self) -> Result<RedisValue, RedisError> {
+    self.finish()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde::{Deserialize, Serialize};
+
+  #[derive(Debug, Deserialize, PartialEq, Serialize)]
+  enum Kind {
+    Admin,
+    Guest { expires: u64 },
+  }
+
+  #[derive(Debug, Deserialize, PartialEq, Serialize)]
+  struct User {
+    name:   String,
+    age:    u32,
+    score:  f64,
+    active: bool,
+    email:  Option<String>,
+    kind:   Kind,
+    tags:   Vec<String>,
+  }
+
+  fn user() -> User {
+    User {
+      name:   "foo".into(),
+      age:    42,
+      score:  1.5,
+      active: true,
+      email:  None,
+      kind:   Kind::Guest { expires: 100 },
+      tags:   vec!["a".into(), "b".into()],
+    }
+  }
+
+  #[test]
+  fn should_round_trip_struct() {
+    let value = to_redis_value(&user()).unwrap();
+    assert!(value.is_map());
+    assert_eq!(from_redis_value::<User>(value).unwrap(), user());
+  }
+
+  #[test]
+  fn should_deserialize_resp2_strings() {
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Hash {
+      name:   String,
+      age:    u32,
+      score:  f64,
+      active: bool,
+      kind:   Kind,
+    }
+
+    // HGETALL in RESP2 mode returns a flat array of strings
+    let value = RedisValue::Array(vec![
+      "name".into(),
+      "foo".into(),
+      "age".into(),
+      "42".into(),
+      "score".into(),
+      "1.5".into(),
+      "active".into(),
+      "true".into(),
+      "kind".into(),
+      "Admin".into(),
+    ]);
+    let expected = Hash {
+      name:   "foo".into(),
+      age:    42,
+      score:  1.5,
+      active: true,
+      kind:   Kind::Admin,
+    };
+
+    assert_eq!(from_redis_value::<Hash>(value).unwrap(), expected);
+  }
+
+  #[test]
+  fn should_map_primitive_types() {
+    assert_eq!(to_redis_value(&1_u8).unwrap(), RedisValue::Integer(1));
+    assert_eq!(to_redis_value(&1.5_f64).unwrap(), RedisValue::Double(1.5));
+    assert_eq!(to_redis_value(&true).unwrap(), RedisValue::Boolean(true));
+    assert_eq!(to_redis_value(&Option::<u8>::None).unwrap(), RedisValue::Null);
+    assert!(to_redis_value(&u64::MAX).is_err());
+
+    let bytes = RedisValue::Bytes(Bytes::from_static(b"foo"));
+    assert_eq!(from_redis_value::<String>(bytes).unwrap(), "foo");
+    assert_eq!(from_redis_value::<Option<i64>>(RedisValue::Null).unwrap(), None);
+    assert_eq!(from_redis_value::<i64>(RedisValue::Integer(-1)).unwrap(), -1);
+    assert!(from_redis_value::<u8>(RedisValue::Integer(-1)).is_err());
+  }
+}
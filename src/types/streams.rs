@@ -1,7 +1,7 @@
 use crate::{
   commands::{MAXLEN, MINID},
   error::{RedisError, RedisErrorKind},
-  types::{LimitCount, RedisKey, RedisValue, StringOrNumber},
+  types::{FromRedis, LimitCount, RedisKey, RedisMap, RedisValue, StringOrNumber},
   utils,
 };
 use bytes_utils::Str;
@@ -490,3 +490,448 @@ pub type XReadValue<I, K, V> = (I, HashMap<K, V>);
 /// To support heterogeneous values in the map describing each stream element it is recommended to declare the last
 /// type as `RedisValue` and [convert](crate::types::RedisValue::convert) as needed.
 pub type XReadResponse<K1, I, K2, V> = HashMap<K1, Vec<XReadValue<I, K2, V>>>;
+
+/// A stream entry with the fields decoded into a user-defined type.
+///
+/// The fields are passed to `T` as a [Map](crate::types::RedisValue::Map), so any type that implements
+/// [FromRedis](crate::types::FromRedis) for a map can be used. This can be used with `XRANGE`, `XREVRANGE`, `XCLAIM`,
+/// etc.
+///
+/// ```rust no_run
+/// # use fred::prelude::*;
+/// # use fred::types::XEntry;
+/// # use std::collections::HashMap;
+/// struct Event {
+///   kind:  String,
+///   count: u64,
+/// }
+///
+/// impl FromRedis for Event {
+///   fn from_value(value: RedisValue) -> Result<Self, RedisError> {
+///     let mut fields: HashMap<String, RedisValue> = value.convert()?;
+///     let kind = fields.remove("kind").unwrap_or(RedisValue::Null).convert()?;
+///     let count = fields.remove("count").unwrap_or(RedisValue::Null).convert()?;
+///
+///     Ok(Event { kind, count })
+///   }
+/// }
+///
+/// async fn example(client: &RedisClient) -> Result<(), RedisError> {
+///   let entries: Vec<XEntry<Event>> = client.xrange("events", "-", "+", None).await?;
+///   for entry in entries.into_iter() {
+///     println!("{}: {} x{}", entry.id, entry.fields.kind, entry.fields.count);
+///   }
+///   Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct XEntry<T> {
+  /// The ID of the entry.
+  pub id:     String,
+  /// The fields associated with the entry.
+  pub fields: T,
+}
+
+impl<T: FromRedis> TryFrom<RedisValue> for XEntry<T> {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let (id, fields): (String, RedisValue) = value.convert()?;
+    let fields = T::from_value(RedisValue::Map(fields.into_map()?))?;
+
+    Ok(XEntry { id, fields })
+  }
+}
+
+/// The response from `XINFO STREAM`.
+///
+/// <https://redis.io/commands/xinfo-stream>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamInfo {
+  pub length:                  u64,
+  pub radix_tree_keys:         u64,
+  pub radix_tree_nodes:        u64,
+  pub last_generated_id:       String,
+  /// Added in Redis 7.0.
+  pub max_deleted_entry_id:    Option<String>,
+  /// Added in Redis 7.0.
+  pub entries_added:           Option<u64>,
+  /// Added in Redis 7.0.
+  pub recorded_first_entry_id: Option<String>,
+  pub groups:                  u64,
+  pub first_entry:             Option<XReadValue<String, String, RedisValue>>,
+  pub last_entry:              Option<XReadValue<String, String, RedisValue>>,
+}
+
+impl TryFrom<RedisValue> for StreamInfo {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let mut map = value.into_map()?;
+
+    Ok(StreamInfo {
+      length:                  take_field(&mut map, "length")?,
+      radix_tree_keys:         take_field(&mut map, "radix-tree-keys")?,
+      radix_tree_nodes:        take_field(&mut map, "radix-tree-nodes")?,
+      last_generated_id:       take_field(&mut map, "last-generated-id")?,
+      max_deleted_entry_id:    take_optional_field(&mut map, "max-deleted-entry-id")?,
+      entries_added:           take_optional_field(&mut map, "entries-added")?,
+      recorded_first_entry_id: take_optional_field(&mut map, "recorded-first-entry-id")?,
+      groups:                  take_field(&mut map, "groups")?,
+      first_entry:             take_optional_field(&mut map, "first-entry")?,
+      last_entry:              take_optional_field(&mut map, "last-entry")?,
+    })
+  }
+}
+
+/// The response from `XINFO STREAM` with the `FULL` modifier.
+///
+/// <https://redis.io/commands/xinfo-stream>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamInfoFull {
+  pub length:                  u64,
+  pub radix_tree_keys:         u64,
+  pub radix_tree_nodes:        u64,
+  pub last_generated_id:       String,
+  /// Added in Redis 7.0.
+  pub max_deleted_entry_id:    Option<String>,
+  /// Added in Redis 7.0.
+  pub entries_added:           Option<u64>,
+  /// Added in Redis 7.0.
+  pub recorded_first_entry_id: Option<String>,
+  pub entries:                 Vec<XReadValue<String, String, RedisValue>>,
+  pub groups:                  Vec<StreamGroupInfoFull>,
+}
+
+impl TryFrom<RedisValue> for StreamInfoFull {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let mut map = value.into_map()?;
+
+    Ok(StreamInfoFull {
+      length:                  take_field(&mut map, "length")?,
+      radix_tree_keys:         take_field(&mut map, "radix-tree-keys")?,
+      radix_tree_nodes:        take_field(&mut map, "radix-tree-nodes")?,
+      last_generated_id:       take_field(&mut map, "last-generated-id")?,
+      max_deleted_entry_id:    take_optional_field(&mut map, "max-deleted-entry-id")?,
+      entries_added:           take_optional_field(&mut map, "entries-added")?,
+      recorded_first_entry_id: take_optional_field(&mut map, "recorded-first-entry-id")?,
+      entries:                 take_field(&mut map, "entries")?,
+      groups:                  take_field(&mut map, "groups")?,
+    })
+  }
+}
+
+/// A consumer group in the response from `XINFO GROUPS`.
+///
+/// <https://redis.io/commands/xinfo-groups>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamGroupInfo {
+  pub name:              String,
+  pub consumers:         u64,
+  pub pending:           u64,
+  pub last_delivered_id: String,
+  /// Added in Redis 7.0.
+  pub entries_read:      Option<u64>,
+  /// Added in Redis 7.0.
+  pub lag:               Option<u64>,
+}
+
+impl TryFrom<RedisValue> for StreamGroupInfo {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let mut map = value.into_map()?;
+
+    Ok(StreamGroupInfo {
+      name:              take_field(&mut map, "name")?,
+      consumers:         take_field(&mut map, "consumers")?,
+      pending:           take_field(&mut map, "pending")?,
+      last_delivered_id: take_field(&mut map, "last-delivered-id")?,
+      entries_read:      take_optional_field(&mut map, "entries-read")?,
+      lag:               take_optional_field(&mut map, "lag")?,
+    })
+  }
+}
+
+/// A consumer group in the response from `XINFO STREAM FULL`.
+///
+/// <https://redis.io/commands/xinfo-stream>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamGroupInfoFull {
+  pub name:              String,
+  pub last_delivered_id: String,
+  /// Added in Redis 7.0.
+  pub entries_read:      Option<u64>,
+  /// Added in Redis 7.0.
+  pub lag:               Option<u64>,
+  pub pel_count:         u64,
+  pub pending:           Vec<PendingDelivery>,
+  pub consumers:         Vec<StreamConsumerInfoFull>,
+}
+
+impl TryFrom<RedisValue> for StreamGroupInfoFull {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let mut map = value.into_map()?;
+
+    Ok(StreamGroupInfoFull {
+      name:              take_field(&mut map, "name")?,
+      last_delivered_id: take_field(&mut map, "last-delivered-id")?,
+      entries_read:      take_optional_field(&mut map, "entries-read")?,
+      lag:               take_optional_field(&mut map, "lag")?,
+      pel_count:         take_field(&mut map, "pel-count")?,
+      pending:           take_field(&mut map, "pending")?,
+      consumers:         take_field(&mut map, "consumers")?,
+    })
+  }
+}
+
+/// A consumer in the response from `XINFO CONSUMERS`.
+///
+/// <https://redis.io/commands/xinfo-consumers>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamConsumerInfo {
+  pub name:     String,
+  pub pending:  u64,
+  /// The number of milliseconds since the consumer's last attempted interaction.
+  pub idle:     u64,
+  /// The number of milliseconds since the consumer's last successful interaction, or `-1` if the consumer never had
+  /// a successful interaction. Added in Redis 7.2.
+  pub inactive: Option<i64>,
+}
+
+impl TryFrom<RedisValue> for StreamConsumerInfo {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let mut map = value.into_map()?;
+
+    Ok(StreamConsumerInfo {
+      name:     take_field(&mut map, "name")?,
+      pending:  take_field(&mut map, "pending")?,
+      idle:     take_field(&mut map, "idle")?,
+      inactive: take_optional_field(&mut map, "inactive")?,
+    })
+  }
+}
+
+/// A consumer in the response from `XINFO STREAM FULL`.
+///
+/// <https://redis.io/commands/xinfo-stream>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamConsumerInfoFull {
+  pub name:        String,
+  /// The UNIX timestamp, in milliseconds, of the consumer's last attempted interaction.
+  pub seen_time:   u64,
+  /// The UNIX timestamp, in milliseconds, of the consumer's last successful interaction, or `-1` if the consumer
+  /// never had a successful interaction. Added in Redis 7.2.
+  pub active_time: Option<i64>,
+  pub pel_count:   u64,
+  pub pending:     Vec<PendingDelivery>,
+}
+
+impl TryFrom<RedisValue> for StreamConsumerInfoFull {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let mut map = value.into_map()?;
+
+    Ok(StreamConsumerInfoFull {
+      name:        take_field(&mut map, "name")?,
+      seen_time:   take_field(&mut map, "seen-time")?,
+      active_time: take_optional_field(&mut map, "active-time")?,
+      pel_count:   take_field(&mut map, "pel-count")?,
+      pending:     take_field(&mut map, "pending")?,
+    })
+  }
+}
+
+/// An entry in the pending entries list of a group or consumer in the response from `XINFO STREAM FULL`.
+///
+/// <https://redis.io/commands/xinfo-stream>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingDelivery {
+  pub id:            String,
+  /// The consumer that owns the entry. This is `None` when the entry is listed under a consumer.
+  pub consumer:      Option<String>,
+  /// The UNIX timestamp, in milliseconds, of the last delivery.
+  pub delivery_time: u64,
+  pub deliveries:    u64,
+}
+
+impl TryFrom<RedisValue> for PendingDelivery {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let mut values: Vec<RedisValue> = value.convert()?;
+
+    let consumer = match values.len() {
+      3 => None,
+      4 => Some(values.remove(1).convert()?),
+      _ => return Err(RedisError::new_parse("Expected 3 or 4 pending entry values.")),
+    };
+    let (id, delivery_time, deliveries) = RedisValue::Array(values).convert()?;
+
+    Ok(PendingDelivery {
+      id,
+      consumer,
+      delivery_time,
+      deliveries,
+    })
+  }
+}
+
+/// The summary form of the response from `XPENDING`.
+///
+/// <https://redis.io/commands/xpending>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingSummary {
+  /// The total number of pending entries in the group.
+  pub count:     u64,
+  /// The smallest pending ID, if any.
+  pub min_id:    Option<String>,
+  /// The largest pending ID, if any.
+  pub max_id:    Option<String>,
+  /// The number of pending entries owned by each consumer with at least one pending entry.
+  pub consumers: Vec<(String, u64)>,
+}
+
+impl TryFrom<RedisValue> for PendingSummary {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let (count, min_id, max_id, consumers): (u64, Option<String>, Option<String>, RedisValue) = value.convert()?;
+    let consumers = match consumers {
+      RedisValue::Null => Vec::new(),
+      consumers => consumers.convert()?,
+    };
+
+    Ok(PendingSummary {
+      count,
+      min_id,
+      max_id,
+      consumers,
+    })
+  }
+}
+
+/// An entry in the extended form of the response from `XPENDING`.
+///
+/// <https://redis.io/commands/xpending>
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingEntry {
+  pub id:         String,
+  pub consumer:   String,
+  /// The number of milliseconds since the entry was last delivered.
+  pub idle:       u64,
+  pub deliveries: u64,
+}
+
+impl TryFrom<RedisValue> for PendingEntry {
+  type Error = RedisError;
+
+  fn try_from(value: RedisValue) -> Result<Self, Self::Error> {
+    let (id, consumer, idle, deliveries) = value.convert()?;
+
+    Ok(PendingEntry {
+      id,
+      consumer,
+      idle,
+      deliveries,
+    })
+  }
+}
+
+/// Remove and convert a required field from the map returned by `XINFO`.
+fn take_field<T: FromRedis>(map: &mut RedisMap, field: &'static str) -> Result<T, RedisError> {
+  match map.inner.remove(&RedisKey::from_static_str(field)) {
+    Some(value) => T::from_value(value),
+    None => Err(RedisError::new_parse(format!("Missing `{}` field.", field))),
+  }
+}
+
+/// Remove and convert a field from the map returned by `XINFO` that may be missing or `nil`.
+fn take_optional_field<T: FromRedis>(map: &mut RedisMap, field: &'static str) -> Result<Option<T>, RedisError> {
+  match map.inner.remove(&RedisKey::from_static_str(field)) {
+    Some(RedisValue::Null) | None => Ok(None),
+    Some(value) => T::from_value(value).map(Some),
+  }
+}
+
+/// Configuration options for a [StreamConsumer](crate::clients::StreamConsumer).
+#[cfg(feature = "stream-consumer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stream-consumer")))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StreamConsumerConfig {
+  /// The key of the stream.
+  pub stream:             RedisKey,
+  /// The name of the consumer group. The group is created with `MKSTREAM` if it does not exist.
+  pub group:              Str,
+  /// The name of the consumer within the group.
+  pub consumer:           Str,
+  /// The ID of the last entry delivered to the group, used only when the group is created.
+  ///
+  /// Default: `$`
+  pub group_start:        XID,
+  /// The maximum number of entries passed to the handler concurrently.
+  ///
+  /// Default: 1
+  pub concurrency:        u64,
+  /// The maximum amount of time, in milliseconds, to block while waiting for new entries.
+  ///
+  /// Default: 5000
+  pub block:              u64,
+  /// The minimum amount of time, in milliseconds, that an entry must be pending before it is claimed with
+  /// `XAUTOCLAIM`.
+  ///
+  /// Default: 60000
+  pub claim_min_idle:     u64,
+  /// The number of deliveries after which a pending entry is moved to the dead letter stream.
+  ///
+  /// Default: 5
+  pub max_deliveries:     u64,
+  /// The stream that receives entries that were delivered more than `max_deliveries` times. If `None` these entries
+  /// are retried indefinitely.
+  ///
+  /// Default: `None`
+  pub dead_letter_stream: Option<RedisKey>,
+}
+
+#[cfg(feature = "stream-consumer")]
+impl StreamConsumerConfig {
+  /// Create a new config with the default options.
+  pub fn new<K, G, C>(stream: K, group: G, consumer: C) -> Self
+  where
+    K: Into<RedisKey>,
+    G: Into<Str>,
+    C: Into<Str>,
+  {
+    StreamConsumerConfig {
+      stream:             stream.into(),
+      group:              group.into(),
+      consumer:           consumer.into(),
+      group_start:        XID::Max,
+      concurrency:        1,
+      block:              5000,
+      claim_min_idle:     60000,
+      max_deliveries:     5,
+      dead_letter_stream: None,
+    }
+  }
+}
+
+/// An entry read from a stream by a [StreamConsumer](crate::clients::StreamConsumer).
+#[cfg(feature = "stream-consumer")]
+#[cfg_attr(docsrs, doc(cfg(feature = "stream-consumer")))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamEntry {
+  /// The ID of the entry.
+  pub id:         String,
+  /// The fields and values associated with the entry.
+  pub fields:     HashMap<String, RedisValue>,
+  /// The number of times the entry has been delivered to a consumer in the group, including the current delivery.
+  pub deliveries: u64,
+}
@@ -2,10 +2,14 @@ use crate::{
   error::{RedisError, RedisErrorKind},
   globals::globals,
   interfaces::ClientLike,
-  modules::inner::{CommandSender, RedisClientInner},
+  modules::{
+    circuit_breaker::CircuitBreakers,
+    inner::{CommandSender, RedisClientInner},
+  },
   protocol::{
-    command::{RedisCommand, RedisCommandKind},
+    command::{RedisCommand, RedisCommandKind, RESPONSE_STREAM_CAPACITY},
     responders::ResponseKind,
+    types::Server,
     utils as protocol_utils,
   },
   types::*,
@@ -17,7 +21,9 @@ use float_cmp::approx_eq;
 use futures::{
   future::{select, Either},
   pin_mut,
+  stream,
   Future,
+  Stream,
   TryFutureExt,
 };
 use parking_lot::{Mutex, RwLock};
@@ -37,6 +43,7 @@ use std::{
 use tokio::{
   sync::{
     broadcast::{channel as broadcast_channel, Sender as BroadcastSender},
+    mpsc::{channel, Receiver},
     oneshot::channel as oneshot_channel,
   },
   time::sleep,
@@ -44,10 +51,14 @@ use tokio::{
 use url::Url;
 use urlencoding::decode as percent_decode;
 
+#[cfg(feature = "middleware")]
+use crate::modules::middleware::{self, MiddlewareCommand};
 #[cfg(any(feature = "enable-native-tls", feature = "enable-rustls"))]
 use crate::protocol::tls::{TlsConfig, TlsConnector};
 #[cfg(any(feature = "full-tracing", feature = "partial-tracing"))]
 use crate::trace;
+#[cfg(feature = "serde-json")]
+use std::time::{SystemTime, UNIX_EPOCH};
 #[cfg(any(feature = "full-tracing", feature = "partial-tracing"))]
 use tracing_futures::Instrument;
 
@@ -164,6 +175,34 @@ pub fn random_u64(max: u64) -> u64 {
   rand::thread_rng().gen_range(0 .. max)
 }
 
+/// Read the number of milliseconds since the unix epoch.
+#[cfg(feature = "serde-json")]
+pub fn unix_ms() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_millis() as u64)
+    .unwrap_or(0)
+}
+
+/// Add up to `jitter * ttl` milliseconds to `ttl`.
+#[cfg(feature = "serde-json")]
+pub fn jittered_ttl(ttl: u64, jitter: f64) -> u64 {
+  let max = (ttl as f64 * jitter.max(0.0)) as u64;
+  if max == 0 {
+    ttl
+  } else {
+    ttl.saturating_add(random_u64(max + 1))
+  }
+}
+
+/// Whether a cached value should be refreshed before it expires, according to the XFetch algorithm.
+///
+/// `delta` is the time it took to compute the value and `random` must be in the range `(0, 1]`.
+#[cfg(feature = "serde-json")]
+pub fn should_refresh_early(now: u64, expires: u64, delta: u64, beta: f64, random: f64) -> bool {
+  now as f64 - delta as f64 * beta * random.ln() >= expires as f64
+}
+
 pub fn set_client_state(state: &RwLock<ClientState>, new_state: ClientState) {
   let mut state_guard = state.write();
   *state_guard = new_state;
@@ -383,11 +422,238 @@ async fn check_blocking_policy(inner: &Arc<RedisClientInner>, command: &RedisCom
 
 /// Prepare the command options, returning the timeout duration to apply.
 pub fn prepare_command<C: ClientLike>(client: &C, command: &mut RedisCommand) -> Duration {
+  inherit_command_options(client, command);
+  prefix_command_keys(client.inner(), command)
+}
+
+/// Apply the client and default options to the command without modifying the arguments.
+fn inherit_command_options<C: ClientLike>(client: &C, command: &mut RedisCommand) {
   client.change_command(command);
   command.inherit_options(client.inner());
-  command
-    .timeout_dur
-    .unwrap_or_else(|| client.inner().default_command_timeout())
+}
+
+/// Apply the key prefix to the command arguments, returning the timeout duration to apply.
+fn prefix_command_keys(inner: &Arc<RedisClientInner>, command: &mut RedisCommand) -> Duration {
+  command.apply_key_prefix();
+  command.timeout_dur.unwrap_or_else(|| inner.default_command_timeout())
+}
+
+/// Prepend `prefix` to a key argument.
+pub fn add_key_prefix(prefix: &[u8], value: &mut RedisValue) {
+  let key = match value {
+    RedisValue::String(ref s) => [prefix, s.as_bytes()].concat(),
+    RedisValue::Bytes(ref b) => [prefix, &b[..]].concat(),
+    RedisValue::Integer(ref i) => [prefix, i.to_string().as_bytes()].concat(),
+    RedisValue::Double(ref f) => [prefix, f.to_string().as_bytes()].concat(),
+    _ => return,
+  };
+
+  *value = RedisValue::Bytes(key.into());
+}
+
+/// Escape the glob-style pattern characters (`*`, `?`, `[`, `]`, and `\`) in `prefix`.
+fn escape_pattern(prefix: &[u8]) -> Vec<u8> {
+  let mut out = Vec::with_capacity(prefix.len());
+  for byte in prefix.iter() {
+    if matches!(*byte, b'*' | b'?' | b'[' | b']' | b'\\') {
+      out.push(b'\\');
+    }
+    out.push(*byte);
+  }
+  out
+}
+
+/// Prepend the escaped `prefix` to a key pattern used with `SCAN`.
+pub fn add_pattern_prefix(prefix: Option<&Str>, pattern: Str) -> Str {
+  match prefix {
+    Some(prefix) if !prefix.is_empty() => {
+      let mut out = escape_pattern(prefix.as_bytes());
+      out.extend_from_slice(pattern.as_bytes());
+      // escaping ASCII characters keeps the pattern valid UTF-8
+      Str::from(String::from_utf8_lossy(&out).into_owned())
+    },
+    _ => pattern,
+  }
+}
+
+/// Prepend the escaped `prefix` to a key pattern used with `KEYS`.
+pub fn add_key_pattern_prefix(prefix: &[u8], value: &mut RedisValue) {
+  let mut pattern = escape_pattern(prefix);
+  match value {
+    RedisValue::String(ref s) => pattern.extend_from_slice(s.as_bytes()),
+    RedisValue::Bytes(ref b) => pattern.extend_from_slice(b),
+    _ => return,
+  };
+
+  *value = RedisValue::Bytes(pattern.into());
+}
+
+/// Remove `prefix` from the start of a key, if present.
+pub fn strip_key_prefix(prefix: Option<&Str>, key: RedisKey) -> RedisKey {
+  match prefix {
+    Some(prefix) if !prefix.is_empty() && key.as_bytes().starts_with(prefix.as_bytes()) => {
+      RedisKey::from(key.into_bytes().slice(prefix.len() ..))
+    },
+    _ => key,
+  }
+}
+
+/// Run the request hooks on any configured middleware.
+#[cfg(feature = "middleware")]
+fn middleware_request<C: ClientLike>(
+  client: &C,
+  command: &mut RedisCommand,
+) -> Result<Option<MiddlewareCommand>, RedisError> {
+  middleware::process_request(client.inner(), command, client.is_buffered())
+}
+
+#[cfg(not(feature = "middleware"))]
+fn middleware_request<C: ClientLike>(_: &C, _: &mut RedisCommand) -> Result<Option<()>, RedisError> {
+  Ok(None)
+}
+
+/// Run the response hooks on any configured middleware.
+#[cfg(feature = "middleware")]
+fn middleware_response(
+  inner: &Arc<RedisClientInner>,
+  command: &MiddlewareState,
+  result: Result<Resp3Frame, RedisError>,
+) -> Result<Resp3Frame, RedisError> {
+  middleware::process_response(inner, command.as_ref(), result)
+}
+
+#[cfg(not(feature = "middleware"))]
+fn middleware_response(
+  _: &Arc<RedisClientInner>,
+  _: &MiddlewareState,
+  result: Result<Resp3Frame, RedisError>,
+) -> Result<Resp3Frame, RedisError> {
+  result
+}
+
+#[cfg(feature = "middleware")]
+type MiddlewareState = Option<MiddlewareCommand>;
+#[cfg(not(feature = "middleware"))]
+type MiddlewareState = Option<()>;
+
+/// Attach the command and queued spans to a command whose response is streamed to the caller.
+#[cfg(any(feature = "full-tracing", feature = "partial-tracing"))]
+fn set_stream_traces(inner: &Arc<RedisClientInner>, command: &mut RedisCommand) {
+  if !inner.should_trace() {
+    return;
+  }
+
+  let cmd_span = trace::create_command_span(inner);
+  cmd_span.record("cmd", command.kind.to_str_debug());
+  cmd_span.record("req_size", protocol_utils::args_size(command.args()));
+  trace::record_statement(inner, &cmd_span, command);
+  command.traces.queued = Some(trace::create_queued_span(cmd_span.id(), inner));
+  command.traces.cmd = Some(cmd_span);
+}
+
+#[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
+fn set_stream_traces(_: &Arc<RedisClientInner>, _: &mut RedisCommand) {}
+
+/// The state of a response stream returned to the caller.
+enum ResponseStreamState<C, F> {
+  Pending(C, F),
+  Streaming(Box<StreamedResponse>),
+  Finished,
+}
+
+/// The receiver half of a response stream and the state needed to handle each element.
+struct StreamedResponse {
+  inner:       Arc<RedisClientInner>,
+  rx:          Receiver<Result<Resp3Frame, RedisError>>,
+  timeout_dur: Duration,
+  timed_out:   Arc<AtomicBool>,
+  written_to:  Option<Arc<Mutex<Option<Server>>>>,
+  middleware:  MiddlewareState,
+}
+
+impl StreamedResponse {
+  /// Wait for the next element of the response, applying the command timeout to each element.
+  async fn next(&mut self) -> Option<Result<RedisValue, RedisError>> {
+    let rx = &mut self.rx;
+    let result = match apply_timeout(async { Ok::<_, RedisError>(rx.recv().await) }, self.timeout_dur).await {
+      Ok(Some(result)) => result,
+      Ok(None) => return None,
+      Err(error) => {
+        set_bool_atomic(&self.timed_out, true);
+        self
+          .inner
+          .circuit_breakers
+          .record_timeout(&self.inner, &self.written_to);
+        Err(error)
+      },
+    };
+
+    Some(middleware_response(&self.inner, &self.middleware, result).and_then(protocol_utils::frame_to_results))
+  }
+}
+
+/// Prepare and send a command whose response elements are streamed to the caller.
+async fn send_stream_request<C, F, R>(client: &C, func: F) -> Result<StreamedResponse, RedisError>
+where
+  C: ClientLike,
+  R: Into<RedisCommand>,
+  F: FnOnce() -> Result<R, RedisError>,
+{
+  let inner = client.inner();
+  let mut command: RedisCommand = func()?.into();
+  let (tx, rx) = channel(RESPONSE_STREAM_CAPACITY);
+  command.response = ResponseKind::Stream(tx);
+
+  let timed_out = command.timed_out.clone();
+  let written_to = CircuitBreakers::track(inner, &mut command);
+  inherit_command_options(client, &mut command);
+  let middleware = middleware_request(client, &mut command)?;
+  // elements may have already been sent to the caller when the connection closes
+  command.attempts_remaining = 1;
+  let timeout_dur = prefix_command_keys(inner, &mut command);
+  set_stream_traces(inner, &mut command);
+  check_blocking_policy(inner, &command).await?;
+  client.send_command(command)?;
+
+  Ok(StreamedResponse {
+    inner: inner.clone(),
+    rx,
+    timeout_dur,
+    timed_out,
+    written_to,
+    middleware,
+  })
+}
+
+/// Send a command to the server and stream the elements of the response to the caller.
+///
+/// The command is sent when the stream is first polled. The command timeout applies to each element, and any
+/// middleware response hooks run on each element.
+pub fn stream_request<C, F, R>(client: &C, func: F) -> impl Stream<Item = Result<RedisValue, RedisError>> + Unpin
+where
+  C: ClientLike,
+  R: Into<RedisCommand>,
+  F: FnOnce() -> Result<R, RedisError>,
+{
+  let state = ResponseStreamState::Pending(client.clone(), func);
+
+  Box::pin(stream::unfold(state, |state| async move {
+    let mut response = match state {
+      ResponseStreamState::Pending(client, func) => match send_stream_request(&client, func).await {
+        Ok(response) => Box::new(response),
+        Err(error) => return Some((Err(error), ResponseStreamState::Finished)),
+      },
+      ResponseStreamState::Streaming(response) => response,
+      ResponseStreamState::Finished => return None,
+    };
+
+    let result = response.next().await?;
+    if read_bool_atomic(&response.timed_out) {
+      Some((result, ResponseStreamState::Finished))
+    } else {
+      Some((result, ResponseStreamState::Streaming(response)))
+    }
+  }))
 }
 
 /// Send a command to the server using the default response handler.
@@ -403,17 +669,25 @@ where
   command.response = ResponseKind::Respond(Some(tx));
 
   let timed_out = command.timed_out.clone();
-  let timeout_dur = prepare_command(client, &mut command);
+  let written_to = CircuitBreakers::track(inner, &mut command);
+  // middleware sees the caller's keys, and any keys it changes are prefixed afterwards
+  inherit_command_options(client, &mut command);
+  let middleware = middleware_request(client, &mut command)?;
+  let timeout_dur = prefix_command_keys(inner, &mut command);
   check_blocking_policy(inner, &command).await?;
   client.send_command(command)?;
 
-  apply_timeout(rx, timeout_dur)
+  let result = apply_timeout(rx, timeout_dur)
     .and_then(|r| async { r })
     .map_err(move |error| {
       set_bool_atomic(&timed_out, true);
+      if *error.kind() == RedisErrorKind::Timeout {
+        inner.circuit_breakers.record_timeout(inner, &written_to);
+      }
       error
     })
-    .await
+    .await;
+  middleware_response(inner, &middleware, result)
 }
 
 /// Send a command to the server, with tracing.
@@ -446,9 +720,11 @@ where
   };
   cmd_span.record("cmd", &command.kind.to_str_debug());
   cmd_span.record("req_size", &req_size);
+  trace::record_statement(inner, &cmd_span, &command);
 
   let queued_span = trace::create_queued_span(cmd_span.id(), inner);
   let timed_out = command.timed_out.clone();
+  let written_to = CircuitBreakers::track(inner, &mut command);
   _trace!(
     inner,
     "Setting command trace ID: {:?} for {} ({})",
@@ -459,14 +735,19 @@ where
   command.traces.cmd = Some(cmd_span.clone());
   command.traces.queued = Some(queued_span);
 
-  let timeout_dur = prepare_command(client, &mut command);
+  inherit_command_options(client, &mut command);
+  let middleware = middleware_request(client, &mut command)?;
+  let timeout_dur = prefix_command_keys(inner, &mut command);
   let _ = check_blocking_policy(inner, &command).await?;
   let _ = client.send_command(command)?;
 
-  apply_timeout(rx, timeout_dur)
+  let result = apply_timeout(rx, timeout_dur)
     .and_then(|r| async { r })
     .map_err(move |error| {
       set_bool_atomic(&timed_out, true);
+      if *error.kind() == RedisErrorKind::Timeout {
+        inner.circuit_breakers.record_timeout(inner, &written_to);
+      }
       error
     })
     .and_then(|frame| async move {
@@ -474,7 +755,8 @@ where
       Ok::<_, RedisError>(frame)
     })
     .instrument(cmd_span)
-    .await
+    .await;
+  middleware_response(inner, &middleware, result)
 }
 
 #[cfg(not(any(feature = "full-tracing", feature = "partial-tracing")))]
@@ -848,6 +1130,25 @@ mod tests {
     assert_eq!(add_jitter(10, 0), 10);
   }
 
+  #[test]
+  #[cfg(feature = "serde-json")]
+  fn should_add_jitter_to_cache_ttl() {
+    assert_eq!(jittered_ttl(1000, 0.0), 1000);
+    for _ in 0 .. 100 {
+      let ttl = jittered_ttl(1000, 0.1);
+      assert!((1000 ..= 1100).contains(&ttl));
+    }
+  }
+
+  #[test]
+  #[cfg(feature = "serde-json")]
+  fn should_refresh_early_near_expiration() {
+    assert!(!should_refresh_early(1000, 2000, 100, 1.0, 0.5));
+    assert!(should_refresh_early(1950, 2000, 100, 1.0, 0.5));
+    assert!(!should_refresh_early(1950, 2000, 100, 0.0, 0.5));
+    assert!(should_refresh_early(2000, 2000, 100, 0.0, 1.0));
+  }
+
   #[test]
   fn should_flatten_xread_example() {
     // 127.0.0.1:6379> xread count 2 streams foo bar 1643479648480-0 1643479834990-0
@@ -920,4 +1221,137 @@ mod tests {
     assert_eq!(username.unwrap(), "default");
     assert_eq!(password.unwrap(), "abc/123");
   }
+
+  #[test]
+  fn should_add_key_prefix() {
+    let mut key = RedisValue::from("foo");
+    add_key_prefix(b"app:", &mut key);
+    assert_eq!(key.as_bytes(), Some(&b"app:foo"[..]));
+
+    let mut key = RedisValue::from(1);
+    add_key_prefix(b"app:", &mut key);
+    assert_eq!(key.as_bytes(), Some(&b"app:1"[..]));
+  }
+
+  #[test]
+  fn should_strip_key_prefix() {
+    let prefix = Str::from("app:");
+    assert_eq!(strip_key_prefix(Some(&prefix), "app:foo".into()), RedisKey::from("foo"));
+    assert_eq!(strip_key_prefix(Some(&prefix), "bar".into()), RedisKey::from("bar"));
+    assert_eq!(strip_key_prefix(None, "app:foo".into()), RedisKey::from("app:foo"));
+  }
+
+  #[test]
+  fn should_prefix_only_key_arguments() {
+    let mut command = RedisCommand::new(RedisCommandKind::Eval, vec![
+      "return 1".into(),
+      2.into(),
+      "foo".into(),
+      "bar".into(),
+      "baz".into(),
+    ]);
+    command.key_prefix = Some("app:".into());
+    command.apply_key_prefix();
+
+    let expected = vec!["return 1", "2", "app:foo", "app:bar", "baz"];
+    let actual: Vec<String> = command.args().iter().map(|v| v.as_str().unwrap().to_string()).collect();
+    assert_eq!(actual, expected);
+  }
+
+  #[test]
+  fn should_read_stream_key_indexes() {
+    let args: Vec<RedisValue> = vec![
+      "COUNT".into(),
+      1.into(),
+      "STREAMS".into(),
+      "a".into(),
+      "b".into(),
+      "0".into(),
+      "0".into(),
+    ];
+    assert_eq!(RedisCommandKind::Xread.key_indexes(&args), Some(vec![3, 4]));
+  }
+
+  #[test]
+  fn should_read_custom_key_indexes_from_cluster_hash() {
+    let args: Vec<RedisValue> = vec![1.into(), "foo".into(), "bar".into()];
+    let kind = |hasher: ClusterHash| RedisCommandKind::_Custom(CustomCommand::new_static("FOO.BAR", hasher, false));
+
+    assert_eq!(
+      ClusterHash::FirstKey.key_indexes(&kind(ClusterHash::FirstKey), &args),
+      vec![1]
+    );
+    assert_eq!(
+      ClusterHash::Offset(2).key_indexes(&kind(ClusterHash::Offset(2)), &args),
+      vec![2]
+    );
+    assert_eq!(
+      ClusterHash::Offset(3).key_indexes(&kind(ClusterHash::Offset(3)), &args),
+      Vec::<usize>::new()
+    );
+    assert_eq!(
+      ClusterHash::Random.key_indexes(&kind(ClusterHash::Random), &args),
+      Vec::<usize>::new()
+    );
+    assert_eq!(
+      ClusterHash::FirstKey.key_indexes(&RedisCommandKind::Ping, &args),
+      Vec::<usize>::new()
+    );
+
+    let args: Vec<RedisValue> = vec!["AND".into(), "dest".into(), "a".into()];
+    assert_eq!(
+      ClusterHash::FirstKey.key_indexes(&RedisCommandKind::BitOp, &args),
+      vec![1, 2]
+    );
+    assert_eq!(
+      ClusterHash::FirstKey.hash_command(&RedisCommandKind::BitOp, &args),
+      Some(redis_protocol::redis_keyslot(b"dest"))
+    );
+  }
+
+  #[test]
+  fn should_escape_pattern_prefix() {
+    let prefix = Str::from("a*b?[c]\\:");
+    assert_eq!(
+      add_pattern_prefix(Some(&prefix), "foo*".into()),
+      Str::from("a\\*b\\?\\[c\\]\\\\:foo*")
+    );
+    assert_eq!(add_pattern_prefix(None, "foo*".into()), Str::from("foo*"));
+
+    let mut command = RedisCommand::new(RedisCommandKind::Keys, vec!["foo*".into()]);
+    command.key_prefix = Some("app[1]:".into());
+    command.apply_key_prefix();
+    assert_eq!(command.args()[0].as_bytes(), Some(&b"app\\[1\\]:foo*"[..]));
+  }
+
+  #[test]
+  fn should_classify_idempotent_commands() {
+    let commands = vec![
+      (RedisCommandKind::Get, true),
+      (RedisCommandKind::Set, true),
+      (RedisCommandKind::Del, true),
+      (RedisCommandKind::Expire, true),
+      (RedisCommandKind::HSet, true),
+      (RedisCommandKind::Sadd, true),
+      (RedisCommandKind::Zrem, true),
+      (RedisCommandKind::LTrim, true),
+      (RedisCommandKind::Zremrangebyscore, true),
+      (RedisCommandKind::Incr, false),
+      (RedisCommandKind::LPush, false),
+      (RedisCommandKind::Spop, false),
+      (RedisCommandKind::Zremrangebyrank, false),
+      (RedisCommandKind::Xadd, false),
+      (RedisCommandKind::EvalSha, false),
+      (RedisCommandKind::Publish, false),
+      (RedisCommandKind::JsonArrTrim, false),
+      (
+        RedisCommandKind::_Custom(CustomCommand::new_static("FOO", ClusterHash::FirstKey, false)),
+        false,
+      ),
+    ];
+
+    for (kind, idempotent) in commands.into_iter() {
+      assert_eq!(kind.is_idempotent(), idempotent, "{}", kind.to_str_debug());
+    }
+  }
 }